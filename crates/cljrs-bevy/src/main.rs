@@ -120,6 +120,16 @@ fn setup_cljrs_source_to_cljrs_values(
                                             "unclosed collection",
                                         )
                                     }
+                                    ReadError::DuplicateKey((first_begin_idx, first_end_idx), (begin_idx, end_idx)) => {
+                                        tracing::error!(
+                                            src = &src[..=end_idx],
+                                            err_src = &src[begin_idx..=end_idx],
+                                            first_src = &src[first_begin_idx..=first_end_idx],
+                                            err_begin_idx = begin_idx,
+                                            err_end_idx = end_idx,
+                                            "duplicate key",
+                                        )
+                                    }
                                 }
                             }
                         }
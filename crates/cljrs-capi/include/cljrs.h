@@ -0,0 +1,151 @@
+/* generated from src/lib.rs by the cljrs-capi header test, do not edit */
+
+#ifndef CLJRS_H
+#define CLJRS_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/**
+ * a C API for reading EDN into values, inspecting them and printing them back
+ *
+ * Readers, values, errors and printed strings are owned by the caller, who frees each with its
+ * `_free` function. Functions taking one accept NULL and treat it as absent.
+ * Values are immutable and atomically reference counted: an element taken from a collection
+ * stays valid after the collection is freed, and values may be shared between threads.
+ * Strings are UTF-8 and lengths are in bytes. Spans are byte offsets into the source read, where
+ * `end` is the offset of the first byte of the span's last char.
+ *
+ * `include/cljrs.h` is generated from this file by the crate's `header` test.
+ */
+
+/** the type of a value */
+typedef enum CljrsType {
+    CLJRS_TYPE_NIL = 0,
+    CLJRS_TYPE_BOOL = 1,
+    CLJRS_TYPE_NUMBER = 2,
+    CLJRS_TYPE_STRING = 3,
+    CLJRS_TYPE_CHAR = 4,
+    CLJRS_TYPE_KEYWORD = 5,
+    CLJRS_TYPE_SYMBOL = 6,
+    CLJRS_TYPE_LIST = 7,
+    CLJRS_TYPE_VECTOR = 8,
+    CLJRS_TYPE_SET = 9,
+    CLJRS_TYPE_MAP = 10,
+} CljrsType;
+
+/** what went wrong reading */
+typedef enum CljrsErrorKind {
+    CLJRS_ERROR_KIND_INSUFFICIENT_INPUT = 1,
+    CLJRS_ERROR_KIND_INVALID_INPUT = 2,
+    CLJRS_ERROR_KIND_UNCLOSED_COLLECTION = 3,
+    CLJRS_ERROR_KIND_DUPLICATE_KEY = 4,
+    CLJRS_ERROR_KIND_INVALID_UTF8 = 5,
+    CLJRS_ERROR_KIND_INTERNAL = 6,
+} CljrsErrorKind;
+
+/** the bytes from `begin` through the char starting at `end` */
+typedef struct CljrsSpan {
+    size_t begin;
+    size_t end;
+} CljrsSpan;
+
+/** reads top-level values from a copy of its source */
+typedef struct CljrsReader CljrsReader;
+
+/** a value, or an element of one */
+typedef struct CljrsValue CljrsValue;
+
+/** an error reading, with the span it was found at */
+typedef struct CljrsError CljrsError;
+
+/**
+ * a reader of a copy of the `len` bytes at `src`, or NULL if they aren't UTF-8, in which case
+ * an error is put in `*error` if `error` isn't NULL
+ */
+CljrsReader *cljrs_reader_new(const uint8_t *src, size_t len, CljrsError **error);
+
+/**
+ * the next top-level value, with its span put in `*span` if `span` isn't NULL, or NULL at the
+ * end of the source or on an error, which is put in `*error` if `error` isn't NULL
+ *
+ * Reading stops at the first error, after which this returns NULL without an error.
+ */
+CljrsValue *cljrs_reader_next(CljrsReader *reader, CljrsSpan *span, CljrsError **error);
+
+void cljrs_reader_free(CljrsReader *reader);
+
+/** the type of a value, where NULL is nil */
+CljrsType cljrs_value_type(const CljrsValue *value);
+
+/** whether a value is `true` */
+bool cljrs_value_bool(const CljrsValue *value);
+
+/** a number's value, or NaN for anything else */
+double cljrs_value_number(const CljrsValue *value);
+
+/** a char's code point, or 0xFFFFFFFF for anything else */
+uint32_t cljrs_value_char(const CljrsValue *value);
+
+/**
+ * a string's contents, or a keyword's or symbol's name, with its length put in `*len` if `len`
+ * isn't NULL, or NULL for anything else
+ *
+ * The bytes aren't NUL-terminated and are valid for as long as `value` is.
+ */
+const char *cljrs_value_string(const CljrsValue *value, size_t *len);
+
+/**
+ * a qualified keyword's or symbol's namespace (the alias of `::alias/name`), with its length put
+ * in `*len` if `len` isn't NULL, or NULL for anything else
+ *
+ * The bytes aren't NUL-terminated and are valid for as long as `value` is.
+ */
+const char *cljrs_value_namespace(const CljrsValue *value, size_t *len);
+
+/** whether a value is a keyword resolved in the current namespace, `::name` or `::alias/name` */
+bool cljrs_value_is_auto_resolved(const CljrsValue *value);
+
+/** the number of elements of a list, vector or set, or of entries of a map, or 0 */
+size_t cljrs_value_count(const CljrsValue *value);
+
+/** the element at `idx` of a list, vector or set, in the order read, or NULL if there's none */
+CljrsValue *cljrs_value_nth(const CljrsValue *value, size_t idx);
+
+/** the key of the entry at `idx` of a map, in the order read, or NULL if there's none */
+CljrsValue *cljrs_value_key_at(const CljrsValue *value, size_t idx);
+
+/** the value of the entry at `idx` of a map, in the order read, or NULL if there's none */
+CljrsValue *cljrs_value_val_at(const CljrsValue *value, size_t idx);
+
+/**
+ * a value printed readably as a NUL-terminated string, with its length put in `*len` if `len`
+ * isn't NULL, or NULL if the value is NULL or its printed form contains a NUL
+ */
+char *cljrs_value_pr_str(const CljrsValue *value, size_t *len);
+
+void cljrs_value_free(CljrsValue *value);
+
+/** frees a string returned by `cljrs_value_pr_str` */
+void cljrs_string_free(char *s);
+
+CljrsErrorKind cljrs_error_kind(const CljrsError *error);
+
+/** where an error was found, which for a duplicate key is the duplicate */
+CljrsSpan cljrs_error_span(const CljrsError *error);
+
+/** a NUL-terminated description of an error, which is freed with it, or NULL for NULL */
+const char *cljrs_error_message(const CljrsError *error);
+
+void cljrs_error_free(CljrsError *error);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CLJRS_H */
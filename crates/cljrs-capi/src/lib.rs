@@ -0,0 +1,432 @@
+//! a C API for reading EDN into values, inspecting them and printing them back
+//!
+//! Readers, values, errors and printed strings are owned by the caller, who frees each with its
+//! `_free` function. Functions taking one accept NULL and treat it as absent.
+//! Values are immutable and atomically reference counted: an element taken from a collection
+//! stays valid after the collection is freed, and values may be shared between threads.
+//! Strings are UTF-8 and lengths are in bytes. Spans are byte offsets into the source read, where
+//! `end` is the offset of the first byte of the span's last char.
+//!
+//! `include/cljrs.h` is generated from this file by the crate's `header` test.
+
+// every function's pointer arguments are as the crate docs above describe
+#![allow(clippy::missing_safety_doc)]
+
+use std::{
+    ffi::{c_char, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+    slice,
+    str,
+};
+use archery::ArcK;
+use cljrs_reader::{
+    char_reader::CharReader,
+    keyword::Keyword,
+    printer::pr_str,
+    value::{Value, ValuePtr},
+    ByteIndexSpan,
+    ReadClj,
+    ReadError,
+};
+
+/// the type of a value
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CljrsType {
+    Nil = 0,
+    Bool = 1,
+    Number = 2,
+    String = 3,
+    Char = 4,
+    Keyword = 5,
+    Symbol = 6,
+    List = 7,
+    Vector = 8,
+    Set = 9,
+    Map = 10,
+}
+
+/// what went wrong reading
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CljrsErrorKind {
+    InsufficientInput = 1,
+    InvalidInput = 2,
+    UnclosedCollection = 3,
+    DuplicateKey = 4,
+    InvalidUtf8 = 5,
+    Internal = 6,
+}
+
+/// the bytes from `begin` through the char starting at `end`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CljrsSpan {
+    pub begin: usize,
+    pub end: usize,
+}
+
+/// reads top-level values from a copy of its source
+pub struct CljrsReader {
+    /// borrows `src`, until `cljrs_reader_free` drops it before freeing `src`
+    rdr: Option<CharReader<'static>>,
+    /// from `Box::into_raw`, and freed by `cljrs_reader_free`
+    src: *mut str,
+    /// set on an error, after which nothing more is read
+    failed: bool,
+}
+
+/// a value, or an element of one
+pub struct CljrsValue(ValuePtr<ArcK>);
+
+/// an error reading, with the span it was found at
+pub struct CljrsError {
+    kind: CljrsErrorKind,
+    span: CljrsSpan,
+    /// NUL-terminated for `cljrs_error_message`
+    message: CString,
+}
+
+impl CljrsError {
+    fn new(kind: CljrsErrorKind, span: ByteIndexSpan, message: &str) -> Self {
+        let message = CString::new(message).expect("no NUL in error messages");
+        Self { kind, span: span.into(), message }
+    }
+}
+
+impl From<ByteIndexSpan> for CljrsSpan {
+    fn from((begin, end): ByteIndexSpan) -> Self {
+        Self { begin, end }
+    }
+}
+
+impl From<ReadError> for CljrsError {
+    fn from(err: ReadError) -> Self {
+        let kind = match err {
+            ReadError::InsufficientInput(..) => CljrsErrorKind::InsufficientInput,
+            ReadError::InvalidInput(..) => CljrsErrorKind::InvalidInput,
+            ReadError::UnclosedCollection(..) => CljrsErrorKind::UnclosedCollection,
+            ReadError::DuplicateKey(..) => CljrsErrorKind::DuplicateKey,
+        };
+        Self::new(kind, err.span(), err.message())
+    }
+}
+
+fn put_error(error: *mut *mut CljrsError, err: CljrsError) {
+    if !error.is_null() {
+        unsafe { *error = Box::into_raw(Box::new(err)) };
+    }
+}
+
+fn new_value(ptr: ValuePtr<ArcK>) -> *mut CljrsValue {
+    Box::into_raw(Box::new(CljrsValue(ptr)))
+}
+
+fn value<'v>(value: *const CljrsValue) -> Option<&'v Value<ArcK>> {
+    unsafe { value.as_ref() }.map(|value| &*value.0)
+}
+
+/// puts `s`'s length in `*len`, if `len` isn't NULL, and returns its bytes
+fn put_str(s: &str, len: *mut usize) -> *const c_char {
+    if !len.is_null() {
+        unsafe { *len = s.len() };
+    }
+    s.as_ptr().cast()
+}
+
+/// a reader of a copy of the `len` bytes at `src`, or NULL if they aren't UTF-8, in which case
+/// an error is put in `*error` if `error` isn't NULL
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_reader_new(src: *const u8, len: usize, error: *mut *mut CljrsError) -> *mut CljrsReader {
+    let bytes = match len {
+        0 => &[][..],
+        _ => unsafe { slice::from_raw_parts(src, len) },
+    };
+    let src: Box<str> = match str::from_utf8(bytes) {
+        Ok(src) => src.into(),
+        Err(err) => {
+            let at = err.valid_up_to();
+            put_error(error, CljrsError::new(CljrsErrorKind::InvalidUtf8, (at, at), "invalid UTF-8"));
+            return ptr::null_mut();
+        }
+    };
+    // the reader borrows the str, which isn't freed until after the reader is dropped
+    let src = Box::into_raw(src);
+    let rdr = CharReader::try_from_str(unsafe { &*src });
+    Box::into_raw(Box::new(CljrsReader { rdr, src, failed: false }))
+}
+
+/// the next top-level value, with its span put in `*span` if `span` isn't NULL, or NULL at the
+/// end of the source or on an error, which is put in `*error` if `error` isn't NULL
+///
+/// Reading stops at the first error, after which this returns NULL without an error.
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_reader_next(reader: *mut CljrsReader, span: *mut CljrsSpan, error: *mut *mut CljrsError) -> *mut CljrsValue {
+    let Some(reader) = (unsafe { reader.as_mut() }) else {
+        return ptr::null_mut();
+    };
+    let Some(rdr) = reader.rdr.as_mut().filter(|_| !reader.failed) else {
+        return ptr::null_mut();
+    };
+    let begin = rdr.current_byte_idx();
+    // a panic reading is a bug, which mustn't unwind into C
+    let read = panic::catch_unwind(AssertUnwindSafe(|| ReadClj::<ArcK>::try_read_one(rdr)));
+    let err = match read {
+        Ok(Ok(Some(read))) => {
+            if !span.is_null() {
+                unsafe { *span = read.span.into() };
+            }
+            return new_value(ValuePtr::from(read.data));
+        }
+        Ok(Ok(None)) => return ptr::null_mut(),
+        Ok(Err(err)) => err.into(),
+        Err(_panic) => {
+            let src = unsafe { &*reader.src };
+            let end = src.char_indices().last().map(|(idx, _)| idx).unwrap_or(0);
+            CljrsError::new(CljrsErrorKind::Internal, (begin.unwrap_or(end), end), "internal error")
+        }
+    };
+    reader.failed = true;
+    put_error(error, err);
+    ptr::null_mut()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_reader_free(reader: *mut CljrsReader) {
+    if !reader.is_null() {
+        let mut reader = unsafe { Box::from_raw(reader) };
+        drop(reader.rdr.take());
+        drop(unsafe { Box::from_raw(reader.src) });
+    }
+}
+
+/// the type of a value, where NULL is nil
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_value_type(value: *const CljrsValue) -> CljrsType {
+    match self::value(value) {
+        None | Some(Value::Nil) => CljrsType::Nil,
+        Some(Value::Bool(_)) => CljrsType::Bool,
+        Some(Value::Num(_)) => CljrsType::Number,
+        Some(Value::Str(_)) => CljrsType::String,
+        Some(Value::Char(_)) => CljrsType::Char,
+        Some(Value::Keyword(_)) => CljrsType::Keyword,
+        Some(Value::Symbol(_)) => CljrsType::Symbol,
+        Some(Value::List(_)) => CljrsType::List,
+        Some(Value::Vect(_)) => CljrsType::Vector,
+        Some(Value::Set(_)) => CljrsType::Set,
+        Some(Value::Map(_)) => CljrsType::Map,
+    }
+}
+
+/// whether a value is `true`
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_value_bool(value: *const CljrsValue) -> bool {
+    matches!(self::value(value), Some(Value::Bool(true)))
+}
+
+/// a number's value, or NaN for anything else
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_value_number(value: *const CljrsValue) -> f64 {
+    match self::value(value) {
+        Some(Value::Num(num)) => *num,
+        _ => f64::NAN,
+    }
+}
+
+/// a char's code point, or 0xFFFFFFFF for anything else
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_value_char(value: *const CljrsValue) -> u32 {
+    match self::value(value) {
+        Some(Value::Char(ch)) => *ch as u32,
+        _ => u32::MAX,
+    }
+}
+
+/// a string's contents, or a keyword's or symbol's name, with its length put in `*len` if `len`
+/// isn't NULL, or NULL for anything else
+///
+/// The bytes aren't NUL-terminated and are valid for as long as `value` is.
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_value_string(value: *const CljrsValue, len: *mut usize) -> *const c_char {
+    match self::value(value) {
+        Some(Value::Str(s)) => put_str(s, len),
+        Some(Value::Keyword(
+            Keyword::Unqualified { name }
+            | Keyword::Qualified { name, .. }
+            | Keyword::SelfQualified { name }
+            | Keyword::AliasQualified { name, .. },
+        )) => put_str(name, len),
+        Some(Value::Symbol(symbol)) => put_str(symbol.name(), len),
+        _ => ptr::null(),
+    }
+}
+
+/// a qualified keyword's or symbol's namespace (the alias of `::alias/name`), with its length put
+/// in `*len` if `len` isn't NULL, or NULL for anything else
+///
+/// The bytes aren't NUL-terminated and are valid for as long as `value` is.
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_value_namespace(value: *const CljrsValue, len: *mut usize) -> *const c_char {
+    match self::value(value) {
+        Some(Value::Keyword(
+            Keyword::Qualified { namespace, .. } | Keyword::AliasQualified { alias: namespace, .. },
+        )) => put_str(namespace, len),
+        Some(Value::Symbol(symbol)) => symbol.try_namespace().map_or(ptr::null(), |namespace| put_str(namespace, len)),
+        _ => ptr::null(),
+    }
+}
+
+/// whether a value is a keyword resolved in the current namespace, `::name` or `::alias/name`
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_value_is_auto_resolved(value: *const CljrsValue) -> bool {
+    matches!(
+        self::value(value),
+        Some(Value::Keyword(Keyword::SelfQualified { .. } | Keyword::AliasQualified { .. }))
+    )
+}
+
+/// the number of elements of a list, vector or set, or of entries of a map, or 0
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_value_count(value: *const CljrsValue) -> usize {
+    match self::value(value) {
+        Some(Value::List(elems) | Value::Vect(elems) | Value::Set(elems)) => elems.len(),
+        Some(Value::Map(entries)) => entries.len(),
+        _ => 0,
+    }
+}
+
+/// the element at `idx` of a list, vector or set, in the order read, or NULL if there's none
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_value_nth(value: *const CljrsValue, idx: usize) -> *mut CljrsValue {
+    match self::value(value) {
+        Some(Value::List(elems) | Value::Vect(elems) | Value::Set(elems)) => {
+            elems.get(idx).cloned().map_or(ptr::null_mut(), new_value)
+        }
+        _ => ptr::null_mut(),
+    }
+}
+
+/// the key of the entry at `idx` of a map, in the order read, or NULL if there's none
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_value_key_at(value: *const CljrsValue, idx: usize) -> *mut CljrsValue {
+    match self::value(value) {
+        Some(Value::Map(entries)) => entries.get(idx).map_or(ptr::null_mut(), |(k, _)| new_value(k.clone())),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// the value of the entry at `idx` of a map, in the order read, or NULL if there's none
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_value_val_at(value: *const CljrsValue, idx: usize) -> *mut CljrsValue {
+    match self::value(value) {
+        Some(Value::Map(entries)) => entries.get(idx).map_or(ptr::null_mut(), |(_, v)| new_value(v.clone())),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// a value printed readably as a NUL-terminated string, with its length put in `*len` if `len`
+/// isn't NULL, or NULL if the value is NULL or its printed form contains a NUL
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_value_pr_str(value: *const CljrsValue, len: *mut usize) -> *mut c_char {
+    let Some(printed) = self::value(value).and_then(|value| CString::new(pr_str(value)).ok()) else {
+        return ptr::null_mut();
+    };
+    if !len.is_null() {
+        unsafe { *len = printed.as_bytes().len() };
+    }
+    printed.into_raw()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_value_free(value: *mut CljrsValue) {
+    if !value.is_null() {
+        drop(unsafe { Box::from_raw(value) });
+    }
+}
+
+/// frees a string returned by `cljrs_value_pr_str`
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_error_kind(error: *const CljrsError) -> CljrsErrorKind {
+    unsafe { error.as_ref() }.map_or(CljrsErrorKind::InvalidInput, |error| error.kind)
+}
+
+/// where an error was found, which for a duplicate key is the duplicate
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_error_span(error: *const CljrsError) -> CljrsSpan {
+    unsafe { error.as_ref() }.map_or_else(CljrsSpan::default, |error| error.span)
+}
+
+/// a NUL-terminated description of an error, which is freed with it, or NULL for NULL
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_error_message(error: *const CljrsError) -> *const c_char {
+    unsafe { error.as_ref() }.map_or(ptr::null(), |error| error.message.as_ptr())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cljrs_error_free(error: *mut CljrsError) {
+    if !error.is_null() {
+        drop(unsafe { Box::from_raw(error) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    /// reads all of `src`, printing each value, or the error read
+    fn read_all(src: &str) -> Result<Vec<(String, CljrsSpan)>, (CljrsErrorKind, CljrsSpan)> {
+        unsafe {
+            let mut error = ptr::null_mut();
+            let reader = cljrs_reader_new(src.as_ptr(), src.len(), &mut error);
+            let mut read = vec![];
+            let mut span = CljrsSpan::default();
+            loop {
+                let value = cljrs_reader_next(reader, &mut span, &mut error);
+                if value.is_null() {
+                    break;
+                }
+                let printed = cljrs_value_pr_str(value, ptr::null_mut());
+                read.push((CStr::from_ptr(printed).to_str().unwrap().to_owned(), span));
+                cljrs_string_free(printed);
+                cljrs_value_free(value);
+            }
+            cljrs_reader_free(reader);
+            if error.is_null() {
+                return Ok(read);
+            }
+            let err = (cljrs_error_kind(error), cljrs_error_span(error));
+            cljrs_error_free(error);
+            Err(err)
+        }
+    }
+
+    #[test]
+    fn reads() {
+        assert_eq!(read_all(""), Ok(vec![]));
+        assert_eq!(
+            read_all(" :a  [1 b]"),
+            Ok(vec![
+                (":a".to_owned(), CljrsSpan { begin: 1, end: 2 }),
+                ("[1.0 b]".to_owned(), CljrsSpan { begin: 5, end: 9 }),
+            ])
+        );
+        assert_eq!(read_all("1 (a"), Err((CljrsErrorKind::InsufficientInput, CljrsSpan { begin: 2, end: 3 })));
+        assert_eq!(read_all("{:a 1 :a 2}"), Err((CljrsErrorKind::DuplicateKey, CljrsSpan { begin: 6, end: 7 })));
+        unsafe {
+            let mut error = ptr::null_mut();
+            assert!(cljrs_reader_new(b"(a \xff)".as_ptr(), 6, &mut error).is_null());
+            assert_eq!(cljrs_error_kind(error), CljrsErrorKind::InvalidUtf8);
+            assert_eq!(cljrs_error_span(error), CljrsSpan { begin: 3, end: 3 });
+            cljrs_error_free(error);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! compiles each of `tests/c/*.c` against the header and the cdylib built for these tests, and
+//! runs it
+
+#![cfg(unix)]
+
+use std::{env, fs, path::Path, process::Command};
+
+#[test]
+fn c_tests_pass() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // cargo builds the cdylib into target/<profile>/deps, beside this test
+    let exe = env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap();
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+
+    let mut sources: Vec<_> = fs::read_dir(dir.join("tests/c"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "c"))
+        .collect();
+    sources.sort();
+    assert!(!sources.is_empty());
+
+    for source in sources {
+        let binary = out_dir.join(source.file_stem().unwrap());
+        let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_owned()))
+            .args(["-std=c99", "-Wall", "-Wextra", "-Werror"])
+            .arg("-I")
+            .arg(dir.join("include"))
+            .arg(&source)
+            .arg("-o")
+            .arg(&binary)
+            .arg("-L")
+            .arg(lib_dir)
+            .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+            .args(["-lcljrs_capi", "-lm"])
+            .status()
+            .expect("a C compiler");
+        assert!(status.success(), "compiling {}", source.display());
+
+        let status = Command::new(&binary).status().unwrap();
+        assert!(status.success(), "running {}", source.display());
+    }
+}
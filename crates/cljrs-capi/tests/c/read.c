@@ -0,0 +1,167 @@
+/* reads, walks and prints values through the C API, exiting 1 on the first failed check */
+
+#include <math.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "cljrs.h"
+
+static int failures = 0;
+
+#define CHECK(cond)                                                      \
+    do {                                                                 \
+        if (!(cond)) {                                                   \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+            failures++;                                                  \
+        }                                                                \
+    } while (0)
+
+static CljrsReader *reader_of(const char *src, CljrsError **error) {
+    return cljrs_reader_new((const uint8_t *)src, strlen(src), error);
+}
+
+/* whether `value`'s string (or name) is `expected` */
+static bool string_eq(const CljrsValue *value, const char *expected) {
+    size_t len = 0;
+    const char *s = cljrs_value_string(value, &len);
+    return s != NULL && len == strlen(expected) && memcmp(s, expected, len) == 0;
+}
+
+static bool namespace_eq(const CljrsValue *value, const char *expected) {
+    size_t len = 0;
+    const char *s = cljrs_value_namespace(value, &len);
+    return s != NULL && len == strlen(expected) && memcmp(s, expected, len) == 0;
+}
+
+static bool printed_eq(const CljrsValue *value, const char *expected) {
+    size_t len = 0;
+    char *printed = cljrs_value_pr_str(value, &len);
+    bool eq = printed != NULL && len == strlen(expected) && strcmp(printed, expected) == 0;
+    cljrs_string_free(printed);
+    return eq;
+}
+
+static void walks(void) {
+    CljrsError *error = NULL;
+    CljrsReader *reader = reader_of("(a/b \"s\" :k ::x ::y/z \\λ 2.5 nil true)\n{:m [1]}", &error);
+    CHECK(reader != NULL);
+
+    CljrsSpan span = {0, 0};
+    CljrsValue *list = cljrs_reader_next(reader, &span, &error);
+    CHECK(cljrs_value_type(list) == CLJRS_TYPE_LIST);
+    CHECK(span.begin == 0 && span.end == 38);
+    CHECK(cljrs_value_count(list) == 9);
+
+    CljrsValue *elem = cljrs_value_nth(list, 0);
+    CHECK(cljrs_value_type(elem) == CLJRS_TYPE_SYMBOL);
+    CHECK(string_eq(elem, "b") && namespace_eq(elem, "a"));
+    cljrs_value_free(elem);
+
+    elem = cljrs_value_nth(list, 1);
+    CHECK(cljrs_value_type(elem) == CLJRS_TYPE_STRING && string_eq(elem, "s"));
+    cljrs_value_free(elem);
+
+    elem = cljrs_value_nth(list, 2);
+    CHECK(cljrs_value_type(elem) == CLJRS_TYPE_KEYWORD && string_eq(elem, "k"));
+    CHECK(cljrs_value_namespace(elem, NULL) == NULL && !cljrs_value_is_auto_resolved(elem));
+    cljrs_value_free(elem);
+
+    elem = cljrs_value_nth(list, 3);
+    CHECK(string_eq(elem, "x") && cljrs_value_namespace(elem, NULL) == NULL);
+    CHECK(cljrs_value_is_auto_resolved(elem));
+    cljrs_value_free(elem);
+
+    elem = cljrs_value_nth(list, 4);
+    CHECK(string_eq(elem, "z") && namespace_eq(elem, "y") && cljrs_value_is_auto_resolved(elem));
+    cljrs_value_free(elem);
+
+    elem = cljrs_value_nth(list, 5);
+    CHECK(cljrs_value_type(elem) == CLJRS_TYPE_CHAR && cljrs_value_char(elem) == 0x3BB);
+    cljrs_value_free(elem);
+
+    elem = cljrs_value_nth(list, 6);
+    CHECK(cljrs_value_type(elem) == CLJRS_TYPE_NUMBER && cljrs_value_number(elem) == 2.5);
+    CHECK(isnan(cljrs_value_number(list)));
+    cljrs_value_free(elem);
+
+    elem = cljrs_value_nth(list, 7);
+    CHECK(cljrs_value_type(elem) == CLJRS_TYPE_NIL);
+    cljrs_value_free(elem);
+
+    elem = cljrs_value_nth(list, 8);
+    CHECK(cljrs_value_type(elem) == CLJRS_TYPE_BOOL && cljrs_value_bool(elem));
+    cljrs_value_free(elem);
+
+    CHECK(cljrs_value_nth(list, 9) == NULL);
+    cljrs_value_free(list);
+
+    CljrsValue *map = cljrs_reader_next(reader, &span, &error);
+    CHECK(cljrs_value_type(map) == CLJRS_TYPE_MAP && cljrs_value_count(map) == 1);
+    CHECK(span.begin == 40 && span.end == 47);
+    CljrsValue *key = cljrs_value_key_at(map, 0);
+    CljrsValue *val = cljrs_value_val_at(map, 0);
+    /* elements outlive their collection */
+    cljrs_value_free(map);
+    CHECK(printed_eq(key, ":m"));
+    CHECK(cljrs_value_type(val) == CLJRS_TYPE_VECTOR && printed_eq(val, "[1.0]"));
+    cljrs_value_free(key);
+    cljrs_value_free(val);
+
+    CHECK(cljrs_reader_next(reader, &span, &error) == NULL);
+    CHECK(error == NULL);
+    cljrs_reader_free(reader);
+}
+
+static void errors(void) {
+    CljrsError *error = NULL;
+    CljrsReader *reader = reader_of("1 [2", &error);
+    CljrsValue *one = cljrs_reader_next(reader, NULL, &error);
+    CHECK(printed_eq(one, "1.0"));
+    cljrs_value_free(one);
+    CHECK(cljrs_reader_next(reader, NULL, &error) == NULL);
+    CHECK(cljrs_error_kind(error) == CLJRS_ERROR_KIND_INSUFFICIENT_INPUT);
+    CljrsSpan span = cljrs_error_span(error);
+    CHECK(span.begin == 2 && span.end == 3);
+    CHECK(strcmp(cljrs_error_message(error), "unexpected end of input") == 0);
+    cljrs_error_free(error);
+    error = NULL;
+    /* nothing is read after an error */
+    CHECK(cljrs_reader_next(reader, NULL, &error) == NULL && error == NULL);
+    cljrs_reader_free(reader);
+
+    reader = reader_of("{:a 1 :a 2}", &error);
+    CHECK(cljrs_reader_next(reader, NULL, &error) == NULL);
+    CHECK(cljrs_error_kind(error) == CLJRS_ERROR_KIND_DUPLICATE_KEY);
+    span = cljrs_error_span(error);
+    CHECK(span.begin == 6 && span.end == 7);
+    cljrs_error_free(error);
+    error = NULL;
+    cljrs_reader_free(reader);
+
+    const uint8_t invalid[] = {'(', 'a', ' ', 0xFF, ')'};
+    CHECK(cljrs_reader_new(invalid, sizeof invalid, &error) == NULL);
+    CHECK(cljrs_error_kind(error) == CLJRS_ERROR_KIND_INVALID_UTF8);
+    CHECK(cljrs_error_span(error).begin == 3);
+    cljrs_error_free(error);
+}
+
+static void empty_and_null(void) {
+    CljrsReader *reader = cljrs_reader_new(NULL, 0, NULL);
+    CHECK(reader != NULL && cljrs_reader_next(reader, NULL, NULL) == NULL);
+    cljrs_reader_free(reader);
+
+    CHECK(cljrs_value_type(NULL) == CLJRS_TYPE_NIL);
+    CHECK(cljrs_value_count(NULL) == 0 && cljrs_value_nth(NULL, 0) == NULL);
+    CHECK(cljrs_value_pr_str(NULL, NULL) == NULL);
+    cljrs_value_free(NULL);
+    cljrs_error_free(NULL);
+    cljrs_string_free(NULL);
+    cljrs_reader_free(NULL);
+}
+
+int main(void) {
+    walks();
+    errors();
+    empty_and_null();
+    return failures == 0 ? 0 : 1;
+}
@@ -0,0 +1,185 @@
+//! generates `include/cljrs.h` from `src/lib.rs` and checks it's up to date, or with
+//! `CLJRS_CAPI_BLESS=1` in the environment, rewrites it
+//!
+//! Only what the C API uses is understood: docs, `#[repr(C)]` enums with explicit discriminants
+//! and structs, other structs (which are opaque), and `extern "C"` fns with one-line signatures.
+
+use std::{env, fs, path::Path};
+
+const PRELUDE: &str = "/* generated from src/lib.rs by the cljrs-capi header test, do not edit */
+
+#ifndef CLJRS_H
+#define CLJRS_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern \"C\" {
+#endif
+";
+
+const POSTLUDE: &str = "
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CLJRS_H */
+";
+
+/// `CljrsErrorKind` as `CLJRS_ERROR_KIND`
+fn screaming_snake(name: &str) -> String {
+    let mut snake = String::new();
+    for (idx, ch) in name.char_indices() {
+        if ch.is_ascii_uppercase() && idx > 0 {
+            snake.push('_');
+        }
+        snake.push(ch.to_ascii_uppercase());
+    }
+    snake
+}
+
+fn c_type(ty: &str) -> String {
+    if let Some(pointee) = ty.strip_prefix("*const ") {
+        return format!("const {}", pointer(&c_type(pointee)));
+    }
+    if let Some(pointee) = ty.strip_prefix("*mut ") {
+        return pointer(&c_type(pointee));
+    }
+    match ty {
+        "bool" => "bool",
+        "c_char" => "char",
+        "u8" => "uint8_t",
+        "u32" => "uint32_t",
+        "usize" => "size_t",
+        "f64" => "double",
+        named => named,
+    }
+    .to_owned()
+}
+
+fn pointer(ty: &str) -> String {
+    match ty.ends_with('*') {
+        true => format!("{ty}*"),
+        false => format!("{ty} *"),
+    }
+}
+
+/// `name: Type` as `CType name`
+fn c_decl(decl: &str) -> String {
+    let (name, ty) = decl.split_once(": ").expect("a name and type");
+    let ty = c_type(ty.trim());
+    match ty.ends_with('*') {
+        true => format!("{ty}{name}"),
+        false => format!("{ty} {name}"),
+    }
+}
+
+fn comment(docs: &[&str], indent: &str) -> String {
+    match docs {
+        [] => String::new(),
+        [line] => format!("{indent}/** {line} */\n"),
+        lines => {
+            let mut comment = format!("{indent}/**\n");
+            for line in lines {
+                match line.is_empty() {
+                    true => comment += &format!("{indent} *\n"),
+                    false => comment += &format!("{indent} * {line}\n"),
+                }
+            }
+            comment + &format!("{indent} */\n")
+        }
+    }
+}
+
+fn generate(src: &str) -> String {
+    let mut header = String::from(PRELUDE);
+    let crate_docs: Vec<&str> = src
+        .lines()
+        .map_while(|line| line.strip_prefix("//!"))
+        .map(str::trim)
+        .collect();
+    header += &format!("\n{}", comment(&crate_docs, ""));
+
+    let mut lines = src.lines().map(str::trim);
+    let mut docs = vec![];
+    let mut repr_c = false;
+    while let Some(line) = lines.next() {
+        if let Some(doc) = line.strip_prefix("///") {
+            docs.push(doc.trim());
+            continue;
+        }
+        if line.starts_with("#[") {
+            repr_c |= line == "#[repr(C)]";
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("pub enum ").and_then(|rest| rest.strip_suffix(" {")) {
+            assert!(repr_c, "{name} must be #[repr(C)]");
+            let prefix = screaming_snake(name);
+            header += &format!("\n{}typedef enum {name} {{\n", comment(&docs, ""));
+            for variant in lines.by_ref().take_while(|line| *line != "}") {
+                let (variant, discriminant) = variant.trim_end_matches(',').split_once(" = ").expect("a discriminant");
+                header += &format!("    {prefix}_{} = {discriminant},\n", screaming_snake(variant));
+            }
+            header += &format!("}} {name};\n");
+        } else if let Some(rest) = line.strip_prefix("pub struct ") {
+            let name = rest.split(|ch: char| !ch.is_alphanumeric()).next().unwrap_or(rest);
+            header += &format!("\n{}", comment(&docs, ""));
+            if !repr_c {
+                header += &format!("typedef struct {name} {name};\n");
+                if rest.ends_with('{') {
+                    lines.by_ref().take_while(|line| *line != "}").for_each(drop);
+                }
+            } else {
+                header += &format!("typedef struct {name} {{\n");
+                let mut field_docs = vec![];
+                for field in lines.by_ref().take_while(|line| *line != "}") {
+                    match field.strip_prefix("///") {
+                        Some(doc) => field_docs.push(doc.trim()),
+                        None => {
+                            let field = field.strip_prefix("pub ").expect("public fields").trim_end_matches(',');
+                            header += &format!("{}    {};\n", comment(&field_docs, "    "), c_decl(field));
+                            field_docs.clear();
+                        }
+                    }
+                }
+                header += &format!("}} {name};\n");
+            }
+        } else if let Some(rest) = line.strip_prefix("pub unsafe extern \"C\" fn ") {
+            let (name, rest) = rest.split_once('(').expect("parameters");
+            let (params, ret) = rest.rsplit_once(')').expect("parameters");
+            let ret = ret.trim().trim_end_matches('{').trim();
+            let ret = ret.strip_prefix("-> ").map_or("void".to_owned(), c_type);
+            let params = match params.is_empty() {
+                true => "void".to_owned(),
+                false => params.split(", ").map(c_decl).collect::<Vec<_>>().join(", "),
+            };
+            let ret = match ret.ends_with('*') {
+                true => ret,
+                false => ret + " ",
+            };
+            header += &format!("\n{}{ret}{name}({params});\n", comment(&docs, ""));
+        }
+        docs.clear();
+        repr_c = false;
+    }
+
+    header + POSTLUDE
+}
+
+#[test]
+fn header_is_up_to_date() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let src = fs::read_to_string(dir.join("src/lib.rs")).unwrap();
+    let generated = generate(&src);
+    let path = dir.join("include/cljrs.h");
+    if env::var_os("CLJRS_CAPI_BLESS").is_some() {
+        fs::write(&path, &generated).unwrap();
+    }
+    let checked_in = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        generated == checked_in,
+        "include/cljrs.h is out of date, rerun this test with CLJRS_CAPI_BLESS=1 to rewrite it"
+    );
+}
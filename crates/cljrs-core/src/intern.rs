@@ -0,0 +1,379 @@
+//! a global, thread-safe interner of the strings naming symbols and keywords
+//!
+//! Interned values are counted: one is shared by every symbol and keyword it names, and freed
+//! once the last of them is dropped, so memory doesn't grow with every distinct name ever read.
+
+use alloc::{boxed::Box, collections::BTreeSet};
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    ptr::{self, NonNull},
+    sync::atomic::{self, AtomicUsize},
+};
+
+/// an interned string, which is cloned as a counted pointer and compared and hashed by identity
+///
+/// Ordering is by the strings themselves, so sorting names is stable from one run to the next.
+#[derive(Clone)]
+pub struct Name(Interned<str>);
+
+static NAMES: Interner<str> = Interner::new();
+
+impl Name {
+    pub fn new(s: &str) -> Self {
+        Self(NAMES.intern(s, |s| s.into()))
+    }
+    /// the name equal to `s` if one is interned, without interning it
+    pub fn existing(s: &str) -> Option<Self> {
+        NAMES.get(s).map(Self)
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Name {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Name {}
+
+impl PartialEq<str> for Name {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Name {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Hash for Name {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl PartialOrd for Name {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Name {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Deref for Name {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Name {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Name {
+    fn from(s: &str) -> Self {
+        Self::new(s)
+    }
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// a value of an [`Interner`], and how many [`Interned`]s refer to it
+struct Entry<T: ?Sized + Ord + 'static> {
+    refs: AtomicUsize,
+    interner: &'static Interner<T>,
+    value: Box<T>,
+}
+
+/// a counted reference to an interned value, which is removed from its interner and freed along
+/// with the last reference
+pub(crate) struct Interned<T: ?Sized + Ord + 'static>(NonNull<Entry<T>>);
+
+// the value is only read through a reference, and freed by whichever thread drops the last one
+unsafe impl<T: ?Sized + Ord + Send + Sync> Send for Interned<T> {}
+unsafe impl<T: ?Sized + Ord + Send + Sync> Sync for Interned<T> {}
+
+impl<T: ?Sized + Ord> Interned<T> {
+    fn entry(&self) -> &Entry<T> {
+        // entries are freed only once the last reference is dropped
+        unsafe { self.0.as_ref() }
+    }
+}
+
+impl<T: ?Sized + Ord> Deref for Interned<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.entry().value
+    }
+}
+
+impl<T: ?Sized + Ord> Clone for Interned<T> {
+    fn clone(&self) -> Self {
+        self.entry().refs.fetch_add(1, atomic::Ordering::Relaxed);
+        Self(self.0)
+    }
+}
+
+impl<T: ?Sized + Ord> Drop for Interned<T> {
+    fn drop(&mut self) {
+        let entry = self.entry();
+        // all but the last reference are dropped without locking the interner
+        let mut refs = entry.refs.load(atomic::Ordering::Relaxed);
+        while refs > 1 {
+            match entry.refs.compare_exchange_weak(refs, refs - 1, atomic::Ordering::Release, atomic::Ordering::Relaxed) {
+                Ok(_) => return,
+                Err(now) => refs = now,
+            }
+        }
+        // until it's locked, the last may be cloned or interned again, but not after
+        let mut set = lock::lock(&entry.interner.set);
+        if entry.refs.fetch_sub(1, atomic::Ordering::AcqRel) == 1 {
+            set.remove(&*entry.value);
+            drop(set);
+            // freed once unlocked, as freeing a symbol drops the names of its parts
+            drop(unsafe { Box::from_raw(self.0.as_ptr()) });
+        }
+    }
+}
+
+impl<T: ?Sized + Ord> PartialEq for Interned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: ?Sized + Ord> Eq for Interned<T> {}
+
+impl<T: ?Sized + Ord> Hash for Interned<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ptr::hash(self.0.as_ptr(), state)
+    }
+}
+
+impl<T: ?Sized + Ord> PartialOrd for Interned<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: ?Sized + Ord> Ord for Interned<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self == other {
+            true => Ordering::Equal,
+            false => (**self).cmp(&**other),
+        }
+    }
+}
+
+/// an entry of an interner's set, which is ordered by its value
+struct Key<T: ?Sized + Ord + 'static>(NonNull<Entry<T>>);
+
+// keys are only read, and entries only freed, while the interner is locked
+unsafe impl<T: ?Sized + Ord + Send + Sync> Send for Key<T> {}
+
+impl<T: ?Sized + Ord> Borrow<T> for Key<T> {
+    fn borrow(&self) -> &T {
+        unsafe { &self.0.as_ref().value }
+    }
+}
+
+impl<T: ?Sized + Ord> PartialEq for Key<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: ?Sized + Ord> Eq for Key<T> {}
+
+impl<T: ?Sized + Ord> PartialOrd for Key<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: ?Sized + Ord> Ord for Key<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        Borrow::<T>::borrow(self).cmp(other.borrow())
+    }
+}
+
+/// a set of counted values, each of which is only ever allocated once at a time
+pub(crate) struct Interner<T: ?Sized + Ord + 'static> {
+    set: lock::Mutex<BTreeSet<Key<T>>>,
+}
+
+impl<T: ?Sized + Ord + 'static> Interner<T> {
+    pub(crate) const fn new() -> Self {
+        Self { set: lock::Mutex::new(BTreeSet::new()) }
+    }
+
+    /// the interned value equal to `value`, allocated by `to_owned` if there's none yet
+    pub(crate) fn intern(&'static self, value: &T, to_owned: impl FnOnce(&T) -> Box<T>) -> Interned<T> {
+        let mut set = lock::lock(&self.set);
+        if let Some(key) = set.get(value) {
+            return Self::refer(key);
+        }
+        let entry = Box::new(Entry { refs: AtomicUsize::new(1), interner: self, value: to_owned(value) });
+        let entry = NonNull::from(Box::leak(entry));
+        set.insert(Key(entry));
+        Interned(entry)
+    }
+
+    /// the interned value equal to `value`, if there is one
+    pub(crate) fn get(&'static self, value: &T) -> Option<Interned<T>> {
+        lock::lock(&self.set).get(value).map(Self::refer)
+    }
+
+    /// expects the interner to be locked, so the entry can't be freed
+    fn refer(key: &Key<T>) -> Interned<T> {
+        unsafe { key.0.as_ref() }.refs.fetch_add(1, atomic::Ordering::Relaxed);
+        Interned(key.0)
+    }
+}
+
+#[cfg(feature = "std")]
+mod lock {
+    pub(crate) use std::sync::{Mutex, MutexGuard};
+
+    pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+        // an interner is left consistent by a panic, which can only be of an allocation
+        mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// a spin lock, as without `std` there's no way to park a thread, which is good enough for the
+/// few instructions an interner holds it for
+#[cfg(not(feature = "std"))]
+mod lock {
+    use core::{
+        cell::UnsafeCell,
+        hint,
+        ops::{Deref, DerefMut},
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    pub(crate) struct Mutex<T> {
+        locked: AtomicBool,
+        value: UnsafeCell<T>,
+    }
+
+    // only one thread at a time has the value, while it holds the lock
+    unsafe impl<T: Send> Sync for Mutex<T> {}
+
+    impl<T> Mutex<T> {
+        pub(crate) const fn new(value: T) -> Self {
+            Self { locked: AtomicBool::new(false), value: UnsafeCell::new(value) }
+        }
+    }
+
+    pub(crate) struct MutexGuard<'m, T>(&'m Mutex<T>);
+
+    pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+        while mutex.locked.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            hint::spin_loop();
+        }
+        MutexGuard(mutex)
+    }
+
+    impl<T> Deref for MutexGuard<'_, T> {
+        type Target = T;
+        fn deref(&self) -> &T {
+            unsafe { &*self.0.value.get() }
+        }
+    }
+
+    impl<T> DerefMut for MutexGuard<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            unsafe { &mut *self.0.value.get() }
+        }
+    }
+
+    impl<T> Drop for MutexGuard<'_, T> {
+        fn drop(&mut self) {
+            self.0.locked.store(false, Ordering::Release);
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::Name;
+    use crate::symbol::Symbol;
+    use std::thread;
+
+    #[test]
+    fn interns() {
+        let a = Name::new("abc");
+        let b = Name::new(&String::from("abc"));
+        assert_eq!(a, b);
+        assert!(core::ptr::eq(a.as_str(), b.as_str()));
+        assert_ne!(a, Name::new("abd"));
+        assert!(a < Name::new("abd") && Name::new("") < a);
+        assert_eq!(a, "abc");
+    }
+
+    #[test]
+    fn frees_unreferred_names() {
+        let a = Name::new("unreferred");
+        let b = a.clone();
+        let symbol = Symbol::qualified("unreferred", "unreferred-too");
+        drop(a);
+        assert_eq!(Name::existing("unreferred"), Some(b.clone()));
+        drop((b, symbol.clone()));
+        assert!(Name::existing("unreferred").is_some());
+        drop(symbol);
+        assert_eq!(Name::existing("unreferred"), None);
+        assert_eq!(Name::existing("unreferred-too"), None);
+    }
+
+    #[test]
+    fn frees_across_threads() {
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                thread::spawn(|| {
+                    for n in 0..1000 {
+                        let name = Name::new(&format!("contended{}", n % 3));
+                        assert_eq!(Symbol::from_names(None, name.clone()).name(), &*name);
+                    }
+                })
+            })
+            .collect();
+        threads.into_iter().for_each(|thread| thread.join().unwrap());
+        assert!((0..3).all(|n| Name::existing(&format!("contended{n}")).is_none()));
+    }
+
+    #[test]
+    fn interns_across_threads() {
+        let threads: Vec<_> = (0..8)
+            .map(|_| thread::spawn(|| (0..100).map(|n| Name::new(&format!("t{n}"))).collect::<Vec<_>>()))
+            .collect();
+        let names: Vec<_> = threads.into_iter().map(|thread| thread.join().unwrap()).collect();
+        assert!(names.windows(2).all(|pair| pair[0] == pair[1]));
+    }
+}
@@ -0,0 +1,222 @@
+//! keywords as read, which name their namespace or leave it to be resolved, and as resolved
+
+use core::fmt;
+use crate::{intern::Name, ns::NsContext, symbol::Symbol};
+
+/// names are interned, so a keyword is cloned as counted pointers and compared by identity
+///
+/// Keywords beginning with `::` are auto-resolved: they stand for a qualified keyword once
+/// [`resolve`](Self::resolve)d in the namespace they were read in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Keyword {
+    /// Clojure example:
+    /// ```clojure
+    /// :foo
+    /// ```
+    Unqualified { name: Name },
+    //
+    /// Clojure example:
+    /// ```clojure
+    /// :foo/bar
+    /// ```
+    Qualified { namespace: Name, name: Name },
+    //
+    /// Clojure example:
+    /// ```clojure
+    /// (ns something.xyz)
+    /// ::foo
+    /// ```
+    SelfQualified { name: Name },
+    //
+    /// Clojure example:
+    /// ```clojure
+    /// (ns something.xyz (:require [something.abc :as abc]))
+    /// ::abc/foo
+    /// ```
+    AliasQualified { alias: Name, name: Name },
+}
+
+/// a keyword that isn't auto-resolved, which is all runtime values may hold: one made from a
+/// namespace and name, or by [`Keyword::resolve`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResolvedKeyword(Keyword);
+
+impl ResolvedKeyword {
+    pub fn unqualified<N: AsRef<str>>(name: N) -> Self {
+        Self(Keyword::unqualified(name))
+    }
+    pub fn qualified<NS: AsRef<str>, N: AsRef<str>>(namespace: NS, name: N) -> Self {
+        Self(Keyword::qualified(namespace, name))
+    }
+    /// `foo/bar` → `:foo/bar`
+    pub fn from_symbol(symbol: Symbol) -> Self {
+        Self(Keyword::from_symbol(symbol))
+    }
+    /// either [`Keyword::Unqualified`] or [`Keyword::Qualified`]
+    pub fn keyword(&self) -> &Keyword {
+        &self.0
+    }
+    pub fn name(&self) -> &str {
+        self.0.name()
+    }
+    pub fn try_namespace(&self) -> Option<&str> {
+        self.0.try_namespace()
+    }
+}
+
+impl From<ResolvedKeyword> for Keyword {
+    fn from(keyword: ResolvedKeyword) -> Self {
+        keyword.0
+    }
+}
+
+// constructors
+impl Keyword {
+    pub fn unqualified<N: AsRef<str>>(name: N) -> Self {
+        Self::Unqualified { name: Name::new(name.as_ref()) }
+    }
+    pub fn qualified<NS: AsRef<str>, N: AsRef<str>>(namespace: NS, name: N) -> Self {
+        Self::Qualified { namespace: Name::new(namespace.as_ref()), name: Name::new(name.as_ref()) }
+    }
+    pub fn self_qualified<N: AsRef<str>>(name: N) -> Self {
+        Self::SelfQualified { name: Name::new(name.as_ref()) }
+    }
+    pub fn alias_qualified<A: AsRef<str>, N: AsRef<str>>(alias: A, name: N) -> Self {
+        Self::AliasQualified { alias: Name::new(alias.as_ref()), name: Name::new(name.as_ref()) }
+    }
+    /// `foo/bar` → `:foo/bar`
+    pub fn from_symbol(symbol: Symbol) -> Self {
+        match symbol.names() {
+            (None, name) => Self::Unqualified { name },
+            (Some(namespace), name) => Self::Qualified { namespace, name },
+        }
+    }
+}
+
+// data readers
+impl Keyword {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Unqualified { name }
+            | Self::Qualified { name, .. }
+            | Self::SelfQualified { name }
+            | Self::AliasQualified { name, .. } => name.as_str(),
+        }
+    }
+    /// the namespace of a qualified keyword, which an auto-resolved one has none of until resolved
+    pub fn try_namespace(&self) -> Option<&str> {
+        match self {
+            Self::Qualified { namespace, .. } => Some(namespace.as_str()),
+            _ => None,
+        }
+    }
+    /// whether this keyword begins with `::`
+    pub fn is_auto_resolved(&self) -> bool {
+        matches!(self, Self::SelfQualified { .. } | Self::AliasQualified { .. })
+    }
+    /// itself, or none if it's auto-resolved and so has to be [`resolve`](Self::resolve)d
+    pub fn try_resolved(self) -> Option<ResolvedKeyword> {
+        match self.is_auto_resolved() {
+            true => None,
+            false => Some(ResolvedKeyword(self)),
+        }
+    }
+    /// `:foo/bar` → `foo/bar`, or none for an auto-resolved keyword
+    pub fn try_symbol(&self) -> Option<Symbol> {
+        match self {
+            Self::Unqualified { name } => Some(Symbol::from_names(None, name.clone())),
+            Self::Qualified { namespace, name } => Some(Symbol::from_names(Some(namespace.clone()), name.clone())),
+            Self::SelfQualified { .. } | Self::AliasQualified { .. } => None,
+        }
+    }
+}
+
+/// why an auto-resolved keyword couldn't be resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    /// the `abc` of `::abc/foo` isn't an alias in the namespace
+    UnknownAlias(Name),
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownAlias(alias) => write!(f, "no namespace is aliased as {alias}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ResolveError {}
+
+impl Keyword {
+    /// this keyword as read in the namespace `ns`, which is itself unless it's auto-resolved
+    ///
+    /// Clojure example:
+    /// ```clojure
+    /// (ns something.xyz (:require [something.abc :as abc]))
+    /// [::foo ::abc/foo :foo] ;=> [:something.xyz/foo :something.abc/foo :foo]
+    /// ```
+    pub fn resolve(self, ns: &NsContext) -> Result<ResolvedKeyword, ResolveError> {
+        let resolved = match self {
+            Self::SelfQualified { name } => Self::Qualified { namespace: ns.current().name(), name },
+            Self::AliasQualified { alias, name } => match ns.resolve_alias(&alias) {
+                Some(namespace) => Self::Qualified { namespace: namespace.name(), name },
+                None => return Err(ResolveError::UnknownAlias(alias)),
+            },
+            resolved => resolved,
+        };
+        Ok(ResolvedKeyword(resolved))
+    }
+}
+
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unqualified { name } => write!(f, ":{name}"),
+            Self::Qualified { namespace, name } => write!(f, ":{namespace}/{name}"),
+            Self::SelfQualified { name } => write!(f, "::{name}"),
+            Self::AliasQualified { alias, name } => write!(f, "::{alias}/{name}"),
+        }
+    }
+}
+
+impl fmt::Display for ResolvedKeyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::{Keyword, ResolveError, ResolvedKeyword};
+    use crate::{intern::Name, ns::NsContext, symbol::Symbol};
+
+    #[test]
+    fn resolves() {
+        let ns = NsContext::new("something.xyz".parse().unwrap())
+            .with_alias(Name::new("abc"), "something.abc".parse().unwrap());
+        let resolved = ResolvedKeyword::qualified("something.xyz", "foo");
+        assert_eq!(Keyword::self_qualified("foo").resolve(&ns), Ok(resolved.clone()));
+        assert_eq!(resolved.keyword(), &Keyword::qualified("something.xyz", "foo"));
+        assert_eq!(Keyword::alias_qualified("abc", "foo").resolve(&ns), Ok(ResolvedKeyword::qualified("something.abc", "foo")));
+        assert_eq!(Keyword::unqualified("foo").resolve(&ns), Ok(ResolvedKeyword::unqualified("foo")));
+        assert_eq!(Keyword::qualified("a", "foo").resolve(&ns), Ok(ResolvedKeyword::qualified("a", "foo")));
+        assert_eq!(
+            Keyword::alias_qualified("nope", "foo").resolve(&ns),
+            Err(ResolveError::UnknownAlias(Name::new("nope")))
+        );
+    }
+
+    #[test]
+    fn symbols() {
+        let keyword = Keyword::from_symbol(Symbol::qualified("a", "b"));
+        assert_eq!(keyword, Keyword::qualified("a", "b"));
+        assert_eq!(keyword.to_string(), ":a/b");
+        assert_eq!(keyword.try_symbol(), Some(Symbol::qualified("a", "b")));
+        assert_eq!(Keyword::alias_qualified("a", "b").try_symbol(), None);
+        assert_eq!(Keyword::alias_qualified("a", "b").to_string(), "::a/b");
+        assert_eq!(keyword.try_resolved(), Some(ResolvedKeyword::qualified("a", "b")));
+        assert_eq!(Keyword::self_qualified("b").try_resolved(), None);
+    }
+}
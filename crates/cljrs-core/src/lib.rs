@@ -1 +1,10 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod intern;
 pub mod symbol;
+pub mod ns;
+pub mod keyword;
+pub mod printer;
+pub mod pretty;
@@ -0,0 +1,165 @@
+//! namespace names, and where the sources defining them are found
+
+use alloc::{collections::BTreeMap, string::String};
+use core::{fmt, str::{FromStr, Split}};
+use crate::{intern::Name, symbol::{check_unqualified, Symbol, SymbolError}};
+
+/// the name of a namespace, an unqualified symbol of `.`-separated segments, none of them empty
+///
+/// Clojure example:
+/// ```clojure
+/// (ns clojure.string)
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NsName(Name);
+
+impl NsName {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+    pub fn name(&self) -> Name {
+        self.0.clone()
+    }
+    /// `clojure.string` → `["clojure", "string"]`
+    pub fn segments(&self) -> Split<'_, char> {
+        self.as_str().split('.')
+    }
+    /// the path of the source defining this namespace relative to a source root, with `/`
+    /// separators: each `.` begins a directory and `-` is replaced by `_`
+    ///
+    /// `foo-bar.baz` with extension `clj` → `foo_bar/baz.clj`
+    pub fn to_relative_path(&self, extension: &str) -> String {
+        let mut path = self.as_str().replace('-', "_").replace('.', "/");
+        path.push('.');
+        path.push_str(extension);
+        path
+    }
+    /// as [`to_relative_path`](Self::to_relative_path), with the platform's separators
+    #[cfg(feature = "std")]
+    pub fn to_path(&self, extension: &str) -> std::path::PathBuf {
+        let mut path: std::path::PathBuf = self.segments().map(|segment| segment.replace('-', "_")).collect();
+        path.set_extension(extension);
+        path
+    }
+}
+
+/// the namespace forms are read in, in which `::foo` and `::alias/foo` keywords are resolved
+///
+/// Clojure example:
+/// ```clojure
+/// (ns something.xyz (:require [something.abc :as abc]))
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NsContext {
+    current: NsName,
+    aliases: BTreeMap<Name, NsName>,
+}
+
+impl NsContext {
+    pub fn new(current: NsName) -> Self {
+        Self { current, aliases: BTreeMap::new() }
+    }
+    pub fn with_alias(mut self, alias: Name, ns: NsName) -> Self {
+        self.aliases.insert(alias, ns);
+        self
+    }
+    pub fn current(&self) -> &NsName {
+        &self.current
+    }
+    pub fn resolve_alias(&self, alias: &Name) -> Option<&NsName> {
+        self.aliases.get(alias)
+    }
+}
+
+/// Clojure example:
+/// ```clojure
+/// (ns clojure.string) ; but neither a/b, .a, a. nor a..b name a namespace
+/// ```
+impl FromStr for NsName {
+    type Err = SymbolError;
+    fn from_str(s: &str) -> Result<Self, SymbolError> {
+        if let Some(idx) = s.find('/') {
+            return Err(SymbolError::InvalidChar(idx, '/'));
+        }
+        check_unqualified(s)?;
+        // a `.` mustn't begin or end the name, nor follow another
+        let mut segment_begin = 0;
+        for (idx, _) in s.match_indices('.') {
+            if idx == segment_begin {
+                return Err(SymbolError::InvalidChar(idx, '.'));
+            }
+            segment_begin = idx + 1;
+        }
+        if segment_begin == s.len() {
+            return Err(SymbolError::InvalidChar(s.len() - 1, '.'));
+        }
+        Ok(Self(Name::new(s)))
+    }
+}
+
+impl TryFrom<&str> for NsName {
+    type Error = SymbolError;
+    fn try_from(s: &str) -> Result<Self, SymbolError> {
+        s.parse()
+    }
+}
+
+impl TryFrom<Symbol> for NsName {
+    type Error = SymbolError;
+    fn try_from(symbol: Symbol) -> Result<Self, SymbolError> {
+        match symbol.try_namespace() {
+            Some(namespace) => Err(SymbolError::InvalidChar(namespace.len(), '/')),
+            None => symbol.name().parse(),
+        }
+    }
+}
+
+impl From<NsName> for Symbol {
+    fn from(ns: NsName) -> Self {
+        Symbol::from_names(None, ns.0)
+    }
+}
+
+impl fmt::Debug for NsName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NsName").field(&self.0).finish()
+    }
+}
+
+impl fmt::Display for NsName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::NsName;
+    use crate::symbol::{Symbol, SymbolError};
+
+    #[test]
+    fn parses() {
+        let ns: NsName = "clojure.string".parse().unwrap();
+        assert_eq!(ns.segments().collect::<Vec<_>>(), ["clojure", "string"]);
+        assert_eq!(NsName::try_from("user").unwrap().segments().collect::<Vec<_>>(), ["user"]);
+        assert_eq!(NsName::try_from(Symbol::unqualified("a.b")), "a.b".parse());
+        assert_eq!(Symbol::from(ns), Symbol::unqualified("clojure.string"));
+
+        assert_eq!("".parse::<NsName>(), Err(SymbolError::Empty));
+        assert_eq!("a/b".parse::<NsName>(), Err(SymbolError::InvalidChar(1, '/')));
+        assert_eq!("/".parse::<NsName>(), Err(SymbolError::InvalidChar(0, '/')));
+        assert_eq!(".a".parse::<NsName>(), Err(SymbolError::InvalidChar(0, '.')));
+        assert_eq!("a.".parse::<NsName>(), Err(SymbolError::InvalidChar(1, '.')));
+        assert_eq!("a..b".parse::<NsName>(), Err(SymbolError::InvalidChar(2, '.')));
+        assert!("a.1b".parse::<NsName>().is_ok());
+        assert_eq!("1a".parse::<NsName>(), Err(SymbolError::InvalidChar(0, '1')));
+        assert_eq!(NsName::try_from(Symbol::qualified("a", "b")), Err(SymbolError::InvalidChar(1, '/')));
+    }
+
+    #[test]
+    fn paths() {
+        let ns: NsName = "foo-bar.baz".parse().unwrap();
+        assert_eq!(ns.to_relative_path("clj"), "foo_bar/baz.clj");
+        assert_eq!(ns.to_path("cljc"), ["foo_bar", "baz.cljc"].iter().collect::<std::path::PathBuf>());
+    }
+}
@@ -0,0 +1,371 @@
+//! a Wadler-style pretty printer: a value is first turned into a [`Doc`],
+//! which is then laid out in as few lines as fit within [`PrettyOptions::width`]
+
+use alloc::{borrow::ToOwned, boxed::Box, format, string::String, vec, vec::Vec};
+use crate::printer::PrintMode;
+
+/// what a piece of [`Doc::Text`] is, so a [`Theme`] can colour it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Highlight {
+    Delimiter,
+    Keyword,
+    Symbol,
+    Str,
+    Char,
+    Num,
+    /// `nil`, `true` and `false`
+    Literal,
+    /// `...` and `#` written in place of elided items and collections
+    Elision,
+    Comment,
+    ReaderMacro,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub enum Doc {
+    Text(String, Option<Highlight>),
+    /// a space if the enclosing [`Doc::Group`] fits on one line, otherwise a newline
+    Line,
+    Concat(Vec<Doc>),
+    /// indents lines broken within by the given number of columns (relative to the current indentation)
+    Nest(usize, Box<Doc>),
+    /// indents lines broken within to the column this [`Doc`] begins on
+    Align(Box<Doc>),
+    /// laid out on a single line if it fits, otherwise every [`Doc::Line`] directly within breaks
+    Group(Box<Doc>),
+}
+
+// constructors
+impl Doc {
+    pub fn text<S: Into<String>>(s: S) -> Self {
+        Self::Text(s.into(), None)
+    }
+    pub fn highlighted<S: Into<String>>(s: S, highlight: Highlight) -> Self {
+        Self::Text(s.into(), Some(highlight))
+    }
+    pub fn concat<I: IntoIterator<Item = Doc>>(docs: I) -> Self {
+        Self::Concat(docs.into_iter().collect())
+    }
+    pub fn nest(indent: usize, doc: Doc) -> Self {
+        Self::Nest(indent, Box::new(doc))
+    }
+    pub fn align(doc: Doc) -> Self {
+        Self::Align(Box::new(doc))
+    }
+    pub fn group(doc: Doc) -> Self {
+        Self::Group(Box::new(doc))
+    }
+    /// `docs` separated by `sep` followed by a [`Doc::Line`]
+    pub fn join<I: IntoIterator<Item = Doc>>(docs: I, sep: &str) -> Self {
+        let mut out = vec![];
+        for (idx, doc) in docs.into_iter().enumerate() {
+            if idx > 0 {
+                if !sep.is_empty() {
+                    out.push(Self::text(sep));
+                }
+                out.push(Self::Line);
+            }
+            out.push(doc);
+        }
+        Self::Concat(out)
+    }
+    /// Clojure-conventional collection layout, items aligned just beyond `open`:
+    /// ```clojure
+    /// [:a
+    ///  :b]
+    /// ```
+    pub fn collection<I: IntoIterator<Item = Doc>>(open: &str, items: I, sep: &str, close: &str) -> Self {
+        Self::group(Self::concat([
+            Self::highlighted(open, Highlight::Delimiter),
+            Self::align(Self::join(items, sep)),
+            Self::highlighted(close, Highlight::Delimiter),
+        ]))
+    }
+    /// the value goes on its own line, aligned with the key, when the entry does not fit on one
+    pub fn map_entry(key: Doc, value: Doc) -> Self {
+        Self::group(Self::concat([key, Self::Line, value]))
+    }
+}
+
+/// ANSI SGR parameters (e.g. `"1;34"` for bold blue) per [`Highlight`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub delimiter: &'static str,
+    pub keyword: &'static str,
+    pub symbol: &'static str,
+    pub string: &'static str,
+    pub char: &'static str,
+    pub number: &'static str,
+    pub literal: &'static str,
+    pub elision: &'static str,
+    pub comment: &'static str,
+    pub reader_macro: &'static str,
+    pub error: &'static str,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            delimiter: "2",
+            keyword: "35",
+            symbol: "",
+            string: "32",
+            char: "32",
+            number: "36",
+            literal: "33",
+            elision: "2",
+            comment: "2;3",
+            reader_macro: "34",
+            error: "1;31",
+        }
+    }
+}
+
+impl Theme {
+    pub fn sgr(&self, highlight: Highlight) -> &'static str {
+        match highlight {
+            Highlight::Delimiter => self.delimiter,
+            Highlight::Keyword => self.keyword,
+            Highlight::Symbol => self.symbol,
+            Highlight::Str => self.string,
+            Highlight::Char => self.char,
+            Highlight::Num => self.number,
+            Highlight::Literal => self.literal,
+            Highlight::Elision => self.elision,
+            Highlight::Comment => self.comment,
+            Highlight::ReaderMacro => self.reader_macro,
+            Highlight::Error => self.error,
+        }
+    }
+
+    pub fn paint(&self, s: &str, highlight: Highlight) -> String {
+        match self.sgr(highlight) {
+            "" => s.to_owned(),
+            sgr => format!("\x1b[{sgr}m{s}\x1b[0m"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PrettyOptions {
+    /// the column count lines should fit in
+    pub width: usize,
+    /// like Clojure's `*print-length*`, items of a collection beyond this many are written as `...`
+    pub print_length: Option<usize>,
+    /// like Clojure's `*print-level*`, collections nested deeper than this are written as `#`
+    pub print_level: Option<usize>,
+    pub mode: PrintMode,
+    pub theme: Option<Theme>,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            print_length: None,
+            print_level: None,
+            mode: PrintMode::Pr,
+            theme: None,
+        }
+    }
+}
+
+impl PrettyOptions {
+    pub fn with_width(self, width: usize) -> Self {
+        Self { width, ..self }
+    }
+    pub fn with_print_length(self, print_length: usize) -> Self {
+        Self { print_length: Some(print_length), ..self }
+    }
+    pub fn with_print_level(self, print_level: usize) -> Self {
+        Self { print_level: Some(print_level), ..self }
+    }
+    pub fn with_mode(self, mode: PrintMode) -> Self {
+        Self { mode, ..self }
+    }
+    pub fn with_theme(self, theme: Theme) -> Self {
+        Self { theme: Some(theme), ..self }
+    }
+
+    /// the items of a collection at `depth` (0 being top-level) to lay out, or `None` if
+    /// the whole collection is to be elided
+    pub fn truncate<T, I: IntoIterator<Item = T>>(&self, depth: usize, items: I) -> Option<(Vec<T>, bool)> {
+        if self.print_level.map(|level| depth >= level).unwrap_or(false) {
+            return None;
+        }
+        let mut items = items.into_iter();
+        let taken = match self.print_length {
+            Some(len) => items.by_ref().take(len).collect(),
+            None => items.by_ref().collect(),
+        };
+        let is_truncated = items.next().is_some();
+        Some((taken, is_truncated))
+    }
+}
+
+impl PrettyOptions {
+    /// [`Doc::collection`] of `items` at `depth`, truncated as per
+    /// [`PrettyOptions::print_length`] and [`PrettyOptions::print_level`]
+    pub fn collection_doc<T, I, F>(
+        &self,
+        depth: usize,
+        (open, sep, close): (&str, &str, &str),
+        items: I,
+        item_doc: F,
+    ) -> Doc
+    where
+        I: IntoIterator<Item = T>,
+        F: FnMut(T) -> Doc,
+    {
+        match self.truncate(depth, items) {
+            None => Doc::highlighted("#", Highlight::Elision),
+            Some((items, is_truncated)) => {
+                let mut docs = items.into_iter().map(item_doc).collect::<Vec<_>>();
+                if is_truncated {
+                    docs.push(Doc::highlighted("...", Highlight::Elision));
+                }
+                Doc::collection(open, docs, sep, close)
+            }
+        }
+    }
+}
+
+/// in [`Mode::Flat`] a [`Doc::Line`] is a space, in [`Mode::Break`] it is a newline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+fn text_width(s: &str) -> usize {
+    s.chars().count()
+}
+
+/// whether `doc` in [`Mode::Flat`], followed by `rest` up to its first line break, fits in `remaining` columns
+fn fits(remaining: usize, doc: &Doc, rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut remaining = remaining as isize;
+    let mut stack: Vec<(Mode, &Doc)> = vec![(Mode::Flat, doc)];
+    let mut rest = rest.iter().rev();
+    loop {
+        let (mode, doc) = match stack.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some((_, mode, doc)) => (*mode, *doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(s, _) => {
+                if let Some(idx) = s.find('\n') {
+                    return remaining >= text_width(&s[..idx]) as isize;
+                }
+                remaining -= text_width(s) as isize;
+            }
+            Doc::Line => match mode {
+                Mode::Flat => remaining -= 1,
+                Mode::Break => return true,
+            },
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (mode, doc))),
+            Doc::Nest(_, doc) | Doc::Align(doc) => stack.push((mode, doc)),
+            Doc::Group(doc) => stack.push((mode, doc)),
+        }
+        if remaining < 0 {
+            return false;
+        }
+    }
+}
+
+pub fn render(doc: &Doc, options: &PrettyOptions) -> String {
+    let mut out = String::new();
+    let mut col = 0;
+    let mut stack: Vec<(usize, Mode, &Doc)> = vec![(0, Mode::Break, doc)];
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(s, highlight) => {
+                match (&options.theme, highlight) {
+                    (Some(theme), Some(highlight)) => out.push_str(&theme.paint(s, *highlight)),
+                    _ => out.push_str(s),
+                }
+                col = match s.rfind('\n') {
+                    Some(idx) => text_width(&s[idx + 1..]),
+                    None => col + text_width(s),
+                };
+            }
+            Doc::Line => match mode {
+                Mode::Flat => {
+                    out.push(' ');
+                    col += 1;
+                }
+                Mode::Break => {
+                    out.push('\n');
+                    out.extend(core::iter::repeat_n(' ', indent));
+                    col = indent;
+                }
+            },
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Nest(n, doc) => stack.push((indent + n, mode, doc)),
+            Doc::Align(doc) => stack.push((col, mode, doc)),
+            Doc::Group(doc) => {
+                let mode = match mode {
+                    Mode::Flat => Mode::Flat,
+                    Mode::Break if fits(options.width.saturating_sub(col), doc, &stack) => Mode::Flat,
+                    Mode::Break => Mode::Break,
+                };
+                stack.push((indent, mode, doc));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod t {
+    use super::{render, Doc, Highlight, PrettyOptions, Theme};
+
+    fn vect(items: Vec<Doc>) -> Doc {
+        Doc::collection("[", items, "", "]")
+    }
+
+    fn nums(n: usize) -> Vec<Doc> {
+        (0..n).map(|n| Doc::text(n.to_string())).collect()
+    }
+
+    #[test]
+    fn fits_on_one_line() {
+        let doc = vect(nums(5));
+        assert_eq!(render(&doc, &PrettyOptions::default()), "[0 1 2 3 4]");
+    }
+
+    #[test]
+    fn breaks_aligned_to_opening_delimiter() {
+        let doc = vect(vec![Doc::text(":a"), vect(nums(3)), vect(nums(12))]);
+        let opts = PrettyOptions::default().with_width(20);
+        assert_eq!(
+            render(&doc, &opts),
+            "[:a\n [0 1 2]\n [0\n  1\n  2\n  3\n  4\n  5\n  6\n  7\n  8\n  9\n  10\n  11]]",
+        );
+    }
+
+    #[test]
+    fn closing_delimiters_count_towards_width() {
+        let doc = vect(vec![vect(nums(3))]);
+        assert_eq!(render(&doc, &PrettyOptions::default().with_width(9)), "[[0 1 2]]");
+        assert_eq!(render(&doc, &PrettyOptions::default().with_width(8)), "[[0\n  1\n  2]]");
+    }
+
+    #[test]
+    fn themed() {
+        let doc = Doc::highlighted(":a", Highlight::Keyword);
+        let opts = PrettyOptions::default().with_theme(Theme::default());
+        assert_eq!(render(&doc, &opts), "\x1b[35m:a\x1b[0m");
+    }
+
+    #[test]
+    fn truncate() {
+        let opts = PrettyOptions::default().with_print_length(2).with_print_level(1);
+        assert_eq!(opts.truncate(0, 0..5), Some((vec![0, 1], true)));
+        assert_eq!(opts.truncate(0, 0..2), Some((vec![0, 1], false)));
+        assert_eq!(opts.truncate(1, 0..2), None);
+    }
+}
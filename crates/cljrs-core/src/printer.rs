@@ -0,0 +1,168 @@
+use core::fmt;
+
+/// Clojure example:
+/// ```clojure
+/// (pr "a\nb")    ; prints "a\nb"
+/// (print "a\nb") ; prints a
+///                ;        b
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrintMode {
+    /// output can be read back into an equal value
+    #[default]
+    Pr,
+    /// output is meant for humans, strings and chars are written as-is
+    Print,
+}
+
+impl PrintMode {
+    pub fn is_readably(&self) -> bool {
+        matches!(self, Self::Pr)
+    }
+}
+
+pub fn write_str<W: fmt::Write>(w: &mut W, s: &str, mode: PrintMode) -> fmt::Result {
+    if !mode.is_readably() {
+        return w.write_str(s);
+    }
+    w.write_char('"')?;
+    for ch in s.chars() {
+        match ch {
+            '"' => w.write_str("\\\"")?,
+            '\\' => w.write_str("\\\\")?,
+            '\n' => w.write_str("\\n")?,
+            '\t' => w.write_str("\\t")?,
+            '\r' => w.write_str("\\r")?,
+            '\u{8}' => w.write_str("\\b")?,
+            '\u{c}' => w.write_str("\\f")?,
+            ch if ch.is_control() => write!(w, "\\u{:04x}", ch as u32)?,
+            ch => w.write_char(ch)?,
+        }
+    }
+    w.write_char('"')
+}
+
+/// the name a char literal is written with, e.g. `\newline`
+pub fn char_name(ch: char) -> Option<&'static str> {
+    match ch {
+        '\n' => Some("newline"),
+        ' ' => Some("space"),
+        '\t' => Some("tab"),
+        '\u{8}' => Some("backspace"),
+        '\u{c}' => Some("formfeed"),
+        '\r' => Some("return"),
+        _ => None,
+    }
+}
+
+/// inverse of [`char_name`], also accepts `uXXXX` and `oNNN`
+pub fn char_from_name(name: &str) -> Option<char> {
+    match name {
+        "newline" => Some('\n'),
+        "space" => Some(' '),
+        "tab" => Some('\t'),
+        "backspace" => Some('\u{8}'),
+        "formfeed" => Some('\u{c}'),
+        "return" => Some('\r'),
+        _ if name.len() == 5 && name.starts_with('u') => {
+            u32::from_str_radix(&name[1..], 16).ok().and_then(char::from_u32)
+        }
+        _ if (2..=4).contains(&name.len()) && name.starts_with('o') => u32::from_str_radix(&name[1..], 8)
+            .ok()
+            .filter(|n| *n <= 0o377)
+            .and_then(char::from_u32),
+        _ => None,
+    }
+}
+
+pub fn write_char<W: fmt::Write>(w: &mut W, ch: char, mode: PrintMode) -> fmt::Result {
+    if !mode.is_readably() {
+        return w.write_char(ch);
+    }
+    match char_name(ch) {
+        Some(name) => write!(w, "\\{name}"),
+        None if ch.is_control() || ch.is_whitespace() => {
+            write!(w, "\\u{:04x}", ch as u32)
+        }
+        None => write!(w, "\\{ch}"),
+    }
+}
+
+/// `1.0` stays `1.0` (not `1`) and non-finite numbers use the `##` symbolic values
+pub fn write_f64<W: fmt::Write>(w: &mut W, n: f64) -> fmt::Result {
+    if n.is_nan() {
+        w.write_str("##NaN")
+    } else if n == f64::INFINITY {
+        w.write_str("##Inf")
+    } else if n == f64::NEG_INFINITY {
+        w.write_str("##-Inf")
+    } else {
+        write!(w, "{n:?}")
+    }
+}
+
+/// inverse of the `##` cases of [`write_f64`], `name` excludes the leading `##`
+pub fn f64_from_symbolic_name(name: &str) -> Option<f64> {
+    match name {
+        "Inf" => Some(f64::INFINITY),
+        "-Inf" => Some(f64::NEG_INFINITY),
+        "NaN" => Some(f64::NAN),
+        _ => None,
+    }
+}
+
+/// writes `items` separated by `sep`, each via `write_item`
+pub fn write_joined<W, I, F>(w: &mut W, items: I, sep: &str, mut write_item: F) -> fmt::Result
+where
+    W: fmt::Write,
+    I: IntoIterator,
+    F: FnMut(&mut W, I::Item) -> fmt::Result,
+{
+    for (idx, item) in items.into_iter().enumerate() {
+        if idx > 0 {
+            w.write_str(sep)?;
+        }
+        write_item(w, item)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod t {
+    use super::{char_from_name, write_char, write_f64, write_str, PrintMode};
+
+    fn pr_str(s: &str) -> String {
+        let mut buf = String::new();
+        write_str(&mut buf, s, PrintMode::Pr).unwrap();
+        buf
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        assert_eq!(pr_str("a\"b\\c\nd"), r#""a\"b\\c\nd""#);
+        assert_eq!(pr_str("\u{1}"), r#""\u0001""#);
+    }
+
+    #[test]
+    fn chars_are_named_or_escaped() {
+        let mut buf = String::new();
+        for ch in ['a', '\n', ' ', '(', '\u{2003}'] {
+            write_char(&mut buf, ch, PrintMode::Pr).unwrap();
+            buf.push(' ');
+        }
+        assert_eq!(buf, r"\a \newline \space \( \u2003 ");
+        assert_eq!(char_from_name("u2003"), Some('\u{2003}'));
+        assert_eq!(char_from_name("o101"), Some('A'));
+        assert_eq!(char_from_name("nope"), None);
+    }
+
+    #[test]
+    fn floats_keep_their_point() {
+        let mut buf = String::new();
+        for n in [1.0, -0.5, 1e100, f64::NEG_INFINITY] {
+            write_f64(&mut buf, n).unwrap();
+            buf.push(' ');
+        }
+        assert_eq!(buf, "1.0 -0.5 1e100 ##-Inf ");
+    }
+}
@@ -1,65 +1,254 @@
-use std::fmt;
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub enum Symbol {
-    /// Clojure example:
-    /// ```clojure
-    /// foo
-    /// ```
-    Unqualified { name: String },
-    //
-    /// Clojure example:
-    /// ```clojure
-    /// foo/bar
-    /// ```
-    Qualified { namespace: String, name: String },
+use core::{cmp::Ordering, fmt, hash::{Hash, Hasher}, str::FromStr};
+use alloc::boxed::Box;
+use crate::intern::{Interned, Interner, Name};
+
+/// Clojure example:
+/// ```clojure
+/// foo
+/// foo/bar
+/// ```
+///
+/// Symbols are interned, so one is cloned as a counted pointer and compared and hashed by identity.
+/// Ordering is by namespace then name, with unqualified symbols first.
+#[derive(Clone)]
+pub struct Symbol(Interned<Parts>);
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Parts {
+    namespace: Option<Name>,
+    name: Name,
 }
 
+static SYMBOLS: Interner<Parts> = Interner::new();
+
 // constructors
 impl Symbol {
-    pub fn qualified<NS: Into<String>, N: Into<String>>(namespace: NS, name: N) -> Self {
-        let (namespace, name) = (namespace.into(), name.into());
-        Self::Qualified { namespace, name }
+    pub fn qualified<NS: AsRef<str>, N: AsRef<str>>(namespace: NS, name: N) -> Self {
+        Self::from_names(Some(Name::new(namespace.as_ref())), Name::new(name.as_ref()))
     }
-    pub fn unqualified<N: Into<String>>(name: N) -> Self {
-        let name = name.into();
-        Self::Unqualified { name }
+    pub fn unqualified<N: AsRef<str>>(name: N) -> Self {
+        Self::from_names(None, Name::new(name.as_ref()))
+    }
+    pub fn from_names(namespace: Option<Name>, name: Name) -> Self {
+        Self(SYMBOLS.intern(&Parts { namespace, name }, |parts| {
+            Box::new(Parts { namespace: parts.namespace.clone(), name: parts.name.clone() })
+        }))
     }
 }
 
 // data readers
 impl Symbol {
-    pub fn try_namespace(&self) -> Option<String> {
-        match self {
-            Self::Unqualified { .. } => None,
-            Self::Qualified { namespace, .. } => Some(namespace.clone()),
-        }
+    pub fn try_namespace(&self) -> Option<&str> {
+        self.0.namespace.as_deref()
     }
-    pub fn name(&self) -> String {
-        match self {
-            Self::Unqualified { name } => name.clone(),
-            Self::Qualified { name, .. } => name.clone(),
-        }
+    pub fn name(&self) -> &str {
+        self.0.name.as_str()
+    }
+    pub fn is_qualified(&self) -> bool {
+        self.0.namespace.is_some()
+    }
+    /// the interned namespace and name, for building keywords or other symbols from
+    pub fn names(&self) -> (Option<Name>, Name) {
+        (self.0.namespace.clone(), self.0.name.clone())
     }
 }
 
 // potentially panicing data readers
 impl Symbol {
     /// panics if this [`Symbol`] has no namespace
-    pub fn namespace(&self) -> String {
+    pub fn namespace(&self) -> &str {
         self.try_namespace().unwrap()
     }
 }
 
-impl fmt::Display for Symbol {
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+/// why a string isn't a [`Symbol`], or an [`NsName`](crate::ns::NsName)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolError {
+    /// the string, or its namespace or name, is empty, e.g. `"a/"`
+    Empty,
+    /// the char at a byte index can't be there, e.g. the `1` of `"1a"` or the second `/` of
+    /// `"a/b/c"`
+    InvalidChar(usize, char),
+    /// `nil`, `true` or `false`, which read as themselves rather than as symbols
+    Literal,
+}
+
+impl fmt::Display for SymbolError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Symbol::Unqualified { name } => name.to_owned(),
-                Symbol::Qualified { namespace, name } => format!("{namespace}/{name}"),
+        match self {
+            Self::Empty => write!(f, "empty symbol, namespace or name"),
+            Self::InvalidChar(idx, ch) => write!(f, "invalid char {ch:?} at byte {idx}"),
+            Self::Literal => write!(f, "nil, true and false aren't symbols"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SymbolError {}
+
+/// Clojure example:
+/// ```clojure
+/// (symbol "clojure.core/+") ; but "1a", "a/b/c", "a b" and ":a" aren't symbols
+/// ```
+impl FromStr for Symbol {
+    type Err = SymbolError;
+    fn from_str(s: &str) -> Result<Self, SymbolError> {
+        match s.find('/') {
+            Some(idx) if s != "/" => {
+                let (namespace, name) = (&s[..idx], &s[idx + 1..]);
+                check_part(namespace, 0)?;
+                // `clojure.core//` is the division fn
+                if name != "/" {
+                    check_part(name, idx + 1)?;
+                }
+                Ok(Self::qualified(namespace, name))
+            }
+            _ => {
+                check_unqualified(s)?;
+                Ok(Self::unqualified(s))
             }
-        )
+        }
+    }
+}
+
+impl TryFrom<&str> for Symbol {
+    type Error = SymbolError;
+    fn try_from(s: &str) -> Result<Self, SymbolError> {
+        s.parse()
+    }
+}
+
+/// checks the name of an unqualified symbol, which has no `/` unless it's `/` itself
+pub(crate) fn check_unqualified(s: &str) -> Result<(), SymbolError> {
+    match s {
+        "/" => Ok(()),
+        "nil" | "true" | "false" => Err(SymbolError::Literal),
+        _ => check_part(s, 0),
+    }
+}
+
+/// checks one side of a symbol's `/`, which begins at byte `offset` of the whole
+fn check_part(part: &str, offset: usize) -> Result<(), SymbolError> {
+    let invalid = |idx: usize, ch| Err(SymbolError::InvalidChar(offset + idx, ch));
+    let mut chars = part.char_indices().peekable();
+    let Some(&(_, first)) = chars.peek() else {
+        return Err(SymbolError::Empty);
+    };
+    // these begin numbers, keywords, dispatch macros and quotes
+    if first.is_ascii_digit() || matches!(first, ':' | '#' | '\'') {
+        return invalid(0, first);
+    }
+    if let ('+' | '-' | '.', Some((idx, second))) = (first, chars.clone().nth(1)) {
+        if second.is_ascii_digit() {
+            return invalid(idx, second);
+        }
+    }
+    while let Some((idx, ch)) = chars.next() {
+        let is_delimiter = ch.is_whitespace() || "()[]{}\"\\;,@^`~/".contains(ch);
+        // `:` can't end a symbol, nor be doubled within one
+        let is_bad_colon = ch == ':' && chars.peek().is_none_or(|&(_, next)| next == ':');
+        if is_delimiter || is_bad_colon {
+            return invalid(idx, ch);
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Symbol")
+            .field("namespace", &self.0.namespace)
+            .field("name", &self.0.name)
+            .finish()
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0.namespace {
+            None => write!(f, "{}", self.0.name),
+            Some(namespace) => write!(f, "{namespace}/{}", self.0.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::{Symbol, SymbolError};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn interns() {
+        let a = Symbol::qualified("a", "b");
+        assert_eq!(a, Symbol::qualified(String::from("a"), "b"));
+        assert_ne!(a, Symbol::unqualified("b"));
+        assert_ne!(a, Symbol::qualified("a", "c"));
+        assert_eq!((a.try_namespace(), a.name()), (Some("a"), "b"));
+        assert_eq!(Symbol::unqualified("b").try_namespace(), None);
+        assert_eq!(a.to_string(), "a/b");
+    }
+
+    #[test]
+    fn parses() {
+        assert_eq!("a".parse(), Ok(Symbol::unqualified("a")));
+        assert_eq!("clojure.core/+".parse(), Ok(Symbol::qualified("clojure.core", "+")));
+        assert_eq!("a.b/c->d?".parse(), Ok(Symbol::qualified("a.b", "c->d?")));
+        assert_eq!("/".parse(), Ok(Symbol::unqualified("/")));
+        assert_eq!("clojure.core//".parse(), Ok(Symbol::qualified("clojure.core", "/")));
+        assert_eq!(Symbol::try_from("-a'"), Ok(Symbol::unqualified("-a'")));
+        assert_eq!("a:b".parse(), Ok(Symbol::unqualified("a:b")));
+        assert_eq!("λ".parse(), Ok(Symbol::unqualified("λ")));
+        assert_eq!("a/nil".parse(), Ok(Symbol::qualified("a", "nil")));
+
+        assert_eq!("".parse::<Symbol>(), Err(SymbolError::Empty));
+        assert_eq!("a/".parse::<Symbol>(), Err(SymbolError::Empty));
+        assert_eq!("/a".parse::<Symbol>(), Err(SymbolError::Empty));
+        assert_eq!("nil".parse::<Symbol>(), Err(SymbolError::Literal));
+        assert_eq!("1a".parse::<Symbol>(), Err(SymbolError::InvalidChar(0, '1')));
+        assert_eq!("-1".parse::<Symbol>(), Err(SymbolError::InvalidChar(1, '1')));
+        assert_eq!(":a".parse::<Symbol>(), Err(SymbolError::InvalidChar(0, ':')));
+        assert_eq!("a b".parse::<Symbol>(), Err(SymbolError::InvalidChar(1, ' ')));
+        assert_eq!("a/b/c".parse::<Symbol>(), Err(SymbolError::InvalidChar(3, '/')));
+        assert_eq!("a/#b".parse::<Symbol>(), Err(SymbolError::InvalidChar(2, '#')));
+        assert_eq!("a:".parse::<Symbol>(), Err(SymbolError::InvalidChar(1, ':')));
+        assert_eq!("a::b".parse::<Symbol>(), Err(SymbolError::InvalidChar(1, ':')));
+        assert_eq!("a(".parse::<Symbol>(), Err(SymbolError::InvalidChar(1, '(')));
+    }
+
+    #[test]
+    fn orders() {
+        let sorted: Vec<_> = [Symbol::qualified("a", "b"), Symbol::unqualified("z"), Symbol::unqualified("c")]
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|sym| sym.to_string())
+            .collect();
+        assert_eq!(sorted, ["c", "z", "a/b"]);
     }
 }
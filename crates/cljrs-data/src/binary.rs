@@ -0,0 +1,610 @@
+//! a compact binary encoding of [`Value`]s, for sending them between processes
+//!
+//! A message is the magic bytes `CLJB`, a version byte, the keywords and then the symbols it
+//! contains (each once, in the order they first appear), and the value itself. Integers are
+//! LEB128 varints (zigzagged when signed), strings and collections are prefixed with their
+//! length, and keywords and symbols are indices into the tables. Equal values always encode to
+//! the same bytes.
+//!
+//! Decoding never panics: lengths, nesting and table sizes are checked against the input and
+//! [`DecodeLimits`]. [`decode_ref`] borrows strings from the input rather than copying them.
+
+use std::{collections::HashMap, fmt};
+use archery::SharedPointerKind;
+use cljrs_core::symbol::Symbol;
+use cljrs_reader::{keyword::Keyword, value::Value};
+
+pub const MAGIC: &[u8; 4] = b"CLJB";
+pub const VERSION: u8 = 1;
+
+const NIL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+/// a number that's an integer within ±2^53, as a zigzag varint
+const INT: u8 = 3;
+/// any other number, as its 8 little-endian IEEE 754 bytes
+const FLOAT: u8 = 4;
+const STR: u8 = 5;
+const CHAR: u8 = 6;
+const KEYWORD: u8 = 7;
+const SYMBOL: u8 = 8;
+const LIST: u8 = 9;
+const VECT: u8 = 10;
+const SET: u8 = 11;
+const MAP: u8 = 12;
+
+/// keyword and symbol table entry kinds
+const UNQUALIFIED: u8 = 0;
+const QUALIFIED: u8 = 1;
+const SELF_QUALIFIED: u8 = 2;
+const ALIAS_QUALIFIED: u8 = 3;
+
+const MAX_INT: f64 = (1u64 << 53) as f64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// of nested collections
+    pub max_depth: usize,
+    /// of strings in bytes, and of collections in elements
+    pub max_len: usize,
+    /// of the keyword and symbol tables, each
+    pub max_interned: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 512,
+            max_len: 16 * 1024 * 1024,
+            max_interned: 64 * 1024,
+        }
+    }
+}
+
+impl DecodeLimits {
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+    pub fn with_max_interned(mut self, max_interned: usize) -> Self {
+        self.max_interned = max_interned;
+        self
+    }
+}
+
+/// offsets are of the byte at which decoding failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinaryError {
+    /// the input doesn't begin with [`MAGIC`]
+    NotBinary,
+    /// a version other than [`VERSION`]
+    Version(u8),
+    Truncated(usize),
+    /// what was wrong, e.g. `"unknown tag 13"`
+    Invalid(usize, String),
+    /// which of the [`DecodeLimits`] was exceeded
+    Limit(usize, &'static str),
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotBinary => write!(f, "not a cljrs binary message"),
+            Self::Version(version) => write!(f, "unsupported binary version {version}"),
+            Self::Truncated(offset) => write!(f, "unexpected end of input at byte {offset}"),
+            Self::Invalid(offset, msg) => write!(f, "invalid input at byte {offset}: {msg}"),
+            Self::Limit(offset, limit) => write!(f, "{limit} exceeded at byte {offset}"),
+        }
+    }
+}
+
+impl std::error::Error for BinaryError {}
+
+/// a keyword or symbol as stored in a table, `qualifier` being the namespace or alias
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NameRef<'b> {
+    kind: u8,
+    pub qualifier: Option<&'b str>,
+    pub name: &'b str,
+}
+
+impl NameRef<'_> {
+    pub fn to_keyword(&self) -> Keyword {
+        let name = self.name;
+        match (self.kind, self.qualifier) {
+            (QUALIFIED, Some(ns)) => Keyword::qualified(ns, name),
+            (SELF_QUALIFIED, _) => Keyword::self_qualified(name),
+            (ALIAS_QUALIFIED, Some(alias)) => Keyword::alias_qualified(alias, name),
+            _ => Keyword::unqualified(name),
+        }
+    }
+    pub fn to_symbol(&self) -> Symbol {
+        match self.qualifier {
+            Some(ns) => Symbol::qualified(ns, self.name),
+            None => Symbol::unqualified(self.name),
+        }
+    }
+}
+
+/// a decoded value borrowing its strings from the input
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueRef<'b> {
+    Nil,
+    Bool(bool),
+    Num(f64),
+    Str(&'b str),
+    Char(char),
+    Keyword(NameRef<'b>),
+    Symbol(NameRef<'b>),
+    List(Vec<ValueRef<'b>>),
+    Vect(Vec<ValueRef<'b>>),
+    Set(Vec<ValueRef<'b>>),
+    Map(Vec<(ValueRef<'b>, ValueRef<'b>)>),
+}
+
+impl ValueRef<'_> {
+    pub fn to_value<P: SharedPointerKind>(&self) -> Value<P> {
+        let values = |refs: &[ValueRef]| refs.iter().map(|r| r.to_value()).collect::<Vec<_>>();
+        match self {
+            Self::Nil => Value::Nil,
+            Self::Bool(b) => Value::Bool(*b),
+            Self::Num(n) => Value::Num(*n),
+            Self::Str(s) => Value::string(s.to_string()),
+            Self::Char(ch) => Value::Char(*ch),
+            Self::Keyword(k) => Value::keyword(k.to_keyword()),
+            Self::Symbol(sym) => Value::symbol(sym.to_symbol()),
+            Self::List(refs) => Value::list_from_values(values(refs)),
+            Self::Vect(refs) => Value::vect_from_values(values(refs)),
+            Self::Set(refs) => Value::set_from_values(values(refs)),
+            Self::Map(entries) => Value::map_from_value_pairs(
+                entries.iter().map(|(k, v)| (k.to_value(), v.to_value())),
+            ),
+        }
+    }
+}
+
+/// Clojure example:
+/// ```clojure
+/// (nippy/freeze [:a :a]) ; the keyword's name is written once
+/// ```
+pub fn encode<P: SharedPointerKind>(value: &Value<P>) -> Vec<u8> {
+    let mut encoder = Encoder::default();
+    encoder.value(value);
+    let mut buf = Vec::with_capacity(encoder.body.len() + 16);
+    buf.extend_from_slice(MAGIC);
+    buf.push(VERSION);
+    for table in [&encoder.keywords, &encoder.symbols] {
+        write_uint(&mut buf, table.len() as u64);
+        let mut entries = table.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(_, idx)| **idx);
+        for (entry, _) in entries {
+            buf.push(entry.kind);
+            if let Some(qualifier) = entry.qualifier {
+                write_str(&mut buf, qualifier);
+            }
+            write_str(&mut buf, entry.name);
+        }
+    }
+    buf.extend_from_slice(&encoder.body);
+    buf
+}
+
+pub fn decode<P: SharedPointerKind>(bytes: &[u8], limits: &DecodeLimits) -> Result<Value<P>, BinaryError> {
+    Ok(decode_ref(bytes, limits)?.to_value())
+}
+
+pub fn decode_ref<'b>(bytes: &'b [u8], limits: &DecodeLimits) -> Result<ValueRef<'b>, BinaryError> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(BinaryError::NotBinary);
+    }
+    let mut decoder = Decoder {
+        bytes,
+        offset: MAGIC.len(),
+        limits,
+        keywords: vec![],
+        symbols: vec![],
+    };
+    match decoder.byte()? {
+        VERSION => {}
+        version => return Err(BinaryError::Version(version)),
+    }
+    decoder.keywords = decoder.table(&[UNQUALIFIED, QUALIFIED, SELF_QUALIFIED, ALIAS_QUALIFIED])?;
+    decoder.symbols = decoder.table(&[UNQUALIFIED, QUALIFIED])?;
+    let value = decoder.value(0)?;
+    match decoder.offset == bytes.len() {
+        true => Ok(value),
+        false => Err(decoder.invalid("trailing bytes")),
+    }
+}
+
+/// `-0.0` isn't, so that it reads back as itself
+fn is_int(n: f64) -> bool {
+    n.fract() == 0.0 && n.abs() <= MAX_INT && !(n == 0.0 && n.is_sign_negative())
+}
+
+fn write_uint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+    write_uint(buf, s.len() as u64);
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn name_ref(keyword: &Keyword) -> NameRef<'_> {
+    let (kind, qualifier, name) = match keyword {
+        Keyword::Unqualified { name } => (UNQUALIFIED, None, name),
+        Keyword::Qualified { namespace, name } => (QUALIFIED, Some(namespace.as_str()), name),
+        Keyword::SelfQualified { name } => (SELF_QUALIFIED, None, name),
+        Keyword::AliasQualified { alias, name } => (ALIAS_QUALIFIED, Some(alias.as_str()), name),
+    };
+    NameRef {
+        kind,
+        qualifier,
+        name,
+    }
+}
+
+fn symbol_ref(symbol: &Symbol) -> NameRef<'_> {
+    NameRef {
+        kind: if symbol.is_qualified() { QUALIFIED } else { UNQUALIFIED },
+        qualifier: symbol.try_namespace(),
+        name: symbol.name(),
+    }
+}
+
+#[derive(Default)]
+struct Encoder<'v> {
+    /// of entries to their indices
+    keywords: HashMap<NameRef<'v>, usize>,
+    symbols: HashMap<NameRef<'v>, usize>,
+    body: Vec<u8>,
+}
+
+impl<'v> Encoder<'v> {
+    fn interned(table: &mut HashMap<NameRef<'v>, usize>, entry: NameRef<'v>) -> u64 {
+        let next = table.len();
+        *table.entry(entry).or_insert(next) as u64
+    }
+
+    fn values<P: SharedPointerKind + 'v>(&mut self, tag: u8, values: impl ExactSizeIterator<Item = &'v Value<P>>) {
+        self.body.push(tag);
+        write_uint(&mut self.body, values.len() as u64);
+        for value in values {
+            self.value(value);
+        }
+    }
+
+    fn value<P: SharedPointerKind>(&mut self, value: &'v Value<P>) {
+        match value {
+            Value::Nil => self.body.push(NIL),
+            Value::Bool(false) => self.body.push(FALSE),
+            Value::Bool(true) => self.body.push(TRUE),
+            Value::Num(n) if is_int(*n) => {
+                self.body.push(INT);
+                let n = *n as i64;
+                write_uint(&mut self.body, ((n << 1) ^ (n >> 63)) as u64);
+            }
+            Value::Num(n) => {
+                self.body.push(FLOAT);
+                let n = if n.is_nan() { f64::NAN } else { *n };
+                self.body.extend_from_slice(&n.to_le_bytes());
+            }
+            Value::Str(s) => {
+                self.body.push(STR);
+                write_str(&mut self.body, s);
+            }
+            Value::Char(ch) => {
+                self.body.push(CHAR);
+                write_uint(&mut self.body, *ch as u64);
+            }
+            Value::Keyword(k) => {
+                self.body.push(KEYWORD);
+                let idx = Self::interned(&mut self.keywords, name_ref(k));
+                write_uint(&mut self.body, idx);
+            }
+            Value::Symbol(sym) => {
+                self.body.push(SYMBOL);
+                let idx = Self::interned(&mut self.symbols, symbol_ref(sym));
+                write_uint(&mut self.body, idx);
+            }
+            Value::List(values) => self.values(LIST, values.iter().map(|v| v.as_ref())),
+            Value::Vect(values) => self.values(VECT, values.iter().map(|v| v.as_ref())),
+            Value::Set(values) => self.values(SET, values.iter().map(|v| v.as_ref())),
+            Value::Map(entries) => {
+                self.body.push(MAP);
+                write_uint(&mut self.body, entries.len() as u64);
+                for (k, v) in entries {
+                    self.value(k);
+                    self.value(v);
+                }
+            }
+        }
+    }
+}
+
+struct Decoder<'b, 'l> {
+    bytes: &'b [u8],
+    offset: usize,
+    limits: &'l DecodeLimits,
+    keywords: Vec<NameRef<'b>>,
+    symbols: Vec<NameRef<'b>>,
+}
+
+impl<'b> Decoder<'b, '_> {
+    fn invalid(&self, msg: &str) -> BinaryError {
+        BinaryError::Invalid(self.offset, msg.to_owned())
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'b [u8], BinaryError> {
+        let end = self.offset.checked_add(n).filter(|end| *end <= self.bytes.len());
+        match end {
+            Some(end) => {
+                let taken = &self.bytes[self.offset..end];
+                self.offset = end;
+                Ok(taken)
+            }
+            None => Err(BinaryError::Truncated(self.bytes.len())),
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, BinaryError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn uint(&mut self) -> Result<u64, BinaryError> {
+        let start = self.offset;
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            let bits = (byte & 0x7f) as u64;
+            if bits << shift >> shift != bits {
+                break;
+            }
+            n |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        self.offset = start;
+        Err(self.invalid("varint overflows 64 bits"))
+    }
+
+    /// a length within `max_len` and, at `min_size` bytes per element, the remaining input
+    fn len(&mut self, min_size: usize) -> Result<usize, BinaryError> {
+        let start = self.offset;
+        let len = self.uint()?;
+        let len = match usize::try_from(len) {
+            Ok(len) if len <= self.limits.max_len => len,
+            _ => return Err(BinaryError::Limit(start, "max_len")),
+        };
+        match len.checked_mul(min_size) {
+            Some(size) if size <= self.bytes.len() - self.offset => Ok(len),
+            _ => Err(BinaryError::Truncated(self.bytes.len())),
+        }
+    }
+
+    fn str(&mut self) -> Result<&'b str, BinaryError> {
+        let len = self.len(1)?;
+        let start = self.offset;
+        let bytes = self.take(len)?;
+        std::str::from_utf8(bytes).map_err(|_| BinaryError::Invalid(start, "invalid UTF-8".to_owned()))
+    }
+
+    fn table(&mut self, kinds: &[u8]) -> Result<Vec<NameRef<'b>>, BinaryError> {
+        let start = self.offset;
+        // an entry is at least a kind and an empty name
+        let len = self.len(2)?;
+        if len > self.limits.max_interned {
+            return Err(BinaryError::Limit(start, "max_interned"));
+        }
+        let mut entries = Vec::with_capacity(len);
+        for _ in 0..len {
+            let kind = self.byte()?;
+            if !kinds.contains(&kind) {
+                self.offset -= 1;
+                return Err(self.invalid(&format!("unknown name kind {kind}")));
+            }
+            let qualifier = match kind {
+                QUALIFIED | ALIAS_QUALIFIED => Some(self.str()?),
+                _ => None,
+            };
+            let name = self.str()?;
+            entries.push(NameRef {
+                kind,
+                qualifier,
+                name,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// an entry in the keyword or symbol table
+    fn interned(&mut self, keyword: bool) -> Result<NameRef<'b>, BinaryError> {
+        let start = self.offset;
+        let idx = self.uint()?;
+        let table = if keyword { &self.keywords } else { &self.symbols };
+        let entry = usize::try_from(idx).ok().and_then(|idx| table.get(idx));
+        match entry {
+            Some(entry) => Ok(*entry),
+            None => Err(BinaryError::Invalid(start, format!("no table entry {idx}"))),
+        }
+    }
+
+    fn values(&mut self, depth: usize) -> Result<Vec<ValueRef<'b>>, BinaryError> {
+        let len = self.len(1)?;
+        let mut values = Vec::with_capacity(len);
+        for _ in 0..len {
+            values.push(self.value(depth + 1)?);
+        }
+        Ok(values)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<ValueRef<'b>, BinaryError> {
+        if depth > self.limits.max_depth {
+            return Err(BinaryError::Limit(self.offset, "max_depth"));
+        }
+        match self.byte()? {
+            LIST => Ok(ValueRef::List(self.values(depth)?)),
+            VECT => Ok(ValueRef::Vect(self.values(depth)?)),
+            SET => Ok(ValueRef::Set(self.values(depth)?)),
+            MAP => {
+                let len = self.len(2)?;
+                let mut entries = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let k = self.value(depth + 1)?;
+                    let v = self.value(depth + 1)?;
+                    entries.push((k, v));
+                }
+                Ok(ValueRef::Map(entries))
+            }
+            tag => self.scalar(tag),
+        }
+    }
+
+    /// kept out of [`Self::value`] so that deep nesting needs less stack
+    fn scalar(&mut self, tag: u8) -> Result<ValueRef<'b>, BinaryError> {
+        let start = self.offset;
+        Ok(match tag {
+            NIL => ValueRef::Nil,
+            FALSE => ValueRef::Bool(false),
+            TRUE => ValueRef::Bool(true),
+            INT => {
+                let zigzag = self.uint()?;
+                let n = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+                let n = n as f64;
+                if n.abs() > MAX_INT {
+                    return Err(BinaryError::Invalid(start, "integer out of range".to_owned()));
+                }
+                ValueRef::Num(n)
+            }
+            FLOAT => {
+                let mut bits = [0; 8];
+                bits.copy_from_slice(self.take(8)?);
+                ValueRef::Num(f64::from_le_bytes(bits))
+            }
+            STR => ValueRef::Str(self.str()?),
+            CHAR => {
+                let code = self.uint()?;
+                match u32::try_from(code).ok().and_then(char::from_u32) {
+                    Some(ch) => ValueRef::Char(ch),
+                    None => return Err(BinaryError::Invalid(start, format!("invalid char {code}"))),
+                }
+            }
+            KEYWORD => ValueRef::Keyword(self.interned(true)?),
+            SYMBOL => ValueRef::Symbol(self.interned(false)?),
+            tag => {
+                self.offset -= 1;
+                return Err(self.invalid(&format!("unknown tag {tag}")));
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::{decode, decode_ref, encode, BinaryError, DecodeLimits, ValueRef};
+    use archery::RcK;
+    use cljrs_reader::{reader, value::RcValue};
+
+    fn edn(src: &str) -> RcValue {
+        reader::<RcK>(src).unwrap().try_read_one().unwrap().unwrap().data
+    }
+
+    fn round_trip(value: &RcValue) -> RcValue {
+        decode(&encode(value), &DecodeLimits::default()).unwrap()
+    }
+
+    #[test]
+    fn round_trips() {
+        let value = edn(
+            r#"{:a [1 -2 2.5 -0.0 9007199254740993 ##Inf nil true false]
+                :b/c #{"str" \λ sym ns/sym}
+                ::d (::alias/e :a :b/c)
+                "" {[] ()}}"#,
+        );
+        assert_eq!(round_trip(&value), value);
+        match round_trip(&edn("[-0.0 ##NaN]")) {
+            RcValue::Vect(values) => {
+                assert!(matches!(values[0].as_ref(), RcValue::Num(n) if n.is_sign_negative()));
+                assert!(matches!(values[1].as_ref(), RcValue::Num(n) if n.is_nan()));
+            }
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn compact_and_deterministic() {
+        let value = edn("[:kw :kw sym 300 -1]");
+        let bytes = encode(&value);
+        assert_eq!(
+            bytes,
+            [
+                b'C', b'L', b'J', b'B', 1,
+                1, 0, 2, b'k', b'w', // keywords
+                1, 0, 3, b's', b'y', b'm', // symbols
+                10, 5, 7, 0, 7, 0, 8, 0, 3, 0xd8, 0x04, 3, 1,
+            ]
+        );
+        assert_eq!(encode(&edn("[:kw :kw sym 300 -1]")), bytes);
+    }
+
+    #[test]
+    fn strings_are_borrowed() {
+        let bytes = encode(&edn(r#"["hello" :kw]"#));
+        let value = decode_ref(&bytes, &DecodeLimits::default()).unwrap();
+        let ValueRef::Vect(values) = value else { panic!("{value:?}") };
+        let (ValueRef::Str(s), ValueRef::Keyword(k)) = (&values[0], &values[1]) else {
+            panic!("{values:?}")
+        };
+        assert!(bytes.as_ptr_range().contains(&s.as_ptr()));
+        assert!(bytes.as_ptr_range().contains(&k.name.as_ptr()));
+    }
+
+    #[test]
+    fn invalid_input() {
+        let limits = DecodeLimits::default();
+        let bytes = encode(&edn(r#"{:a ["b" c 1.5 \d]}"#));
+        for len in 0..bytes.len() {
+            assert!(decode_ref(&bytes[..len], &limits).is_err(), "{len}");
+        }
+        for idx in 0..bytes.len() {
+            for bit in 0..8 {
+                let mut flipped = bytes.clone();
+                flipped[idx] ^= 1 << bit;
+                let _ = decode_ref(&flipped, &limits);
+            }
+        }
+        assert_eq!(decode_ref(b"EDN", &limits), Err(BinaryError::NotBinary));
+        assert_eq!(decode_ref(b"CLJB\x02", &limits), Err(BinaryError::Version(2)));
+        let invalid = |offset, msg: &str| Err(BinaryError::Invalid(offset, msg.to_owned()));
+        assert_eq!(decode_ref(b"CLJB\x01\x00\x00\x07\x00", &limits), invalid(8, "no table entry 0"));
+        assert_eq!(decode_ref(b"CLJB\x01\x00\x00\x0a\xff\xff\x03", &limits), Err(BinaryError::Truncated(11)));
+        assert_eq!(decode_ref(b"CLJB\x01\x00\x00\x05\x01\xff", &limits), invalid(9, "invalid UTF-8"));
+        assert_eq!(decode_ref(b"CLJB\x01\x00\x00\x00\x00", &limits), invalid(8, "trailing bytes"));
+    }
+
+    #[test]
+    fn limits() {
+        let nested = (0..512).fold(RcValue::Nil, |value, _| RcValue::vect_from_values([value]));
+        let bytes = encode(&nested);
+        assert_eq!(decode(&bytes, &DecodeLimits::default()), Ok(nested));
+        let shallow = DecodeLimits::default().with_max_depth(10);
+        assert_eq!(decode_ref(&bytes, &shallow), Err(BinaryError::Limit(29, "max_depth")));
+
+        let bytes = encode(&edn(r#"["abcd"]"#));
+        let short = DecodeLimits::default().with_max_len(3);
+        assert_eq!(decode_ref(&bytes, &short), Err(BinaryError::Limit(10, "max_len")));
+        let bytes = encode(&edn("[:a :b]"));
+        let few = DecodeLimits::default().with_max_interned(1);
+        assert_eq!(decode_ref(&bytes, &few), Err(BinaryError::Limit(5, "max_interned")));
+    }
+}
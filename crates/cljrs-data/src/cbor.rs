@@ -0,0 +1,544 @@
+//! [CBOR](https://www.rfc-editor.org/rfc/rfc8949) to and from [`Value`]s
+//!
+//! Maps, vectors, strings, numbers, booleans and nil are plain CBOR. What CBOR has no type
+//! for is written with registered tags, as `clj-cbor` does, so it reads back as the same
+//! [`Value`] variant:
+//!
+//! | value         | CBOR                                             |
+//! |---------------|--------------------------------------------------|
+//! | `:a`, `::a/b` | tag 39 (identifier) `":a"`, `"::a/b"`            |
+//! | `a`, `a/b`    | tag 39 (identifier) `"a"`, `"a/b"`               |
+//! | `#{1}`        | tag 258 (set) `[1]`                              |
+//! | `(1)`, `\a`   | tag 27 (object) `["list", [1]]`, `["char", "a"]` |
+//!
+//! Tagged literals such as `#inst "..."` would be tag 27 `["inst", "..."]`, but the reader
+//! doesn't produce them yet so there is no [`Value`] to write or read them as.
+
+use std::{
+    fmt,
+    io::{self, Write},
+};
+use archery::SharedPointerKind;
+use cljrs_core::symbol::Symbol;
+use cljrs_reader::value::{Value, ValuePtr};
+use crate::UnknownTags;
+
+/// identifier, for keywords and symbols
+pub const TAG_IDENTIFIER: u64 = 39;
+/// mathematical finite set
+pub const TAG_SET: u64 = 258;
+/// generic object, `[type name, constructor args]`
+pub const TAG_OBJECT: u64 = 27;
+/// self-described CBOR, which is skipped when reading
+pub const TAG_SELF_DESCRIBED: u64 = 55799;
+
+const LIST_TYPE: &str = "list";
+const CHAR_TYPE: &str = "char";
+
+const UINT: u8 = 0;
+const NEG_INT: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+const SIMPLE: u8 = 7;
+/// additional info for indefinite lengths, and the "break" that ends them
+const INDEFINITE: u8 = 31;
+
+/// integers within ±2^53 are exactly representable as `f64`s
+const MAX_INT: f64 = (1u64 << 53) as f64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CborOptions {
+    pub unknown_tags: UnknownTags,
+    /// of nested arrays, maps and tags
+    pub max_depth: usize,
+}
+
+impl Default for CborOptions {
+    fn default() -> Self {
+        Self {
+            unknown_tags: UnknownTags::default(),
+            max_depth: 512,
+        }
+    }
+}
+
+impl CborOptions {
+    pub fn with_unknown_tags(mut self, unknown_tags: UnknownTags) -> Self {
+        self.unknown_tags = unknown_tags;
+        self
+    }
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+/// offsets are of the byte at which reading failed
+#[derive(Debug)]
+pub enum CborError {
+    Truncated(usize),
+    /// what was wrong, e.g. `"invalid UTF-8"`
+    Invalid(usize, String),
+    /// well-formed CBOR without a [`Value`] equivalent, such as byte strings
+    Unsupported(usize, String),
+    Io(io::Error),
+}
+
+impl From<io::Error> for CborError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl fmt::Display for CborError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated(offset) => write!(f, "unexpected end of CBOR at byte {offset}"),
+            Self::Invalid(offset, msg) => write!(f, "invalid CBOR at byte {offset}: {msg}"),
+            Self::Unsupported(offset, what) => write!(f, "unsupported CBOR at byte {offset}: {what}"),
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for CborError {}
+
+/// Clojure example:
+/// ```clojure
+/// (cbor/encode {:a #{1}}) ; {39(":a"): 258([1])}
+/// ```
+pub fn to_cbor_vec<P: SharedPointerKind>(value: &Value<P>) -> Vec<u8> {
+    let mut buf = vec![];
+    write_cbor(&mut buf, value).expect("writing to a Vec doesn't fail");
+    buf
+}
+
+pub fn write_cbor<P: SharedPointerKind, W: Write>(w: &mut W, value: &Value<P>) -> Result<(), CborError> {
+    let mut buf = vec![];
+    write_value(&mut buf, value);
+    w.write_all(&buf)?;
+    Ok(())
+}
+
+/// reads exactly one CBOR item, nothing may follow it
+pub fn from_cbor_slice<P: SharedPointerKind>(bytes: &[u8], options: &CborOptions) -> Result<Value<P>, CborError> {
+    let mut rdr = Reader {
+        bytes,
+        offset: 0,
+        options,
+    };
+    let value = rdr.read_value(0)?;
+    match rdr.offset == bytes.len() {
+        true => Ok(value),
+        false => Err(CborError::Invalid(rdr.offset, "trailing bytes".to_owned())),
+    }
+}
+
+fn write_head(buf: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+    match n {
+        0..=23 => buf.push(major | n as u8),
+        24..=0xff => buf.extend_from_slice(&[major | 24, n as u8]),
+        0x100..=0xffff => {
+            buf.push(major | 25);
+            buf.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            buf.push(major | 26);
+            buf.extend_from_slice(&(n as u32).to_be_bytes());
+        }
+        _ => {
+            buf.push(major | 27);
+            buf.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+}
+
+fn write_text(buf: &mut Vec<u8>, s: &str) {
+    write_head(buf, TEXT, s.len() as u64);
+    buf.extend_from_slice(s.as_bytes());
+}
+
+/// integers as integers, and other numbers as the shortest of `f32` and `f64` that's exact
+fn write_num(buf: &mut Vec<u8>, n: f64) {
+    let is_neg_zero = n == 0.0 && n.is_sign_negative();
+    if n.fract() == 0.0 && n.abs() <= MAX_INT && !is_neg_zero {
+        match n >= 0.0 {
+            true => write_head(buf, UINT, n as u64),
+            false => write_head(buf, NEG_INT, (-1.0 - n) as u64),
+        }
+    } else if (n as f32) as f64 == n || n.is_nan() {
+        buf.push(SIMPLE << 5 | 26);
+        buf.extend_from_slice(&(n as f32).to_be_bytes());
+    } else {
+        buf.push(SIMPLE << 5 | 27);
+        buf.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn write_object<F>(buf: &mut Vec<u8>, type_name: &str, write_args: F)
+where
+    F: FnOnce(&mut Vec<u8>),
+{
+    write_head(buf, TAG, TAG_OBJECT);
+    write_head(buf, ARRAY, 2);
+    write_text(buf, type_name);
+    write_args(buf);
+}
+
+fn write_values<P: SharedPointerKind>(buf: &mut Vec<u8>, values: &[ValuePtr<P>]) {
+    write_head(buf, ARRAY, values.len() as u64);
+    for value in values {
+        write_value(buf, value);
+    }
+}
+
+fn write_value<P: SharedPointerKind>(buf: &mut Vec<u8>, value: &Value<P>) {
+    match value {
+        Value::Nil => buf.push(SIMPLE << 5 | 22),
+        Value::Bool(false) => buf.push(SIMPLE << 5 | 20),
+        Value::Bool(true) => buf.push(SIMPLE << 5 | 21),
+        Value::Num(n) => write_num(buf, *n),
+        Value::Str(s) => write_text(buf, s),
+        Value::Char(ch) => write_object(buf, CHAR_TYPE, |buf| write_text(buf, ch.encode_utf8(&mut [0; 4]))),
+        Value::Keyword(k) => {
+            write_head(buf, TAG, TAG_IDENTIFIER);
+            write_text(buf, &k.to_string());
+        }
+        Value::Symbol(sym) => {
+            write_head(buf, TAG, TAG_IDENTIFIER);
+            write_text(buf, &sym.to_string());
+        }
+        Value::List(values) => write_object(buf, LIST_TYPE, |buf| write_values(buf, values)),
+        Value::Vect(values) => write_values(buf, values),
+        Value::Set(values) => {
+            write_head(buf, TAG, TAG_SET);
+            write_values(buf, values);
+        }
+        Value::Map(entries) => {
+            write_head(buf, MAP, entries.len() as u64);
+            for (k, v) in entries {
+                write_value(buf, k);
+                write_value(buf, v);
+            }
+        }
+    }
+}
+
+/// `:a`, `:a/b`, `::a` and `::a/b` are keywords, anything else a symbol
+fn from_identifier<P: SharedPointerKind>(s: &str) -> Value<P> {
+    fn split(s: &str) -> (Option<&str>, &str) {
+        match s.split_once('/') {
+            Some((ns, name)) if !ns.is_empty() && !name.is_empty() => (Some(ns), name),
+            _ => (None, s),
+        }
+    }
+    if let Some(s) = s.strip_prefix("::") {
+        return match split(s) {
+            (Some(alias), name) => Value::alias_qualified_keyword(alias.to_owned(), name.to_owned()),
+            (None, name) => Value::self_qualified_keyword(name.to_owned()),
+        };
+    }
+    if let Some(s) = s.strip_prefix(':') {
+        return match split(s) {
+            (Some(ns), name) => Value::qualified_keyword(ns.to_owned(), name.to_owned()),
+            (None, name) => Value::unqualified_keyword(name.to_owned()),
+        };
+    }
+    match split(s) {
+        (Some(ns), name) => Value::symbol(Symbol::qualified(ns, name)),
+        (None, name) => Value::symbol(Symbol::unqualified(name)),
+    }
+}
+
+/// IEEE 754 half precision
+fn f16_to_f64(half: u16) -> f64 {
+    let exp = (half >> 10) & 0x1f;
+    let mant = (half & 0x3ff) as f64;
+    let magnitude = match exp {
+        0 => mant * 2f64.powi(-24),
+        0x1f if mant == 0.0 => f64::INFINITY,
+        0x1f => f64::NAN,
+        exp => (mant + 1024.0) * 2f64.powi(exp as i32 - 25),
+    };
+    match half & 0x8000 {
+        0 => magnitude,
+        _ => -magnitude,
+    }
+}
+
+type Pair<P> = (Value<P>, Value<P>);
+
+/// an item's major type and argument, `None` for indefinite lengths
+struct Head {
+    major: u8,
+    info: u8,
+    arg: Option<u64>,
+}
+
+struct Reader<'b, 'o> {
+    bytes: &'b [u8],
+    offset: usize,
+    options: &'o CborOptions,
+}
+
+impl<'b> Reader<'b, '_> {
+    fn take(&mut self, n: usize) -> Result<&'b [u8], CborError> {
+        let end = self.offset.checked_add(n).filter(|end| *end <= self.bytes.len());
+        match end {
+            Some(end) => {
+                let taken = &self.bytes[self.offset..end];
+                self.offset = end;
+                Ok(taken)
+            }
+            None => Err(CborError::Truncated(self.bytes.len())),
+        }
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], CborError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn head(&mut self) -> Result<Head, CborError> {
+        let start = self.offset;
+        let initial = self.take_array::<1>()?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        let arg = match info {
+            0..=23 => Some(info as u64),
+            24 => Some(self.take_array::<1>()?[0] as u64),
+            25 => Some(u16::from_be_bytes(self.take_array()?) as u64),
+            26 => Some(u32::from_be_bytes(self.take_array()?) as u64),
+            27 => Some(u64::from_be_bytes(self.take_array()?)),
+            INDEFINITE if matches!(major, BYTES | TEXT | ARRAY | MAP | SIMPLE) => None,
+            _ => return Err(CborError::Invalid(start, format!("reserved additional info {info}"))),
+        };
+        Ok(Head { major, info, arg })
+    }
+
+    fn is_break(&self) -> bool {
+        self.bytes.get(self.offset) == Some(&(SIMPLE << 5 | INDEFINITE))
+    }
+
+    /// a definite length, which can't exceed the remaining bytes at `min_size` bytes per item
+    fn len(&self, arg: u64, min_size: u64) -> Result<usize, CborError> {
+        let remaining = (self.bytes.len() - self.offset) as u64;
+        match arg.checked_mul(min_size) {
+            Some(size) if size <= remaining => Ok(arg as usize),
+            _ => Err(CborError::Truncated(self.bytes.len())),
+        }
+    }
+
+    fn text(&mut self, arg: Option<u64>) -> Result<String, CborError> {
+        let Some(arg) = arg else {
+            // indefinite, a sequence of definite chunks
+            let mut s = String::new();
+            while !self.is_break() {
+                let chunk_start = self.offset;
+                match self.head()? {
+                    Head { major: TEXT, arg: Some(arg), .. } => s.push_str(&self.text(Some(arg))?),
+                    _ => return Err(CborError::Invalid(chunk_start, "expected a text chunk".to_owned())),
+                }
+            }
+            self.offset += 1;
+            return Ok(s);
+        };
+        let len = self.len(arg, 1)?;
+        let bytes_start = self.offset;
+        let bytes = self.take(len)?;
+        match std::str::from_utf8(bytes) {
+            Ok(s) => Ok(s.to_owned()),
+            Err(_) => Err(CborError::Invalid(bytes_start, "invalid UTF-8".to_owned())),
+        }
+    }
+
+    fn read_values<P: SharedPointerKind>(&mut self, arg: Option<u64>, depth: usize) -> Result<Vec<Value<P>>, CborError> {
+        let mut values = vec![];
+        match arg {
+            Some(arg) => {
+                let len = self.len(arg, 1)?;
+                values.reserve(len);
+                for _ in 0..len {
+                    values.push(self.read_value(depth + 1)?);
+                }
+            }
+            None => {
+                while !self.is_break() {
+                    values.push(self.read_value(depth + 1)?);
+                }
+                self.offset += 1;
+            }
+        }
+        Ok(values)
+    }
+
+    fn read_entries<P: SharedPointerKind>(&mut self, arg: Option<u64>, depth: usize) -> Result<Vec<Pair<P>>, CborError> {
+        let mut entries = vec![];
+        let mut read_entry = |rdr: &mut Self| -> Result<(), CborError> {
+            let k = rdr.read_value(depth + 1)?;
+            let v = rdr.read_value(depth + 1)?;
+            entries.push((k, v));
+            Ok(())
+        };
+        match arg {
+            Some(arg) => {
+                for _ in 0..self.len(arg, 2)? {
+                    read_entry(self)?;
+                }
+            }
+            None => {
+                while !self.is_break() {
+                    read_entry(self)?;
+                }
+                self.offset += 1;
+            }
+        }
+        Ok(entries)
+    }
+
+    fn read_tagged<P: SharedPointerKind>(&mut self, start: usize, tag: u64, depth: usize) -> Result<Value<P>, CborError> {
+        let content_start = self.offset;
+        let content = self.read_value(depth + 1)?;
+        let unexpected = |what: &str| Err(CborError::Invalid(content_start, format!("tag {tag} must be {what}")));
+        match (tag, content) {
+            (TAG_SELF_DESCRIBED, content) => Ok(content),
+            (TAG_IDENTIFIER, Value::Str(s)) => Ok(from_identifier(&s)),
+            (TAG_IDENTIFIER, _) => unexpected("a string"),
+            (TAG_SET, Value::Vect(values)) => Ok(Value::set_from_value_ptrs(values)),
+            (TAG_SET, _) => unexpected("an array"),
+            (TAG_OBJECT, Value::Vect(parts)) => match (parts.first().map(|p| p.as_ref()), parts.get(1).map(|p| p.as_ref()), parts.len()) {
+                (Some(Value::Str(type_name)), Some(Value::Vect(values)), 2) if type_name == LIST_TYPE => {
+                    Ok(Value::list_from_value_ptrs(values.iter().cloned()))
+                }
+                (Some(Value::Str(type_name)), Some(Value::Str(s)), 2) if type_name == CHAR_TYPE => {
+                    let mut chars = s.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(ch), None) => Ok(Value::Char(ch)),
+                        _ => unexpected("[\"char\", a one-char string]"),
+                    }
+                }
+                (Some(Value::Str(type_name)), _, 2) => {
+                    Err(CborError::Unsupported(start, format!("object of type {type_name}")))
+                }
+                _ => unexpected("[type name, constructor args]"),
+            },
+            (_, content) => match self.options.unknown_tags {
+                UnknownTags::Ignore => Ok(content),
+                UnknownTags::Error => Err(CborError::Unsupported(start, format!("tag {tag}"))),
+            },
+        }
+    }
+
+    fn read_value<P: SharedPointerKind>(&mut self, depth: usize) -> Result<Value<P>, CborError> {
+        let start = self.offset;
+        if depth > self.options.max_depth {
+            return Err(CborError::Invalid(start, "nested too deeply".to_owned()));
+        }
+        let head = self.head()?;
+        match (head.major, head.arg) {
+            (UINT, Some(n)) => Ok(Value::Num(n as f64)),
+            (NEG_INT, Some(n)) => Ok(Value::Num(-1.0 - n as f64)),
+            (BYTES, _) => Err(CborError::Unsupported(start, "byte string".to_owned())),
+            (TEXT, arg) => Ok(Value::string(self.text(arg)?)),
+            (ARRAY, arg) => Ok(Value::vect_from_values(self.read_values(arg, depth)?)),
+            (MAP, arg) => Ok(Value::map_from_value_pairs(self.read_entries(arg, depth)?)),
+            (TAG, Some(tag)) => self.read_tagged(start, tag, depth),
+            (SIMPLE, arg) => self.read_simple(start, head.info, arg),
+            _ => Err(CborError::Invalid(start, "indefinite length".to_owned())),
+        }
+    }
+
+    /// kept out of [`Self::read_value`] so that deep nesting needs less stack
+    fn read_simple<P: SharedPointerKind>(&mut self, start: usize, info: u8, arg: Option<u64>) -> Result<Value<P>, CborError> {
+        match (info, arg) {
+            (20, _) => Ok(Value::Bool(false)),
+            (21, _) => Ok(Value::Bool(true)),
+            // null and undefined
+            (22 | 23, _) => Ok(Value::Nil),
+            (25, Some(half)) => Ok(Value::Num(f16_to_f64(half as u16))),
+            (26, Some(single)) => Ok(Value::Num(f32::from_bits(single as u32) as f64)),
+            (27, Some(double)) => Ok(Value::Num(f64::from_bits(double))),
+            (INDEFINITE, _) => Err(CborError::Invalid(start, "unexpected break".to_owned())),
+            (_, value) => Err(CborError::Unsupported(start, format!("simple value {}", value.unwrap_or(0)))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::{from_cbor_slice, to_cbor_vec, CborError, CborOptions};
+    use crate::UnknownTags;
+    use archery::RcK;
+    use cljrs_reader::{printer::pr_str, reader, value::RcValue};
+
+    fn edn(src: &str) -> RcValue {
+        reader::<RcK>(src).unwrap().try_read_one().unwrap().unwrap().data
+    }
+
+    fn read(bytes: &[u8]) -> Result<RcValue, CborError> {
+        from_cbor_slice(bytes, &CborOptions::default())
+    }
+
+    #[test]
+    fn round_trips() {
+        let value = edn(
+            r#"{:a [1 -1 0.5 1.1 -0.0 ##Inf 1e300 nil true false]
+                :b/c #{"str" \λ sym ns/sym}
+                ::d (::alias/e ::f)
+                [1] {"" ()}}"#,
+        );
+        let bytes = to_cbor_vec(&value);
+        assert_eq!(pr_str(&read(&bytes).unwrap()), pr_str(&value));
+        assert_eq!(read(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn plain_cbor() {
+        // {"a": [1, -500, 1.5, "x"]} as any CBOR tool would write it
+        let plain = [0xa1, 0x61, b'a', 0x84, 0x01, 0x39, 0x01, 0xf3, 0xf9, 0x3e, 0x00, 0x61, b'x'];
+        let value = edn(r#"{"a" [1 -500 1.5 "x"]}"#);
+        assert_eq!(read(&plain).unwrap(), value);
+        // f16 1.5 is written as an f32
+        assert_eq!(to_cbor_vec(&value)[8..], [0xfa, 0x3f, 0xc0, 0x00, 0x00, 0x61, b'x']);
+        assert_eq!(to_cbor_vec(&edn(":a")), [0xd8, 39, 0x62, b':', b'a']);
+        assert_eq!(to_cbor_vec(&edn("#{}")), [0xd9, 0x01, 0x02, 0x80]);
+
+        // indefinite lengths and the self-described tag
+        let indefinite = [0xd9, 0xd9, 0xf7, 0x9f, 0x7f, 0x61, b'a', 0x61, b'b', 0xff, 0xbf, 0xff, 0xff];
+        assert_eq!(read(&indefinite).unwrap(), edn(r#"["ab" {}]"#));
+    }
+
+    #[test]
+    fn unknown_tags() {
+        // 0("2013-03-21T20:04:00Z")
+        let mut date = vec![0xc0, 0x74];
+        date.extend_from_slice(b"2013-03-21T20:04:00Z");
+        assert!(matches!(read(&date), Err(CborError::Unsupported(0, _))));
+        let ignore = CborOptions::default().with_unknown_tags(UnknownTags::Ignore);
+        let value: RcValue = from_cbor_slice(&date, &ignore).unwrap();
+        assert_eq!(value, edn(r#""2013-03-21T20:04:00Z""#));
+    }
+
+    #[test]
+    fn invalid_cbor() {
+        let bytes = to_cbor_vec(&edn(r#"{:a ["b" c 1.5 \d (1)]}"#));
+        for len in 0..bytes.len() {
+            assert!(read(&bytes[..len]).is_err(), "{len}");
+        }
+        assert!(matches!(read(&[0x9b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]), Err(CborError::Truncated(9))));
+        assert!(matches!(read(&[0x41, 0x00]), Err(CborError::Unsupported(0, _))));
+        assert!(matches!(read(&[0x61, 0xff]), Err(CborError::Invalid(1, _))));
+        assert!(matches!(read(&[0x1c]), Err(CborError::Invalid(0, _))));
+        assert!(matches!(read(&[0xff]), Err(CborError::Invalid(0, _))));
+        assert!(matches!(read(&[0xf6, 0xf6]), Err(CborError::Invalid(1, _))));
+        assert!(matches!(read(&[0xd8, 39, 0x01]), Err(CborError::Invalid(2, _))));
+        let deep = [[0x81; 512].as_slice(), &[0xf6]].concat();
+        assert!(read(&deep).is_ok());
+        let too_deep = CborOptions::default().with_max_depth(2);
+        assert!(from_cbor_slice::<RcK>(&[0x81, 0x81, 0x81, 0x01], &too_deep).is_err());
+    }
+}
@@ -0,0 +1,516 @@
+//! CSV, per RFC 4180, to and from vectors of maps
+//!
+//! The first record is the header, whose names become the keys of each following record's
+//! map, as keywords by default. Fields may be quoted with `"`, within which separators and line
+//! breaks are literal and `""` is a `"`. Reading streams: [`records`] and [`rows`] read a record
+//! at a time from any [`BufRead`].
+
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+    marker::PhantomData,
+};
+use archery::SharedPointerKind;
+use cljrs_reader::{printer::pr_str, value::Value};
+use crate::{json::JsonKeys, key_name};
+
+/// which columns' fields read as numbers where they look like them
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Numbers {
+    /// every field reads as a string
+    #[default]
+    None,
+    All,
+    /// the columns with these header names
+    Columns(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineTerminator {
+    /// as RFC 4180 says
+    #[default]
+    CrLf,
+    Lf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub separator: char,
+    /// what header names become
+    pub keys: JsonKeys,
+    /// coerced fields that are empty read as nil
+    pub numbers: Numbers,
+    /// when writing, which keys to write and in what order, by name (e.g. `"a"` for `:a`);
+    /// those of the first row by default
+    pub columns: Option<Vec<String>>,
+    /// when writing; either reads
+    pub line_terminator: LineTerminator,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            separator: ',',
+            keys: JsonKeys::Keywords { namespace: None },
+            numbers: Numbers::default(),
+            columns: None,
+            line_terminator: LineTerminator::default(),
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn with_separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+    pub fn with_keys(mut self, keys: JsonKeys) -> Self {
+        self.keys = keys;
+        self
+    }
+    pub fn with_numbers(mut self, numbers: Numbers) -> Self {
+        self.numbers = numbers;
+        self
+    }
+    pub fn with_columns<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.columns = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+    pub fn with_line_terminator(mut self, line_terminator: LineTerminator) -> Self {
+        self.line_terminator = line_terminator;
+        self
+    }
+}
+
+/// lines are 1-based, and those at which the offending record begins
+#[derive(Debug)]
+pub enum CsvError {
+    /// what was wrong, e.g. `"unclosed quote"`
+    Syntax(usize, String),
+    /// a record with more or fewer fields than the header
+    RowLength { line: usize, expected: usize, found: usize },
+    DuplicateColumn(String),
+    /// what can't be written as a row, e.g. `"[1 2]"`
+    Unrepresentable(String),
+    Io(io::Error),
+}
+
+impl From<io::Error> for CsvError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(line, msg) => write!(f, "invalid CSV on line {line}: {msg}"),
+            Self::RowLength { line, expected, found } => {
+                write!(f, "CSV record on line {line} has {found} fields, not {expected}")
+            }
+            Self::DuplicateColumn(name) => write!(f, "duplicate CSV column {name:?}"),
+            Self::Unrepresentable(what) => write!(f, "not representable as a CSV row: {what}"),
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// every record as its fields, including the header's
+pub fn records<R: BufRead>(rdr: R, options: &CsvOptions) -> Records<R> {
+    Records {
+        rdr,
+        separator: options.separator,
+        buf: String::new(),
+        lines_read: 0,
+        record_line: 0,
+    }
+}
+
+/// every record after the header as a map
+pub fn rows<P: SharedPointerKind, R: BufRead>(rdr: R, options: &CsvOptions) -> Rows<P, R> {
+    Rows {
+        records: records(rdr, options),
+        keys: options.keys.clone(),
+        numbers: options.numbers.clone(),
+        header: None,
+        _p: PhantomData,
+    }
+}
+
+/// Clojure example:
+/// ```clojure
+/// (read-csv "a,b\n1,x") ; [{:a "1", :b "x"}]
+/// ```
+pub fn from_csv_str<P: SharedPointerKind>(src: &str, options: &CsvOptions) -> Result<Value<P>, CsvError> {
+    let rows = rows(src.as_bytes(), options).collect::<Result<Vec<_>, _>>()?;
+    Ok(Value::vect_from_values(rows))
+}
+
+/// the maps as CSV, with a header
+///
+/// Keys that aren't columns aren't written, and columns a row doesn't have are empty. Nil is
+/// written as an empty field, keywords and symbols as their names, and collections as their
+/// `pr-str`.
+pub fn to_csv_string<'v, P, I>(rows: I, options: &CsvOptions) -> Result<String, CsvError>
+where
+    P: SharedPointerKind + 'v,
+    I: IntoIterator<Item = &'v Value<P>>,
+{
+    let mut writer = CsvWriter::new(vec![], options);
+    for row in rows {
+        writer.write_row(row)?;
+    }
+    let bytes = writer.finish()?;
+    Ok(String::from_utf8(bytes).expect("fields are strings"))
+}
+
+pub struct Records<R> {
+    rdr: R,
+    separator: char,
+    buf: String,
+    /// how many lines have been read
+    lines_read: usize,
+    /// the line at which the last record read began
+    record_line: usize,
+}
+
+impl<R: BufRead> Records<R> {
+    /// the line at which the last record read began
+    pub fn line(&self) -> usize {
+        self.record_line
+    }
+
+    fn read_line(&mut self) -> Result<bool, CsvError> {
+        let read = self.rdr.read_line(&mut self.buf)?;
+        self.lines_read += (read > 0) as usize;
+        Ok(read > 0)
+    }
+
+    fn read_record(&mut self) -> Result<Option<Vec<String>>, CsvError> {
+        // blank lines aren't records
+        loop {
+            self.buf.clear();
+            if !self.read_line()? {
+                return Ok(None);
+            }
+            if !self.buf.trim_end_matches(['\r', '\n']).is_empty() {
+                break;
+            }
+        }
+        self.record_line = self.lines_read;
+        let line = self.record_line;
+        let syntax = |msg: &str| Err(CsvError::Syntax(line, msg.to_owned()));
+
+        let mut fields = vec![];
+        let mut field = String::new();
+        let mut field_start = true;
+        let mut in_quotes = false;
+        let mut quoted = false;
+        let mut pos = 0;
+        loop {
+            let Some(ch) = self.buf[pos..].chars().next() else {
+                if in_quotes && self.read_line()? {
+                    continue;
+                }
+                if in_quotes {
+                    return syntax("unclosed quote");
+                }
+                break;
+            };
+            pos += ch.len_utf8();
+            if in_quotes {
+                match ch {
+                    '"' if self.buf[pos..].starts_with('"') => {
+                        field.push('"');
+                        pos += 1;
+                    }
+                    '"' => in_quotes = false,
+                    ch => field.push(ch),
+                }
+                continue;
+            }
+            match ch {
+                '"' if field_start => {
+                    in_quotes = true;
+                    quoted = true;
+                    field_start = false;
+                }
+                ch if ch == self.separator => {
+                    fields.push(std::mem::take(&mut field));
+                    field_start = true;
+                    quoted = false;
+                }
+                '\n' => break,
+                '\r' if self.buf[pos..].starts_with('\n') || pos == self.buf.len() => break,
+                '"' => return syntax("quote within an unquoted field"),
+                _ if quoted => return syntax("characters after a closing quote"),
+                ch => {
+                    field.push(ch);
+                    field_start = false;
+                }
+            }
+        }
+        fields.push(field);
+        Ok(Some(fields))
+    }
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = Result<Vec<String>, CsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+pub struct Rows<P: SharedPointerKind, R> {
+    records: Records<R>,
+    keys: JsonKeys,
+    numbers: Numbers,
+    /// each column's key, and whether its fields are coerced to numbers
+    header: Option<Vec<(Value<P>, bool)>>,
+    _p: PhantomData<P>,
+}
+
+impl<P: SharedPointerKind, R: BufRead> Rows<P, R> {
+    fn read_header(&mut self) -> Result<bool, CsvError> {
+        let Some(names) = self.records.next().transpose()? else {
+            return Ok(false);
+        };
+        let mut header = Vec::with_capacity(names.len());
+        for (idx, name) in names.iter().enumerate() {
+            if names[..idx].contains(name) {
+                return Err(CsvError::DuplicateColumn(name.clone()));
+            }
+            let coerced = match &self.numbers {
+                Numbers::None => false,
+                Numbers::All => true,
+                Numbers::Columns(columns) => columns.contains(name),
+            };
+            header.push((self.keys.key(name.clone()), coerced));
+        }
+        self.header = Some(header);
+        Ok(true)
+    }
+
+    fn read_row(&mut self) -> Result<Option<Value<P>>, CsvError> {
+        if self.header.is_none() && !self.read_header()? {
+            return Ok(None);
+        }
+        let Some(fields) = self.records.next().transpose()? else {
+            return Ok(None);
+        };
+        let header = self.header.as_ref().expect("the header was read");
+        if fields.len() != header.len() {
+            return Err(CsvError::RowLength {
+                line: self.records.line(),
+                expected: header.len(),
+                found: fields.len(),
+            });
+        }
+        let entries = header.iter().zip(fields).map(|((key, coerced), field)| {
+            let value = match coerced {
+                true if field.is_empty() => Value::Nil,
+                true => number(&field).map_or(Value::Str(field), Value::Num),
+                false => Value::Str(field),
+            };
+            (key.clone(), value)
+        });
+        Ok(Some(Value::map_from_value_pairs(entries)))
+    }
+}
+
+impl<P: SharedPointerKind, R: BufRead> Iterator for Rows<P, R> {
+    type Item = Result<Value<P>, CsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_row().transpose()
+    }
+}
+
+/// decimal numbers only, so e.g. `inf` stays a string
+fn number(field: &str) -> Option<f64> {
+    let decimal = field.bytes().any(|b| b.is_ascii_digit())
+        && field.bytes().all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b));
+    decimal.then(|| field.parse().ok()).flatten()
+}
+
+/// writes maps as CSV rows, after a header
+pub struct CsvWriter<W: Write> {
+    w: W,
+    separator: char,
+    line_terminator: &'static str,
+    /// from the options, or else the first row
+    columns: Option<Vec<String>>,
+    wrote_header: bool,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(w: W, options: &CsvOptions) -> Self {
+        Self {
+            w,
+            separator: options.separator,
+            line_terminator: match options.line_terminator {
+                LineTerminator::CrLf => "\r\n",
+                LineTerminator::Lf => "\n",
+            },
+            columns: options.columns.clone(),
+            wrote_header: false,
+        }
+    }
+
+    fn write_record<'f>(&mut self, fields: impl IntoIterator<Item = &'f str>) -> Result<(), CsvError> {
+        let mut line = String::new();
+        for (idx, field) in fields.into_iter().enumerate() {
+            if idx > 0 {
+                line.push(self.separator);
+            }
+            let needs_quotes = field.contains([self.separator, '"', '\r', '\n']);
+            match needs_quotes {
+                true => {
+                    line.push('"');
+                    line.push_str(&field.replace('"', "\"\""));
+                    line.push('"');
+                }
+                false => line.push_str(field),
+            }
+        }
+        line.push_str(self.line_terminator);
+        self.w.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    fn write_header(&mut self, columns: Vec<String>) -> Result<(), CsvError> {
+        self.write_record(columns.iter().map(String::as_str))?;
+        self.columns = Some(columns);
+        self.wrote_header = true;
+        Ok(())
+    }
+
+    /// writes the header first if this is the first row
+    pub fn write_row<P: SharedPointerKind>(&mut self, row: &Value<P>) -> Result<(), CsvError> {
+        let Value::Map(entries) = row else {
+            return Err(CsvError::Unrepresentable(pr_str(row)));
+        };
+        if !self.wrote_header {
+            let columns = match self.columns.take() {
+                Some(columns) => columns,
+                None => entries.iter().map(|(k, _)| key_name(k)).collect(),
+            };
+            self.write_header(columns)?;
+        }
+        let columns = self.columns.as_deref().unwrap_or_default();
+        let fields: Vec<String> = columns
+            .iter()
+            .map(|column| match entries.iter().find(|(k, _)| key_name(k) == *column) {
+                Some((_, v)) => field(v),
+                None => String::new(),
+            })
+            .collect();
+        self.write_record(fields.iter().map(String::as_str))
+    }
+
+    /// the writer, after writing the header if no rows were and there are columns, and flushing
+    pub fn finish(mut self) -> Result<W, CsvError> {
+        if let (false, Some(columns)) = (self.wrote_header, self.columns.take()) {
+            self.write_header(columns)?;
+        }
+        self.w.flush()?;
+        Ok(self.w)
+    }
+}
+
+fn field<P: SharedPointerKind>(value: &Value<P>) -> String {
+    match value {
+        Value::Nil => String::new(),
+        Value::Bool(b) => b.to_string(),
+        Value::Num(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => (*n as i64).to_string(),
+        Value::Num(n) => n.to_string(),
+        Value::Str(s) => s.clone(),
+        Value::Char(ch) => ch.to_string(),
+        Value::Keyword(..) | Value::Symbol(..) => key_name(value),
+        value => pr_str(value),
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::{from_csv_str, records, rows, to_csv_string, CsvError, CsvOptions, LineTerminator, Numbers};
+    use archery::RcK;
+    use cljrs_reader::{printer::pr_str, reader, value::RcValue};
+
+    fn edn(src: &str) -> RcValue {
+        reader::<RcK>(src).unwrap().try_read_one().unwrap().unwrap().data
+    }
+
+    fn csv_to_edn(src: &str, options: &CsvOptions) -> String {
+        pr_str(&from_csv_str::<RcK>(src, options).unwrap())
+    }
+
+    #[test]
+    fn quoting() {
+        let src = "a,b\r\n\"x, \"\"y\"\"\",\"two\r\nlines\"\r\n\r\n,é\n";
+        let fields: Vec<Vec<String>> = records(src.as_bytes(), &CsvOptions::default())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(fields, [vec!["a", "b"], vec!["x, \"y\"", "two\r\nlines"], vec!["", "é"]]);
+        assert_eq!(
+            csv_to_edn(src, &CsvOptions::default()),
+            r#"[{:a "x, \"y\"", :b "two\r\nlines"} {:a "", :b "é"}]"#
+        );
+    }
+
+    #[test]
+    fn options() {
+        let src = "id;name;score\n1;a;2.5\n2;b;\n";
+        let options = CsvOptions::default().with_separator(';');
+        assert_eq!(csv_to_edn(src, &options), r#"[{:id "1", :name "a", :score "2.5"} {:id "2", :name "b", :score ""}]"#);
+        let all = options.clone().with_numbers(Numbers::All);
+        assert_eq!(csv_to_edn(src, &all), r#"[{:id 1.0, :name "a", :score 2.5} {:id 2.0, :name "b", :score nil}]"#);
+        let some = options.with_numbers(Numbers::Columns(vec!["score".to_owned()]));
+        assert_eq!(csv_to_edn(src, &some), r#"[{:id "1", :name "a", :score 2.5} {:id "2", :name "b", :score nil}]"#);
+    }
+
+    #[test]
+    fn streams() {
+        let src = "n\n1\n2\nx,y\n";
+        let mut rows = rows::<RcK, _>(src.as_bytes(), &CsvOptions::default());
+        assert_eq!(pr_str(&rows.next().unwrap().unwrap()), r#"{:n "1"}"#);
+        assert_eq!(pr_str(&rows.next().unwrap().unwrap()), r#"{:n "2"}"#);
+        assert!(matches!(rows.next(), Some(Err(CsvError::RowLength { line: 4, expected: 1, found: 2 }))));
+    }
+
+    #[test]
+    fn errors() {
+        let err = |src: &str| from_csv_str::<RcK>(src, &CsvOptions::default()).unwrap_err();
+        assert!(matches!(err("a\n\"x\n"), CsvError::Syntax(2, _)));
+        assert!(matches!(err("a\nx\"y\n"), CsvError::Syntax(2, _)));
+        assert!(matches!(err("a\n\"x\"y\n"), CsvError::Syntax(2, _)));
+        assert!(matches!(err("a,a\n"), CsvError::DuplicateColumn(name) if name == "a"));
+    }
+
+    #[test]
+    fn writes() {
+        let rows = [
+            edn(r#"{:name "a, b", :n 1, :tags [:x]}"#),
+            edn(r#"{:n 2.5, :name "say \"hi\"", :extra true}"#),
+        ];
+        let csv = to_csv_string(&rows, &CsvOptions::default()).unwrap();
+        assert_eq!(csv, "name,n,tags\r\n\"a, b\",1,[:x]\r\n\"say \"\"hi\"\"\",2.5,\r\n");
+
+        let options = CsvOptions::default()
+            .with_columns(["n", "name"])
+            .with_line_terminator(LineTerminator::Lf);
+        let csv = to_csv_string(&rows, &options).unwrap();
+        assert_eq!(csv, "n,name\n1,\"a, b\"\n2.5,\"say \"\"hi\"\"\"\n");
+        let read = from_csv_str::<RcK>(&csv, &options.clone().with_numbers(Numbers::All)).unwrap();
+        assert_eq!(pr_str(&read), r#"[{:n 1.0, :name "a, b"} {:n 2.5, :name "say \"hi\""}]"#);
+        assert_eq!(to_csv_string::<RcK, _>([], &options).unwrap(), "n,name\n");
+    }
+}
@@ -0,0 +1,773 @@
+//! JSON text to and from [`Value`]s
+//!
+//! JSON has no sets, symbols, non-finite numbers or non-string keys, what happens to those
+//! when writing is chosen with [`Unrepresentable`]. Keywords are written as their name (without
+//! the leading `:`) and chars as one-char strings, as `cheshire` does.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, BufRead, BufReader, Read, Write},
+    marker::PhantomData,
+};
+use archery::SharedPointerKind;
+use cljrs_core::{printer::f64_from_symbolic_name, symbol::Symbol};
+use cljrs_reader::{
+    keyword::Keyword,
+    printer::pr_str,
+    value::{Value, ValuePtr},
+    ByteIndexSpan, DuplicateKeys, ReadError,
+};
+use crate::keyword_name;
+
+/// what JSON object keys become
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonKeys {
+    Strings,
+    /// `{"a": 1}` reads as `{:a 1}`, or `{:ns/a 1}` given a namespace
+    Keywords { namespace: Option<String> },
+}
+
+impl JsonKeys {
+    /// what a key named `name` reads as
+    pub(crate) fn key<P: SharedPointerKind>(&self, name: String) -> Value<P> {
+        match self {
+            Self::Strings => Value::Str(name),
+            Self::Keywords { namespace: None } => Value::Keyword(Keyword::unqualified(name)),
+            Self::Keywords { namespace: Some(namespace) } => {
+                Value::Keyword(Keyword::qualified(namespace, name))
+            }
+        }
+    }
+}
+
+/// what to write in place of a value JSON can't represent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Unrepresentable {
+    /// fail with [`JsonError::Unrepresentable`]
+    #[default]
+    Error,
+    /// write the value's `pr-str` as a JSON string, which does not read back as the value
+    Stringify,
+    /// write a single-entry object such as `{"#set": [1, 2]}`, which reads back as the value
+    /// when reading with this policy too (see [`TAGS`])
+    Tagged,
+}
+
+/// the keys of [`Unrepresentable::Tagged`] envelopes
+pub const TAGS: &[&str] = &[SET_TAG, SYMBOL_TAG, NUM_TAG, MAP_TAG];
+const SET_TAG: &str = "#set";
+const SYMBOL_TAG: &str = "#symbol";
+/// `##Inf`, `##-Inf` and `##NaN`
+const NUM_TAG: &str = "#num";
+/// a map with keys other than strings and keywords, as an array of `[key, value]` arrays
+const MAP_TAG: &str = "#map";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonOptions {
+    pub keys: JsonKeys,
+    pub unrepresentable: Unrepresentable,
+    /// repeated object keys are allowed (last wins) by default, as most JSON parsers do
+    pub duplicate_keys: DuplicateKeys,
+    /// of nested arrays and objects
+    pub max_depth: usize,
+}
+
+impl Default for JsonOptions {
+    fn default() -> Self {
+        Self {
+            keys: JsonKeys::Keywords { namespace: None },
+            unrepresentable: Unrepresentable::default(),
+            duplicate_keys: DuplicateKeys::LastWins,
+            max_depth: 512,
+        }
+    }
+}
+
+impl JsonOptions {
+    pub fn with_keys(mut self, keys: JsonKeys) -> Self {
+        self.keys = keys;
+        self
+    }
+    pub fn with_unrepresentable(mut self, unrepresentable: Unrepresentable) -> Self {
+        self.unrepresentable = unrepresentable;
+        self
+    }
+    pub fn with_duplicate_keys(mut self, duplicate_keys: DuplicateKeys) -> Self {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+#[derive(Debug)]
+pub enum JsonError {
+    /// invalid or incomplete JSON, spans are byte offsets into the JSON text
+    Read(ReadError),
+    Io(io::Error),
+    /// the `pr-str` of a value that can't be written under [`Unrepresentable::Error`]
+    Unrepresentable(String),
+}
+
+impl From<ReadError> for JsonError {
+    fn from(err: ReadError) -> Self {
+        Self::Read(err)
+    }
+}
+
+impl From<io::Error> for JsonError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(err) => write!(f, "invalid JSON: {err}"),
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Unrepresentable(value) => write!(f, "not representable as JSON: {value}"),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+/// Clojure example:
+/// ```clojure
+/// (json/read-str "{\"a\": [1, null]}") ;=> {:a [1.0 nil]}
+/// ```
+pub fn from_json_str<P: SharedPointerKind>(s: &str, options: &JsonOptions) -> Result<Value<P>, JsonError> {
+    from_json_reader(s.as_bytes(), options)
+}
+
+/// reads exactly one JSON value, only whitespace may follow it
+pub fn from_json_reader<P: SharedPointerKind, R: Read>(r: R, options: &JsonOptions) -> Result<Value<P>, JsonError> {
+    let mut parser = Parser::new(BufReader::new(r), options.clone());
+    let value = parser.parse_value(0)?;
+    parser.skip_whitespace()?;
+    match parser.peek()? {
+        None => Ok(value),
+        Some(_) => Err(ReadError::invalid_input((parser.offset, parser.offset)).into()),
+    }
+}
+
+/// Clojure example:
+/// ```clojure
+/// (json/write-str {:a [1 nil]}) ;=> "{\"a\":[1,null]}"
+/// ```
+pub fn to_json_string<P: SharedPointerKind>(value: &Value<P>, options: &JsonOptions) -> Result<String, JsonError> {
+    let mut buf = vec![];
+    write_json(&mut buf, value, options)?;
+    Ok(String::from_utf8(buf).expect("JSON is written as UTF-8"))
+}
+
+pub fn write_json<P: SharedPointerKind, W: Write>(w: &mut W, value: &Value<P>, options: &JsonOptions) -> Result<(), JsonError> {
+    write_value(w, value, options)
+}
+
+/// writes `values` as a JSON array one at a time, so they needn't all be held in memory
+pub fn write_json_array<'v, P, W, I>(w: &mut W, values: I, options: &JsonOptions) -> Result<(), JsonError>
+where
+    P: SharedPointerKind + 'v,
+    W: Write,
+    I: IntoIterator<Item = &'v Value<P>>,
+{
+    w.write_all(b"[")?;
+    for (idx, value) in values.into_iter().enumerate() {
+        if idx > 0 {
+            w.write_all(b",")?;
+        }
+        write_value(w, value, options)?;
+    }
+    w.write_all(b"]")?;
+    Ok(())
+}
+
+/// the elements of a top-level JSON array, read one at a time so the whole array needn't fit
+/// in memory
+///
+/// iteration stops after the first error
+pub struct JsonArrayReader<P: SharedPointerKind, R: Read> {
+    parser: Parser<BufReader<R>>,
+    state: ArrayState,
+    _values: PhantomData<P>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArrayState {
+    BeforeOpen,
+    BeforeFirst,
+    AfterElement,
+    Done,
+}
+
+impl<P: SharedPointerKind, R: Read> JsonArrayReader<P, R> {
+    pub fn new(r: R, options: &JsonOptions) -> Self {
+        Self {
+            parser: Parser::new(BufReader::new(r), options.clone()),
+            state: ArrayState::BeforeOpen,
+            _values: PhantomData,
+        }
+    }
+
+    fn try_next(&mut self) -> Result<Option<Value<P>>, JsonError> {
+        let parser = &mut self.parser;
+        parser.skip_whitespace()?;
+        if self.state == ArrayState::BeforeOpen {
+            parser.expect(b'[')?;
+            parser.skip_whitespace()?;
+            self.state = ArrayState::BeforeFirst;
+        }
+        match (self.state, parser.peek()?) {
+            (ArrayState::Done, _) => return Ok(None),
+            (_, Some(b']')) => {
+                parser.bump()?;
+                parser.skip_whitespace()?;
+                return match parser.peek()? {
+                    None => Ok(None),
+                    Some(_) => Err(ReadError::invalid_input((parser.offset, parser.offset)).into()),
+                };
+            }
+            (ArrayState::AfterElement, _) => {
+                parser.expect(b',')?;
+            }
+            _ => {}
+        }
+        let value = parser.parse_value(1)?;
+        self.state = ArrayState::AfterElement;
+        Ok(Some(value))
+    }
+}
+
+impl<P: SharedPointerKind, R: Read> Iterator for JsonArrayReader<P, R> {
+    type Item = Result<Value<P>, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.try_next().transpose();
+        if !matches!(next, Some(Ok(..))) {
+            self.state = ArrayState::Done;
+        }
+        next
+    }
+}
+
+struct Parser<R: BufRead> {
+    input: R,
+    /// of the next byte
+    offset: usize,
+    options: JsonOptions,
+}
+
+fn is_number_byte(b: u8) -> bool {
+    b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E')
+}
+
+/// RFC 8259's `number` grammar, which is stricter than `f64::from_str`
+fn is_json_number(s: &str) -> bool {
+    let digits = |s: &str| s.len() - s.trim_start_matches(|ch: char| ch.is_ascii_digit()).len();
+    let s = s.strip_prefix('-').unwrap_or(s);
+    let int_len = digits(s);
+    if int_len == 0 || (int_len > 1 && s.starts_with('0')) {
+        return false;
+    }
+    let mut rest = &s[int_len..];
+    if let Some(frac) = rest.strip_prefix('.') {
+        let frac_len = digits(frac);
+        if frac_len == 0 {
+            return false;
+        }
+        rest = &frac[frac_len..];
+    }
+    if let Some(exp) = rest.strip_prefix(['e', 'E']) {
+        let exp = exp.strip_prefix(['+', '-']).unwrap_or(exp);
+        let exp_len = digits(exp);
+        if exp_len == 0 {
+            return false;
+        }
+        rest = &exp[exp_len..];
+    }
+    rest.is_empty()
+}
+
+impl<R: BufRead> Parser<R> {
+    fn new(input: R, options: JsonOptions) -> Self {
+        Self {
+            input,
+            offset: 0,
+            options,
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, JsonError> {
+        Ok(self.input.fill_buf()?.first().copied())
+    }
+
+    fn bump(&mut self) -> Result<Option<u8>, JsonError> {
+        let b = self.peek()?;
+        if b.is_some() {
+            self.input.consume(1);
+            self.offset += 1;
+        }
+        Ok(b)
+    }
+
+    /// the span of an error at the end of the input
+    fn insufficient(&self, begin: usize) -> JsonError {
+        ReadError::insufficient_input((begin, self.offset.saturating_sub(1).max(begin))).into()
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), JsonError> {
+        let offset = self.offset;
+        match self.bump()? {
+            Some(b) if b == expected => Ok(()),
+            Some(_) => Err(ReadError::invalid_input((offset, offset)).into()),
+            None => Err(self.insufficient(offset)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), JsonError> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek()? {
+            self.bump()?;
+        }
+        Ok(())
+    }
+
+    fn parse_value<P: SharedPointerKind>(&mut self, depth: usize) -> Result<Value<P>, JsonError> {
+        self.skip_whitespace()?;
+        let begin = self.offset;
+        if depth > self.options.max_depth {
+            return Err(ReadError::invalid_input((begin, begin)).into());
+        }
+        match self.peek()? {
+            None => Err(self.insufficient(begin)),
+            Some(b'{') => self.parse_object(depth),
+            Some(b'[') => {
+                self.bump()?;
+                let mut value_ptrs = vec![];
+                self.skip_whitespace()?;
+                if self.peek()? == Some(b']') {
+                    self.bump()?;
+                    return Ok(Value::vect_from_value_ptrs(value_ptrs));
+                }
+                loop {
+                    value_ptrs.push(ValuePtr::from(self.parse_value(depth + 1)?));
+                    self.skip_whitespace()?;
+                    let offset = self.offset;
+                    match self.bump()? {
+                        Some(b',') => {}
+                        Some(b']') => return Ok(Value::vect_from_value_ptrs(value_ptrs)),
+                        Some(_) => return Err(ReadError::invalid_input((offset, offset)).into()),
+                        None => return Err(self.insufficient(begin)),
+                    }
+                }
+            }
+            Some(b'"') => Ok(Value::Str(self.parse_string()?)),
+            Some(b'-' | b'0'..=b'9') => {
+                let mut token = String::new();
+                while let Some(b) = self.peek()?.filter(|b| is_number_byte(*b)) {
+                    token.push(b as char);
+                    self.bump()?;
+                }
+                let span = (begin, self.offset - 1);
+                match is_json_number(&token) {
+                    true => token.parse().map(Value::Num).map_err(|_| ReadError::invalid_input(span).into()),
+                    false => Err(ReadError::invalid_input(span).into()),
+                }
+            }
+            Some(b't') => self.parse_literal(b"true", Value::Bool(true)),
+            Some(b'f') => self.parse_literal(b"false", Value::Bool(false)),
+            Some(b'n') => self.parse_literal(b"null", Value::Nil),
+            Some(_) => Err(ReadError::invalid_input((begin, begin)).into()),
+        }
+    }
+
+    fn parse_literal<P: SharedPointerKind>(&mut self, literal: &[u8], value: Value<P>) -> Result<Value<P>, JsonError> {
+        let begin = self.offset;
+        for expected in literal {
+            let offset = self.offset;
+            match self.bump()? {
+                Some(b) if b == *expected => {}
+                Some(_) => return Err(ReadError::invalid_input((begin, offset)).into()),
+                None => return Err(self.insufficient(begin)),
+            }
+        }
+        Ok(value)
+    }
+
+    fn parse_hex4(&mut self, escape_begin: usize) -> Result<u32, JsonError> {
+        let mut n = 0;
+        for _ in 0..4 {
+            let digit = match self.bump()? {
+                Some(b) => (b as char).to_digit(16),
+                None => return Err(self.insufficient(escape_begin)),
+            };
+            match digit {
+                Some(digit) => n = n * 16 + digit,
+                None => return Err(ReadError::invalid_input((escape_begin, self.offset - 1)).into()),
+            }
+        }
+        Ok(n)
+    }
+
+    fn parse_string(&mut self) -> Result<String, JsonError> {
+        let begin = self.offset;
+        self.expect(b'"')?;
+        let mut buf = vec![];
+        loop {
+            let offset = self.offset;
+            match self.bump()? {
+                None => return Err(self.insufficient(begin)),
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    let escaped = match self.bump()? {
+                        None => return Err(self.insufficient(begin)),
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let mut code = self.parse_hex4(offset)?;
+                            if (0xD800..0xDC00).contains(&code) {
+                                // a high surrogate must be followed by an escaped low one
+                                self.expect(b'\\')?;
+                                self.expect(b'u')?;
+                                let low = self.parse_hex4(offset)?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(ReadError::invalid_input((offset, self.offset - 1)).into());
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            char::from_u32(code)
+                                .ok_or(ReadError::invalid_input((offset, self.offset - 1)))?
+                        }
+                        Some(_) => return Err(ReadError::invalid_input((offset, self.offset - 1)).into()),
+                    };
+                    let mut utf8 = [0; 4];
+                    buf.extend_from_slice(escaped.encode_utf8(&mut utf8).as_bytes());
+                }
+                Some(b) if b < 0x20 => return Err(ReadError::invalid_input((offset, offset)).into()),
+                Some(b) => buf.push(b),
+            }
+        }
+        String::from_utf8(buf).map_err(|_| ReadError::invalid_input((begin, self.offset - 1)).into())
+    }
+
+    fn parse_object<P: SharedPointerKind>(&mut self, depth: usize) -> Result<Value<P>, JsonError> {
+        let begin = self.offset;
+        self.expect(b'{')?;
+        // keys as written, with their spans
+        let mut entries: Vec<(String, ByteIndexSpan, Value<P>)> = vec![];
+        let mut idxs_by_key: HashMap<String, usize> = HashMap::new();
+        self.skip_whitespace()?;
+        if self.peek()? == Some(b'}') {
+            self.bump()?;
+            return Ok(Value::empty_map());
+        }
+        loop {
+            self.skip_whitespace()?;
+            let key_begin = self.offset;
+            match self.peek()? {
+                Some(b'"') => {}
+                Some(_) => return Err(ReadError::invalid_input((key_begin, key_begin)).into()),
+                None => return Err(self.insufficient(begin)),
+            }
+            let key = self.parse_string()?;
+            let key_span = (key_begin, self.offset - 1);
+            self.skip_whitespace()?;
+            self.expect(b':')?;
+            let value = self.parse_value(depth + 1)?;
+            match idxs_by_key.get(&key) {
+                Some(&idx) => match self.options.duplicate_keys {
+                    DuplicateKeys::Reject => return Err(ReadError::duplicate_key(entries[idx].1, key_span).into()),
+                    DuplicateKeys::LastWins => entries[idx].2 = value,
+                },
+                None => {
+                    idxs_by_key.insert(key.clone(), entries.len());
+                    entries.push((key, key_span, value));
+                }
+            }
+            self.skip_whitespace()?;
+            let offset = self.offset;
+            match self.bump()? {
+                Some(b',') => {}
+                Some(b'}') => break,
+                Some(_) => return Err(ReadError::invalid_input((offset, offset)).into()),
+                None => return Err(self.insufficient(begin)),
+            }
+        }
+
+        if self.options.unrepresentable == Unrepresentable::Tagged && entries.len() == 1 {
+            let (tag, _, value) = &entries[0];
+            if let Some(value) = untag(tag, value) {
+                return value.ok_or(ReadError::invalid_input((begin, self.offset - 1)).into());
+            }
+        }
+
+        let keys = &self.options.keys;
+        Ok(Value::map_from_value_pairs(entries.into_iter().map(|(k, _, v)| (keys.key(k), v))))
+    }
+}
+
+/// `None` if `tag` isn't one of [`TAGS`], `Some(None)` if it is but `value` doesn't fit it
+fn untag<P: SharedPointerKind>(tag: &str, value: &Value<P>) -> Option<Option<Value<P>>> {
+    let untagged = match (tag, value) {
+        (SET_TAG, Value::Vect(vs)) => Some(Value::Set(vs.clone())),
+        (SYMBOL_TAG, Value::Str(s)) => match s.split_once('/') {
+            Some((namespace, name)) if !namespace.is_empty() && !name.is_empty() => {
+                Some(Value::Symbol(Symbol::qualified(namespace, name)))
+            }
+            _ => Some(Value::Symbol(Symbol::unqualified(s.as_str()))),
+        },
+        (NUM_TAG, Value::Str(s)) => s.strip_prefix("##").and_then(f64_from_symbolic_name).map(Value::Num),
+        (MAP_TAG, Value::Vect(entries)) => entries
+            .iter()
+            .map(|entry| match entry.as_ref() {
+                Value::Vect(kv) if kv.len() == 2 => Some((kv[0].clone(), kv[1].clone())),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(Value::map_from_value_ptr_pairs),
+        (SET_TAG | SYMBOL_TAG | NUM_TAG | MAP_TAG, _) => None,
+        _ => return None,
+    };
+    Some(untagged)
+}
+
+pub(crate) fn write_str<W: Write>(w: &mut W, s: &str) -> Result<(), JsonError> {
+    w.write_all(b"\"")?;
+    for ch in s.chars() {
+        match ch {
+            '"' => w.write_all(b"\\\"")?,
+            '\\' => w.write_all(b"\\\\")?,
+            '\n' => w.write_all(b"\\n")?,
+            '\r' => w.write_all(b"\\r")?,
+            '\t' => w.write_all(b"\\t")?,
+            ch if (ch as u32) < 0x20 => write!(w, "\\u{:04x}", ch as u32)?,
+            ch => write!(w, "{ch}")?,
+        }
+    }
+    w.write_all(b"\"")?;
+    Ok(())
+}
+
+/// integral values are written without a fraction, as JSON producers usually expect
+fn finite_num_str(n: f64) -> String {
+    match n.fract() == 0.0 && n.abs() < 1e15 {
+        true => (n as i64).to_string(),
+        false => format!("{n:?}"),
+    }
+}
+
+/// writes `value` per [`JsonOptions::unrepresentable`], `write_tagged` writes the tagged form
+fn write_unrepresentable<P, W, F>(w: &mut W, value: &Value<P>, tag: &str, options: &JsonOptions, write_tagged: F) -> Result<(), JsonError>
+where
+    P: SharedPointerKind,
+    W: Write,
+    F: FnOnce(&mut W) -> Result<(), JsonError>,
+{
+    match options.unrepresentable {
+        Unrepresentable::Error => Err(JsonError::Unrepresentable(pr_str(value))),
+        Unrepresentable::Stringify => write_str(w, &pr_str(value)),
+        Unrepresentable::Tagged => {
+            w.write_all(b"{")?;
+            write_str(w, tag)?;
+            w.write_all(b":")?;
+            write_tagged(w)?;
+            w.write_all(b"}")?;
+            Ok(())
+        }
+    }
+}
+
+fn write_values<'v, P, W, I>(w: &mut W, values: I, options: &JsonOptions) -> Result<(), JsonError>
+where
+    P: SharedPointerKind + 'v,
+    W: Write,
+    I: IntoIterator<Item = &'v ValuePtr<P>>,
+{
+    write_json_array(w, values.into_iter().map(AsRef::as_ref), options)
+}
+
+fn write_value<P: SharedPointerKind, W: Write>(w: &mut W, value: &Value<P>, options: &JsonOptions) -> Result<(), JsonError> {
+    match value {
+        Value::Nil => w.write_all(b"null")?,
+        Value::Bool(b) => write!(w, "{b}")?,
+        Value::Num(n) if n.is_finite() => w.write_all(finite_num_str(*n).as_bytes())?,
+        Value::Num(..) => {
+            write_unrepresentable(w, value, NUM_TAG, options, |w| write_str(w, &pr_str(value)))?
+        }
+        Value::Str(s) => write_str(w, s)?,
+        Value::Char(ch) => write_str(w, ch.encode_utf8(&mut [0; 4]))?,
+        Value::Keyword(k) => write_str(w, &keyword_name(k))?,
+        Value::Symbol(s) => write_unrepresentable(w, value, SYMBOL_TAG, options, |w| write_str(w, &s.to_string()))?,
+        Value::List(vs) | Value::Vect(vs) => write_values(w, vs, options)?,
+        Value::Set(vs) => write_unrepresentable(w, value, SET_TAG, options, |w| write_values(w, vs, options))?,
+        Value::Map(kvs) => {
+            let has_json_keys = kvs.iter().all(|(k, _)| matches!(k.as_ref(), Value::Str(..) | Value::Keyword(..)));
+            if has_json_keys || options.unrepresentable == Unrepresentable::Stringify {
+                w.write_all(b"{")?;
+                for (idx, (k, v)) in kvs.iter().enumerate() {
+                    if idx > 0 {
+                        w.write_all(b",")?;
+                    }
+                    match k.as_ref() {
+                        Value::Str(s) => write_str(w, s)?,
+                        Value::Keyword(k) => write_str(w, &keyword_name(k))?,
+                        Value::Num(n) if n.is_finite() => write_str(w, &finite_num_str(*n))?,
+                        k => write_str(w, &pr_str(k))?,
+                    }
+                    w.write_all(b":")?;
+                    write_value(w, v, options)?;
+                }
+                w.write_all(b"}")?;
+            } else {
+                write_unrepresentable(w, value, MAP_TAG, options, |w| {
+                    w.write_all(b"[")?;
+                    for (idx, (k, v)) in kvs.iter().enumerate() {
+                        if idx > 0 {
+                            w.write_all(b",")?;
+                        }
+                        write_values(w, [k, v], options)?;
+                    }
+                    w.write_all(b"]")?;
+                    Ok(())
+                })?
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod t {
+    use super::{
+        from_json_str, to_json_string, write_json_array, JsonArrayReader, JsonError, JsonKeys,
+        JsonOptions, Unrepresentable,
+    };
+    use archery::RcK;
+    use cljrs_reader::{printer::pr_str, reader, value::RcValue, DuplicateKeys, ReadError};
+
+    fn edn(src: &str) -> RcValue {
+        reader::<RcK>(src).unwrap().try_read_one().unwrap().unwrap().data
+    }
+
+    fn json_to_edn(json: &str, options: &JsonOptions) -> String {
+        pr_str(&from_json_str::<RcK>(json, options).unwrap())
+    }
+
+    #[test]
+    fn reads_json() {
+        let json = r#" {"a": [1, -2.5e2, true, null], "b": {"c": "\u00e9\ud83d\ude00\n"}} "#;
+        assert_eq!(
+            json_to_edn(json, &JsonOptions::default()),
+            r#"{:a [1.0 -250.0 true nil], :b {:c "é😀\n"}}"#,
+        );
+    }
+
+    #[test]
+    fn key_handling() {
+        let json = r#"{"a": {"b": 1}}"#;
+        let strings = JsonOptions::default().with_keys(JsonKeys::Strings);
+        assert_eq!(json_to_edn(json, &strings), r#"{"a" {"b" 1.0}}"#);
+        let namespaced = JsonOptions::default().with_keys(JsonKeys::Keywords {
+            namespace: Some(String::from("user")),
+        });
+        assert_eq!(json_to_edn(json, &namespaced), "{:user/a {:user/b 1.0}}");
+    }
+
+    #[test]
+    fn invalid_json() {
+        let err = |json: &str| match from_json_str::<RcK>(json, &JsonOptions::default()) {
+            Err(JsonError::Read(err)) => err,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(err("[1,]"), ReadError::invalid_input((3, 3)));
+        assert_eq!(err("[1"), ReadError::insufficient_input((0, 1)));
+        assert_eq!(err("01"), ReadError::invalid_input((0, 1)));
+        for number in ["1.", "1e", "-", "1e+", "+1", ".5"] {
+            assert!(from_json_str::<RcK>(number, &JsonOptions::default()).is_err(), "{number}");
+        }
+        assert_eq!(err("\"\\x\""), ReadError::invalid_input((1, 2)));
+        assert_eq!(err("{\"a\": 1} 2"), ReadError::invalid_input((9, 9)));
+        assert_eq!(err("tru"), ReadError::insufficient_input((0, 2)));
+        let too_deep = JsonOptions::default().with_max_depth(2);
+        assert!(from_json_str::<RcK>("[[[1]]]", &too_deep).is_err());
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let json = r#"{"a": 1, "a": 2}"#;
+        assert_eq!(json_to_edn(json, &JsonOptions::default()), "{:a 2.0}");
+        let reject = JsonOptions::default().with_duplicate_keys(DuplicateKeys::Reject);
+        assert!(matches!(
+            from_json_str::<RcK>(json, &reject),
+            Err(JsonError::Read(ReadError::DuplicateKey((1, 3), (9, 11)))),
+        ));
+    }
+
+    #[test]
+    fn writes_json() {
+        let value = edn(r#"{:a [1 2.5 "x\"y"], "b" (nil true \c), :ns/k :v}"#);
+        assert_eq!(
+            to_json_string(&value, &JsonOptions::default()).unwrap(),
+            r#"{"a":[1,2.5,"x\"y"],"b":[null,true,"c"],"ns/k":"v"}"#,
+        );
+        let value = edn("{::k ::v, ::alias/k ::alias/v}");
+        assert_eq!(to_json_string(&value, &JsonOptions::default()).unwrap(), r#"{"k":"v","alias/k":"alias/v"}"#);
+    }
+
+    #[test]
+    fn unrepresentable_policies() {
+        let value = edn("[#{1} sym ##Inf {1 2}]");
+        let with = |policy| to_json_string(&value, &JsonOptions::default().with_unrepresentable(policy));
+        assert!(matches!(with(Unrepresentable::Error), Err(JsonError::Unrepresentable(v)) if v == "#{1.0}"));
+        assert_eq!(
+            with(Unrepresentable::Stringify).unwrap(),
+            r###"["#{1.0}","sym","##Inf",{"1":2}]"###,
+        );
+        let tagged = with(Unrepresentable::Tagged).unwrap();
+        assert_eq!(
+            tagged,
+            r###"[{"#set":[1]},{"#symbol":"sym"},{"#num":"##Inf"},{"#map":[[1,2]]}]"###,
+        );
+        let options = JsonOptions::default().with_unrepresentable(Unrepresentable::Tagged);
+        assert_eq!(from_json_str::<RcK>(&tagged, &options).unwrap(), value);
+    }
+
+    #[test]
+    fn stringifies_number_keys() {
+        let value = edn("{1 :a, -2 :b, 2.5 :c, ##Inf :d}");
+        let options = JsonOptions::default().with_unrepresentable(Unrepresentable::Stringify);
+        assert_eq!(
+            to_json_string(&value, &options).unwrap(),
+            r###"{"1":"a","-2":"b","2.5":"c","##Inf":"d"}"###,
+        );
+    }
+
+    #[test]
+    fn streaming_arrays() {
+        let json = "[{\"a\": 1},\n 2, [3]] ";
+        let values = JsonArrayReader::<RcK, _>::new(json.as_bytes(), &JsonOptions::default())
+            .map(|value| pr_str(&value.unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec!["{:a 1.0}", "2.0", "[3.0]"]);
+
+        let mut values = JsonArrayReader::<RcK, _>::new("[1, }".as_bytes(), &JsonOptions::default());
+        assert!(values.next().unwrap().is_ok());
+        assert!(values.next().unwrap().is_err());
+        assert!(values.next().is_none());
+
+        let mut buf = vec![];
+        let values = [edn("1"), edn(":a")];
+        write_json_array(&mut buf, &values, &JsonOptions::default()).unwrap();
+        assert_eq!(buf, br#"[1,"a"]"#);
+    }
+}
@@ -0,0 +1,72 @@
+//! conversions between cljrs values and other data formats
+
+pub mod binary;
+pub mod cbor;
+pub mod csv;
+pub mod json;
+pub mod spans;
+pub mod toml;
+pub mod transit;
+pub mod yaml;
+
+use archery::SharedPointerKind;
+use cljrs_core::keyword::Keyword;
+use cljrs_reader::{printer::pr_str, value::Value};
+
+/// what to do with tags a format's reader doesn't know, e.g. CBOR's tag 0 date-times or YAML's
+/// `!Ref`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownTags {
+    /// fail with the format's error
+    #[default]
+    Error,
+    /// read the tagged item as if it weren't, e.g. a tag 0 date-time as its string
+    Ignore,
+}
+
+/// a map key as text: strings as themselves, keywords and symbols as their names, and anything
+/// else as its `pr-str`
+pub(crate) fn key_name<P: SharedPointerKind>(key: &Value<P>) -> String {
+    match key {
+        Value::Str(s) => s.clone(),
+        Value::Keyword(k) => keyword_name(k),
+        Value::Symbol(sym) => sym.to_string(),
+        key => pr_str(key),
+    }
+}
+
+/// a keyword without its colons: `:a` as `a`, `:ns/a` as `ns/a`, `::a` as `a` and `::alias/a` as
+/// `alias/a`
+pub(crate) fn keyword_name(k: &Keyword) -> String {
+    match k {
+        Keyword::Qualified { namespace, name } => format!("{namespace}/{name}"),
+        Keyword::AliasQualified { alias, name } => format!("{alias}/{name}"),
+        k => k.name().to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::key_name;
+    use archery::RcK;
+    use cljrs_reader::{reader, value::RcValue};
+
+    fn edn(src: &str) -> RcValue {
+        reader::<RcK>(src).unwrap().try_read_one().unwrap().unwrap().data
+    }
+
+    #[test]
+    fn key_names() {
+        for (src, name) in [
+            (r#""a b""#, "a b"),
+            (":a", "a"),
+            (":ns/a", "ns/a"),
+            ("::a", "a"),
+            ("::alias/a", "alias/a"),
+            ("ns/a", "ns/a"),
+            ("1", "1.0"),
+        ] {
+            assert_eq!(key_name(&edn(src)), name, "{src}");
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! where in their source the parts of an imported value came from
+
+use std::collections::HashMap;
+use archery::SharedPointerKind;
+use cljrs_reader::{value::Value, ByteIndexSpan};
+use crate::key_name;
+
+/// a step from a collection to one of its elements
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PathSegment {
+    /// a map key as written, e.g. `port` for `:port` and `"1"` for `1`
+    Key(String),
+    Index(usize),
+}
+
+impl PathSegment {
+    pub(crate) fn key<P: SharedPointerKind>(key: &Value<P>) -> Self {
+        Self::Key(key_name(key))
+    }
+}
+
+/// the spans of an imported value's elements, by their path from the value
+///
+/// Values reached through YAML aliases have the span of the alias, and nothing within them
+/// has a span.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Spans {
+    values: HashMap<Vec<PathSegment>, ByteIndexSpan>,
+    keys: HashMap<Vec<PathSegment>, ByteIndexSpan>,
+}
+
+impl Spans {
+    /// Clojure example:
+    /// ```clojure
+    /// ;; for {:servers [{:port 80}]}
+    /// (value-span spans [:servers 0 :port]) ; the span of 80
+    /// ```
+    pub fn value(&self, path: &[PathSegment]) -> Option<ByteIndexSpan> {
+        self.values.get(path).copied()
+    }
+    /// the span of the key of the map entry at `path`
+    pub fn key(&self, path: &[PathSegment]) -> Option<ByteIndexSpan> {
+        self.keys.get(path).copied()
+    }
+    pub(crate) fn insert_value(&mut self, path: Vec<PathSegment>, span: ByteIndexSpan) {
+        self.values.insert(path, span);
+    }
+    pub(crate) fn insert_key(&mut self, path: Vec<PathSegment>, span: ByteIndexSpan) {
+        self.keys.insert(path, span);
+    }
+}
+
+/// an imported value and the spans of its elements
+#[derive(Debug)]
+pub struct Document<P: SharedPointerKind> {
+    pub value: Value<P>,
+    pub spans: Spans,
+}
+
+/// 1-based `(line, col)` of a byte index, for error messages
+pub fn line_col(src: &str, byte_idx: usize) -> (usize, usize) {
+    let before = src.get(..byte_idx).unwrap_or(src);
+    let line = before.matches('\n').count() + 1;
+    let col = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, col)
+}
@@ -0,0 +1,301 @@
+//! TOML documents to and from [`Value`]s
+//!
+//! A document reads as a map, with keys becoming keywords per [`TomlOptions::keys`]. Integers
+//! read as numbers, and date-times as their RFC 3339 strings.
+
+use std::{fmt, ops::Range};
+use archery::SharedPointerKind;
+use cljrs_reader::{
+    printer::pr_str,
+    value::{Value, ValuePtr},
+    ByteIndexSpan,
+};
+use toml_edit::{Array, ArrayOfTables, DocumentMut, ImDocument, InlineTable, Item, Table, TableLike};
+use crate::{
+    json::JsonKeys,
+    key_name,
+    spans::{Document, PathSegment, Spans},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TomlOptions {
+    /// what keys become, as for JSON
+    pub keys: JsonKeys,
+}
+
+impl Default for TomlOptions {
+    fn default() -> Self {
+        Self {
+            keys: JsonKeys::Keywords { namespace: None },
+        }
+    }
+}
+
+impl TomlOptions {
+    pub fn with_keys(mut self, keys: JsonKeys) -> Self {
+        self.keys = keys;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TomlError {
+    /// what the TOML parser reported, which includes the offending line, and where
+    Syntax(Option<ByteIndexSpan>, String),
+    /// what [`Value`]s TOML can't represent when writing, e.g. `"nil"`
+    Unrepresentable(String),
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(Some((begin, _)), msg) => write!(f, "invalid TOML at byte {begin}: {msg}"),
+            Self::Syntax(None, msg) => write!(f, "invalid TOML: {msg}"),
+            Self::Unrepresentable(what) => write!(f, "not representable as TOML: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for TomlError {}
+
+/// Clojure example:
+/// ```clojure
+/// (toml/parse-string "[server]\nport = 80") ; {:server {:port 80.0}}
+/// ```
+pub fn from_toml_str<P: SharedPointerKind>(src: &str, options: &TomlOptions) -> Result<Document<P>, TomlError> {
+    let doc = ImDocument::parse(src).map_err(|err| {
+        TomlError::Syntax(err.span().map(span), err.message().to_owned())
+    })?;
+    let mut importer = Importer {
+        options,
+        path: vec![],
+        spans: Spans::default(),
+    };
+    let value = importer.table(doc.as_table());
+    Ok(Document {
+        value,
+        spans: importer.spans,
+    })
+}
+
+/// the map as a TOML document
+///
+/// TOML has no nil, keywords, symbols, chars, sets or lists, so keywords and symbols are
+/// written as their names (without the leading `:`), chars as strings and lists as arrays.
+/// Nil, sets and anything but a map at the root are [`TomlError::Unrepresentable`].
+pub fn to_toml_string<P: SharedPointerKind>(value: &Value<P>) -> Result<String, TomlError> {
+    let Value::Map(entries) = value else {
+        return Err(TomlError::Unrepresentable(format!("{} at the root", pr_str(value))));
+    };
+    let mut doc = DocumentMut::new();
+    for (k, v) in entries {
+        doc.insert(&key_name(k), to_item(v)?);
+    }
+    Ok(doc.to_string())
+}
+
+/// `Range`s are exclusive, but `ByteIndexSpan`s inclusive
+fn span(range: Range<usize>) -> ByteIndexSpan {
+    (range.start, range.end.saturating_sub(1).max(range.start))
+}
+
+fn to_item<P: SharedPointerKind>(value: &Value<P>) -> Result<Item, TomlError> {
+    Ok(match value {
+        Value::Map(entries) => {
+            let mut table = Table::new();
+            for (k, v) in entries {
+                table.insert(&key_name(k), to_item(v)?);
+            }
+            Item::Table(table)
+        }
+        Value::List(values) | Value::Vect(values) if !values.is_empty() && values.iter().all(|v| v.is_map()) => {
+            let mut tables = ArrayOfTables::new();
+            for v in values {
+                match to_item(v)? {
+                    Item::Table(table) => tables.push(table),
+                    _ => unreachable!("maps become tables"),
+                }
+            }
+            Item::ArrayOfTables(tables)
+        }
+        value => Item::Value(to_value(value)?),
+    })
+}
+
+fn to_value<P: SharedPointerKind>(value: &Value<P>) -> Result<toml_edit::Value, TomlError> {
+    Ok(match value {
+        Value::Nil => return Err(TomlError::Unrepresentable("nil".to_owned())),
+        Value::Bool(b) => (*b).into(),
+        Value::Num(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => (*n as i64).into(),
+        Value::Num(n) => (*n).into(),
+        Value::Str(s) => s.as_str().into(),
+        Value::Char(ch) => ch.to_string().into(),
+        Value::Keyword(..) | Value::Symbol(..) => key_name(value).into(),
+        Value::List(values) | Value::Vect(values) => {
+            let mut array = Array::new();
+            for v in values {
+                array.push(to_value(v)?);
+            }
+            array.into()
+        }
+        Value::Set(..) => return Err(TomlError::Unrepresentable("sets".to_owned())),
+        Value::Map(entries) => {
+            let mut table = InlineTable::new();
+            for (k, v) in entries {
+                table.insert(key_name(k), to_value(v)?);
+            }
+            table.into()
+        }
+    })
+}
+
+struct Importer<'o> {
+    options: &'o TomlOptions,
+    /// of the table or array being imported
+    path: Vec<PathSegment>,
+    spans: Spans,
+}
+
+impl Importer<'_> {
+    fn within<T>(&mut self, segment: PathSegment, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.path.push(segment);
+        let result = f(self);
+        self.path.pop();
+        result
+    }
+
+    fn record(&mut self, range: Option<Range<usize>>) {
+        if let Some(range) = range {
+            self.spans.insert_value(self.path.clone(), span(range));
+        }
+    }
+
+    fn table<P: SharedPointerKind>(&mut self, table: &dyn TableLike) -> Value<P> {
+        let mut entries = Vec::with_capacity(table.len());
+        for (name, item) in table.iter() {
+            let key = self.options.keys.key::<P>(name.to_owned());
+            let segment = PathSegment::key(&key);
+            let value = self.within(segment, |this| {
+                if let Some(range) = table.key(name).and_then(|key| key.span()) {
+                    this.spans.insert_key(this.path.clone(), span(range));
+                }
+                this.item(item)
+            });
+            entries.push((key.to_ptr(), value.to_ptr()));
+        }
+        Value::map_from_value_ptr_pairs(entries)
+    }
+
+    fn item<P: SharedPointerKind>(&mut self, item: &Item) -> Value<P> {
+        match item {
+            Item::None => Value::Nil,
+            Item::Value(value) => self.value(value),
+            Item::Table(table) => {
+                self.record(table.span());
+                self.table(table)
+            }
+            Item::ArrayOfTables(tables) => {
+                self.record(tables.span());
+                let values: Vec<ValuePtr<P>> = tables
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, table)| {
+                        self.within(PathSegment::Index(idx), |this| {
+                            this.record(table.span());
+                            this.table(table).to_ptr()
+                        })
+                    })
+                    .collect();
+                Value::vect_from_value_ptrs(values)
+            }
+        }
+    }
+
+    fn value<P: SharedPointerKind>(&mut self, value: &toml_edit::Value) -> Value<P> {
+        self.record(value.span());
+        match value {
+            toml_edit::Value::String(s) => Value::Str(s.value().clone()),
+            toml_edit::Value::Integer(n) => Value::Num(*n.value() as f64),
+            toml_edit::Value::Float(n) => Value::Num(*n.value()),
+            toml_edit::Value::Boolean(b) => Value::Bool(*b.value()),
+            toml_edit::Value::Datetime(dt) => Value::Str(dt.value().to_string()),
+            toml_edit::Value::Array(array) => {
+                let values: Vec<ValuePtr<P>> = array
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, v)| self.within(PathSegment::Index(idx), |this| this.value(v).to_ptr()))
+                    .collect();
+                Value::vect_from_value_ptrs(values)
+            }
+            toml_edit::Value::InlineTable(table) => self.table(table),
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::{from_toml_str, to_toml_string, TomlError, TomlOptions};
+    use crate::{json::JsonKeys, spans::PathSegment};
+    use archery::RcK;
+    use cljrs_reader::{printer::pr_str, reader, value::RcValue};
+
+    fn edn(src: &str) -> RcValue {
+        reader::<RcK>(src).unwrap().try_read_one().unwrap().unwrap().data
+    }
+
+    #[test]
+    fn reads() {
+        let src = r#"
+title = "example"
+when = 1979-05-27T07:32:00Z
+ratio = 0.5
+point = { x = 1, y = -2 }
+
+[server]
+ports = [80, 443]
+enabled = true
+
+[[users]]
+name = "a"
+
+[[users]]
+name = "b"
+"#;
+        let doc = from_toml_str::<RcK>(src, &TomlOptions::default()).unwrap();
+        assert_eq!(
+            pr_str(&doc.value),
+            r#"{:title "example", :when "1979-05-27T07:32:00Z", :ratio 0.5, :point {:x 1.0, :y -2.0}, :server {:ports [80.0 443.0], :enabled true}, :users [{:name "a"} {:name "b"}]}"#
+        );
+        let strings = TomlOptions::default().with_keys(JsonKeys::Strings);
+        let doc = from_toml_str::<RcK>("a = 1", &strings).unwrap();
+        assert_eq!(pr_str(&doc.value), r#"{"a" 1.0}"#);
+    }
+
+    #[test]
+    fn positions() {
+        let src = "[server]\nport = 80\nhosts = [\"a\", \"b\"]\n\n[[users]]\nname = \"x\"\n";
+        let doc = from_toml_str::<RcK>(src, &TomlOptions::default()).unwrap();
+        let text = |span: Option<(usize, usize)>| span.map(|(begin, end)| &src[begin..=end]);
+        let key = |k: &str| PathSegment::Key(k.to_owned());
+        assert_eq!(text(doc.spans.value(&[key("server"), key("port")])), Some("80"));
+        assert_eq!(text(doc.spans.key(&[key("server"), key("port")])), Some("port"));
+        assert_eq!(text(doc.spans.value(&[key("server"), key("hosts"), PathSegment::Index(1)])), Some("\"b\""));
+        assert_eq!(text(doc.spans.value(&[key("users"), PathSegment::Index(0), key("name")])), Some("\"x\""));
+
+        let err = from_toml_str::<RcK>("a = 1\nb = \n", &TomlOptions::default()).unwrap_err();
+        assert!(matches!(err, TomlError::Syntax(Some((10, _)), _)), "{err:?}");
+    }
+
+    #[test]
+    fn round_trips() {
+        let value = edn(r#"{:name "web", :ratio 0.5, :tags [:a "b"], :server {:port 80}, :users [{:name "x"} {:name "y"}]}"#);
+        let toml = to_toml_string(&value).unwrap();
+        let doc = from_toml_str::<RcK>(&toml, &TomlOptions::default()).unwrap();
+        assert_eq!(
+            pr_str(&doc.value),
+            r#"{:name "web", :ratio 0.5, :tags ["a" "b"], :server {:port 80.0}, :users [{:name "x"} {:name "y"}]}"#
+        );
+        assert!(matches!(to_toml_string(&edn("[1]")), Err(TomlError::Unrepresentable(..))));
+        assert!(matches!(to_toml_string(&edn("{:a nil}")), Err(TomlError::Unrepresentable(..))));
+    }
+}
@@ -0,0 +1,727 @@
+//! [Transit](https://github.com/cognitect/transit-format) to and from runtime [`Value`]s
+//!
+//! Both transit+json (including its verbose form) and transit+msgpack are supported, with the
+//! key cache. Runtime values have no floats, so floats only read when they're integral, and
+//! `#inst`s and `#uuid`s read as their RFC 3339 and canonical strings unless
+//! [`TransitReader::with_handler`] says otherwise. Values of other types are written by
+//! registering a handler for their [`ValueType`] with [`TransitWriter::with_handler`].
+
+mod msgpack;
+mod rep;
+
+use crate::json::JsonError;
+use archery::SharedPointerKind;
+use cljrs_core::{keyword::ResolvedKeyword, symbol::Symbol};
+use cljrs_runtime::value::Value;
+use rep::Rep;
+use std::{collections::HashMap, fmt, io};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    /// maps as JSON objects and no key cache, for humans rather than machines
+    JsonVerbose,
+    MsgPack,
+}
+
+/// the [`Value`] variants, which write handlers are registered for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    Nil,
+    Bool,
+    Num,
+    Str,
+    Char,
+    Keyword,
+    Symbol,
+    List,
+    Vect,
+    Set,
+    Map,
+    IFn,
+}
+
+impl ValueType {
+    pub fn of<P: SharedPointerKind>(value: &Value<P>) -> Self {
+        match value {
+            Value::Nil => Self::Nil,
+            Value::Bool(..) => Self::Bool,
+            Value::Num(..) => Self::Num,
+            Value::Str(..) => Self::Str,
+            Value::Char(..) => Self::Char,
+            Value::Keyword(..) => Self::Keyword,
+            Value::Symbol(..) => Self::Symbol,
+            Value::List(..) => Self::List,
+            Value::Vect(..) => Self::Vect,
+            Value::Set(..) => Self::Set,
+            Value::Map(..) => Self::Map,
+            Value::IFn(..) => Self::IFn,
+        }
+    }
+}
+
+/// the tag and representation to write a value as, or `None` to write it as usual
+///
+/// a single-character tag with a string representation is written as a scalar, e.g. `"~t..."`
+pub type WriteHandler<P> = Box<dyn Fn(&Value<P>) -> Option<(String, Value<P>)>>;
+
+/// the value a tagged representation reads as
+pub type ReadHandler<P> = Box<dyn Fn(Value<P>) -> Result<Value<P>, TransitError>>;
+
+#[derive(Debug)]
+pub enum TransitError {
+    Json(JsonError),
+    MsgPack { offset: usize, msg: String },
+    Io(io::Error),
+    /// well-formed JSON or msgpack that isn't valid Transit
+    Malformed(String),
+    /// a tag without a read handler
+    UnknownTag(String),
+    /// a value that can't be written or read, such as a fn or a fractional number
+    Unsupported(String),
+}
+
+impl From<JsonError> for TransitError {
+    fn from(err: JsonError) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<io::Error> for TransitError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl fmt::Display for TransitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(err) => write!(f, "{err}"),
+            Self::MsgPack { offset, msg } => write!(f, "invalid msgpack at byte {offset}: {msg}"),
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Malformed(msg) => write!(f, "invalid transit: {msg}"),
+            Self::UnknownTag(tag) => write!(f, "no read handler for tag {tag}"),
+            Self::Unsupported(what) => write!(f, "unsupported: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for TransitError {}
+
+fn malformed<T>(msg: &str) -> Result<T, TransitError> {
+    Err(TransitError::Malformed(msg.to_owned()))
+}
+
+/// of nested arrays and maps when reading
+const MAX_DEPTH: usize = 512;
+/// the key cache is cleared whenever it fills up
+const CACHE_SIZE: usize = 44 * 44;
+const CACHE_BASE: usize = 44;
+const CACHE_DIGIT_OFFSET: u8 = b'0';
+
+/// strings longer than 3 characters are cached when they're map keys, keywords, symbols or tags
+fn is_cacheable(s: &str, as_key: bool) -> bool {
+    s.len() > 3 && (as_key || s.starts_with("~:") || s.starts_with("~$") || s.starts_with("~#"))
+}
+
+fn cache_code(idx: usize) -> String {
+    let digit = |n: usize| (n as u8 + CACHE_DIGIT_OFFSET) as char;
+    match idx < CACHE_BASE {
+        true => format!("^{}", digit(idx)),
+        false => format!("^{}{}", digit(idx / CACHE_BASE), digit(idx % CACHE_BASE)),
+    }
+}
+
+fn cache_idx(code: &str) -> Option<usize> {
+    let digit = |b: u8| {
+        let n = b.checked_sub(CACHE_DIGIT_OFFSET)? as usize;
+        (n < CACHE_BASE).then_some(n)
+    };
+    match code.as_bytes() {
+        [b'^', d] => digit(*d),
+        [b'^', hi, lo] => Some(digit(*hi)? * CACHE_BASE + digit(*lo)?),
+        _ => None,
+    }
+}
+
+/// Clojure example:
+/// ```clojure
+/// (transit/write (transit/writer out :json) {:a #{1}}) ; ["^ ","~:a",["~#set",[1]]]
+/// ```
+pub fn to_transit<P: SharedPointerKind>(value: &Value<P>, encoding: Encoding) -> Result<Vec<u8>, TransitError> {
+    TransitWriter::new(encoding).write(value)
+}
+
+pub fn from_transit<P: SharedPointerKind + 'static>(bytes: &[u8], encoding: Encoding) -> Result<Value<P>, TransitError> {
+    TransitReader::new(encoding).read(bytes)
+}
+
+pub struct TransitWriter<P: SharedPointerKind> {
+    encoding: Encoding,
+    handlers: HashMap<ValueType, WriteHandler<P>>,
+}
+
+impl<P: SharedPointerKind> TransitWriter<P> {
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// replaces any handler already registered for `value_type`
+    pub fn with_handler(mut self, value_type: ValueType, handler: WriteHandler<P>) -> Self {
+        self.handlers.insert(value_type, handler);
+        self
+    }
+
+    pub fn write(&self, value: &Value<P>) -> Result<Vec<u8>, TransitError> {
+        let mut emitter = Emitter {
+            writer: self,
+            cache: HashMap::new(),
+        };
+        let rep = match emitter.rep(value, false)? {
+            rep @ (Rep::Array(..) | Rep::Map(..)) => rep,
+            // top-level scalars are quoted
+            scalar => emitter.tagged("'", |_| Ok(scalar))?,
+        };
+        let mut buf = vec![];
+        match self.encoding {
+            Encoding::Json | Encoding::JsonVerbose => rep::write_json(&mut buf, &rep)?,
+            Encoding::MsgPack => msgpack::write(&mut buf, &rep),
+        }
+        Ok(buf)
+    }
+}
+
+struct Emitter<'w, P: SharedPointerKind> {
+    writer: &'w TransitWriter<P>,
+    /// of strings already written to their codes
+    cache: HashMap<String, String>,
+}
+
+impl<P: SharedPointerKind> Emitter<'_, P> {
+    fn string(&mut self, s: String, as_key: bool) -> Rep {
+        if self.writer.encoding == Encoding::JsonVerbose || !is_cacheable(&s, as_key) {
+            return Rep::Str(s);
+        }
+        if let Some(code) = self.cache.get(&s) {
+            return Rep::str(code.as_str());
+        }
+        if self.cache.len() == CACHE_SIZE {
+            self.cache.clear();
+        }
+        self.cache.insert(s.clone(), cache_code(self.cache.len()));
+        Rep::Str(s)
+    }
+
+    /// `rep` is called after the tag is written, so the cache sees them in order
+    fn tagged<F>(&mut self, tag: &str, rep: F) -> Result<Rep, TransitError>
+    where
+        F: FnOnce(&mut Self) -> Result<Rep, TransitError>,
+    {
+        let tag = self.string(format!("~#{tag}"), false);
+        let rep = rep(self)?;
+        Ok(match self.writer.encoding {
+            Encoding::JsonVerbose => Rep::Map(vec![(tag, rep)]),
+            Encoding::Json | Encoding::MsgPack => Rep::Array(vec![tag, rep]),
+        })
+    }
+
+    fn handled(&self, value: &Value<P>) -> Option<(String, Value<P>)> {
+        let handler = self.writer.handlers.get(&ValueType::of(value))?;
+        handler(value)
+    }
+
+    /// whether `value` is written as a string when it's a map key
+    fn is_stringable(&self, value: &Value<P>) -> bool {
+        match self.handled(value) {
+            Some((tag, rep)) => tag.chars().count() == 1 && rep.is_str(),
+            None => matches!(
+                value,
+                Value::Nil
+                    | Value::Bool(..)
+                    | Value::Num(..)
+                    | Value::Str(..)
+                    | Value::Char(..)
+                    | Value::Keyword(..)
+                    | Value::Symbol(..)
+            ),
+        }
+    }
+
+    fn reps<'v, I>(&mut self, values: I) -> Result<Rep, TransitError>
+    where
+        P: 'v,
+        I: IntoIterator<Item = &'v Value<P>>,
+    {
+        let reps = values
+            .into_iter()
+            .map(|value| self.rep(value, false))
+            .collect::<Result<_, _>>()?;
+        Ok(Rep::Array(reps))
+    }
+
+    fn rep(&mut self, value: &Value<P>, as_key: bool) -> Result<Rep, TransitError> {
+        if let Some((tag, rep)) = self.handled(value) {
+            return match (tag.chars().count(), rep) {
+                (1, Value::Str(s)) => Ok(self.string(format!("~{tag}{s}"), as_key)),
+                (_, rep) => self.tagged(&tag, |emitter| emitter.rep(&rep, false)),
+            };
+        }
+        const MAX_JSON_INT: u64 = 1 << 53;
+        let is_json = self.writer.encoding != Encoding::MsgPack;
+        Ok(match value {
+            Value::Nil if as_key => self.string("~_".to_owned(), as_key),
+            Value::Nil => Rep::Null,
+            Value::Bool(b) if as_key => self.string(format!("~?{}", if *b { 't' } else { 'f' }), as_key),
+            Value::Bool(b) => Rep::Bool(*b),
+            Value::Num(n) if as_key || (is_json && (*n as i64).unsigned_abs() > MAX_JSON_INT) => {
+                self.string(format!("~i{n}"), as_key)
+            }
+            Value::Num(n) => Rep::Int(*n as i64),
+            Value::Str(s) if s.starts_with(['~', '^', '`']) => self.string(format!("~{s}"), as_key),
+            Value::Str(s) => self.string(s.clone(), as_key),
+            Value::Char(ch) => self.string(format!("~c{ch}"), as_key),
+            Value::Keyword(keyword) => self.string(format!("~{keyword}"), as_key),
+            Value::Symbol(sym) => self.string(format!("~${sym}"), as_key),
+            Value::List(list) => self.tagged("list", |emitter| emitter.reps(list))?,
+            Value::Vect(vect) => self.reps(vect)?,
+            Value::Set(set) => self.tagged("set", |emitter| emitter.reps(set))?,
+            Value::Map(map) if map.into_iter().all(|(k, _)| self.is_stringable(k)) => {
+                let mut entries = vec![];
+                for (k, v) in map {
+                    entries.push((self.rep(k, true)?, self.rep(v, false)?));
+                }
+                match self.writer.encoding {
+                    Encoding::Json => {
+                        let mut reps = vec![Rep::str("^ ")];
+                        reps.extend(entries.into_iter().flat_map(|(k, v)| [k, v]));
+                        Rep::Array(reps)
+                    }
+                    Encoding::JsonVerbose | Encoding::MsgPack => Rep::Map(entries),
+                }
+            }
+            // maps with composite keys
+            Value::Map(map) => self.tagged("cmap", |emitter| {
+                emitter.reps(map.into_iter().flat_map(|(k, v)| [k, v]))
+            })?,
+            Value::IFn(..) => return Err(TransitError::Unsupported(format!("{value:?}"))),
+        })
+    }
+}
+
+pub struct TransitReader<P: SharedPointerKind> {
+    encoding: Encoding,
+    handlers: HashMap<String, ReadHandler<P>>,
+}
+
+impl<P: SharedPointerKind + 'static> TransitReader<P> {
+    /// with handlers for `t` and `m` (`#inst`), `u` (`#uuid`) and `r` (URIs), which read as
+    /// strings
+    pub fn new(encoding: Encoding) -> Self {
+        let reader = Self {
+            encoding,
+            handlers: HashMap::new(),
+        };
+        let as_str: fn(Value<P>) -> Result<Value<P>, TransitError> = Ok;
+        reader
+            .with_handler("t", Box::new(as_str))
+            .with_handler("u", Box::new(as_str))
+            .with_handler("r", Box::new(as_str))
+            .with_handler("m", Box::new(inst_from_millis))
+    }
+
+    /// replaces any handler already registered for `tag`, which excludes the ground types and
+    /// the `'`, `set`, `list` and `cmap` tags
+    pub fn with_handler<S: Into<String>>(mut self, tag: S, handler: ReadHandler<P>) -> Self {
+        self.handlers.insert(tag.into(), handler);
+        self
+    }
+
+    pub fn read(&self, bytes: &[u8]) -> Result<Value<P>, TransitError> {
+        let rep = match self.encoding {
+            Encoding::Json | Encoding::JsonVerbose => match std::str::from_utf8(bytes) {
+                Ok(s) => rep::read_json(s, MAX_DEPTH)?,
+                Err(err) => return malformed(&format!("invalid UTF-8: {err}")),
+            },
+            Encoding::MsgPack => msgpack::read(bytes, MAX_DEPTH)?,
+        };
+        let mut decoder = Decoder {
+            handlers: &self.handlers,
+            cache: vec![],
+        };
+        decoder.value(rep, false)
+    }
+}
+
+/// the default `m` handler, `1000` reads as `"1970-01-01T00:00:01.000Z"`
+fn inst_from_millis<P: SharedPointerKind>(value: Value<P>) -> Result<Value<P>, TransitError> {
+    let millis = match &value {
+        Value::Num(n) => *n as i64,
+        Value::Str(s) => match s.parse() {
+            Ok(n) => n,
+            Err(_) => return malformed(&format!("invalid #inst {s}")),
+        },
+        _ => return malformed("#inst must be a number of milliseconds"),
+    };
+    let (secs, millis) = (millis.div_euclid(1000), millis.rem_euclid(1000));
+    let (days, secs) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    let (year, month, day) = civil_from_days(days);
+    let (hour, min, sec) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    Ok(Value::string(format!(
+        "{year:04}-{month:02}-{day:02}T{hour:02}:{min:02}:{sec:02}.{millis:03}Z"
+    )))
+}
+
+/// the proleptic Gregorian date `days` after 1970-01-01, per
+/// <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+type Pair<P> = (Value<P>, Value<P>);
+
+struct Decoder<'r, P: SharedPointerKind> {
+    handlers: &'r HashMap<String, ReadHandler<P>>,
+    /// strings in the order they were cached
+    cache: Vec<String>,
+}
+
+impl<P: SharedPointerKind> Decoder<'_, P> {
+    /// looks up cache codes and caches the strings that the writer would have
+    fn resolve(&mut self, s: String, as_key: bool) -> Result<String, TransitError> {
+        if s.starts_with('^') && s != "^ " {
+            let cached = cache_idx(&s).and_then(|idx| self.cache.get(idx));
+            return match cached {
+                Some(cached) => Ok(cached.clone()),
+                None => malformed(&format!("unknown cache code {s}")),
+            };
+        }
+        if is_cacheable(&s, as_key) {
+            if self.cache.len() == CACHE_SIZE {
+                self.cache.clear();
+            }
+            self.cache.push(s.clone());
+        }
+        Ok(s)
+    }
+
+    fn value(&mut self, rep: Rep, as_key: bool) -> Result<Value<P>, TransitError> {
+        match rep {
+            Rep::Null => Ok(Value::Nil),
+            Rep::Bool(b) => Ok(Value::Bool(b)),
+            Rep::Int(n) => int(n),
+            Rep::Float(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => int(n as i64),
+            Rep::Float(n) => Err(TransitError::Unsupported(format!("fractional number {n}"))),
+            Rep::Str(s) => {
+                let s = self.resolve(s, as_key)?;
+                self.string(s)
+            }
+            Rep::Array(reps) => self.array(reps),
+            Rep::Map(entries) => self.map(entries),
+        }
+    }
+
+    fn values(&mut self, reps: Vec<Rep>) -> Result<Vec<Value<P>>, TransitError> {
+        reps.into_iter().map(|rep| self.value(rep, false)).collect()
+    }
+
+    fn pairs<I>(&mut self, reps: I, keys_are_keys: bool) -> Result<Vec<Pair<P>>, TransitError>
+    where
+        I: ExactSizeIterator<Item = Rep>,
+    {
+        if !reps.len().is_multiple_of(2) {
+            return malformed("map with an odd number of keys and values");
+        }
+        let mut reps = reps;
+        let mut pairs = vec![];
+        while let (Some(k), Some(v)) = (reps.next(), reps.next()) {
+            pairs.push((self.value(k, keys_are_keys)?, self.value(v, false)?));
+        }
+        Ok(pairs)
+    }
+
+    /// a string that's been through [`Self::resolve`]
+    fn string(&mut self, s: String) -> Result<Value<P>, TransitError> {
+        let Some(rest) = s.strip_prefix('~') else {
+            return Ok(Value::Str(s));
+        };
+        let mut chars = rest.chars();
+        let Some(tag) = chars.next() else {
+            return malformed("empty tag");
+        };
+        let rep = chars.as_str();
+        match tag {
+            '~' | '^' | '`' => Ok(Value::string(rest.to_owned())),
+            '_' => Ok(Value::Nil),
+            '?' => match rep {
+                "t" => Ok(Value::Bool(true)),
+                "f" => Ok(Value::Bool(false)),
+                _ => malformed(&format!("invalid boolean {s}")),
+            },
+            ':' => Ok(Value::keyword(ResolvedKeyword::from_symbol(symbol(rep)))),
+            '$' => Ok(Value::symbol(symbol(rep))),
+            'c' => {
+                let mut chars = rep.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) => Ok(Value::Char(ch)),
+                    _ => malformed(&format!("invalid char {s}")),
+                }
+            }
+            'i' | 'n' => match rep.parse::<i64>() {
+                Ok(n) => int(n),
+                Err(_) if tag == 'n' && rep.bytes().all(|b| b.is_ascii_digit() || b == b'-') => {
+                    Err(TransitError::Unsupported(format!("integer {rep} out of range")))
+                }
+                Err(_) => malformed(&format!("invalid integer {s}")),
+            },
+            tag => self.handle(&tag.to_string(), Value::string(rep.to_owned())),
+        }
+    }
+
+    fn handle(&self, tag: &str, rep: Value<P>) -> Result<Value<P>, TransitError> {
+        match self.handlers.get(tag) {
+            Some(handler) => handler(rep),
+            None => Err(TransitError::UnknownTag(tag.to_owned())),
+        }
+    }
+
+    fn tagged(&mut self, tag: &str, rep: Rep) -> Result<Value<P>, TransitError> {
+        match (tag, rep) {
+            ("'", rep) => self.value(rep, false),
+            ("set", Rep::Array(reps)) => Ok(Value::set_from_values(self.values(reps)?)),
+            ("list", Rep::Array(reps)) => Ok(Value::list_from_values(self.values(reps)?)),
+            ("cmap", Rep::Array(reps)) => Ok(Value::map_from_value_pairs(self.pairs(reps.into_iter(), false)?)),
+            ("set" | "list" | "cmap", _) => malformed(&format!("#{tag} must be an array")),
+            (tag, rep) => {
+                let rep = self.value(rep, false)?;
+                self.handle(tag, rep)
+            }
+        }
+    }
+
+    fn array(&mut self, reps: Vec<Rep>) -> Result<Value<P>, TransitError> {
+        let mut reps = reps.into_iter();
+        let first = match reps.next() {
+            None => return Ok(Value::empty_vect()),
+            Some(Rep::Str(s)) => self.resolve(s, false)?,
+            Some(first) => {
+                let first = self.value(first, false)?;
+                let rest = self.values(reps.collect())?;
+                return Ok(Value::vect_from_values(std::iter::once(first).chain(rest)));
+            }
+        };
+        if first == "^ " {
+            return Ok(Value::map_from_value_pairs(self.pairs(reps, true)?));
+        }
+        if let (Some(tag), 1) = (first.strip_prefix("~#"), reps.len()) {
+            let rep = reps.next().expect("one rep remains");
+            return self.tagged(tag, rep);
+        }
+        let first = self.string(first)?;
+        let rest = self.values(reps.collect())?;
+        Ok(Value::vect_from_values(std::iter::once(first).chain(rest)))
+    }
+
+    fn map(&mut self, entries: Vec<(Rep, Rep)>) -> Result<Value<P>, TransitError> {
+        let is_single = entries.len() == 1;
+        let mut pairs = vec![];
+        for (k, v) in entries {
+            let k = match k {
+                Rep::Str(s) => self.resolve(s, true)?,
+                k => {
+                    pairs.push((self.value(k, true)?, self.value(v, false)?));
+                    continue;
+                }
+            };
+            match k.strip_prefix("~#") {
+                // a verbose tagged value
+                Some(tag) if is_single => return self.tagged(tag, v),
+                _ => pairs.push((self.string(k)?, self.value(v, false)?)),
+            }
+        }
+        Ok(Value::map_from_value_pairs(pairs))
+    }
+}
+
+fn int<P: SharedPointerKind>(n: i64) -> Result<Value<P>, TransitError> {
+    match isize::try_from(n) {
+        Ok(n) => Ok(Value::Num(n)),
+        Err(_) => Err(TransitError::Unsupported(format!("integer {n} out of range"))),
+    }
+}
+
+/// `ns/name` or `name`, `/` alone being a name
+fn symbol(s: &str) -> Symbol {
+    match s.split_once('/') {
+        Some((ns, name)) if !ns.is_empty() && !name.is_empty() => Symbol::qualified(ns, name),
+        _ => Symbol::unqualified(s),
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::{
+        from_transit, to_transit, Encoding, TransitError, TransitReader, TransitWriter, ValueType,
+    };
+    use archery::RcK;
+    use cljrs_runtime::value::RcValue;
+
+    fn kw(name: &str) -> RcValue {
+        RcValue::unqualified_keyword(name.to_owned())
+    }
+
+    fn s(s: &str) -> RcValue {
+        RcValue::string(s.to_owned())
+    }
+
+    fn json(value: &RcValue) -> String {
+        String::from_utf8(to_transit(value, Encoding::Json).unwrap()).unwrap()
+    }
+
+    fn read_json(json: &str) -> RcValue {
+        from_transit::<RcK>(json.as_bytes(), Encoding::Json).unwrap()
+    }
+
+    fn sample() -> RcValue {
+        RcValue::map_from_value_pairs([
+            (kw("nil"), RcValue::Nil),
+            (kw("nums"), RcValue::vect_from_values([RcValue::Num(-1), RcValue::Num(1 << 60)])),
+            (s("~tricky"), s("^caret")),
+            (RcValue::Num(7), RcValue::Char('λ')),
+            (RcValue::Bool(false), RcValue::qualified_symbol("ns".to_owned(), "sym".to_owned())),
+            (kw("coll"), RcValue::list_from_values([RcValue::set_from_values([kw("a")])])),
+            (RcValue::vect_from_values([kw("composite")]), RcValue::empty_map()),
+        ])
+    }
+
+    #[test]
+    fn round_trips() {
+        for encoding in [Encoding::Json, Encoding::JsonVerbose, Encoding::MsgPack] {
+            let value = RcValue::map_from_value_pairs([(kw("v"), sample())]);
+            let bytes = to_transit(&value, encoding).unwrap();
+            assert_eq!(from_transit::<RcK>(&bytes, encoding).unwrap(), value, "{encoding:?}");
+        }
+    }
+
+    #[test]
+    fn writes_json() {
+        assert_eq!(json(&RcValue::Num(1)), r#"["~#'",1]"#);
+        assert_eq!(json(&RcValue::Num(1 << 60)), r#"["~#'","~i1152921504606846976"]"#);
+        assert_eq!(json(&RcValue::set_from_values([kw("a")])), r#"["~#set",["~:a"]]"#);
+        assert_eq!(
+            json(&RcValue::list_from_values([s("~x"), RcValue::Nil])),
+            r#"["~#list",["~~x",null]]"#
+        );
+        let maps = RcValue::vect_from_values([
+            RcValue::map_from_value_pairs([(kw("foo"), RcValue::Num(1))]),
+            RcValue::map_from_value_pairs([(kw("foo"), RcValue::Num(2))]),
+            RcValue::map_from_value_pairs([(RcValue::Num(3), RcValue::Bool(true))]),
+        ]);
+        assert_eq!(json(&maps), r#"[["^ ","~:foo",1],["^ ","^0",2],["^ ","~i3",true]]"#);
+        let verbose = to_transit(&maps, Encoding::JsonVerbose).unwrap();
+        assert_eq!(
+            String::from_utf8(verbose).unwrap(),
+            r#"[{"~:foo":1},{"~:foo":2},{"~i3":true}]"#
+        );
+        let cmap = RcValue::map_from_value_pairs([(RcValue::vect_from_values([]), RcValue::Nil)]);
+        assert_eq!(json(&cmap), r#"["~#cmap",[[],null]]"#);
+    }
+
+    #[test]
+    fn reads_insts_and_uuids() {
+        let value = read_json(
+            r#"["^ ","~:at","~m946684800123","~:on",["~#m",-1],"~:when","~t2000-01-01T00:00:00Z",
+               "~:id","~u550e8400-e29b-41d4-a716-446655440000","~:ref","^0"]"#,
+        );
+        let expected = RcValue::map_from_value_pairs([
+            (kw("at"), s("2000-01-01T00:00:00.123Z")),
+            (kw("on"), s("1969-12-31T23:59:59.999Z")),
+            (kw("when"), s("2000-01-01T00:00:00Z")),
+            (kw("id"), s("550e8400-e29b-41d4-a716-446655440000")),
+            // `^0` refers back to the first cached string
+            (kw("ref"), kw("at")),
+        ]);
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn key_cache_rolls_over() {
+        let keys = (0..5000).map(|idx| kw(&format!("key{idx}"))).collect::<Vec<_>>();
+        let value = RcValue::vect_from_values(
+            keys.iter()
+                .chain(keys.iter().rev())
+                .map(|k| RcValue::map_from_value_pairs([(RcValue::clone(k), RcValue::Nil)])),
+        );
+        for encoding in [Encoding::Json, Encoding::MsgPack] {
+            let bytes = to_transit(&value, encoding).unwrap();
+            assert_eq!(from_transit::<RcK>(&bytes, encoding).unwrap(), value, "{encoding:?}");
+        }
+    }
+
+    #[test]
+    fn handlers() {
+        // {:uuid "..."} maps stand in for a uuid type
+        let writer = TransitWriter::<RcK>::new(Encoding::Json).with_handler(
+            ValueType::Map,
+            Box::new(|value| match value {
+                RcValue::Map(map) => map
+                    .into_iter()
+                    .find(|(k, _)| **k == kw("uuid"))
+                    .map(|(_, v)| ("u".to_owned(), RcValue::clone(v))),
+                _ => None,
+            }),
+        );
+        let uuid = RcValue::map_from_value_pairs([(kw("uuid"), s("550e8400"))]);
+        let value = RcValue::vect_from_values([uuid, RcValue::empty_map()]);
+        let bytes = writer.write(&value).unwrap();
+        assert_eq!(String::from_utf8(bytes.clone()).unwrap(), r#"["~u550e8400",["^ "]]"#);
+
+        let reader = TransitReader::<RcK>::new(Encoding::Json).with_handler(
+            "u",
+            Box::new(|rep| Ok(RcValue::map_from_value_pairs([(kw("uuid"), rep)]))),
+        );
+        assert_eq!(reader.read(&bytes).unwrap(), value);
+        assert!(matches!(
+            from_transit::<RcK>(br#"["~#point",[1,2]]"#, Encoding::Json),
+            Err(TransitError::UnknownTag(tag)) if tag == "point"
+        ));
+        assert!(matches!(
+            from_transit::<RcK>(br#"["~#'","~d1.5"]"#, Encoding::Json),
+            Err(TransitError::UnknownTag(tag)) if tag == "d"
+        ));
+    }
+
+    #[test]
+    fn msgpack() {
+        let value = RcValue::map_from_value_pairs([(kw("a"), RcValue::Num(-300))]);
+        let bytes = to_transit(&value, Encoding::MsgPack).unwrap();
+        assert_eq!(bytes, [0x81, 0xa3, b'~', b':', b'a', 0xd1, 0xfe, 0xd4]);
+
+        let err = |bytes: &[u8]| match from_transit::<RcK>(bytes, Encoding::MsgPack) {
+            Err(TransitError::MsgPack { offset, .. }) => offset,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(err(&bytes[..6]), 6);
+        assert_eq!(err(&[0xdd, 0xff, 0xff, 0xff, 0xff]), 5);
+        assert_eq!(err(&[0xa1, 0xff]), 1);
+        assert_eq!(err(&[0xc1]), 0);
+        assert_eq!(err(&[0x91; 600]), 513);
+        let deep = [[0x91; 512].as_slice(), &[0xc0]].concat();
+        assert!(from_transit::<RcK>(&deep, Encoding::MsgPack).is_ok());
+        let deep = format!("{}null{}", "[".repeat(511), "]".repeat(511));
+        assert!(from_transit::<RcK>(deep.as_bytes(), Encoding::Json).is_ok());
+        assert_eq!(err(&[0xc0, 0xc0]), 1);
+    }
+}
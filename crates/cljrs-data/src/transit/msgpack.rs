@@ -0,0 +1,217 @@
+//! the subset of msgpack Transit uses: nil, bools, ints, floats, strings, arrays and maps
+
+use super::{rep::Rep, TransitError};
+
+pub(crate) fn write(buf: &mut Vec<u8>, rep: &Rep) {
+    match rep {
+        Rep::Null => buf.push(0xc0),
+        Rep::Bool(false) => buf.push(0xc2),
+        Rep::Bool(true) => buf.push(0xc3),
+        Rep::Int(n) => write_int(buf, *n),
+        Rep::Float(n) => {
+            buf.push(0xcb);
+            buf.extend_from_slice(&n.to_be_bytes());
+        }
+        Rep::Str(s) => {
+            write_len(buf, s.len(), (0xa0, 32), [0xd9, 0xda, 0xdb]);
+            buf.extend_from_slice(s.as_bytes());
+        }
+        Rep::Array(reps) => {
+            write_len(buf, reps.len(), (0x90, 16), [0xdc, 0xdc, 0xdd]);
+            for rep in reps {
+                write(buf, rep);
+            }
+        }
+        Rep::Map(entries) => {
+            write_len(buf, entries.len(), (0x80, 16), [0xde, 0xde, 0xdf]);
+            for (k, v) in entries {
+                write(buf, k);
+                write(buf, v);
+            }
+        }
+    }
+}
+
+fn write_int(buf: &mut Vec<u8>, n: i64) {
+    match n {
+        0..=0x7f => buf.push(n as u8),
+        -32..=-1 => buf.push(n as i8 as u8),
+        0x80..=0xff => buf.extend_from_slice(&[0xcc, n as u8]),
+        0x100..=0xffff => {
+            buf.push(0xcd);
+            buf.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            buf.push(0xce);
+            buf.extend_from_slice(&(n as u32).to_be_bytes());
+        }
+        -0x80..=-33 => buf.extend_from_slice(&[0xd0, n as i8 as u8]),
+        -0x8000..=-0x81 => {
+            buf.push(0xd1);
+            buf.extend_from_slice(&(n as i16).to_be_bytes());
+        }
+        -0x8000_0000..=-0x8001 => {
+            buf.push(0xd2);
+            buf.extend_from_slice(&(n as i32).to_be_bytes());
+        }
+        _ => {
+            buf.push(0xd3);
+            buf.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+}
+
+/// `fix` is the fixed-size marker and its exclusive length limit, `markers` those followed by
+/// 8, 16 and 32 bit lengths (arrays and maps have no 8 bit form)
+fn write_len(buf: &mut Vec<u8>, len: usize, fix: (u8, usize), markers: [u8; 3]) {
+    let (fix_marker, fix_limit) = fix;
+    if len < fix_limit {
+        buf.push(fix_marker | len as u8);
+    } else if len <= 0xff && markers[0] != markers[1] {
+        buf.extend_from_slice(&[markers[0], len as u8]);
+    } else if len <= 0xffff {
+        buf.push(markers[1]);
+        buf.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        buf.push(markers[2]);
+        buf.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+pub(crate) fn read(bytes: &[u8], max_depth: usize) -> Result<Rep, TransitError> {
+    let mut rdr = Reader {
+        bytes,
+        offset: 0,
+        max_depth,
+    };
+    let rep = rdr.read(0)?;
+    match rdr.offset == bytes.len() {
+        true => Ok(rep),
+        false => Err(rdr.malformed("trailing bytes")),
+    }
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+    offset: usize,
+    max_depth: usize,
+}
+
+impl<'b> Reader<'b> {
+    fn malformed(&self, msg: &str) -> TransitError {
+        TransitError::MsgPack {
+            offset: self.offset,
+            msg: msg.to_owned(),
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'b [u8], TransitError> {
+        let end = self.offset.checked_add(n).filter(|end| *end <= self.bytes.len());
+        match end {
+            Some(end) => {
+                let taken = &self.bytes[self.offset..end];
+                self.offset = end;
+                Ok(taken)
+            }
+            None => Err(self.malformed("unexpected end of input")),
+        }
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], TransitError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn take_len(&mut self, width: usize) -> Result<usize, TransitError> {
+        Ok(match width {
+            1 => self.take_array::<1>()?[0] as usize,
+            2 => u16::from_be_bytes(self.take_array()?) as usize,
+            _ => u32::from_be_bytes(self.take_array()?) as usize,
+        })
+    }
+
+    fn read_str(&mut self, len: usize) -> Result<Rep, TransitError> {
+        let start = self.offset;
+        let bytes = self.take(len)?;
+        match std::str::from_utf8(bytes) {
+            Ok(s) => Ok(Rep::str(s)),
+            Err(_) => {
+                self.offset = start;
+                Err(self.malformed("invalid UTF-8"))
+            }
+        }
+    }
+
+    fn read_array(&mut self, len: usize, depth: usize) -> Result<Rep, TransitError> {
+        // each element takes at least a byte, so a bogus length can't allocate much
+        let mut reps = Vec::with_capacity(len.min(self.bytes.len() - self.offset));
+        for _ in 0..len {
+            reps.push(self.read(depth + 1)?);
+        }
+        Ok(Rep::Array(reps))
+    }
+
+    fn read_map(&mut self, len: usize, depth: usize) -> Result<Rep, TransitError> {
+        let mut entries = Vec::with_capacity(len.min(self.bytes.len() - self.offset));
+        for _ in 0..len {
+            let k = self.read(depth + 1)?;
+            let v = self.read(depth + 1)?;
+            entries.push((k, v));
+        }
+        Ok(Rep::Map(entries))
+    }
+
+    fn read(&mut self, depth: usize) -> Result<Rep, TransitError> {
+        if depth > self.max_depth {
+            return Err(self.malformed("nested too deeply"));
+        }
+        let marker = self.take_array::<1>()?[0];
+        match marker {
+            0x80..=0x8f => self.read_map((marker & 0x0f) as usize, depth),
+            0x90..=0x9f => self.read_array((marker & 0x0f) as usize, depth),
+            0xdc | 0xdd => {
+                let len = self.take_len(2 << (marker - 0xdc))?;
+                self.read_array(len, depth)
+            }
+            0xde | 0xdf => {
+                let len = self.take_len(2 << (marker - 0xde))?;
+                self.read_map(len, depth)
+            }
+            _ => self.read_scalar(marker),
+        }
+    }
+
+    /// kept out of [`Self::read`] so that deep nesting needs less stack
+    fn read_scalar(&mut self, marker: u8) -> Result<Rep, TransitError> {
+        Ok(match marker {
+            0x00..=0x7f => Rep::Int(marker as i64),
+            0xa0..=0xbf => self.read_str((marker & 0x1f) as usize)?,
+            0xc0 => Rep::Null,
+            0xc2 => Rep::Bool(false),
+            0xc3 => Rep::Bool(true),
+            0xca => Rep::Float(f32::from_be_bytes(self.take_array()?) as f64),
+            0xcb => Rep::Float(f64::from_be_bytes(self.take_array()?)),
+            0xcc => Rep::Int(self.take_array::<1>()?[0] as i64),
+            0xcd => Rep::Int(u16::from_be_bytes(self.take_array()?) as i64),
+            0xce => Rep::Int(u32::from_be_bytes(self.take_array()?) as i64),
+            0xcf => match i64::try_from(u64::from_be_bytes(self.take_array()?)) {
+                Ok(n) => Rep::Int(n),
+                Err(_) => return Err(self.malformed("integer out of range")),
+            },
+            0xd0 => Rep::Int(i8::from_be_bytes(self.take_array()?) as i64),
+            0xd1 => Rep::Int(i16::from_be_bytes(self.take_array()?) as i64),
+            0xd2 => Rep::Int(i32::from_be_bytes(self.take_array()?) as i64),
+            0xd3 => Rep::Int(i64::from_be_bytes(self.take_array()?)),
+            0xd9..=0xdb => {
+                let len = self.take_len(1 << (marker - 0xd9))?;
+                self.read_str(len)?
+            }
+            0xe0..=0xff => Rep::Int(marker as i8 as i64),
+            _ => {
+                self.offset -= 1;
+                return Err(self.malformed("unsupported msgpack type"));
+            }
+        })
+    }
+}
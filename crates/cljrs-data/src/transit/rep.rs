@@ -0,0 +1,89 @@
+use super::TransitError;
+use crate::json::{self, JsonKeys, JsonOptions};
+use archery::RcK;
+use cljrs_reader::value::Value as ReaderValue;
+use std::io::Write;
+
+/// what Transit values are encoded as, before they're written as JSON or msgpack
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Rep {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Array(Vec<Rep>),
+    /// msgpack maps and verbose JSON objects, in the order they were written
+    Map(Vec<(Rep, Rep)>),
+}
+
+impl Rep {
+    pub(crate) fn str<S: Into<String>>(s: S) -> Self {
+        Self::Str(s.into())
+    }
+}
+
+pub(crate) fn write_json<W: Write>(w: &mut W, rep: &Rep) -> Result<(), TransitError> {
+    match rep {
+        Rep::Null => w.write_all(b"null")?,
+        Rep::Bool(b) => write!(w, "{b}")?,
+        Rep::Int(n) => write!(w, "{n}")?,
+        Rep::Float(n) if n.is_finite() => write!(w, "{n:?}")?,
+        Rep::Float(n) => return Err(TransitError::Unsupported(format!("non-finite number {n}"))),
+        Rep::Str(s) => json::write_str(w, s)?,
+        Rep::Array(reps) => {
+            w.write_all(b"[")?;
+            for (idx, rep) in reps.iter().enumerate() {
+                if idx > 0 {
+                    w.write_all(b",")?;
+                }
+                write_json(w, rep)?;
+            }
+            w.write_all(b"]")?;
+        }
+        Rep::Map(entries) => {
+            w.write_all(b"{")?;
+            for (idx, (k, v)) in entries.iter().enumerate() {
+                if idx > 0 {
+                    w.write_all(b",")?;
+                }
+                match k {
+                    Rep::Str(k) => json::write_str(w, k)?,
+                    _ => unreachable!("verbose JSON object keys are always strings"),
+                }
+                w.write_all(b":")?;
+                write_json(w, v)?;
+            }
+            w.write_all(b"}")?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn read_json(s: &str, max_depth: usize) -> Result<Rep, TransitError> {
+    let options = JsonOptions::default()
+        .with_keys(JsonKeys::Strings)
+        .with_max_depth(max_depth);
+    let value = json::from_json_str::<RcK>(s, &options)?;
+    Ok(from_json_value(&value))
+}
+
+/// JSON numbers that are integers become [`Rep::Int`]s
+fn from_json_value(value: &ReaderValue<RcK>) -> Rep {
+    match value {
+        ReaderValue::Nil => Rep::Null,
+        ReaderValue::Bool(b) => Rep::Bool(*b),
+        ReaderValue::Num(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => Rep::Int(*n as i64),
+        ReaderValue::Num(n) => Rep::Float(*n),
+        ReaderValue::Str(s) => Rep::str(s.as_str()),
+        ReaderValue::Vect(values) => Rep::Array(values.iter().map(|v| from_json_value(v)).collect()),
+        ReaderValue::Map(entries) => Rep::Map(
+            entries
+                .iter()
+                .map(|(k, v)| (from_json_value(k), from_json_value(v)))
+                .collect(),
+        ),
+        // nothing else is read from JSON with string keys
+        _ => Rep::Null,
+    }
+}
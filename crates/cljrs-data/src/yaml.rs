@@ -0,0 +1,673 @@
+//! YAML documents to and from [`Value`]s
+//!
+//! Plain scalars are resolved per the YAML 1.2 core schema: `null` and `~` are nil, `true` and
+//! `false` bools, and integers (including `0x` and `0o`) and floats (including `.inf` and
+//! `.nan`) numbers. Anchors and aliases are supported, as are `<<` merge keys. Mapping keys
+//! that are strings become keywords, per [`YamlOptions::keys`].
+
+use std::{collections::HashMap, fmt};
+use archery::SharedPointerKind;
+use cljrs_reader::{
+    value::{KeyIndex, Value, ValuePtr},
+    ByteIndexSpan, DuplicateKeys,
+};
+use yaml_rust2::{
+    parser::{Event, MarkedEventReceiver, Parser, Tag},
+    scanner::{Marker, TScalarStyle},
+    yaml::{Hash, Yaml},
+    YamlEmitter,
+};
+use crate::{
+    json::JsonKeys,
+    key_name,
+    spans::{Document, PathSegment, Spans},
+    UnknownTags,
+};
+
+const CORE_TAG_HANDLE: &str = "tag:yaml.org,2002:";
+const MERGE_KEY: &str = "<<";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YamlOptions {
+    /// what string mapping keys become, as for JSON
+    pub keys: JsonKeys,
+    /// repeated mapping keys are an error by default, as the YAML spec says
+    pub duplicate_keys: DuplicateKeys,
+    /// tags other than the core schema's, such as CloudFormation's `!Ref`
+    pub unknown_tags: UnknownTags,
+}
+
+impl Default for YamlOptions {
+    fn default() -> Self {
+        Self {
+            keys: JsonKeys::Keywords { namespace: None },
+            duplicate_keys: DuplicateKeys::Reject,
+            unknown_tags: UnknownTags::default(),
+        }
+    }
+}
+
+impl YamlOptions {
+    pub fn with_keys(mut self, keys: JsonKeys) -> Self {
+        self.keys = keys;
+        self
+    }
+    pub fn with_duplicate_keys(mut self, duplicate_keys: DuplicateKeys) -> Self {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+    pub fn with_unknown_tags(mut self, unknown_tags: UnknownTags) -> Self {
+        self.unknown_tags = unknown_tags;
+        self
+    }
+}
+
+/// spans are byte offsets into the YAML text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum YamlError {
+    /// what the YAML parser reported, e.g. `"did not find expected key"`
+    Syntax(ByteIndexSpan, String),
+    /// the spans of the first and the repeated key
+    DuplicateKey(ByteIndexSpan, ByteIndexSpan),
+    /// e.g. an unknown tag or alias
+    Invalid(ByteIndexSpan, String),
+    /// what [`Value`]s YAML can't represent when writing, e.g. `"sets"`
+    Unrepresentable(String),
+}
+
+impl fmt::Display for YamlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax((begin, _), msg) => write!(f, "invalid YAML at byte {begin}: {msg}"),
+            Self::DuplicateKey(_, (begin, _)) => write!(f, "duplicate YAML key at byte {begin}"),
+            Self::Invalid((begin, _), msg) => write!(f, "invalid YAML at byte {begin}: {msg}"),
+            Self::Unrepresentable(what) => write!(f, "not representable as YAML: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for YamlError {}
+
+/// every document in a YAML stream, an empty one reading as nil
+///
+/// Clojure example:
+/// ```clojure
+/// (yaml/parse-string "a: &x [1]\nb: *x\n---\n2") ; [{:a [1.0], :b [1.0]} 2.0]
+/// ```
+pub fn from_yaml_str<P: SharedPointerKind>(src: &str, options: &YamlOptions) -> Result<Vec<Document<P>>, YamlError> {
+    let mut builder = Builder {
+        src,
+        char_offsets: match src.is_ascii() {
+            true => None,
+            false => Some(src.char_indices().map(|(idx, _)| idx).collect()),
+        },
+        options,
+        docs: vec![],
+        stack: vec![],
+        root: None,
+        anchors: HashMap::new(),
+        path: vec![],
+        in_key: 0,
+        spans: Spans::default(),
+        error: None,
+    };
+    let mut parser = Parser::new_from_str(src);
+    if let Err(err) = parser.load(&mut builder, true) {
+        let begin = builder.byte_idx(err.marker());
+        return Err(YamlError::Syntax((begin, begin), err.info().to_owned()));
+    }
+    match builder.error {
+        Some(err) => Err(err),
+        None => Ok(builder.docs),
+    }
+}
+
+/// the values as a YAML stream, a document each
+///
+/// YAML has no keywords, symbols, chars, sets or lists, so keywords and symbols are written as
+/// their names (without the leading `:`), chars as strings and lists as sequences. Sets are
+/// [`YamlError::Unrepresentable`].
+pub fn to_yaml_string<'v, P, I>(values: I) -> Result<String, YamlError>
+where
+    P: SharedPointerKind + 'v,
+    I: IntoIterator<Item = &'v Value<P>>,
+{
+    let mut out = String::new();
+    for value in values {
+        let yaml = to_yaml(value)?;
+        let mut emitter = YamlEmitter::new(&mut out);
+        emitter.dump(&yaml).map_err(|err| YamlError::Unrepresentable(format!("{err:?}")))?;
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn to_yaml<P: SharedPointerKind>(value: &Value<P>) -> Result<Yaml, YamlError> {
+    let yamls = |values: &[ValuePtr<P>]| values.iter().map(|v| to_yaml(v)).collect::<Result<Vec<_>, _>>();
+    Ok(match value {
+        Value::Nil => Yaml::Null,
+        Value::Bool(b) => Yaml::Boolean(*b),
+        Value::Num(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => Yaml::Integer(*n as i64),
+        Value::Num(n) if n.is_nan() => Yaml::Real(".nan".to_owned()),
+        Value::Num(n) if n.is_infinite() => Yaml::Real(if *n > 0.0 { ".inf" } else { "-.inf" }.to_owned()),
+        Value::Num(n) => Yaml::Real(format!("{n:?}")),
+        Value::Str(s) => Yaml::String(s.clone()),
+        Value::Char(ch) => Yaml::String(ch.to_string()),
+        Value::Keyword(..) | Value::Symbol(..) => Yaml::String(key_name(value)),
+        Value::List(values) | Value::Vect(values) => Yaml::Array(yamls(values)?),
+        Value::Set(..) => return Err(YamlError::Unrepresentable("sets".to_owned())),
+        Value::Map(entries) => {
+            let mut hash = Hash::new();
+            for (k, v) in entries {
+                hash.insert(to_yaml(k)?, to_yaml(v)?);
+            }
+            Yaml::Hash(hash)
+        }
+    })
+}
+
+fn is_null(s: &str) -> bool {
+    matches!(s, "" | "~" | "null" | "Null" | "NULL")
+}
+
+fn bool_from(s: &str) -> Option<bool> {
+    match s {
+        "true" | "True" | "TRUE" => Some(true),
+        "false" | "False" | "FALSE" => Some(false),
+        _ => None,
+    }
+}
+
+fn int_from(s: &str) -> Option<f64> {
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let sign = if s.starts_with('-') { -1.0 } else { 1.0 };
+    if let Some(octal) = s.strip_prefix("0o") {
+        return u64::from_str_radix(octal, 8).ok().map(|n| n as f64);
+    }
+    if let Some(hex) = s.strip_prefix("0x") {
+        return u64::from_str_radix(hex, 16).ok().map(|n| n as f64);
+    }
+    match !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+        true => digits.parse::<f64>().ok().map(|n| sign * n),
+        false => None,
+    }
+}
+
+fn float_from(s: &str) -> Option<f64> {
+    let unsigned = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let sign = if s.starts_with('-') { -1.0 } else { 1.0 };
+    match unsigned {
+        ".inf" | ".Inf" | ".INF" => return Some(sign * f64::INFINITY),
+        ".nan" | ".NaN" | ".NAN" if unsigned == s => return Some(f64::NAN),
+        _ => {}
+    }
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (int, fract) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    let valid_mantissa = is_digits(int) && is_digits(fract) && !(int.is_empty() && fract.is_empty());
+    let valid_exponent = exponent
+        .map(|exp| exp.strip_prefix(['-', '+']).unwrap_or(exp))
+        .map(|exp| !exp.is_empty() && is_digits(exp))
+        .unwrap_or(true);
+    match valid_mantissa && valid_exponent {
+        true => s.parse().ok(),
+        false => None,
+    }
+}
+
+/// string keys as keywords, if so configured
+fn keyword_key<P: SharedPointerKind>(key: Value<P>, keys: &JsonKeys) -> Value<P> {
+    match key {
+        Value::Str(name) => keys.key(name),
+        key => key,
+    }
+}
+
+/// a collection being built, and how it's reached from its parent
+enum Frame<P: SharedPointerKind> {
+    Seq {
+        role: Role,
+        anchor: usize,
+        begin: usize,
+        values: Vec<ValuePtr<P>>,
+    },
+    Map {
+        role: Role,
+        anchor: usize,
+        begin: usize,
+        is_set: bool,
+        entries: Vec<(ValuePtr<P>, ValuePtr<P>)>,
+        key_spans: Vec<ByteIndexSpan>,
+        key_index: KeyIndex,
+        /// a key awaiting its value, and whether it's `<<`
+        pending: Option<(Value<P>, ByteIndexSpan, bool)>,
+        /// the values of `<<` keys
+        merges: Vec<Value<P>>,
+    },
+}
+
+/// how a node is reached from its parent
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Role {
+    Root,
+    Element(usize),
+    Key,
+    Value(PathSegment),
+}
+
+struct Builder<'s, 'o, P: SharedPointerKind> {
+    src: &'s str,
+    /// the byte offset of each char, if any char isn't ASCII
+    char_offsets: Option<Vec<usize>>,
+    options: &'o YamlOptions,
+    docs: Vec<Document<P>>,
+    stack: Vec<Frame<P>>,
+    root: Option<Value<P>>,
+    anchors: HashMap<usize, Value<P>>,
+    /// of the collection being built
+    path: Vec<PathSegment>,
+    /// how many of the collections being built are mapping keys, whose spans aren't kept
+    in_key: usize,
+    spans: Spans,
+    error: Option<YamlError>,
+}
+
+impl<P: SharedPointerKind> Builder<'_, '_, P> {
+    fn byte_idx(&self, mark: &Marker) -> usize {
+        match &self.char_offsets {
+            None => mark.index(),
+            Some(offsets) => offsets.get(mark.index()).copied().unwrap_or(self.src.len()),
+        }
+    }
+
+    /// the last byte of the scalar beginning at `begin`, or `begin` if that's not simple to find
+    fn scalar_end(&self, begin: usize, style: TScalarStyle, value: &str) -> usize {
+        let rest = self.src.get(begin..).unwrap_or("");
+        let mut chars = rest.char_indices().skip(1);
+        let closing = match style {
+            TScalarStyle::Plain if !value.is_empty() && rest.starts_with(value) => {
+                return begin + value.len() - 1
+            }
+            TScalarStyle::SingleQuoted => loop {
+                match chars.next() {
+                    Some((idx, '\'')) if rest[idx + 1..].starts_with('\'') => {
+                        chars.next();
+                    }
+                    Some((idx, '\'')) => break Some(idx),
+                    Some(_) => {}
+                    None => break None,
+                }
+            },
+            TScalarStyle::DoubleQuoted => loop {
+                match chars.next() {
+                    Some((_, '\\')) => {
+                        chars.next();
+                    }
+                    Some((idx, '"')) => break Some(idx),
+                    Some(_) => {}
+                    None => break None,
+                }
+            },
+            _ => None,
+        };
+        begin + closing.unwrap_or(0)
+    }
+
+    fn fail(&mut self, err: YamlError) {
+        self.error.get_or_insert(err);
+    }
+
+    fn role(&self) -> Role {
+        match self.stack.last() {
+            None => Role::Root,
+            Some(Frame::Seq { values, .. }) => Role::Element(values.len()),
+            Some(Frame::Map { pending: None, .. }) => Role::Key,
+            Some(Frame::Map {
+                pending: Some((key, ..)),
+                ..
+            }) => Role::Value(PathSegment::key(key)),
+        }
+    }
+
+    /// the path of a node in the collection being built
+    fn path_to(&self, role: &Role) -> Option<Vec<PathSegment>> {
+        let segment = match role {
+            Role::Root => None,
+            Role::Element(idx) => Some(PathSegment::Index(*idx)),
+            Role::Key => return None,
+            Role::Value(segment) => Some(segment.clone()),
+        };
+        let mut path = self.path.clone();
+        path.extend(segment);
+        (self.in_key == 0).then_some(path)
+    }
+
+    fn resolve(&self, s: String, style: TScalarStyle, tag: Option<Tag>, span: ByteIndexSpan) -> Result<Value<P>, YamlError> {
+        let invalid = |what: &str| Err(YamlError::Invalid(span, format!("{s:?} is not {what}")));
+        let core_tag = match &tag {
+            None if style != TScalarStyle::Plain => return Ok(Value::Str(s)),
+            Some(Tag { handle, suffix }) if handle == CORE_TAG_HANDLE => Some(suffix.as_str()),
+            // the non-specific tag, `! foo`
+            Some(Tag { handle, suffix }) if handle == "!" && suffix.is_empty() => return Ok(Value::Str(s)),
+            Some(Tag { handle, suffix }) => match self.options.unknown_tags {
+                UnknownTags::Error => return Err(YamlError::Invalid(span, format!("unknown tag {handle}{suffix}"))),
+                UnknownTags::Ignore if style != TScalarStyle::Plain => return Ok(Value::Str(s)),
+                UnknownTags::Ignore => None,
+            },
+            None => None,
+        };
+        match core_tag {
+            Some("str") => Ok(Value::Str(s)),
+            Some("null") if is_null(&s) => Ok(Value::Nil),
+            Some("null") => invalid("null"),
+            Some("bool") => bool_from(&s).map(Value::Bool).map_or_else(|| invalid("a bool"), Ok),
+            Some("int") => int_from(&s).map(Value::Num).map_or_else(|| invalid("an int"), Ok),
+            Some("float") => match int_from(&s).or_else(|| float_from(&s)) {
+                Some(n) => Ok(Value::Num(n)),
+                None => invalid("a float"),
+            },
+            Some(other) => Err(YamlError::Invalid(span, format!("unsupported tag !!{other}"))),
+            None if is_null(&s) => Ok(Value::Nil),
+            None => Ok(match bool_from(&s) {
+                Some(b) => Value::Bool(b),
+                None => match int_from(&s).or_else(|| float_from(&s)) {
+                    Some(n) => Value::Num(n),
+                    None => Value::Str(s),
+                },
+            }),
+        }
+    }
+
+    fn check_collection_tag(&self, tag: Option<Tag>, expected: &[&str], span: ByteIndexSpan) -> Result<Option<String>, YamlError> {
+        match tag {
+            None => Ok(None),
+            Some(Tag { handle, suffix }) if handle == CORE_TAG_HANDLE && expected.contains(&suffix.as_str()) => {
+                Ok(Some(suffix))
+            }
+            Some(Tag { handle, .. }) if handle != CORE_TAG_HANDLE && self.options.unknown_tags == UnknownTags::Ignore => {
+                Ok(None)
+            }
+            Some(Tag { handle, suffix }) => Err(YamlError::Invalid(span, format!("unsupported tag {handle}{suffix}"))),
+        }
+    }
+
+    /// adds a finished node to the collection being built
+    fn add(&mut self, value: Value<P>, span: ByteIndexSpan, anchor: usize, is_merge_key: bool) {
+        if anchor != 0 {
+            self.anchors.insert(anchor, value.clone());
+        }
+        let in_key = self.in_key;
+        let options = self.options;
+        match self.stack.last_mut() {
+            None => self.root = Some(value),
+            Some(Frame::Seq { values, .. }) => values.push(value.to_ptr()),
+            Some(Frame::Map { pending: pending @ None, .. }) => *pending = Some((value, span, is_merge_key)),
+            Some(Frame::Map {
+                pending: pending @ Some(_),
+                entries,
+                key_spans,
+                key_index,
+                merges,
+                ..
+            }) => {
+                let (key, key_span, is_merge) = pending.take().expect("a pending key");
+                if is_merge {
+                    merges.push(value);
+                    return;
+                }
+                let key = keyword_key(key, &options.keys);
+                let existing = key_index.find_or_insert(&key, entries.len(), |idx| entries[idx].0.as_ref() == &key);
+                match (existing, options.duplicate_keys) {
+                    (Some(idx), DuplicateKeys::Reject) if in_key == 0 => {
+                        let first = key_spans[idx];
+                        self.fail(YamlError::DuplicateKey(first, key_span));
+                    }
+                    (Some(idx), _) => entries[idx].1 = value.to_ptr(),
+                    (None, _) => {
+                        entries.push((key.to_ptr(), value.to_ptr()));
+                        key_spans.push(key_span);
+                    }
+                }
+            }
+        }
+    }
+
+    fn scalar(&mut self, s: String, style: TScalarStyle, anchor: usize, tag: Option<Tag>, mark: Marker) {
+        let begin = self.byte_idx(&mark);
+        let span = (begin, self.scalar_end(begin, style, &s));
+        let role = self.role();
+        let is_merge_key = role == Role::Key && style == TScalarStyle::Plain && tag.is_none() && s == MERGE_KEY;
+        let value = match self.resolve(s, style, tag, span) {
+            Ok(value) => value,
+            Err(err) => return self.fail(err),
+        };
+        self.record(&role, &value, span);
+        self.add(value, span, anchor, is_merge_key);
+    }
+
+    fn record(&mut self, role: &Role, value: &Value<P>, span: ByteIndexSpan) {
+        if *role == Role::Key && self.in_key == 0 {
+            let key = keyword_key(value.clone(), &self.options.keys);
+            let mut path = self.path.clone();
+            path.push(PathSegment::key(&key));
+            self.spans.insert_key(path, span);
+        } else if let Some(path) = self.path_to(role) {
+            self.spans.insert_value(path, span);
+        }
+    }
+
+    fn start(&mut self, frame: impl FnOnce(Role) -> Frame<P>) {
+        let role = self.role();
+        match &role {
+            Role::Key => self.in_key += 1,
+            Role::Root => {}
+            Role::Element(idx) => self.path.push(PathSegment::Index(*idx)),
+            Role::Value(segment) => self.path.push(segment.clone()),
+        }
+        self.stack.push(frame(role));
+    }
+
+    fn end(&mut self, mark: Marker) {
+        // flow collections end at their closing bracket, and block ones where the next token
+        // begins
+        let idx = self.byte_idx(&mark);
+        let end = match self.src.as_bytes().get(idx) {
+            Some(b']' | b'}') => idx,
+            _ => self.src[..idx].trim_end().len().saturating_sub(1),
+        };
+        let Some(frame) = self.stack.pop() else { return };
+        let (role, anchor, begin, value) = match frame {
+            Frame::Seq { role, anchor, begin, values } => (role, anchor, begin, Value::vect_from_value_ptrs(values)),
+            Frame::Map { role, anchor, begin, is_set, mut entries, mut key_index, merges, .. } => {
+                for merged in merges.iter().flat_map(|merge| match merge {
+                    Value::Map(..) => vec![merge],
+                    Value::Vect(maps) => maps.iter().map(|map| map.as_ref()).collect(),
+                    _ => vec![],
+                }) {
+                    for (k, v) in merged.try_as_map().into_iter().flatten() {
+                        let existing = key_index.find_or_insert(k, entries.len(), |idx| &entries[idx].0 == k);
+                        if existing.is_none() {
+                            entries.push((k.clone(), v.clone()));
+                        }
+                    }
+                }
+                let value = match is_set {
+                    true => Value::set_from_value_ptrs(entries.into_iter().map(|(k, _)| k)),
+                    false => Value::map_from_value_ptr_pairs(entries),
+                };
+                (role, anchor, begin, value)
+            }
+        };
+        match &role {
+            Role::Key => self.in_key -= 1,
+            Role::Root => {}
+            Role::Element(..) | Role::Value(..) => {
+                self.path.pop();
+            }
+        }
+        let end = end.max(begin);
+        self.record(&role, &value, (begin, end));
+        self.add(value, (begin, end), anchor, false);
+    }
+}
+
+impl<P: SharedPointerKind> MarkedEventReceiver for Builder<'_, '_, P> {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        if self.error.is_some() {
+            return;
+        }
+        match ev {
+            Event::Nothing | Event::StreamStart | Event::StreamEnd => {}
+            Event::DocumentStart => {
+                self.anchors.clear();
+                self.spans = Spans::default();
+            }
+            Event::DocumentEnd => {
+                let value = self.root.take().unwrap_or(Value::Nil);
+                let spans = std::mem::take(&mut self.spans);
+                self.docs.push(Document { value, spans });
+            }
+            Event::Alias(id) => {
+                let begin = self.byte_idx(&mark);
+                let name_len = self.src[begin..]
+                    .find(|ch: char| ch.is_whitespace() || ",[]{}".contains(ch))
+                    .unwrap_or(self.src.len() - begin);
+                let span = (begin, begin + name_len.max(1) - 1);
+                match self.anchors.get(&id).map(|value| value.clone()) {
+                    Some(value) => {
+                        let role = self.role();
+                        self.record(&role, &value, span);
+                        self.add(value, span, 0, false);
+                    }
+                    None => self.fail(YamlError::Invalid(span, "unknown alias".to_owned())),
+                }
+            }
+            Event::Scalar(s, style, anchor, tag) => self.scalar(s, style, anchor, tag, mark),
+            Event::SequenceStart(anchor, tag) => {
+                let begin = self.byte_idx(&mark);
+                if let Err(err) = self.check_collection_tag(tag, &["seq"], (begin, begin)) {
+                    return self.fail(err);
+                }
+                self.start(|role| Frame::Seq { role, anchor, begin, values: vec![] });
+            }
+            Event::MappingStart(anchor, tag) => {
+                let begin = self.byte_idx(&mark);
+                let is_set = match self.check_collection_tag(tag, &["map", "set"], (begin, begin)) {
+                    Ok(tag) => tag.as_deref() == Some("set"),
+                    Err(err) => return self.fail(err),
+                };
+                self.start(|role| Frame::Map {
+                    role,
+                    anchor,
+                    begin,
+                    is_set,
+                    entries: vec![],
+                    key_spans: vec![],
+                    key_index: KeyIndex::default(),
+                    pending: None,
+                    merges: vec![],
+                });
+            }
+            Event::SequenceEnd | Event::MappingEnd => self.end(mark),
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::{from_yaml_str, to_yaml_string, YamlError, YamlOptions};
+    use crate::{spans::PathSegment, UnknownTags};
+    use archery::RcK;
+    use cljrs_reader::{printer::pr_str, reader, value::RcValue, DuplicateKeys};
+
+    fn edn(src: &str) -> RcValue {
+        reader::<RcK>(src).unwrap().try_read_one().unwrap().unwrap().data
+    }
+
+    fn yaml_to_edn(src: &str) -> Vec<String> {
+        let docs = from_yaml_str::<RcK>(src, &YamlOptions::default()).unwrap();
+        docs.iter().map(|doc| pr_str(&doc.value)).collect()
+    }
+
+    #[test]
+    fn scalars() {
+        let src = "[~, null, '', true, False, 12, -0x1f, 0o17, 1.5e3, .inf, -.INF, '12', \"a\\tb\", plain text, !!str 1, !!float 2]";
+        assert_eq!(
+            yaml_to_edn(src),
+            [r#"[nil nil "" true false 12.0 "-0x1f" 15.0 1500.0 ##Inf ##-Inf "12" "a\tb" "plain text" "1" 2.0]"#]
+        );
+    }
+
+    #[test]
+    fn documents_anchors_and_merges() {
+        let src = "\
+defaults: &defaults
+  timeout: 30
+  retries: 3
+services:
+  - name: web
+    <<: *defaults
+    retries: 5
+  - *defaults
+---
+# empty
+---
+just a string
+";
+        assert_eq!(
+            yaml_to_edn(src),
+            [
+                r#"{:defaults {:timeout 30.0, :retries 3.0}, :services [{:name "web", :retries 5.0, :timeout 30.0} {:timeout 30.0, :retries 3.0}]}"#,
+                "nil",
+                r#""just a string""#,
+            ]
+        );
+        assert_eq!(yaml_to_edn("!!set {a, b}\n"), ["#{:a :b}"]);
+    }
+
+    #[test]
+    fn positions() {
+        let src = "name: café\nports:\n  - 80\n  - \"443\"\nnested: {a: [x]}\n";
+        let docs = from_yaml_str::<RcK>(src, &YamlOptions::default()).unwrap();
+        let spans = &docs[0].spans;
+        let text = |span: Option<(usize, usize)>| span.map(|(begin, end)| &src[begin..=end]);
+        let key = |k: &str| PathSegment::Key(k.to_owned());
+        assert_eq!(text(spans.value(&[key("name")])), Some("café"));
+        assert_eq!(text(spans.key(&[key("ports")])), Some("ports"));
+        assert_eq!(text(spans.value(&[key("ports"), PathSegment::Index(0)])), Some("80"));
+        assert_eq!(text(spans.value(&[key("ports"), PathSegment::Index(1)])), Some("\"443\""));
+        assert_eq!(text(spans.value(&[key("nested")])), Some("{a: [x]}"));
+        assert_eq!(text(spans.value(&[key("ports")])), Some("- 80\n  - \"443\""));
+        assert_eq!(text(spans.value(&[key("nested"), key("a"), PathSegment::Index(0)])), Some("x"));
+    }
+
+    #[test]
+    fn errors() {
+        let err = |src: &str| from_yaml_str::<RcK>(src, &YamlOptions::default()).unwrap_err();
+        assert!(matches!(err("a: [1"), YamlError::Syntax(..)));
+        assert_eq!(err("a: 1\nb: 2\na: 3\n"), YamlError::DuplicateKey((0, 0), (10, 10)));
+        let many = (0..5_000).map(|n| format!("k{n}: {n}\n")).collect::<String>();
+        assert_eq!(from_yaml_str::<RcK>(&many, &YamlOptions::default()).unwrap()[0].value.try_as_map().unwrap().len(), 5_000);
+        assert!(matches!(err(&format!("{many}k4999: x\n")), YamlError::DuplicateKey(..)));
+        assert_eq!(err("a: !Ref b"), YamlError::Invalid((8, 8), "unknown tag !Ref".to_owned()));
+        assert!(matches!(err("a: !!int x"), YamlError::Invalid(..)));
+
+        let lenient = YamlOptions::default()
+            .with_duplicate_keys(DuplicateKeys::LastWins)
+            .with_unknown_tags(UnknownTags::Ignore);
+        let docs = from_yaml_str::<RcK>("a: 1\na: !Ref b\n", &lenient).unwrap();
+        assert_eq!(pr_str(&docs[0].value), r#"{:a "b"}"#);
+    }
+
+    #[test]
+    fn round_trips() {
+        let value = edn(r#"{:name "web", :ports [80 "443"], :ratio 0.5, :on true, :off nil, :nested {:a [:b]}}"#);
+        let yaml = to_yaml_string([&value]).unwrap();
+        let docs = from_yaml_str::<RcK>(&yaml, &YamlOptions::default()).unwrap();
+        assert_eq!(pr_str(&docs[0].value), r#"{:name "web", :ports [80.0 "443"], :ratio 0.5, :on true, :off nil, :nested {:a ["b"]}}"#);
+        assert!(matches!(to_yaml_string([&edn("#{1}")]), Err(YamlError::Unrepresentable(..))));
+    }
+}
@@ -0,0 +1,288 @@
+//! the documented API of a namespace, from the forms of its source rather than by evaluating it
+//!
+//! Sources are read with [`syntax::parse`], which reads all of Clojure's reader macros and keeps
+//! the span of every form; only strings (docstrings and metadata such as `:added`) are read as
+//! values.
+
+use archery::RcK;
+use cljrs_reader::{
+    reader,
+    syntax::{self, Delim, Node, NodeKind},
+    value::Value,
+    ByteIndexSpan, ReadError,
+};
+use cljrs_tooling::syntax::{fn_params, forms, head, is_string, without_meta};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Namespace {
+    pub name: String,
+    pub doc: Option<String>,
+    pub vars: Vec<Var>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Var {
+    pub name: String,
+    /// the defining form's head, e.g. `defn`
+    pub head: String,
+    pub doc: Option<String>,
+    /// the text of each params vector, e.g. `[x & more]`
+    pub arglists: Vec<String>,
+    /// the version in `:added` metadata
+    pub added: Option<String>,
+    /// the version in `:deprecated` metadata, or `true` if it has none
+    pub deprecated: Option<String>,
+    pub private: bool,
+    /// the file the var is defined in, as given to [`extract`]
+    pub file: String,
+    pub span: ByteIndexSpan,
+    /// 1-based, of the first and last lines of the defining form
+    pub lines: (usize, usize),
+}
+
+/// the heads of the forms documented, which must be at the top level of a file
+const DEF_HEADS: &[&str] = &["def", "defn", "defn-", "defmacro", "defmulti", "defonce"];
+
+/// the namespace a file defines, or `None` if it has no `ns` form
+///
+/// Clojure example:
+/// ```clojure
+/// (ns a.core "Does a.")
+/// (defn f "Fs x." {:added "1.0"} [x] x) ; a Var named f, with arglists ["[x]"]
+/// ```
+pub fn extract(file: &str, src: &str) -> Result<Option<Namespace>, ReadError> {
+    let root = syntax::parse(src)?;
+    let tops = forms(src, &root);
+    let Some((ns_name, ns_doc)) = tops.iter().find_map(|form| ns_form(src, form)) else {
+        return Ok(None);
+    };
+    let vars = tops.iter().filter_map(|form| var(file, src, form)).collect();
+    Ok(Some(Namespace {
+        name: ns_name.to_owned(),
+        doc: ns_doc,
+        vars,
+    }))
+}
+
+/// namespaces with the same name (e.g. from `.clj` and `.cljs` files) merged, sorted by name and
+/// with their vars sorted by name
+pub fn merge(namespaces: impl IntoIterator<Item = Namespace>) -> Vec<Namespace> {
+    let mut merged: Vec<Namespace> = vec![];
+    for ns in namespaces {
+        match merged.iter_mut().find(|merged| merged.name == ns.name) {
+            Some(merged) => {
+                merged.doc = merged.doc.take().or(ns.doc);
+                // the first file's definition of a var is documented
+                let new: Vec<_> = ns.vars.into_iter().filter(|var| merged.vars.iter().all(|v| v.name != var.name)).collect();
+                merged.vars.extend(new);
+            }
+            None => merged.push(ns),
+        }
+    }
+    merged.sort_by(|a, b| a.name.cmp(&b.name));
+    for ns in &mut merged {
+        ns.vars.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    merged
+}
+
+fn is_list(node: &Node) -> bool {
+    node.kind == NodeKind::Coll(Delim::List)
+}
+
+/// the contents of a string literal
+fn string_value(src: &str, node: &Node) -> Option<String> {
+    if !is_string(src, node) {
+        return None;
+    }
+    match reader::<RcK>(node.text(src))?.try_read_one().ok()??.data {
+        Value::Str(s) => Some(s),
+        _ => None,
+    }
+}
+
+/// the contents of a string, or the text of any other form, e.g. `true`
+fn string_or_text(src: &str, node: &Node) -> String {
+    string_value(src, node).unwrap_or_else(|| node.text(src).to_owned())
+}
+
+/// the entries of metadata, where `^:private` is `(":private", None)`
+fn meta_entries<'n>(src: &str, meta: &'n Node) -> Vec<(String, Option<&'n Node>)> {
+    match meta.kind {
+        NodeKind::Coll(Delim::Map) => forms(src, meta)
+            .chunks(2)
+            .filter_map(|entry| match entry {
+                [k, v] => Some((k.text(src).to_owned(), Some(*v))),
+                _ => None,
+            })
+            .collect(),
+        NodeKind::Atom if meta.text(src).starts_with(':') => vec![(meta.text(src).to_owned(), None)],
+        _ => vec![],
+    }
+}
+
+fn ns_form<'s>(src: &'s str, form: &Node) -> Option<(&'s str, Option<String>)> {
+    let forms = forms(src, form);
+    if !is_list(form) || head(src, &forms) != Some("ns") {
+        return None;
+    }
+    let (name, meta) = without_meta(src, forms.get(1)?);
+    let mut doc = forms.get(2).and_then(|doc| string_value(src, doc));
+    let attr_map = forms.get(2 + doc.is_some() as usize).filter(|form| form.kind == NodeKind::Coll(Delim::Map));
+    for (key, value) in meta.iter().chain(attr_map).flat_map(|meta| meta_entries(src, meta)) {
+        if key == ":doc" && doc.is_none() {
+            doc = value.and_then(|value| string_value(src, value));
+        }
+    }
+    Some((name.text(src), doc.map(|doc| dedent(&doc))))
+}
+
+fn var(file: &str, src: &str, form: &Node) -> Option<Var> {
+    let forms = forms(src, form);
+    let head = head(src, &forms).filter(|head| is_list(form) && DEF_HEADS.contains(head))?;
+    let (name, name_meta) = without_meta(src, forms.get(1)?);
+    if name.kind != NodeKind::Atom {
+        return None;
+    }
+    let rest = &forms[2..];
+
+    let mut var = Var {
+        name: name.text(src).to_owned(),
+        head: head.to_owned(),
+        doc: None,
+        arglists: vec![],
+        added: None,
+        deprecated: None,
+        private: head == "defn-",
+        file: file.to_owned(),
+        span: form.span,
+        lines: (line(src, form.span.0), line(src, form.span.1)),
+    };
+    // `(def x "doc" init)`, but `(def x "init")` has no docstring
+    let has_doc = match head {
+        "def" | "defonce" => rest.len() == 2,
+        _ => true,
+    };
+    let mut idx = 0;
+    if has_doc {
+        if let Some(doc) = rest.first().and_then(|doc| string_value(src, doc)) {
+            var.doc = Some(doc);
+            idx += 1;
+        }
+    }
+    let mut meta = name_meta;
+    if let "defn" | "defn-" | "defmacro" | "defmulti" = head {
+        if let Some(attr_map) = rest.get(idx).filter(|form| form.kind == NodeKind::Coll(Delim::Map)) {
+            meta.push(attr_map);
+            idx += 1;
+        }
+    }
+    if let "defn" | "defn-" | "defmacro" = head {
+        var.arglists = arglists(src, &rest[idx.min(rest.len())..]);
+    }
+    for (key, value) in meta.into_iter().flat_map(|meta| meta_entries(src, meta)) {
+        match (key.as_str(), value) {
+            (":doc", Some(value)) => var.doc = var.doc.take().or_else(|| string_value(src, value)),
+            (":added", Some(value)) => var.added = Some(string_or_text(src, value)),
+            (":deprecated", value) => var.deprecated = Some(value.map_or("true".to_owned(), |v| string_or_text(src, v))),
+            (":private", value) => var.private = value.is_none_or(|value| value.text(src) == "true"),
+            // `:arglists '([x] [x y])`
+            (":arglists", Some(value)) => {
+                let list = match value.kind {
+                    NodeKind::Prefixed => self::forms(src, value).last().copied(),
+                    _ => Some(value),
+                };
+                let params = list.map(|list| self::forms(src, list)).unwrap_or_default();
+                var.arglists = params.iter().map(|params| params.text(src).to_owned()).collect();
+            }
+            _ => {}
+        }
+    }
+    var.doc = var.doc.map(|doc| dedent(&doc));
+    Some(var)
+}
+
+/// of the forms after a fn's name, docstring and attr-map
+fn arglists(src: &str, forms: &[&Node]) -> Vec<String> {
+    fn_params(src, forms)
+        .iter()
+        .map(|params| params.text(src).split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect()
+}
+
+/// 1-based
+fn line(src: &str, byte_idx: usize) -> usize {
+    src[..byte_idx.min(src.len())].matches('\n').count() + 1
+}
+
+/// a docstring without the indentation its lines after the first have in source
+fn dedent(doc: &str) -> String {
+    let mut lines = doc.lines();
+    let first = lines.next().unwrap_or("").trim();
+    let rest: Vec<_> = lines.collect();
+    let indent = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let rest = rest.iter().map(|line| line.get(indent..).unwrap_or("").trim_end());
+    let lines: Vec<_> = std::iter::once(first).chain(rest).collect();
+    lines.join("\n").trim().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract, merge};
+
+    #[test]
+    fn vars() {
+        let src = r#"(ns ^{:doc "Does a."} a.core
+  (:require [b]))
+
+(defn f
+  "Fs x.
+
+  Or x and y."
+  {:added "1.0" :deprecated "1.2"}
+  ([x] x)
+  ([x   y & more] x))
+
+(def ^:private g 1)
+(def h "doc" 2)
+(def i "not doc")
+(defmacro ^{:arglists '([a])} m [& args] args)
+#_(defn j [])
+(defn- k [^long y] y)
+"#;
+        let ns = extract("src/a/core.clj", src).unwrap().unwrap();
+        assert_eq!((ns.name.as_str(), ns.doc.as_deref()), ("a.core", Some("Does a.")));
+        let names: Vec<_> = ns.vars.iter().map(|var| (var.name.as_str(), var.private)).collect();
+        assert_eq!(names, vec![("f", false), ("g", true), ("h", false), ("i", false), ("m", false), ("k", true)]);
+
+        let f = &ns.vars[0];
+        assert_eq!(f.doc.as_deref(), Some("Fs x.\n\nOr x and y."));
+        assert_eq!(f.arglists, vec!["[x]", "[x y & more]"]);
+        assert_eq!((f.added.as_deref(), f.deprecated.as_deref()), (Some("1.0"), Some("1.2")));
+        assert_eq!(f.lines, (4, 10));
+        assert_eq!(ns.vars[2].doc.as_deref(), Some("doc"));
+        assert_eq!(ns.vars[3].doc, None);
+        assert_eq!(ns.vars[4].arglists, vec!["[a]"]);
+        assert_eq!(ns.vars[5].arglists, vec!["[^long y]"]);
+
+        assert_eq!(extract("b.clj", "(defn f [])"), Ok(None));
+        assert!(extract("b.clj", "(ns b").is_err());
+    }
+
+    #[test]
+    fn merges() {
+        let a = extract("a.clj", "(ns a) (def y 1) (def x 1)").unwrap().unwrap();
+        let b = extract("b.clj", "(ns b)").unwrap().unwrap();
+        let a2 = extract("a.cljs", "(ns a \"A.\") (def x 2) (def z 2)").unwrap().unwrap();
+        let merged = merge([b, a, a2]);
+        assert_eq!(merged.iter().map(|ns| ns.name.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(merged[0].doc.as_deref(), Some("A."));
+        let vars: Vec<_> = merged[0].vars.iter().map(|var| (var.name.as_str(), var.file.as_str())).collect();
+        assert_eq!(vars, vec![("x", "a.clj"), ("y", "a.clj"), ("z", "a.cljs")]);
+    }
+}
@@ -0,0 +1,120 @@
+use cljrs_doc::{
+    extract::{extract, merge},
+    render::{render, Format, Options},
+};
+use cljrs_tooling::source::{describe_error, source_files, CLOJURE_EXTENSIONS};
+use std::{fs, path::PathBuf, process::ExitCode};
+
+const USAGE: &str = "USAGE: cljrs-doc [--format html|markdown] [--out <dir>] [--private] [--source-url <url>] <path>...
+
+  paths may be files or directories (searched for .clj, .cljs and .cljc files), of which those
+  with an ns form are documented
+
+  --format <fmt>      html (the default) or markdown
+  --out <dir>         where the site is written, defaults to ./doc/api
+  --private           document private vars too
+  --source-url <url>  prefixed to source paths in links, e.g. https://github.com/a/b/blob/main
+  -h, --help          print this and exit
+
+  exits 0 on success, 2 on errors";
+
+const DEFAULT_OUT_DIR: &str = "doc/api";
+
+struct Args {
+    options: Options,
+    out_dir: PathBuf,
+    paths: Vec<PathBuf>,
+}
+
+/// the arguments, or none if help was asked for
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut parsed = Args {
+        options: Options::default(),
+        out_dir: DEFAULT_OUT_DIR.into(),
+        paths: vec![],
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => match args.next().as_deref() {
+                Some("html") => parsed.options.format = Format::Html,
+                Some("markdown" | "md") => parsed.options.format = Format::Markdown,
+                _ => return Err("--format requires html or markdown".to_owned()),
+            },
+            "--out" => match args.next() {
+                Some(dir) => parsed.out_dir = dir.into(),
+                None => return Err("--out requires a directory".to_owned()),
+            },
+            "--private" => parsed.options.private = true,
+            "--source-url" => match args.next() {
+                Some(url) => parsed.options.source_url = Some(url),
+                None => return Err("--source-url requires a url".to_owned()),
+            },
+            "-h" | "--help" => return Ok(None),
+            flag if flag.starts_with("--") => return Err(format!("unknown flag {flag}")),
+            path => parsed.paths.push(path.into()),
+        }
+    }
+    if parsed.paths.is_empty() {
+        return Err("no paths given".to_owned());
+    }
+    Ok(Some(parsed))
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(msg) => {
+            eprintln!("{msg}");
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut files = vec![];
+    let mut errored = false;
+    for path in &args.paths {
+        if let Err(err) = source_files(path, CLOJURE_EXTENSIONS, &mut files) {
+            eprintln!("{}: {err}", path.display());
+            errored = true;
+        }
+    }
+    let mut namespaces = vec![];
+    for file in &files {
+        let name = file.display().to_string();
+        let src = match fs::read_to_string(file) {
+            Ok(src) => src,
+            Err(err) => {
+                eprintln!("{name}: {err}");
+                errored = true;
+                continue;
+            }
+        };
+        match extract(&name, &src) {
+            Ok(ns) => namespaces.extend(ns),
+            Err(err) => {
+                eprintln!("{}", describe_error(&err, &src, &name));
+                errored = true;
+            }
+        }
+    }
+
+    let written = fs::create_dir_all(&args.out_dir).and_then(|()| {
+        for page in render(&merge(namespaces), &args.options) {
+            fs::write(args.out_dir.join(&page.path), page.content)?;
+        }
+        Ok(())
+    });
+    if let Err(err) = written {
+        eprintln!("{}: {err}", args.out_dir.display());
+        errored = true;
+    }
+
+    match errored {
+        true => ExitCode::from(2),
+        false => ExitCode::SUCCESS,
+    }
+}
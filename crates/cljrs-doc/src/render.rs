@@ -0,0 +1,344 @@
+//! a static site of namespaces, as HTML or Markdown: an index page, then a page per namespace
+//! with an anchor per var
+//!
+//! Docstrings cross-link as codox's do, with `[[name]]` for a var of the same namespace,
+//! `[[ns/name]]` for one of another, and `[[ns]]` for a namespace.
+
+use crate::extract::{Namespace, Var};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Html,
+    Markdown,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub format: Format,
+    /// whether private vars are documented
+    pub private: bool,
+    /// prefixed to a var's file in links to its source, e.g. `https://github.com/a/b/blob/main`
+    pub source_url: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            format: Format::Html,
+            private: false,
+            source_url: None,
+        }
+    }
+}
+
+/// a file of the site, at `path` relative to the site's root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub path: String,
+    pub content: String,
+}
+
+/// the id of a var's anchor, e.g. `var-valid_3F` for `valid?`
+pub fn anchor(name: &str) -> String {
+    let mut id = String::from("var-");
+    for ch in name.chars() {
+        match ch {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '.' => id.push(ch),
+            ch => {
+                let mut buf = [0; 4];
+                for byte in ch.encode_utf8(&mut buf).bytes() {
+                    id += &format!("_{byte:02X}");
+                }
+            }
+        }
+    }
+    id
+}
+
+/// of namespaces as [`crate::extract::merge`] leaves them
+pub fn render(namespaces: &[Namespace], options: &Options) -> Vec<Page> {
+    let site = Site { namespaces, options };
+    let ext = options.format.extension();
+    let mut pages = vec![Page {
+        path: format!("index.{ext}"),
+        content: site.index(),
+    }];
+    for ns in namespaces {
+        pages.push(Page {
+            path: format!("{}.{ext}", ns.name),
+            content: site.namespace(ns),
+        });
+    }
+    pages
+}
+
+struct Site<'a> {
+    namespaces: &'a [Namespace],
+    options: &'a Options,
+}
+
+impl Site<'_> {
+    fn documented<'n>(&self, ns: &'n Namespace) -> impl Iterator<Item = &'n Var> {
+        let private = self.options.private;
+        ns.vars.iter().filter(move |var| private || !var.private)
+    }
+
+    fn ns_href(&self, ns: &str) -> String {
+        format!("{ns}.{}", self.options.format.extension())
+    }
+
+    /// the href of `[[target]]` in a docstring of `ns`, if it names something documented
+    fn resolve(&self, ns: &Namespace, target: &str) -> Option<String> {
+        let find = |ns: &Namespace, name: &str| self.documented(ns).any(|var| var.name == name);
+        if let Some(other) = self.namespaces.iter().find(|other| other.name == target) {
+            return Some(self.ns_href(&other.name));
+        }
+        match target.split_once('/').filter(|(ns, name)| !ns.is_empty() && !name.is_empty()) {
+            Some((other, name)) => {
+                let other = self.namespaces.iter().find(|ns| ns.name == other)?;
+                find(other, name).then(|| format!("{}#{}", self.ns_href(&other.name), anchor(name)))
+            }
+            None => find(ns, target).then(|| format!("#{}", anchor(target))),
+        }
+    }
+
+    fn source_href(&self, var: &Var) -> String {
+        let file = match &self.options.source_url {
+            Some(url) => format!("{}/{}", url.trim_end_matches('/'), var.file),
+            None => var.file.clone(),
+        };
+        format!("{file}#L{}-L{}", var.lines.0, var.lines.1)
+    }
+
+    /// a docstring with its cross-links resolved, and `[[x]]`s that don't resolve as code
+    fn doc(&self, ns: &Namespace, doc: &str) -> String {
+        let mut out = String::new();
+        let mut rest = doc;
+        while let Some((before, after)) = rest.split_once("[[") {
+            let Some((target, after)) = after.split_once("]]") else {
+                break;
+            };
+            out += &self.text(before);
+            out += &match (self.options.format, self.resolve(ns, target)) {
+                (Format::Html, Some(href)) => format!("<a href=\"{}\"><code>{}</code></a>", escape(&href), escape(target)),
+                (Format::Html, None) => format!("<code>{}</code>", escape(target)),
+                (Format::Markdown, Some(href)) => format!("[`{target}`]({href})"),
+                (Format::Markdown, None) => format!("`{target}`"),
+            };
+            rest = after;
+        }
+        out += &self.text(rest);
+        out
+    }
+
+    fn text(&self, text: &str) -> String {
+        match self.options.format {
+            Format::Html => escape(text),
+            Format::Markdown => text.to_owned(),
+        }
+    }
+
+    fn index(&self) -> String {
+        match self.options.format {
+            Format::Html => {
+                let mut body = String::from("<h1>Namespaces</h1>\n<dl>\n");
+                for ns in self.namespaces {
+                    let href = escape(&self.ns_href(&ns.name));
+                    body += &format!("<dt><a href=\"{href}\">{}</a></dt>\n", escape(&ns.name));
+                    if let Some(summary) = ns.doc.as_deref().and_then(|doc| doc.lines().next()) {
+                        body += &format!("<dd>{}</dd>\n", self.doc(ns, summary));
+                    }
+                }
+                body += "</dl>\n";
+                html_page("Namespaces", &body)
+            }
+            Format::Markdown => {
+                let mut page = String::from("# Namespaces\n\n");
+                for ns in self.namespaces {
+                    page += &format!("- [{}]({})", ns.name, self.ns_href(&ns.name));
+                    if let Some(summary) = ns.doc.as_deref().and_then(|doc| doc.lines().next()) {
+                        page += &format!(": {}", self.doc(ns, summary));
+                    }
+                    page += "\n";
+                }
+                page
+            }
+        }
+    }
+
+    fn namespace(&self, ns: &Namespace) -> String {
+        match self.options.format {
+            Format::Html => {
+                let mut body = format!("<h1>{}</h1>\n", escape(&ns.name));
+                if let Some(doc) = &ns.doc {
+                    body += &format!("<pre class=\"doc\">{}</pre>\n", self.doc(ns, doc));
+                }
+                body += "<ul class=\"index\">\n";
+                for var in self.documented(ns) {
+                    body += &format!("<li><a href=\"#{}\">{}</a></li>\n", anchor(&var.name), escape(&var.name));
+                }
+                body += "</ul>\n";
+                for var in self.documented(ns) {
+                    body += &self.html_var(ns, var);
+                }
+                html_page(&ns.name, &body)
+            }
+            Format::Markdown => {
+                let mut page = format!("# {}\n\n", ns.name);
+                if let Some(doc) = &ns.doc {
+                    page += &format!("{}\n\n", self.doc(ns, doc));
+                }
+                for var in self.documented(ns) {
+                    page += &format!("- [`{}`](#{})\n", var.name, anchor(&var.name));
+                }
+                for var in self.documented(ns) {
+                    page += &self.markdown_var(ns, var);
+                }
+                page
+            }
+        }
+    }
+
+    fn html_var(&self, ns: &Namespace, var: &Var) -> String {
+        let mut html = format!("<section id=\"{}\">\n<h2>{}</h2>\n", anchor(&var.name), escape(&var.name));
+        html += &format!("<p class=\"meta\">{}</p>\n", escape(&details(var).join(" · ")));
+        if !var.arglists.is_empty() {
+            html += &format!("<pre class=\"usage\">{}</pre>\n", escape(&usages(var).join("\n")));
+        }
+        if let Some(doc) = &var.doc {
+            html += &format!("<pre class=\"doc\">{}</pre>\n", self.doc(ns, doc));
+        }
+        let source = escape(&self.source_href(var));
+        html += &format!("<p><a class=\"source\" href=\"{source}\">Source</a></p>\n</section>\n");
+        html
+    }
+
+    fn markdown_var(&self, ns: &Namespace, var: &Var) -> String {
+        let (id, details) = (anchor(&var.name), details(var).join(" · "));
+        let mut md = format!("\n<a id=\"{id}\"></a>\n### `{}`\n\n{details}\n\n", var.name);
+        if !var.arglists.is_empty() {
+            md += &format!("```clojure\n{}\n```\n\n", usages(var).join("\n"));
+        }
+        if let Some(doc) = &var.doc {
+            md += &format!("{}\n\n", self.doc(ns, doc));
+        }
+        md += &format!("[Source]({})\n", self.source_href(var));
+        md
+    }
+}
+
+/// e.g. `defn`, `private`, `added in 1.0`, `deprecated in 1.2`
+fn details(var: &Var) -> Vec<String> {
+    let mut details = vec![var.head.clone()];
+    if var.private {
+        details.push("private".to_owned());
+    }
+    if let Some(added) = &var.added {
+        details.push(format!("added in {added}"));
+    }
+    match var.deprecated.as_deref() {
+        Some("true") => details.push("deprecated".to_owned()),
+        Some(version) => details.push(format!("deprecated in {version}")),
+        None => {}
+    }
+    details
+}
+
+/// e.g. `(f x y)` for `[x y]`
+fn usages(var: &Var) -> Vec<String> {
+    let usage = |params: &String| {
+        let params = params.strip_prefix('[').and_then(|params| params.strip_suffix(']')).unwrap_or(params);
+        match params.trim() {
+            "" => format!("({})", var.name),
+            params => format!("({} {params})", var.name),
+        }
+    };
+    var.arglists.iter().map(usage).collect()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 50em; margin: auto; }
+pre.doc { white-space: pre-wrap; font-family: inherit; }
+p.meta { color: #666; }";
+
+fn html_page(title: &str, body: &str) -> String {
+    let head = format!("<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{STYLE}\n</style>\n", escape(title));
+    format!("<!DOCTYPE html>\n<html>\n<head>\n{head}</head>\n<body>\n{body}</body>\n</html>\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{anchor, render, Format, Options};
+    use crate::extract::{extract, merge};
+
+    fn site(format: Format) -> Vec<(String, String)> {
+        let a = extract(
+            "src/a.clj",
+            concat!(
+                "(ns a \"Uses [[b]].\")\n",
+                "(defn f \"Like [[b/valid?]], not [[h]] or [[b/nope]].\" {:added \"1.0\"} [x & xs] x)\n",
+                "(defn- h [] 1)",
+            ),
+        );
+        let b = extract("src/b.clj", "(ns b)\n(defn valid? \"See [[a/f]].\" {:deprecated true} [])");
+        let namespaces = merge([a.unwrap().unwrap(), b.unwrap().unwrap()]);
+        let options = Options {
+            format,
+            source_url: Some("https://example.com/blob/main/".to_owned()),
+            ..Options::default()
+        };
+        render(&namespaces, &options).into_iter().map(|page| (page.path, page.content)).collect()
+    }
+
+    #[test]
+    fn markdown() {
+        let pages = site(Format::Markdown);
+        let paths: Vec<_> = pages.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, vec!["index.md", "a.md", "b.md"]);
+        assert_eq!(pages[0].1, "# Namespaces\n\n- [a](a.md): Uses [`b`](b.md).\n- [b](b.md)\n");
+        assert_eq!(
+            pages[1].1,
+            concat!(
+                "# a\n\nUses [`b`](b.md).\n\n- [`f`](#var-f)\n",
+                "\n<a id=\"var-f\"></a>\n### `f`\n\ndefn · added in 1.0\n\n```clojure\n(f x & xs)\n```\n\n",
+                "Like [`b/valid?`](b.md#var-valid_3F), not `h` or `b/nope`.\n\n",
+                "[Source](https://example.com/blob/main/src/a.clj#L2-L2)\n",
+            )
+        );
+        let valid = "### `valid?`\n\ndefn · deprecated\n\n```clojure\n(valid?)\n```\n\nSee [`a/f`](a.md#var-f).";
+        assert!(pages[2].1.contains(valid));
+    }
+
+    #[test]
+    fn html() {
+        let pages = site(Format::Html);
+        assert_eq!(pages[1].0, "a.html");
+        assert!(pages[1].1.contains("<section id=\"var-f\">\n<h2>f</h2>\n"));
+        assert!(pages[2].1.contains("<a href=\"#var-valid_3F\">valid?</a>"));
+        assert!(pages[2].1.contains("See <a href=\"a.html#var-f\"><code>a/f</code></a>."));
+        assert!(pages[1].1.contains("<pre class=\"usage\">(f x &amp; xs)</pre>"));
+        assert_eq!(anchor("->a*"), "var--_3Ea_2A");
+    }
+}
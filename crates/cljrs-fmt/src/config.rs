@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use archery::RcK;
+use cljrs_reader::value::{RcValue, Value};
+use cljrs_tooling::config::{invalid, keyword_name, read_config};
+
+pub use cljrs_tooling::config::ConfigError;
+
+/// Clojure example:
+/// ```clojure
+/// {:indents {let     [[:block 1]]
+///            defn    [[:inner 0]]
+///            reify   [[:inner 0] [:inner 1]]}}
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentRule {
+    /// `[:block n]`: if no more than `n` arguments are on the form's first line, the
+    /// following lines are indented 2 columns, otherwise they're aligned with the first argument
+    Block(usize),
+    /// `[:inner depth]` or `[:inner depth idx]`: lists `depth` levels within the form (0 being
+    /// the form itself), or only the one at index `idx` at that level, are indented 2 columns
+    Inner(usize, Option<usize>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// keyed by symbol name, rules for `foo` also apply to `some.ns/foo`
+    pub indents: HashMap<String, Vec<IndentRule>>,
+    /// symbols beginning with `def` without rules of their own are given `[[:inner 0]]`
+    pub def_prefix_is_inner: bool,
+    pub remove_trailing_whitespace: bool,
+    /// `( foo )` becomes `(foo)`
+    pub remove_surrounding_whitespace: bool,
+}
+
+pub fn default_indents() -> HashMap<String, Vec<IndentRule>> {
+    use IndentRule::{Block, Inner};
+    let rules: &[(&str, &[IndentRule])] = &[
+        ("ns", &[Block(1)]),
+        ("do", &[Block(0)]),
+        ("doto", &[Block(1)]),
+        ("if", &[Block(1)]),
+        ("if-not", &[Block(1)]),
+        ("if-let", &[Block(1)]),
+        ("if-some", &[Block(1)]),
+        ("when", &[Block(1)]),
+        ("when-not", &[Block(1)]),
+        ("when-let", &[Block(1)]),
+        ("when-some", &[Block(1)]),
+        ("when-first", &[Block(1)]),
+        ("let", &[Block(1)]),
+        ("loop", &[Block(1)]),
+        ("binding", &[Block(1)]),
+        ("with-open", &[Block(1)]),
+        ("with-redefs", &[Block(1)]),
+        ("doseq", &[Block(1)]),
+        ("dotimes", &[Block(1)]),
+        ("for", &[Block(1)]),
+        ("case", &[Block(1)]),
+        ("condp", &[Block(2)]),
+        ("cond", &[Block(0)]),
+        ("cond->", &[Block(1)]),
+        ("cond->>", &[Block(1)]),
+        ("try", &[Block(0)]),
+        ("catch", &[Block(2)]),
+        ("finally", &[Block(0)]),
+        ("locking", &[Block(1)]),
+        ("future", &[Block(0)]),
+        ("comment", &[Block(0)]),
+        ("fn", &[Inner(0, None)]),
+        ("def", &[Inner(0, None)]),
+        ("defn", &[Inner(0, None)]),
+        ("defn-", &[Inner(0, None)]),
+        ("defmacro", &[Inner(0, None)]),
+        ("defmethod", &[Inner(0, None)]),
+        ("defmulti", &[Inner(0, None)]),
+        ("defprotocol", &[Block(1), Inner(1, None)]),
+        ("defrecord", &[Block(2), Inner(1, None)]),
+        ("deftype", &[Block(2), Inner(1, None)]),
+        ("extend-protocol", &[Block(1), Inner(1, None)]),
+        ("extend-type", &[Block(1), Inner(1, None)]),
+        ("reify", &[Inner(0, None), Inner(1, None)]),
+        ("proxy", &[Block(2), Inner(1, None)]),
+        ("letfn", &[Block(1), Inner(2, Some(0))]),
+    ];
+    rules
+        .iter()
+        .map(|(name, rules)| (name.to_string(), rules.to_vec()))
+        .collect()
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            indents: default_indents(),
+            def_prefix_is_inner: true,
+            remove_trailing_whitespace: true,
+            remove_surrounding_whitespace: true,
+        }
+    }
+}
+
+fn parse_rule(value: &RcValue) -> Result<IndentRule, ConfigError> {
+    let parts = match value {
+        Value::Vect(parts) => parts,
+        _ => return invalid("each indent rule must be a vector, e.g. [:block 1]"),
+    };
+    let nums = parts
+        .iter()
+        .skip(1)
+        .map(|part| match part.as_ref() {
+            Value::Num(n) if *n >= 0.0 && n.fract() == 0.0 => Ok(*n as usize),
+            _ => invalid("indent rule arguments must be non-negative integers"),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match (parts.first().and_then(|kind| keyword_name(kind)), nums.as_slice()) {
+        (Some("block"), [n]) => Ok(IndentRule::Block(*n)),
+        (Some("inner"), [depth]) => Ok(IndentRule::Inner(*depth, None)),
+        (Some("inner"), [depth, idx]) => Ok(IndentRule::Inner(*depth, Some(*idx))),
+        _ => invalid("indent rules are [:block n], [:inner depth] or [:inner depth idx]"),
+    }
+}
+
+fn parse_indents(value: &RcValue) -> Result<HashMap<String, Vec<IndentRule>>, ConfigError> {
+    let entries = match value {
+        Value::Map(entries) => entries,
+        _ => return invalid(":indents must be a map"),
+    };
+    let mut indents = HashMap::new();
+    for (k, v) in entries {
+        let name = match k.as_ref() {
+            Value::Symbol(sym) => sym.to_string(),
+            _ => return invalid(":indents keys must be symbols"),
+        };
+        let rules = match v.as_ref() {
+            Value::Vect(rules) => rules.iter().map(|rule| parse_rule(rule)).collect::<Result<_, _>>()?,
+            _ => return invalid(":indents values must be vectors of rules"),
+        };
+        indents.insert(name, rules);
+    }
+    Ok(indents)
+}
+
+impl Config {
+    /// Clojure example:
+    /// ```clojure
+    /// {:indents {my-macro [[:block 1]]} ; merged over the defaults
+    ///  :replace-default-indents? false
+    ///  :def-prefix-is-inner? true
+    ///  :remove-trailing-whitespace? true
+    ///  :remove-surrounding-whitespace? true}
+    /// ```
+    pub fn from_edn(src: &str) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        let Some(entries) = read_config::<RcK>(src)? else {
+            return Ok(config);
+        };
+
+        let mut indents = None;
+        for (k, v) in &entries {
+            let flag = match v.as_ref() {
+                Value::Bool(b) => Some(*b),
+                _ => None,
+            };
+            match (keyword_name(k), flag) {
+                (Some("indents"), _) => indents = Some(parse_indents(v)?),
+                (Some("replace-default-indents?"), Some(true)) => config.indents.clear(),
+                (Some("replace-default-indents?"), Some(false)) => {}
+                (Some("def-prefix-is-inner?"), Some(b)) => config.def_prefix_is_inner = b,
+                (Some("remove-trailing-whitespace?"), Some(b)) => config.remove_trailing_whitespace = b,
+                (Some("remove-surrounding-whitespace?"), Some(b)) => {
+                    config.remove_surrounding_whitespace = b
+                }
+                (Some(key), _) => return invalid(&format!("unknown or non-boolean option :{key}")),
+                (None, _) => return invalid("config keys must be unqualified keywords"),
+            }
+        }
+        config.indents.extend(indents.unwrap_or_default());
+        Ok(config)
+    }
+
+    /// rules for `sym`, which may be namespace-qualified
+    pub fn rules_for(&self, sym: &str) -> Vec<IndentRule> {
+        let name = match sym.rsplit_once('/') {
+            Some((_ns, name)) if !name.is_empty() => name,
+            _ => sym,
+        };
+        match self.indents.get(sym).or_else(|| self.indents.get(name)) {
+            Some(rules) => rules.clone(),
+            None if self.def_prefix_is_inner && name.starts_with("def") => {
+                vec![IndentRule::Inner(0, None)]
+            }
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, ConfigError, IndentRule};
+
+    #[test]
+    fn merged_over_defaults() {
+        let config = Config::from_edn(
+            "{:indents {my-let [[:block 1]], my-fn [[:inner 0] [:inner 2 0]]}
+              :remove-trailing-whitespace? false}",
+        )
+        .unwrap();
+        assert_eq!(config.rules_for("my-let"), vec![IndentRule::Block(1)]);
+        assert_eq!(
+            config.rules_for("my.ns/my-fn"),
+            vec![IndentRule::Inner(0, None), IndentRule::Inner(2, Some(0))]
+        );
+        assert_eq!(config.rules_for("clojure.core/let"), vec![IndentRule::Block(1)]);
+        assert_eq!(config.rules_for("defthing"), vec![IndentRule::Inner(0, None)]);
+        assert!(!config.remove_trailing_whitespace);
+    }
+
+    #[test]
+    fn replacing_defaults() {
+        let config = Config::from_edn("{:replace-default-indents? true}").unwrap();
+        assert_eq!(config.rules_for("let"), vec![]);
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let err = Config::from_edn("{:indents {let [[:bloc 1]]}}").unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid(..)), "{err}");
+        let err = Config::from_edn("{:indents {let [[:block -1]]}}").unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid(..)), "{err}");
+    }
+}
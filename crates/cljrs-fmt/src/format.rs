@@ -0,0 +1,294 @@
+use crate::config::{Config, IndentRule};
+use cljrs_reader::{
+    syntax::{self, Delim, Node, NodeKind},
+    ReadError,
+};
+
+/// Clojure example:
+/// ```clojure
+/// ;; before
+/// (defn f [x]
+/// (let [y x]
+///      y  ))
+/// ;; after
+/// (defn f [x]
+///   (let [y x]
+///     y))
+/// ```
+pub fn format_source(src: &str, config: &Config) -> Result<String, ReadError> {
+    let root = syntax::parse(src)?;
+    let mut formatter = Formatter {
+        src,
+        config,
+        out: String::with_capacity(src.len()),
+        line: 0,
+        line_start: 0,
+    };
+    formatter.emit_node(&root, &mut vec![]);
+    Ok(formatter.out)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Container {
+    Root,
+    /// lines continuing a prefixed form are aligned with its prefix
+    Prefixed { col: usize },
+    Coll {
+        delim: Delim,
+        open_col: usize,
+        open_line: usize,
+    },
+}
+
+/// a collection enclosing the node being formatted
+#[derive(Debug)]
+struct Level {
+    /// the symbol a list-like collection begins with
+    head: Option<String>,
+    /// index, among the collection's forms, of the one being formatted
+    form_idx: usize,
+}
+
+/// a symbol, as opposed to a number, keyword, string, char, regex etc.
+fn is_symbol(text: &str) -> bool {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some('+' | '-'), Some(ch)) => !ch.is_ascii_digit(),
+        (Some(ch), _) => !ch.is_ascii_digit() && !matches!(ch, ':' | '"' | '\\' | '#'),
+        (None, _) => false,
+    }
+}
+
+struct Formatter<'s> {
+    src: &'s str,
+    config: &'s Config,
+    out: String,
+    line: usize,
+    /// byte index into `out`
+    line_start: usize,
+}
+
+impl Formatter<'_> {
+    fn col(&self) -> usize {
+        self.out[self.line_start..].chars().count()
+    }
+
+    fn push(&mut self, s: &str) {
+        self.out.push_str(s);
+        if let Some(last_newline) = s.rfind('\n') {
+            self.line += s.matches('\n').count();
+            self.line_start = self.out.len() - (s.len() - last_newline - 1);
+        }
+    }
+
+    fn emit_node(&mut self, node: &Node, path: &mut Vec<Level>) {
+        match node.kind {
+            NodeKind::Root => self.emit_children(node, Container::Root, path),
+            NodeKind::Prefixed => {
+                let col = self.col();
+                self.emit_children(node, Container::Prefixed { col }, path)
+            }
+            NodeKind::Coll(delim) => {
+                let container = Container::Coll {
+                    delim,
+                    open_col: self.col(),
+                    open_line: self.line,
+                };
+                self.push(delim.open());
+                let head = node
+                    .forms()
+                    .next()
+                    .filter(|head| delim.is_list_like() && head.kind == NodeKind::Atom)
+                    .map(|head| head.text(self.src))
+                    .filter(|text| is_symbol(text))
+                    .map(str::to_owned);
+                path.push(Level { head, form_idx: 0 });
+                self.emit_children(node, container, path);
+                path.pop();
+                self.push(&delim.close().to_string());
+            }
+            NodeKind::Comment => self.push(node.text(self.src).trim_end()),
+            NodeKind::Whitespace | NodeKind::Newline | NodeKind::Atom | NodeKind::Prefix => {
+                self.push(node.text(self.src))
+            }
+        }
+    }
+
+    fn emit_children(&mut self, node: &Node, container: Container, path: &mut Vec<Level>) {
+        let is_coll = matches!(container, Container::Coll { .. });
+        let trim_surrounding = is_coll && self.config.remove_surrounding_whitespace;
+        let mut at_line_start = matches!(container, Container::Root);
+        let mut prev: Option<NodeKind> = None;
+        // (col, line) each form starts at in the output
+        let mut forms: Vec<(usize, usize)> = vec![];
+
+        for (idx, child) in node.children.iter().enumerate() {
+            let rest_is_blank = node.children[idx..]
+                .iter()
+                .all(|child| matches!(child.kind, NodeKind::Whitespace | NodeKind::Newline));
+            match child.kind {
+                NodeKind::Whitespace => {
+                    let next_is_newline = node
+                        .children
+                        .get(idx + 1)
+                        .map(|next| next.kind == NodeKind::Newline)
+                        .unwrap_or(false);
+                    let is_trailing = next_is_newline || (!is_coll && rest_is_blank);
+                    let is_surrounding = prev.is_none() || rest_is_blank;
+                    let is_dropped = at_line_start
+                        || (self.config.remove_trailing_whitespace && is_trailing)
+                        || (trim_surrounding && is_surrounding);
+                    if !is_dropped {
+                        self.push(child.text(self.src));
+                    }
+                }
+                NodeKind::Newline if trim_surrounding && prev.is_none() => {}
+                NodeKind::Newline if trim_surrounding && rest_is_blank => {
+                    // a closing delimiter can't be moved onto a line ending with a comment
+                    if prev == Some(NodeKind::Comment) {
+                        self.push(child.text(self.src));
+                        at_line_start = true;
+                    }
+                    break;
+                }
+                NodeKind::Newline => {
+                    self.push(child.text(self.src));
+                    at_line_start = true;
+                }
+                _ => {
+                    if at_line_start {
+                        let indent = self.indent(container, &forms, path);
+                        self.push(&" ".repeat(indent));
+                        at_line_start = false;
+                    }
+                    if child.kind != NodeKind::Comment {
+                        if let (true, Some(level)) = (is_coll, path.last_mut()) {
+                            level.form_idx = forms.len();
+                        }
+                        forms.push((self.col(), self.line));
+                    }
+                    self.emit_node(child, path);
+                    prev = Some(child.kind);
+                }
+            }
+        }
+
+        if is_coll && at_line_start {
+            // the closing delimiter
+            let indent = self.indent(container, &forms, path);
+            self.push(&" ".repeat(indent));
+        }
+    }
+
+    /// the column a line within `container` begins at, given its forms so far
+    fn indent(&self, container: Container, forms: &[(usize, usize)], path: &[Level]) -> usize {
+        let (delim, open_col, open_line) = match container {
+            Container::Root => return 0,
+            Container::Prefixed { col } => return col,
+            Container::Coll {
+                delim,
+                open_col,
+                open_line,
+            } => (delim, open_col, open_line),
+        };
+        let base = open_col + delim.open().len();
+        if !delim.is_list_like() {
+            return base;
+        }
+
+        // `path` ends with this collection, which is at depth 0 of its own head
+        for (depth, level) in path.iter().rev().enumerate() {
+            let Some(head) = &level.head else { continue };
+            let inner = self.config.rules_for(head).into_iter().any(|rule| match rule {
+                IndentRule::Inner(d, None) => d == depth,
+                IndentRule::Inner(d, Some(arg_idx)) => {
+                    d == depth && depth > 0 && level.form_idx == arg_idx + 1
+                }
+                IndentRule::Block(..) => false,
+            });
+            if inner {
+                return base + 1;
+            }
+        }
+
+        if let Some(head) = path.last().and_then(|level| level.head.as_ref()) {
+            let args_on_first_line = forms.iter().skip(1).filter(|(_, line)| *line == open_line).count();
+            let is_block = self.config.rules_for(head).into_iter().any(|rule| match rule {
+                IndentRule::Block(n) => args_on_first_line <= n,
+                IndentRule::Inner(..) => false,
+            });
+            if is_block {
+                return base + 1;
+            }
+        }
+
+        match forms.get(1) {
+            Some((col, line)) if *line == open_line => *col,
+            _ => base,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_source;
+    use crate::config::Config;
+    use cljrs_reader::ReadError;
+
+    fn fmt(src: &str) -> String {
+        let formatted = format_source(src, &Config::default()).unwrap();
+        assert_eq!(format_source(&formatted, &Config::default()).unwrap(), formatted, "not idempotent");
+        formatted
+    }
+
+    #[test]
+    fn reindents_by_rules() {
+        let src = "(defn f [x]\n(let [y x]\n     (when y\n  (g y\n   x))))\n";
+        assert_eq!(fmt(src), "(defn f [x]\n  (let [y x]\n    (when y\n      (g y\n         x))))\n");
+    }
+
+    #[test]
+    fn block_with_args_on_first_line_aligns() {
+        let src = "(if a\nb\nc)\n(if a b\nc)";
+        assert_eq!(fmt(src), "(if a\n  b\n  c)\n(if a b\n    c)");
+    }
+
+    #[test]
+    fn inner_rules_apply_below_the_form() {
+        let src = "(letfn [(f [x]\nx)]\n(f 1))\n(reify P\n(m [this]\nthis))";
+        assert_eq!(
+            fmt(src),
+            "(letfn [(f [x]\n          x)]\n  (f 1))\n(reify P\n  (m [this]\n    this))"
+        );
+    }
+
+    #[test]
+    fn data_aligns_with_first_element() {
+        let src = "{:a 1\n:b [1\n2]}\n(:k\nm)";
+        assert_eq!(fmt(src), "{:a 1\n :b [1\n     2]}\n(:k\n m)");
+    }
+
+    #[test]
+    fn whitespace_is_trimmed() {
+        let src = "(  a b   \n  c  )  \n\n\n[ ]";
+        assert_eq!(fmt(src), "(a b\n   c)\n\n\n[]");
+    }
+
+    #[test]
+    fn comments_are_kept() {
+        let src = "(do ; why   \n  a ; because\n   )\n#_(x\ny)";
+        assert_eq!(fmt(src), "(do ; why\n  a ; because\n  )\n#_(x\n   y)");
+    }
+
+    #[test]
+    fn multi_line_strings_are_untouched() {
+        let src = "(str \"a\n  b\"\n\"c\")";
+        assert_eq!(fmt(src), "(str \"a\n  b\"\n     \"c\")");
+    }
+
+    #[test]
+    fn unreadable_source() {
+        let err = format_source("(a", &Config::default()).unwrap_err();
+        assert_eq!(err, ReadError::insufficient_input((0, 1)));
+    }
+}
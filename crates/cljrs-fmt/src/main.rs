@@ -0,0 +1,167 @@
+use cljrs_fmt::{config::Config, format::format_source};
+use cljrs_tooling::source::{describe_error, source_files, CLOJURE_AND_EDN_EXTENSIONS};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+const USAGE: &str = "USAGE: cljrs-fmt [--check] [--config <path>] <path>...
+
+  paths may be files or directories (searched for .clj, .cljs, .cljc and .edn files),
+  or - to format stdin to stdout
+
+  --check          report files that would be reformatted instead of rewriting them
+  --config <path>  EDN config, defaults to ./.cljrs-fmt.edn if it exists
+
+  -h, --help       print this and exit
+
+  exits 0 on success, 1 if --check found unformatted files, 2 on errors";
+
+const DEFAULT_CONFIG_PATH: &str = ".cljrs-fmt.edn";
+
+struct Args {
+    check: bool,
+    config_path: Option<PathBuf>,
+    paths: Vec<PathBuf>,
+}
+
+/// the arguments, or none if help was asked for
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut parsed = Args {
+        check: false,
+        config_path: None,
+        paths: vec![],
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check" => parsed.check = true,
+            "--config" => match args.next() {
+                Some(path) => parsed.config_path = Some(path.into()),
+                None => return Err("--config requires a path".to_owned()),
+            },
+            "-h" | "--help" => return Ok(None),
+            flag if flag.starts_with("--") => return Err(format!("unknown flag {flag}")),
+            path => parsed.paths.push(path.into()),
+        }
+    }
+    if parsed.paths.is_empty() {
+        return Err("no paths given".to_owned());
+    }
+    Ok(Some(parsed))
+}
+
+fn load_config(path: Option<&Path>) -> Result<Config, String> {
+    let (path, required) = match path {
+        Some(path) => (path, true),
+        None => (Path::new(DEFAULT_CONFIG_PATH), false),
+    };
+    match fs::read_to_string(path) {
+        Ok(src) => Config::from_edn(&src).map_err(|err| format!("{}: {err}", path.display())),
+        Err(err) if required || err.kind() != io::ErrorKind::NotFound => {
+            Err(format!("{}: {err}", path.display()))
+        }
+        Err(_) => Ok(Config::default()),
+    }
+}
+
+enum Outcome {
+    Unchanged,
+    Changed,
+}
+
+fn format_path(path: &Path, config: &Config, check: bool) -> Result<Outcome, String> {
+    let is_stdin = path == Path::new("-");
+    let name = path.display().to_string();
+    let mut src = String::new();
+    let read = match is_stdin {
+        true => io::stdin().read_to_string(&mut src).map(|_| ()),
+        false => fs::read_to_string(path).map(|read| src = read),
+    };
+    read.map_err(|err| format!("{name}: {err}"))?;
+
+    let formatted = format_source(&src, config).map_err(|err| describe_error(&err, &src, &name))?;
+    let outcome = match formatted == src {
+        true => Outcome::Unchanged,
+        false => Outcome::Changed,
+    };
+    let written = match (is_stdin, check, &outcome) {
+        (true, false, _) => io::stdout().write_all(formatted.as_bytes()),
+        (false, false, Outcome::Changed) => fs::write(path, &formatted),
+        _ => Ok(()),
+    };
+    written.map_err(|err| format!("{name}: {err}"))?;
+    Ok(outcome)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(msg) => {
+            eprintln!("{msg}");
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let config = match load_config(args.config_path.as_deref()) {
+        Ok(config) => config,
+        Err(msg) => {
+            eprintln!("{msg}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut files = vec![];
+    let mut errored = false;
+    for path in &args.paths {
+        if let Err(err) = source_files(path, CLOJURE_AND_EDN_EXTENSIONS, &mut files) {
+            eprintln!("{}: {err}", path.display());
+            errored = true;
+        }
+    }
+
+    let mut changed = false;
+    for file in &files {
+        match format_path(file, &config, args.check) {
+            Ok(Outcome::Changed) if args.check => {
+                println!("{}", file.display());
+                changed = true;
+            }
+            Ok(_) => {}
+            Err(msg) => {
+                eprintln!("{msg}");
+                errored = true;
+            }
+        }
+    }
+
+    match (errored, changed) {
+        (true, _) => ExitCode::from(2),
+        (false, true) => ExitCode::from(1),
+        (false, false) => ExitCode::SUCCESS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_args;
+
+    fn parse(args: &[&str]) -> Result<Option<Vec<String>>, String> {
+        let args = args.iter().map(|arg| arg.to_string());
+        parse_args(args).map(|args| args.map(|args| args.paths.iter().map(|path| path.display().to_string()).collect()))
+    }
+
+    #[test]
+    fn parses_args() {
+        assert_eq!(parse(&["--check", "src"]), Ok(Some(vec!["src".to_owned()])));
+        assert_eq!(parse(&["src", "--help"]), Ok(None));
+        assert_eq!(parse(&["-h"]), Ok(None));
+        assert_eq!(parse(&["--nope", "src"]), Err("unknown flag --nope".to_owned()));
+        assert_eq!(parse(&[]), Err("no paths given".to_owned()));
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use archery::RcK;
+use cljrs_reader::value::{RcValue, Value};
+use cljrs_tooling::config::{invalid, keyword_name, read_config};
+
+pub use cljrs_tooling::config::ConfigError;
+
+/// what the linter checks for, named in configs and reports as clj-kondo names its linters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Check {
+    /// source that doesn't read, other than an unbalanced `#_`
+    Syntax,
+    /// `(let [x 1] 2)`, also of `loop`, `when-let`, `doseq`, `for` etc., but not of fn params
+    UnusedBinding,
+    /// `[a.b :as ab]` where `ab/` never appears, or `[a.b :refer [x]]` where `x` never does
+    UnusedRequire,
+    /// `(let [x 1] (fn [x] x))`
+    ShadowedLocal,
+    /// `(defn f [])` after `(def f 1)` in the same file
+    RedefinedVar,
+    /// `(f 1 2)` where `f` is defined in the same file without an arity of two
+    WrongArity,
+    /// a public `def`, `defn`, `defmacro` or `defmulti` without a docstring
+    MissingDocstring,
+    /// `#_` with no form after it, e.g. `[a #_]`
+    UnbalancedDiscard,
+    /// `{:a 1 :a 2}` or `#{1 1}`
+    DuplicateKey,
+    /// `[a.b :refer [x]]` where `a.b` is linted too, and doesn't define `x`
+    UnknownRefer,
+}
+
+impl Check {
+    pub const ALL: &'static [Check] = &[
+        Self::Syntax,
+        Self::UnusedBinding,
+        Self::UnusedRequire,
+        Self::ShadowedLocal,
+        Self::RedefinedVar,
+        Self::WrongArity,
+        Self::MissingDocstring,
+        Self::UnbalancedDiscard,
+        Self::DuplicateKey,
+        Self::UnknownRefer,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Syntax => "syntax",
+            Self::UnusedBinding => "unused-binding",
+            Self::UnusedRequire => "unused-require",
+            Self::ShadowedLocal => "shadowed-local",
+            Self::RedefinedVar => "redefined-var",
+            Self::WrongArity => "wrong-arity",
+            Self::MissingDocstring => "missing-docstring",
+            Self::UnbalancedDiscard => "unbalanced-discard",
+            Self::DuplicateKey => "duplicate-key",
+            Self::UnknownRefer => "unknown-refer",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|check| check.name() == name)
+    }
+
+    fn default_level(&self) -> Level {
+        match self {
+            Self::Syntax | Self::WrongArity | Self::UnbalancedDiscard | Self::DuplicateKey => Level::Error,
+            // as in clj-kondo, since most namespaces have undocumented public vars
+            Self::MissingDocstring => Level::Off,
+            _ => Level::Warning,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Off,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    levels: HashMap<Check, Level>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            levels: Check::ALL.iter().map(|check| (*check, check.default_level())).collect(),
+        }
+    }
+}
+
+impl Config {
+    /// Clojure example:
+    /// ```clojure
+    /// {:linters {:missing-docstring {:level :warning}
+    ///            :shadowed-local    {:level :off}}}
+    /// ```
+    pub fn from_edn(src: &str) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        let Some(entries) = read_config::<RcK>(src)? else {
+            return Ok(config);
+        };
+        for (k, v) in &entries {
+            match (keyword_name(k), v.as_ref()) {
+                (Some("linters"), Value::Map(linters)) => {
+                    for (name, options) in linters {
+                        let check = keyword_name(name).and_then(Check::from_name);
+                        let Some(check) = check else {
+                            return invalid(&format!("unknown linter {name}"));
+                        };
+                        config.levels.insert(check, parse_level(options)?);
+                    }
+                }
+                (Some("linters"), _) => return invalid(":linters must be a map"),
+                (Some(key), _) => return invalid(&format!("unknown option :{key}")),
+                (None, _) => return invalid("config keys must be unqualified keywords"),
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn level(&self, check: Check) -> Level {
+        self.levels.get(&check).copied().unwrap_or(Level::Off)
+    }
+
+    pub fn with_level(mut self, check: Check, level: Level) -> Self {
+        self.levels.insert(check, level);
+        self
+    }
+}
+
+fn parse_level(options: &RcValue) -> Result<Level, ConfigError> {
+    let Value::Map(options) = options else {
+        return invalid("each linter's options must be a map, e.g. {:level :off}");
+    };
+    let mut level = None;
+    for (k, v) in options {
+        match (keyword_name(k), keyword_name(v)) {
+            (Some("level"), Some("off")) => level = Some(Level::Off),
+            (Some("level"), Some("warning")) => level = Some(Level::Warning),
+            (Some("level"), Some("error")) => level = Some(Level::Error),
+            (Some("level"), _) => return invalid(":level must be :off, :warning or :error"),
+            _ => return invalid(&format!("unknown linter option {k}")),
+        }
+    }
+    level.ok_or_else(|| ConfigError::Invalid("each linter's options must have a :level".to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Check, Config, ConfigError, Level};
+
+    #[test]
+    fn levels() {
+        let config = Config::from_edn("{:linters {:missing-docstring {:level :warning}, :shadowed-local {:level :off}}}")
+            .unwrap();
+        assert_eq!(config.level(Check::MissingDocstring), Level::Warning);
+        assert_eq!(config.level(Check::ShadowedLocal), Level::Off);
+        assert_eq!(config.level(Check::DuplicateKey), Level::Error);
+        assert_eq!(Config::from_edn(""), Ok(Config::default()));
+        assert!(matches!(Config::from_edn("{:linters {:nope {:level :off}}}"), Err(ConfigError::Invalid(..))));
+        assert!(matches!(Config::from_edn("{:linters {:syntax {:level :loud}}}"), Err(ConfigError::Invalid(..))));
+    }
+}
@@ -0,0 +1,725 @@
+//! the checks of [`Check`], over the lossless parse of [`syntax::parse`], which (unlike reading
+//! values) keeps the span of every form and reads all of Clojure's reader macros
+//!
+//! Locals are resolved lexically through the binding forms of `clojure.core` (`let`, `fn`,
+//! `loop`, `for`, `letfn`, `catch` etc.), with destructuring. Other macros are analyzed as calls,
+//! so the bindings they introduce are neither checked nor resolved.
+
+use std::collections::{HashMap, HashSet};
+use cljrs_reader::{
+    syntax::{self, Delim, Node, NodeKind},
+    ByteIndexSpan, ReadError,
+};
+use cljrs_tooling::syntax::{fn_params, forms, head, is_string, prefix, symbol, without_meta};
+use crate::config::{Check, Config, Level};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub check: Check,
+    pub level: Level,
+    pub span: ByteIndexSpan,
+    pub message: String,
+}
+
+/// the findings of each source, in the order of their spans, where `:refer`s are checked against
+/// the vars of the namespaces the sources define
+pub fn lint_sources(srcs: &[&str], config: &Config) -> Vec<Vec<Finding>> {
+    let parses: Vec<_> = srcs.iter().map(|src| syntax::parse(src)).collect();
+    let mut namespaces: HashMap<String, HashSet<String>> = HashMap::new();
+    for (src, root) in srcs.iter().zip(&parses) {
+        let Ok(root) = root else {
+            continue;
+        };
+        if let Some(ns) = ns_form(src, root).and_then(|ns| ns_name(src, ns)) {
+            let vars = top_level_defs(src, root).map(|def| def.name.to_owned());
+            namespaces.entry(ns.to_owned()).or_default().extend(vars);
+        }
+    }
+
+    let lint = |(src, root): (&&str, Result<Node, ReadError>)| {
+        let found = match root {
+            Ok(root) => {
+                let mut linter = Linter::new(src, &namespaces);
+                linter.lint(&root);
+                linter.findings
+            }
+            Err(err) => vec![read_error(src, err)],
+        };
+        let mut findings: Vec<_> = found
+            .into_iter()
+            .filter_map(|(check, span, message)| match config.level(check) {
+                Level::Off => None,
+                level => Some(Finding {
+                    check,
+                    level,
+                    span,
+                    message,
+                }),
+            })
+            .collect();
+        findings.sort_by_key(|finding| (finding.span, finding.check));
+        findings
+    };
+    srcs.iter().zip(parses).map(lint).collect()
+}
+
+/// Clojure example:
+/// ```clojure
+/// (defn f [x] (let [y 1] x)) ; unused binding y
+/// ```
+pub fn lint_source(src: &str, config: &Config) -> Vec<Finding> {
+    lint_sources(&[src], config).remove(0)
+}
+
+type Found = (Check, ByteIndexSpan, String);
+
+fn read_error(src: &str, err: ReadError) -> Found {
+    let span = err.span();
+    match src[span.0..].starts_with("#_") {
+        true => (Check::UnbalancedDiscard, (span.0, span.0 + 1), "#_ has no form to discard".to_owned()),
+        false => (Check::Syntax, span, err.message().to_owned()),
+    }
+}
+
+fn text<'s>(src: &'s str, node: &Node) -> &'s str {
+    node.text(src)
+}
+
+fn ns_form<'n>(src: &str, root: &'n Node) -> Option<&'n Node> {
+    root.forms()
+        .find(|form| form.kind == NodeKind::Coll(Delim::List) && head(src, &forms(src, form)) == Some("ns"))
+}
+
+fn ns_name<'s>(src: &'s str, ns: &Node) -> Option<&'s str> {
+    let forms = forms(src, ns);
+    symbol(src, without_meta(src, forms.get(1)?).0)
+}
+
+struct Def<'s, 'n> {
+    head: &'s str,
+    name: &'s str,
+    name_node: &'n Node,
+    meta: Vec<&'n Node>,
+    /// the forms after the name
+    rest: Vec<&'n Node>,
+}
+
+/// the top-level forms defining vars, such as `(defn f [])`, but not `declare`s or `defmethod`s
+fn top_level_defs<'s, 'n>(src: &'s str, root: &'n Node) -> impl Iterator<Item = Def<'s, 'n>> {
+    root.forms().filter_map(move |form| {
+        if form.kind != NodeKind::Coll(Delim::List) {
+            return None;
+        }
+        let forms = forms(src, form);
+        let head = head(src, &forms)?;
+        let defines = matches!(
+            head,
+            "def" | "defn" | "defn-" | "defmacro" | "defmulti" | "defonce" | "defprotocol" | "defrecord" | "deftype"
+        );
+        let (name_node, meta) = without_meta(src, forms.get(1).filter(|_| defines)?);
+        Some(Def {
+            head,
+            name: symbol(src, name_node)?,
+            name_node,
+            meta,
+            rest: forms[2..].to_vec(),
+        })
+    })
+}
+
+/// the arities of a fn, from its params vectors
+#[derive(Debug, Default)]
+struct Arities {
+    fixed: Vec<usize>,
+    /// the least number of args of a variadic arity
+    variadic: Option<usize>,
+}
+
+impl Arities {
+    /// of the forms after a fn's name, docstring and attr-map
+    fn of(src: &str, forms: &[&Node]) -> Self {
+        let mut arities = Self::default();
+        for params in fn_params(src, forms) {
+            let params = self::forms(src, params);
+            match params.iter().position(|param| text(src, param) == "&") {
+                Some(fixed) => arities.variadic = Some(arities.variadic.map_or(fixed, |least| least.min(fixed))),
+                None => arities.fixed.push(params.len()),
+            }
+        }
+        arities
+    }
+
+    fn accepts(&self, args: usize) -> bool {
+        self.fixed.contains(&args) || self.variadic.is_some_and(|least| args >= least)
+    }
+
+    fn describe(&self) -> String {
+        let mut fixed = self.fixed.clone();
+        fixed.sort();
+        let mut counts: Vec<String> = fixed.iter().map(usize::to_string).collect();
+        counts.extend(self.variadic.map(|least| format!("{least} or more")));
+        match counts.split_last() {
+            Some((last, [])) => last.clone(),
+            Some((last, rest)) => format!("{} or {last}", rest.join(", ")),
+            None => "no args".to_owned(),
+        }
+    }
+}
+
+struct Local<'s> {
+    name: &'s str,
+    span: ByteIndexSpan,
+    used: bool,
+    /// whether it's reported if unused, which fn params aren't
+    check_unused: bool,
+}
+
+struct Linter<'s, 'a> {
+    src: &'s str,
+    namespaces: &'a HashMap<String, HashSet<String>>,
+    findings: Vec<Found>,
+    /// innermost last
+    locals: Vec<Local<'s>>,
+    /// of fns and macros defined in this file
+    arities: HashMap<&'s str, Arities>,
+    /// symbols used that aren't locals, unqualified
+    used_symbols: HashSet<&'s str>,
+    /// the namespace parts of qualified symbols and `::alias/keywords`
+    used_aliases: HashSet<&'s str>,
+}
+
+impl<'s, 'a> Linter<'s, 'a> {
+    fn new(src: &'s str, namespaces: &'a HashMap<String, HashSet<String>>) -> Self {
+        Self {
+            src,
+            namespaces,
+            findings: vec![],
+            locals: vec![],
+            arities: HashMap::new(),
+            used_symbols: HashSet::new(),
+            used_aliases: HashSet::new(),
+        }
+    }
+
+    fn find(&mut self, check: Check, span: ByteIndexSpan, message: String) {
+        self.findings.push((check, span, message));
+    }
+
+    fn lint(&mut self, root: &Node) {
+        let src = self.src;
+        let mut defined: HashMap<&str, ByteIndexSpan> = HashMap::new();
+        for def in top_level_defs(src, root) {
+            if defined.insert(def.name, def.name_node.span).is_some() {
+                self.find(Check::RedefinedVar, def.name_node.span, format!("redefined var {}", def.name));
+            }
+            self.check_docstring(&def);
+            if let "defn" | "defn-" | "defmacro" = def.head {
+                let skip = def
+                    .rest
+                    .iter()
+                    .take_while(|form| is_string(src, form) || form.kind == NodeKind::Coll(Delim::Map));
+                self.arities.insert(def.name, Arities::of(src, &def.rest[skip.count()..]));
+            }
+        }
+
+        let ns = ns_form(src, root);
+        for form in root.forms().filter(|form| Some(*form) != ns) {
+            self.analyze(form, true);
+        }
+        if let Some(ns) = ns {
+            self.check_requires(ns);
+        }
+    }
+
+    fn check_docstring(&mut self, def: &Def) {
+        let src = self.src;
+        let documented = match def.head {
+            "defn" | "defmacro" | "defmulti" => def.rest.first().is_some_and(|form| is_string(src, form)),
+            "def" => def.rest.len() == 2 && is_string(src, def.rest[0]),
+            "defn-" => true,
+            _ => return,
+        };
+        let meta = def.meta.iter().map(|meta| text(src, meta));
+        let private = meta.clone().any(|meta| meta == ":private" || meta.contains(":private true"));
+        let meta_doc = meta.clone().any(|meta| meta.starts_with('{') && meta.contains(":doc"));
+        let attr_doc = match def.head {
+            "defn" | "defmacro" => def
+                .rest
+                .iter()
+                .take_while(|form| is_string(src, form) || form.kind == NodeKind::Coll(Delim::Map))
+                .any(|form| form.kind == NodeKind::Coll(Delim::Map) && text(src, form).contains(":doc")),
+            _ => false,
+        };
+        if !(documented || private || meta_doc || attr_doc) {
+            self.find(
+                Check::MissingDocstring,
+                def.name_node.span,
+                format!("public var {} has no docstring", def.name),
+            );
+        }
+    }
+
+    fn check_requires(&mut self, ns: &Node) {
+        let src = self.src;
+        let clauses = forms(src, ns);
+        let requires = clauses.iter().filter(|clause| {
+            clause.kind == NodeKind::Coll(Delim::List)
+                && forms(src, clause).first().is_some_and(|head| text(src, head) == ":require")
+        });
+        for require in requires {
+            for libspec in forms(src, require).into_iter().skip(1) {
+                match libspec.kind {
+                    NodeKind::Coll(Delim::Vect) => self.check_libspec(None, libspec),
+                    // `(prefix [suffix :as alias])`
+                    NodeKind::Coll(Delim::List) => {
+                        let forms = forms(src, libspec);
+                        let Some(prefix) = forms.first().and_then(|prefix| symbol(src, prefix)) else {
+                            continue;
+                        };
+                        for libspec in &forms[1..] {
+                            if libspec.kind == NodeKind::Coll(Delim::Vect) {
+                                self.check_libspec(Some(prefix), libspec);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn check_libspec(&mut self, prefix: Option<&str>, libspec: &Node) {
+        let src = self.src;
+        let forms = forms(src, libspec);
+        let Some(lib) = forms.first().and_then(|lib| symbol(src, lib)) else {
+            return;
+        };
+        let lib = match prefix {
+            Some(prefix) => format!("{prefix}.{lib}"),
+            None => lib.to_owned(),
+        };
+        for option in forms[1..].chunks(2) {
+            let [key, value] = option else {
+                continue;
+            };
+            match text(src, key) {
+                ":as" | ":as-alias" => {
+                    if let Some(alias) = symbol(src, value).filter(|alias| !self.used_aliases.contains(alias)) {
+                        let message = format!("{lib} is required as {alias}, which is never used");
+                        self.find(Check::UnusedRequire, value.span, message);
+                    }
+                }
+                ":refer" if value.kind == NodeKind::Coll(Delim::Vect) => {
+                    for referred in self::forms(src, value) {
+                        let Some(name) = symbol(src, referred) else {
+                            continue;
+                        };
+                        if !self.used_symbols.contains(name) {
+                            let message = format!("{name} is referred from {lib}, but never used");
+                            self.find(Check::UnusedRequire, referred.span, message);
+                        }
+                        if self.namespaces.get(&lib).is_some_and(|vars| !vars.contains(name)) {
+                            self.find(Check::UnknownRefer, referred.span, format!("{lib} does not define {name}"));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// `check_arity` is false for the forms threaded through by `->` and the like
+    fn analyze(&mut self, node: &Node, check_arity: bool) {
+        let src = self.src;
+        match node.kind {
+            NodeKind::Atom => self.use_atom(node),
+            NodeKind::Prefixed => match prefix(src, node) {
+                Some("'" | "`" | "#_") => {}
+                _ => {
+                    for form in forms(src, node).into_iter().skip(1) {
+                        self.analyze(form, true);
+                    }
+                }
+            },
+            NodeKind::Coll(Delim::List | Delim::AnonFn) => self.analyze_call(node, check_arity),
+            NodeKind::Coll(delim) => {
+                let forms = forms(src, node);
+                match delim {
+                    Delim::Map => self.check_duplicates(forms.iter().step_by(2), "key"),
+                    Delim::Set => self.check_duplicates(forms.iter(), "set element"),
+                    _ => {}
+                }
+                for form in forms {
+                    self.analyze(form, true);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_duplicates<'n>(&mut self, forms: impl Iterator<Item = &'n &'n Node>, what: &str) {
+        let mut seen = HashSet::new();
+        for form in forms {
+            let text = text(self.src, form);
+            if !seen.insert(text) {
+                self.find(Check::DuplicateKey, form.span, format!("duplicate {what} {text}"));
+            }
+        }
+    }
+
+    fn use_atom(&mut self, node: &Node) {
+        let text = text(self.src, node);
+        if let Some(keyword) = text.strip_prefix("::") {
+            if let Some((alias, _name)) = keyword.split_once('/') {
+                self.used_aliases.insert(alias);
+            }
+            return;
+        }
+        let Some(sym) = symbol(self.src, node) else {
+            return;
+        };
+        match sym.split_once('/') {
+            Some((ns, name)) if !ns.is_empty() && !name.is_empty() => {
+                self.used_aliases.insert(ns);
+            }
+            _ => match self.locals.iter_mut().rev().find(|local| local.name == sym) {
+                Some(local) => local.used = true,
+                None => {
+                    self.used_symbols.insert(sym);
+                }
+            },
+        }
+    }
+
+    fn analyze_call(&mut self, node: &Node, check_arity: bool) {
+        let src = self.src;
+        let forms = forms(src, node);
+        let rest = forms.get(1..).unwrap_or_default();
+        let head = head(src, &forms);
+        match head {
+            Some("quote" | "ns") => {}
+            Some("let" | "loop" | "when-let" | "if-let" | "when-some" | "if-some" | "when-first" | "with-open") => {
+                self.analyze_let(rest, false)
+            }
+            Some("for" | "doseq" | "dotimes") => self.analyze_let(rest, true),
+            Some("fn" | "fn*") => {
+                let mark = self.locals.len();
+                let arities = match rest.first() {
+                    Some(name) if symbol(src, name).is_some() => {
+                        self.bind(name, false);
+                        &rest[1..]
+                    }
+                    _ => rest,
+                };
+                self.analyze_arities(arities);
+                self.pop_locals(mark);
+            }
+            Some("defn" | "defn-" | "defmacro") => {
+                let skip = rest
+                    .iter()
+                    .skip(1)
+                    .take_while(|form| is_string(src, form) || form.kind == NodeKind::Coll(Delim::Map));
+                let arities = rest.get(1 + skip.count()..).unwrap_or_default();
+                self.analyze_arities(arities);
+            }
+            Some("defmethod") => {
+                // `(defmethod name dispatch-val [params] body)`
+                if let Some(dispatch) = rest.get(1) {
+                    self.analyze(dispatch, true);
+                }
+                self.analyze_arities(rest.get(2..).unwrap_or_default());
+            }
+            Some("def" | "defonce" | "defmulti") => {
+                for form in rest.iter().skip(1) {
+                    self.analyze(form, true);
+                }
+            }
+            Some("letfn") => {
+                let mark = self.locals.len();
+                let (specs, body) = match rest.split_first() {
+                    Some((specs, body)) if specs.kind == NodeKind::Coll(Delim::Vect) => (self::forms(src, specs), body),
+                    _ => (vec![], rest),
+                };
+                for spec in &specs {
+                    if let Some(name) = self::forms(src, spec).first() {
+                        self.bind(name, false);
+                    }
+                }
+                for spec in &specs {
+                    self.analyze_arities(self::forms(src, spec).get(1..).unwrap_or_default());
+                }
+                for form in body {
+                    self.analyze(form, true);
+                }
+                self.pop_locals(mark);
+            }
+            Some("catch") => {
+                // `(catch Exception e body)`
+                let mark = self.locals.len();
+                if let Some(class) = rest.first() {
+                    self.analyze(class, true);
+                }
+                if let Some(local) = rest.get(1) {
+                    self.bind(local, false);
+                }
+                for form in rest.iter().skip(2) {
+                    self.analyze(form, true);
+                }
+                self.pop_locals(mark);
+            }
+            Some("->" | "->>" | "some->" | "some->>" | "cond->" | "cond->>" | "as->" | "doto" | "..") => {
+                for form in rest {
+                    self.analyze(form, false);
+                }
+            }
+            _ => {
+                let local = head.is_some_and(|head| self.locals.iter().any(|local| local.name == head));
+                let arities = head.filter(|_| check_arity && !local).and_then(|head| self.arities.get(head));
+                if let Some(arities) = arities.filter(|arities| !arities.accepts(rest.len())) {
+                    let message = format!(
+                        "{} is called with {} args but takes {}",
+                        head.unwrap_or_default(),
+                        rest.len(),
+                        arities.describe()
+                    );
+                    self.find(Check::WrongArity, node.span, message);
+                }
+                for form in forms {
+                    self.analyze(form, true);
+                }
+            }
+        }
+    }
+
+    /// of `[bindings] body...`, where `comprehension` allows `for`'s `:let`, `:when` and `:while`
+    fn analyze_let(&mut self, rest: &[&Node], comprehension: bool) {
+        let src = self.src;
+        let mark = self.locals.len();
+        let (bindings, body) = match rest.split_first() {
+            Some((bindings, body)) if bindings.kind == NodeKind::Coll(Delim::Vect) => (forms(src, bindings), body),
+            _ => (vec![], rest),
+        };
+        for pair in bindings.chunks(2) {
+            match pair {
+                [key, value] if comprehension && text(src, key) == ":let" && value.kind == NodeKind::Coll(Delim::Vect) => {
+                    for pair in forms(src, value).chunks(2) {
+                        self.analyze_binding(pair);
+                    }
+                }
+                [key, value] if comprehension && text(src, key).starts_with(':') => self.analyze(value, true),
+                pair => self.analyze_binding(pair),
+            }
+        }
+        for form in body {
+            self.analyze(form, true);
+        }
+        self.pop_locals(mark);
+    }
+
+    fn analyze_binding(&mut self, pair: &[&Node]) {
+        match pair {
+            [pattern, init] => {
+                self.analyze(init, true);
+                self.bind(pattern, true);
+            }
+            forms => {
+                for form in forms {
+                    self.analyze(form, true);
+                }
+            }
+        }
+    }
+
+    /// of `[params] body...` or `([params] body...)...`
+    fn analyze_arities(&mut self, forms: &[&Node]) {
+        let src = self.src;
+        match forms.split_first() {
+            Some((params, body)) if without_meta(src, params).0.kind == NodeKind::Coll(Delim::Vect) => {
+                self.analyze_arity(params, body)
+            }
+            _ => {
+                for form in forms {
+                    match form.kind {
+                        NodeKind::Coll(Delim::List) => {
+                            let arity = self::forms(src, form);
+                            if let Some((params, body)) = arity.split_first() {
+                                self.analyze_arity(params, body);
+                            }
+                        }
+                        _ => self.analyze(form, true),
+                    }
+                }
+            }
+        }
+    }
+
+    fn analyze_arity(&mut self, params: &Node, body: &[&Node]) {
+        let mark = self.locals.len();
+        self.bind(params, false);
+        for form in body {
+            self.analyze(form, true);
+        }
+        self.pop_locals(mark);
+    }
+
+    /// binds the locals of a (possibly destructuring) binding form
+    fn bind(&mut self, pattern: &Node, check_unused: bool) {
+        let src = self.src;
+        let pattern = without_meta(src, pattern).0;
+        match pattern.kind {
+            NodeKind::Atom => match symbol(src, pattern) {
+                Some("&") | None => {}
+                Some(name) => {
+                    // `:keys [a/b]` binds `b`
+                    let name = name.rsplit_once('/').map_or(name, |(_ns, name)| name);
+                    self.push_local(name, pattern.span, check_unused);
+                }
+            },
+            NodeKind::Coll(Delim::Vect) => {
+                let forms = forms(src, pattern);
+                let mut forms = forms.iter();
+                while let Some(form) = forms.next() {
+                    match text(src, form) {
+                        ":as" => {
+                            if let Some(name) = forms.next() {
+                                self.bind(name, check_unused);
+                            }
+                        }
+                        _ => self.bind(form, check_unused),
+                    }
+                }
+            }
+            NodeKind::Coll(Delim::Map) => {
+                let forms = forms(src, pattern);
+                for entry in forms.chunks(2) {
+                    let [key, value] = entry else {
+                        continue;
+                    };
+                    let key_text = text(src, key);
+                    let is_keys = ["keys", "strs", "syms"]
+                        .iter()
+                        .any(|keys| key_text.starts_with(':') && key_text.ends_with(keys));
+                    match key_text {
+                        ":as" => self.bind(value, check_unused),
+                        ":or" => self.analyze(value, true),
+                        _ if is_keys && value.kind == NodeKind::Coll(Delim::Vect) => {
+                            for key in self::forms(src, value) {
+                                let name = text(src, key).trim_start_matches(':');
+                                let name = name.rsplit_once('/').map_or(name, |(_ns, name)| name);
+                                self.push_local(name, key.span, check_unused);
+                            }
+                        }
+                        _ => {
+                            self.bind(key, check_unused);
+                            self.analyze(value, true);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn push_local(&mut self, name: &'s str, span: ByteIndexSpan, check_unused: bool) {
+        if !name.starts_with('_') && self.locals.iter().any(|local| local.name == name) {
+            self.find(Check::ShadowedLocal, span, format!("{name} shadows a local of the same name"));
+        }
+        self.locals.push(Local {
+            name,
+            span,
+            used: false,
+            check_unused,
+        });
+    }
+
+    /// unbinds the locals bound since there were `mark` of them, reporting those unused
+    fn pop_locals(&mut self, mark: usize) {
+        let popped: Vec<_> = self.locals.drain(mark..).collect();
+        for local in popped {
+            if local.check_unused && !local.used && !local.name.starts_with('_') {
+                self.find(Check::UnusedBinding, local.span, format!("unused binding {}", local.name));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint_source, lint_sources};
+    use crate::config::{Check, Config, Level};
+
+    fn found(src: &str) -> Vec<(Check, &str)> {
+        let config = Config::default().with_level(Check::MissingDocstring, Level::Warning);
+        lint_source(src, &config)
+            .into_iter()
+            .map(|finding| (finding.check, &src[finding.span.0..=finding.span.1]))
+            .collect()
+    }
+
+    #[test]
+    fn bindings() {
+        let src = "(defn f \"doc\" [x {:keys [a b/c] :as m} & _more]
+                     (let [y 1 [z & zs] x _w 2]
+                       (for [i zs :let [j i k 2] :when j] (+ a c))
+                       (fn [z] z)
+                       (letfn [(g [x] x)] (g z))))";
+        assert_eq!(found(src), vec![
+            (Check::UnusedBinding, "y"),
+            (Check::UnusedBinding, "k"),
+            (Check::ShadowedLocal, "z"),
+            (Check::ShadowedLocal, "x"),
+        ]);
+        assert_eq!(found("(defn f \"doc\" [x] (let [x (inc x)] x))"), vec![(Check::ShadowedLocal, "x")]);
+        assert_eq!(found("(defn f \"doc\" [] (try 1 (catch Exception e 2)))"), vec![]);
+    }
+
+    #[test]
+    fn vars() {
+        let src = "(defn f \"doc\" ([x] x) ([x y & zs] x))
+                   (def ^:private g 1)
+                   (defn- h [] (f) (f 1) (f 1 2 3) (-> 1 (f)) (let [f inc] (f 1 2)))
+                   (def f 2)
+                   (defmacro m [a] a)
+                   (m)";
+        let found = found(src);
+        assert_eq!(found, vec![
+            (Check::WrongArity, "(f)"),
+            (Check::RedefinedVar, "f"),
+            (Check::MissingDocstring, "f"),
+            (Check::MissingDocstring, "m"),
+            (Check::WrongArity, "(m)"),
+        ]);
+        let message = &lint_source("(defn f [x] x) (f)", &Config::default())[0].message;
+        assert_eq!(message, "f is called with 0 args but takes 1");
+    }
+
+    #[test]
+    fn data_and_syntax() {
+        assert_eq!(found("{:a 1 :b {:a 2} :a 3} #{1 2 1}"), vec![
+            (Check::DuplicateKey, ":a"),
+            (Check::DuplicateKey, "1"),
+        ]);
+        assert_eq!(found("[1 #_]"), vec![(Check::UnbalancedDiscard, "#_")]);
+        assert_eq!(found("(a #_ b"), vec![(Check::Syntax, "(a #_ b")]);
+        assert_eq!(found("'{:a 1 :a 2} #_{:a 1 :a 2}"), vec![]);
+    }
+
+    #[test]
+    fn requires() {
+        let a = "(ns a) (defn x \"doc\" [] 1)";
+        let b = "(ns b (:require [a :refer [x y]] [c :as c] [d :as d] e (f [g :as g])))
+                 (d/z ::g/k)";
+        let findings = lint_sources(&[a, b], &Config::default());
+        let found: Vec<_> = findings[1]
+            .iter()
+            .map(|finding| (finding.check, &b[finding.span.0..=finding.span.1]))
+            .collect();
+        assert_eq!(found, vec![
+            (Check::UnusedRequire, "x"),
+            (Check::UnusedRequire, "y"),
+            (Check::UnknownRefer, "y"),
+            (Check::UnusedRequire, "c"),
+        ]);
+        assert!(findings[0].is_empty());
+    }
+}
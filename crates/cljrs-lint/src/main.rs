@@ -0,0 +1,137 @@
+use cljrs_lint::{config::Config, lint::lint_sources, report};
+use cljrs_tooling::source::{source_files, CLOJURE_EXTENSIONS};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+const USAGE: &str = "USAGE: cljrs-lint [--config <path>] [--format text|edn] <path>...
+
+  paths may be files or directories (searched for .clj, .cljs and .cljc files)
+
+  --config <path>  EDN config, defaults to ./.cljrs-lint.edn if it exists
+  --format <fmt>   text (the default) or edn, one map per finding
+  -h, --help       print this and exit
+
+  exits 0 if nothing was found, 1 if something was, 2 on errors";
+
+const DEFAULT_CONFIG_PATH: &str = ".cljrs-lint.edn";
+
+enum Format {
+    Text,
+    Edn,
+}
+
+struct Args {
+    config_path: Option<PathBuf>,
+    format: Format,
+    paths: Vec<PathBuf>,
+}
+
+/// the arguments, or none if help was asked for
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut parsed = Args {
+        config_path: None,
+        format: Format::Text,
+        paths: vec![],
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => match args.next() {
+                Some(path) => parsed.config_path = Some(path.into()),
+                None => return Err("--config requires a path".to_owned()),
+            },
+            "--format" => match args.next().as_deref() {
+                Some("text") => parsed.format = Format::Text,
+                Some("edn") => parsed.format = Format::Edn,
+                _ => return Err("--format requires text or edn".to_owned()),
+            },
+            "-h" | "--help" => return Ok(None),
+            flag if flag.starts_with("--") => return Err(format!("unknown flag {flag}")),
+            path => parsed.paths.push(path.into()),
+        }
+    }
+    if parsed.paths.is_empty() {
+        return Err("no paths given".to_owned());
+    }
+    Ok(Some(parsed))
+}
+
+fn load_config(path: Option<&Path>) -> Result<Config, String> {
+    let (path, required) = match path {
+        Some(path) => (path, true),
+        None => (Path::new(DEFAULT_CONFIG_PATH), false),
+    };
+    match fs::read_to_string(path) {
+        Ok(src) => Config::from_edn(&src).map_err(|err| format!("{}: {err}", path.display())),
+        Err(err) if required || err.kind() != io::ErrorKind::NotFound => {
+            Err(format!("{}: {err}", path.display()))
+        }
+        Err(_) => Ok(Config::default()),
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(msg) => {
+            eprintln!("{msg}");
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let config = match load_config(args.config_path.as_deref()) {
+        Ok(config) => config,
+        Err(msg) => {
+            eprintln!("{msg}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut files = vec![];
+    let mut errored = false;
+    for path in &args.paths {
+        if let Err(err) = source_files(path, CLOJURE_EXTENSIONS, &mut files) {
+            eprintln!("{}: {err}", path.display());
+            errored = true;
+        }
+    }
+    // every file is read before any is linted, so `:refer`s can be checked across them
+    let mut sources = vec![];
+    for file in files {
+        match fs::read_to_string(&file) {
+            Ok(src) => sources.push((file.display().to_string(), src)),
+            Err(err) => {
+                eprintln!("{}: {err}", file.display());
+                errored = true;
+            }
+        }
+    }
+
+    let srcs: Vec<&str> = sources.iter().map(|(_name, src)| src.as_str()).collect();
+    let findings = lint_sources(&srcs, &config);
+    let mut stdout = io::stdout().lock();
+    let mut found = false;
+    for ((name, src), findings) in sources.iter().zip(&findings) {
+        found |= !findings.is_empty();
+        let written = match args.format {
+            Format::Text => report::write_text(&mut stdout, name, src, findings),
+            Format::Edn => report::write_edn(&mut stdout, name, src, findings),
+        };
+        if written.and_then(|()| stdout.flush()).is_err() {
+            return ExitCode::from(2);
+        }
+    }
+
+    match (errored, found) {
+        (true, _) => ExitCode::from(2),
+        (false, true) => ExitCode::from(1),
+        (false, false) => ExitCode::SUCCESS,
+    }
+}
@@ -0,0 +1,77 @@
+//! findings as lines of text, or as EDN maps (one per line) for tools such as `cljrs-q`
+
+use std::io::{self, Write};
+use cljrs_data::spans::line_col;
+use cljrs_core::printer::{write_str, PrintMode};
+use crate::lint::Finding;
+
+/// 1-based `(line, col)` of the first char of a finding, and of the char after its last
+fn positions(src: &str, finding: &Finding) -> ((usize, usize), (usize, usize)) {
+    let (begin, end) = finding.span;
+    let after = match src.get(end..).and_then(|rest| rest.chars().next()) {
+        Some(ch) => end + ch.len_utf8(),
+        None => src.len(),
+    };
+    (line_col(src, begin), line_col(src, after))
+}
+
+/// e.g. `src/a.clj:3:8: warning: unused binding y [unused-binding]`
+pub fn write_text(out: &mut impl Write, name: &str, src: &str, findings: &[Finding]) -> io::Result<()> {
+    for finding in findings {
+        let ((line, col), _) = positions(src, finding);
+        let (level, check) = (finding.level.name(), finding.check.name());
+        writeln!(out, "{name}:{line}:{col}: {level}: {} [{check}]", finding.message)?;
+    }
+    Ok(())
+}
+
+/// Clojure example:
+/// ```clojure
+/// {:file "src/a.clj", :row 3, :col 8, :end-row 3, :end-col 9, :span [52 52],
+///  :level :warning, :type :unused-binding, :message "unused binding y"}
+/// ```
+pub fn to_edn(name: &str, src: &str, finding: &Finding) -> String {
+    let ((row, col), (end_row, end_col)) = positions(src, finding);
+    let (begin, end) = finding.span;
+    let (level, check) = (finding.level.name(), finding.check.name());
+    // written rather than printed from a value, whose numbers are all floats
+    let mut edn = String::from("{:file ");
+    write_str(&mut edn, name, PrintMode::Pr).expect("writing to a String does not fail");
+    edn += &format!(", :row {row}, :col {col}, :end-row {end_row}, :end-col {end_col}, :span [{begin} {end}], ");
+    edn += &format!(":level :{level}, :type :{check}, :message ");
+    write_str(&mut edn, &finding.message, PrintMode::Pr).expect("writing to a String does not fail");
+    edn.push('}');
+    edn
+}
+
+pub fn write_edn(out: &mut impl Write, name: &str, src: &str, findings: &[Finding]) -> io::Result<()> {
+    for finding in findings {
+        writeln!(out, "{}", to_edn(name, src, finding))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_edn, write_text};
+    use crate::{config::Config, lint::lint_source};
+
+    #[test]
+    fn formats() {
+        let src = "(ns a)\n(let [😀 1\n      y 2] 😀)";
+        let findings = lint_source(src, &Config::default());
+        let mut text = vec![];
+        write_text(&mut text, "a.clj", src, &findings).unwrap();
+        assert_eq!(String::from_utf8(text).unwrap(), "a.clj:3:7: warning: unused binding y [unused-binding]\n");
+        let mut edn = vec![];
+        write_edn(&mut edn, "a.clj", src, &findings).unwrap();
+        assert_eq!(
+            String::from_utf8(edn).unwrap(),
+            concat!(
+                r#"{:file "a.clj", :row 3, :col 7, :end-row 3, :end-col 8, :span [26 26], "#,
+                r#":level :warning, :type :unused-binding, :message "unused binding y"}"#,
+                "\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! what the server knows about a document, in byte spans, and how those map to LSP positions
+//!
+//! Everything but bracket matching is worked out from [`syntax::parse`], which keeps the spans of
+//! every form, so a document that doesn't read has a diagnostic but no symbols, folds or
+//! selection ranges until it does. Brackets are matched from [`tokens`], which never fail.
+
+use cljrs_reader::{
+    syntax::{self, Node, NodeKind},
+    token::{tokens, TokenKind},
+    ByteIndexSpan,
+};
+
+/// zero-based, with `character` counting UTF-16 code units as LSP positions do by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub character: usize,
+}
+
+/// `end` is exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// maps byte indices of a document to and from [`Position`]s
+pub struct LineIndex<'s> {
+    src: &'s str,
+    /// the byte index each line begins at
+    line_starts: Vec<usize>,
+}
+
+impl<'s> LineIndex<'s> {
+    pub fn new(src: &'s str) -> Self {
+        let bytes = src.as_bytes();
+        let mut line_starts = vec![0];
+        for (idx, byte) in bytes.iter().enumerate() {
+            match byte {
+                b'\n' => line_starts.push(idx + 1),
+                b'\r' if bytes.get(idx + 1) != Some(&b'\n') => line_starts.push(idx + 1),
+                _ => {}
+            }
+        }
+        Self { src, line_starts }
+    }
+
+    pub fn position(&self, byte_idx: usize) -> Position {
+        let byte_idx = byte_idx.min(self.src.len());
+        let line = self.line_starts.partition_point(|start| *start <= byte_idx) - 1;
+        let before = self.src.get(self.line_starts[line]..byte_idx).unwrap_or("");
+        Position {
+            line,
+            character: before.encode_utf16().count(),
+        }
+    }
+
+    /// the byte index of a position, clamped to the end of its line and of the document
+    pub fn byte_idx(&self, position: Position) -> usize {
+        let Some(&start) = self.line_starts.get(position.line) else {
+            return self.src.len();
+        };
+        let line = &self.src[start..];
+        let mut units = 0;
+        for (idx, ch) in line.char_indices() {
+            if units >= position.character || ch == '\n' || ch == '\r' {
+                return start + idx;
+            }
+            units += ch.len_utf16();
+        }
+        self.src.len()
+    }
+
+    /// the range of an inclusive span, which ends after the span's last char
+    pub fn range(&self, (begin, end): ByteIndexSpan) -> Range {
+        let end = match self.src.get(end..).and_then(|rest| rest.chars().next()) {
+            Some(ch) => end + ch.len_utf8(),
+            None => self.src.len(),
+        };
+        Range {
+            start: self.position(begin),
+            end: self.position(end),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub span: ByteIndexSpan,
+    pub message: String,
+}
+
+/// the error reading the document, if any, as `cljrs-fmt` and `cljrs-q` describe them
+pub fn diagnostics(src: &str) -> Vec<Diagnostic> {
+    let Err(err) = syntax::parse(src) else {
+        return vec![];
+    };
+    vec![Diagnostic {
+        span: err.span(),
+        message: err.message().to_owned(),
+    }]
+}
+
+/// LSP's `SymbolKind`s of definitions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefinitionKind {
+    /// `defn`, `defn-`, `defmacro`, `defmulti` and `defmethod`
+    Function,
+    /// `defprotocol`
+    Interface,
+    /// `defrecord` and `deftype`
+    Class,
+    /// `def`, `defonce` and any other `def*`
+    Variable,
+}
+
+impl DefinitionKind {
+    fn from_head(head: &str) -> Self {
+        match head {
+            "defn" | "defn-" | "defmacro" | "defmulti" | "defmethod" => Self::Function,
+            "defprotocol" => Self::Interface,
+            "defrecord" | "deftype" => Self::Class,
+            _ => Self::Variable,
+        }
+    }
+
+    pub fn lsp_symbol_kind(&self) -> usize {
+        match self {
+            Self::Class => 5,
+            Self::Interface => 11,
+            Self::Function => 12,
+            Self::Variable => 13,
+        }
+    }
+}
+
+/// a top-level `(def* name ...)` form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub name: String,
+    /// e.g. `defn`
+    pub head: String,
+    pub kind: DefinitionKind,
+    pub span: ByteIndexSpan,
+    pub name_span: ByteIndexSpan,
+}
+
+/// Clojure example:
+/// ```clojure
+/// (defn ^:private f [x] x) ; a Function named f
+/// (s/def ::spec int?)      ; a Variable named ::spec
+/// ```
+pub fn definitions(src: &str, root: &Node) -> Vec<Definition> {
+    let definition = |form: &Node| {
+        let NodeKind::Coll(delim) = form.kind else {
+            return None;
+        };
+        let mut forms = form.forms().filter(|form| !form.is_discard(src));
+        let head = forms.next().filter(|head| delim.is_list_like() && head.kind == NodeKind::Atom)?;
+        let head = head.text(src);
+        let unqualified = head.rsplit_once('/').map_or(head, |(_ns, name)| name);
+        if !unqualified.starts_with("def") {
+            return None;
+        }
+        // `^:private name` and `^{:doc "..."} name`
+        let mut name = forms.next()?;
+        while name.kind == NodeKind::Prefixed && !name.is_discard(src) {
+            name = name.forms().last()?;
+        }
+        (name.kind == NodeKind::Atom).then(|| Definition {
+            name: name.text(src).to_owned(),
+            head: head.to_owned(),
+            kind: DefinitionKind::from_head(unqualified),
+            span: form.span,
+            name_span: name.span,
+        })
+    };
+    root.forms().filter_map(definition).collect()
+}
+
+/// the spans of every collection, outermost first
+pub fn collection_spans(root: &Node) -> Vec<ByteIndexSpan> {
+    fn walk(node: &Node, spans: &mut Vec<ByteIndexSpan>) {
+        if let NodeKind::Coll(..) = node.kind {
+            spans.push(node.span);
+        }
+        for child in &node.children {
+            walk(child, spans);
+        }
+    }
+    let mut spans = vec![];
+    walk(root, &mut spans);
+    spans
+}
+
+/// the spans of the forms enclosing the byte index, innermost first and ending with the whole
+/// document, for expanding a selection outwards
+pub fn enclosing_spans(root: &Node, byte_idx: usize) -> Vec<ByteIndexSpan> {
+    let mut spans = vec![root.span];
+    let mut node = root;
+    while let Some(child) = node
+        .children
+        .iter()
+        .find(|child| !child.is_trivia() && child.span.0 <= byte_idx && byte_idx <= child.span.1)
+    {
+        if child.span != node.span {
+            spans.push(child.span);
+        }
+        node = child;
+    }
+    spans.reverse();
+    spans
+}
+
+/// the byte index of the delimiter matching the one at (or, failing that, just before) the byte
+/// index, which is the first byte of an opening delimiter such as `#{`
+pub fn matching_delimiter(src: &str, byte_idx: usize) -> Option<usize> {
+    // each delimiter's span and its match's, if any
+    let mut pairs: Vec<(ByteIndexSpan, Option<ByteIndexSpan>)> = vec![];
+    let mut open: Vec<(usize, char)> = vec![];
+    for token in tokens(src).filter(|token| token.kind == TokenKind::Delimiter) {
+        let text = token.text(src);
+        let close = match text.chars().last() {
+            Some('(') => Some(')'),
+            Some('[') => Some(']'),
+            Some('{') => Some('}'),
+            _ => None,
+        };
+        match close {
+            Some(close) => {
+                open.push((pairs.len(), close));
+                pairs.push((token.span, None));
+            }
+            None => match open.last() {
+                Some(&(idx, close)) if text.starts_with(close) => {
+                    open.pop();
+                    let open_span = pairs[idx].0;
+                    pairs[idx].1 = Some(token.span);
+                    pairs.push((token.span, Some(open_span)));
+                }
+                // a mismatched close matches nothing, and leaves what's open as it is
+                _ => pairs.push((token.span, None)),
+            },
+        }
+    }
+    let at = |byte_idx: usize| pairs.iter().find(|((begin, end), _)| *begin <= byte_idx && byte_idx <= *end);
+    let before = byte_idx.checked_sub(1).and_then(&at);
+    let (_, matched) = at(byte_idx).or(before)?;
+    matched.map(|(begin, _)| begin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        collection_spans, definitions, diagnostics, enclosing_spans, matching_delimiter, DefinitionKind, LineIndex,
+        Position,
+    };
+    use cljrs_reader::syntax;
+
+    #[test]
+    fn positions() {
+        let src = "a\r\n😀b\rc\n";
+        let index = LineIndex::new(src);
+        let pos = |line, character| Position { line, character };
+        assert_eq!(index.position(0), pos(0, 0));
+        assert_eq!(index.position(7), pos(1, 2));
+        assert_eq!(index.position(9), pos(2, 0));
+        assert_eq!(index.position(src.len()), pos(3, 0));
+        assert_eq!(index.byte_idx(pos(1, 2)), 7);
+        assert_eq!(index.byte_idx(pos(1, 99)), 8);
+        assert_eq!(index.byte_idx(pos(9, 0)), src.len());
+        assert_eq!(index.range((3, 3)).end, pos(1, 2));
+    }
+
+    #[test]
+    fn read_errors() {
+        assert_eq!(diagnostics("(a)"), vec![]);
+        let diagnostic = &diagnostics("(a\n  [b)")[0];
+        assert_eq!((diagnostic.span, diagnostic.message.as_str()), ((5, 7), "invalid input"));
+    }
+
+    #[test]
+    fn defs() {
+        let src = "(ns a)\n(defn ^:private f [x] x)\n(s/def ::spec int?)\n[def x]\n(defprotocol P)\n#_(def y 1)";
+        let root = syntax::parse(src).unwrap();
+        let defs = definitions(src, &root);
+        let names: Vec<_> = defs.iter().map(|def| (def.name.as_str(), def.kind)).collect();
+        assert_eq!(names, vec![
+            ("f", DefinitionKind::Function),
+            ("::spec", DefinitionKind::Variable),
+            ("P", DefinitionKind::Interface),
+        ]);
+        assert_eq!(&src[defs[0].span.0..=defs[0].span.1], "(defn ^:private f [x] x)");
+        assert_eq!(defs[0].name_span, (23, 23));
+    }
+
+    #[test]
+    fn structure() {
+        let src = "(a [b #{c}])";
+        let root = syntax::parse(src).unwrap();
+        assert_eq!(collection_spans(&root), vec![(0, 11), (3, 10), (6, 9)]);
+        assert_eq!(enclosing_spans(&root, 8), vec![(8, 8), (6, 9), (3, 10), (0, 11)]);
+        assert_eq!(enclosing_spans(&root, 2), vec![(0, 11)]);
+
+        assert_eq!(matching_delimiter(src, 0), Some(11));
+        assert_eq!(matching_delimiter(src, 12), Some(0));
+        assert_eq!(matching_delimiter(src, 7), Some(9));
+        assert_eq!(matching_delimiter(src, 9), Some(6));
+        assert_eq!(matching_delimiter(src, 2), None);
+        assert_eq!(matching_delimiter("(a]", 2), None);
+        assert_eq!(matching_delimiter("(a] b)", 0), Some(5));
+    }
+}
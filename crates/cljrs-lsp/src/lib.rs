@@ -0,0 +1,3 @@
+pub mod rpc;
+pub mod analysis;
+pub mod server;
@@ -0,0 +1,55 @@
+use cljrs_lsp::{rpc, server::Server};
+use std::{
+    io::{self, BufReader},
+    process::ExitCode,
+};
+
+const USAGE: &str = "USAGE: cljrs-lsp [--stdio]
+
+  a language server for Clojure and EDN, speaking JSON-RPC over stdin and stdout
+
+  --stdio     accepted for clients that pass it, as stdio is the only transport
+  -h, --help  print this and exit
+
+  exits 0 after a shutdown request then an exit notification, 2 on invalid arguments, 1 otherwise";
+
+fn main() -> ExitCode {
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--stdio" => {}
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            arg => {
+                eprintln!("unknown argument {arg}");
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    let mut input = BufReader::new(io::stdin().lock());
+    let mut output = io::stdout().lock();
+    let mut server = Server::new();
+    loop {
+        let body = match rpc::read_message(&mut input) {
+            Ok(Some(body)) => body,
+            // the client went away without an exit notification
+            Ok(None) => return ExitCode::from(1),
+            Err(err) => {
+                eprintln!("cljrs-lsp: {err}");
+                return ExitCode::from(1);
+            }
+        };
+        for reply in server.handle_json(&body) {
+            if let Err(err) = rpc::write_message(&mut output, &reply) {
+                eprintln!("cljrs-lsp: {err}");
+                return ExitCode::from(1);
+            }
+        }
+        if let Some(code) = server.exit_code() {
+            return ExitCode::from(code);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! JSON-RPC messages as the language server protocol frames them: a `Content-Length` header, a
+//! blank line, then that many bytes of JSON
+//!
+//! Messages are [`RcValue`]s read and written by [`cljrs_data::json`], with object keys as
+//! unqualified keywords, so `{"id": 1}` is `{:id 1.0}`.
+
+use std::io::{self, BufRead, Write};
+use cljrs_data::json::{from_json_str, to_json_string, JsonError, JsonOptions};
+use cljrs_reader::{keyword::Keyword, value::RcValue, value::Value};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+/// the body of the next message, or `None` at the end of input
+pub fn read_message<R: BufRead>(r: &mut R) -> io::Result<Option<String>> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if r.read_line(&mut line)? == 0 {
+            return match content_length {
+                None => Ok(None),
+                Some(_) => Err(io::ErrorKind::UnexpectedEof.into()),
+            };
+        }
+        let header = line.trim_end_matches(['\r', '\n']);
+        if header.is_empty() {
+            break;
+        }
+        match header.split_once(':') {
+            Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                let len = value.trim().parse::<usize>();
+                content_length = Some(len.map_err(|_| invalid(format!("invalid header {header:?}")))?);
+            }
+            // e.g. Content-Type, which is always utf-8 JSON in practice
+            Some(_) => {}
+            None => return Err(invalid(format!("invalid header {header:?}"))),
+        }
+    }
+    let len = content_length.ok_or_else(|| invalid("no Content-Length header".to_owned()))?;
+    let mut body = vec![0; len];
+    r.read_exact(&mut body)?;
+    String::from_utf8(body).map(Some).map_err(|err| invalid(err.to_string()))
+}
+
+pub fn write_message<W: Write>(w: &mut W, body: &str) -> io::Result<()> {
+    write!(w, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    w.flush()
+}
+
+pub fn parse(body: &str) -> Result<RcValue, JsonError> {
+    from_json_str(body, &JsonOptions::default())
+}
+
+pub fn to_json(value: &RcValue) -> String {
+    // messages are built of strings, numbers, bools, nil, vectors and keyword-keyed maps
+    to_json_string(value, &JsonOptions::default()).expect("messages are representable as JSON")
+}
+
+/// the value of an object's key, e.g. `get(msg, "method")`
+pub fn get<'v>(value: &'v RcValue, key: &str) -> Option<&'v RcValue> {
+    let entries = value.try_as_map()?;
+    entries
+        .iter()
+        .find(|(k, _)| matches!(k.as_ref(), Value::Keyword(Keyword::Unqualified { name }) if name == key))
+        .map(|(_, v)| v.as_ref())
+}
+
+/// the value at a path of keys, e.g. `get_in(msg, &["params", "textDocument", "uri"])`
+pub fn get_in<'v>(value: &'v RcValue, path: &[&str]) -> Option<&'v RcValue> {
+    path.iter().try_fold(value, |value, key| get(value, key))
+}
+
+pub fn get_str<'v>(value: &'v RcValue, path: &[&str]) -> Option<&'v str> {
+    match get_in(value, path)? {
+        Value::Str(s) => Some(s),
+        _ => None,
+    }
+}
+
+pub fn get_usize(value: &RcValue, path: &[&str]) -> Option<usize> {
+    match get_in(value, path)? {
+        Value::Num(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+        _ => None,
+    }
+}
+
+pub fn object<const N: usize>(entries: [(&str, RcValue); N]) -> RcValue {
+    Value::map_from_value_pairs(entries.map(|(k, v)| (Value::unqualified_keyword(k.to_owned()), v)))
+}
+
+pub fn array(values: impl IntoIterator<Item = RcValue>) -> RcValue {
+    Value::vect_from_values(values)
+}
+
+pub fn string(s: impl Into<String>) -> RcValue {
+    Value::Str(s.into())
+}
+
+pub fn number(n: usize) -> RcValue {
+    Value::Num(n as f64)
+}
+
+pub fn response(id: &RcValue, result: RcValue) -> RcValue {
+    object([("jsonrpc", string("2.0")), ("id", id.clone()), ("result", result)])
+}
+
+pub fn error_response(id: &RcValue, code: i64, message: impl Into<String>) -> RcValue {
+    let error = object([("code", Value::Num(code as f64)), ("message", string(message))]);
+    object([("jsonrpc", string("2.0")), ("id", id.clone()), ("error", error)])
+}
+
+pub fn notification(method: &str, params: RcValue) -> RcValue {
+    object([("jsonrpc", string("2.0")), ("method", string(method)), ("params", params)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_str, get_usize, parse, read_message, write_message};
+
+    #[test]
+    fn framing() {
+        let mut framed = vec![];
+        write_message(&mut framed, r#"{"id":1,"params":{"uri":"é"}}"#).unwrap();
+        write_message(&mut framed, "{}").unwrap();
+        assert!(framed.starts_with(b"Content-Length: 30\r\n\r\n{"));
+
+        let mut r = &framed[..];
+        let first = parse(&read_message(&mut r).unwrap().unwrap()).unwrap();
+        assert_eq!(get_usize(&first, &["id"]), Some(1));
+        assert_eq!(get_str(&first, &["params", "uri"]), Some("é"));
+        assert_eq!(read_message(&mut r).unwrap().as_deref(), Some("{}"));
+        assert_eq!(read_message(&mut r).unwrap(), None);
+
+        assert!(read_message(&mut &b"Content-Length: 10\r\n\r\n{}"[..]).is_err());
+        assert!(read_message(&mut &b"Content-Type: x\r\n\r\n{}"[..]).is_err());
+    }
+}
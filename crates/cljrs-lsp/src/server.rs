@@ -0,0 +1,307 @@
+//! the language server's state, and its handling of each message
+//!
+//! Documents are synced whole (`TextDocumentSyncKind.Full`), and positions are UTF-16 based.
+//! Besides the standard requests, `experimental/matchingBrace` takes
+//! `{textDocument, positions}` and gives the position of the bracket matching each (or the
+//! position itself if there is none), as rust-analyzer's request of the same name does.
+
+use std::{collections::HashMap, fs};
+use cljrs_fmt::{config::Config, format::format_source};
+use cljrs_reader::{syntax, value::RcValue, value::Value, ByteIndexSpan};
+use crate::{
+    analysis::{self, LineIndex, Position, Range},
+    rpc::{self, array, get, get_str, get_usize, number, object, string},
+};
+
+const FORMAT_CONFIG_FILE: &str = ".cljrs-fmt.edn";
+
+/// the requests (besides `initialize` and `shutdown`) handled, all of which are of a document
+const DOCUMENT_REQUESTS: &[&str] = &[
+    "textDocument/documentSymbol",
+    "textDocument/foldingRange",
+    "textDocument/selectionRange",
+    "textDocument/formatting",
+    "experimental/matchingBrace",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Uninitialized,
+    Running,
+    ShutDown,
+    /// with the process's exit code
+    Exited(u8),
+}
+
+pub struct Server {
+    state: State,
+    /// keyed by URI
+    documents: HashMap<String, String>,
+    format_config: Config,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self {
+            state: State::Uninitialized,
+            documents: HashMap::new(),
+            format_config: Config::default(),
+        }
+    }
+
+    /// once `exit` is received, 0 if `shutdown` was first, otherwise 1
+    pub fn exit_code(&self) -> Option<u8> {
+        match self.state {
+            State::Exited(code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// the bodies of the responses and notifications to send in reply to a message body
+    pub fn handle_json(&mut self, body: &str) -> Vec<String> {
+        let replies = match rpc::parse(body) {
+            Ok(message) => self.handle(&message),
+            Err(err) => vec![rpc::error_response(&Value::Nil, rpc::PARSE_ERROR, err.to_string())],
+        };
+        replies.iter().map(rpc::to_json).collect()
+    }
+
+    pub fn handle(&mut self, message: &RcValue) -> Vec<RcValue> {
+        let method = get_str(message, &["method"]);
+        let params = get(message, "params").unwrap_or(&Value::Nil);
+        let Some(id) = get(message, "id") else {
+            // a notification, which has no reply even if it fails
+            return match method {
+                Some(method) => self.notify(method, params),
+                None => vec![],
+            };
+        };
+        let Some(method) = method else {
+            // a response to a request this server didn't make
+            return vec![];
+        };
+        let reply = match (self.state, method) {
+            (State::Uninitialized, "initialize") => Ok(self.initialize(params)),
+            (State::Uninitialized, _) => Err((rpc::SERVER_NOT_INITIALIZED, "not initialized".to_owned())),
+            (State::Running, "shutdown") => {
+                self.state = State::ShutDown;
+                Ok(Value::Nil)
+            }
+            (State::Running, _) => self.request(method, params),
+            (..) => Err((rpc::INVALID_REQUEST, "shut down".to_owned())),
+        };
+        vec![match reply {
+            Ok(result) => rpc::response(id, result),
+            Err((code, message)) => rpc::error_response(id, code, message),
+        }]
+    }
+
+    fn initialize(&mut self, params: &RcValue) -> RcValue {
+        self.state = State::Running;
+        let root = get_str(params, &["rootUri"]).and_then(path_of_uri);
+        if let Some(src) = root.and_then(|root| fs::read_to_string(format!("{root}/{FORMAT_CONFIG_FILE}")).ok()) {
+            // an invalid config formats as the defaults do, as cljrs-fmt can't be told otherwise
+            self.format_config = Config::from_edn(&src).unwrap_or_default();
+        }
+        let capabilities = object([
+            ("positionEncoding", string("utf-16")),
+            ("textDocumentSync", object([("openClose", Value::Bool(true)), ("change", number(1))])),
+            ("documentSymbolProvider", Value::Bool(true)),
+            ("foldingRangeProvider", Value::Bool(true)),
+            ("selectionRangeProvider", Value::Bool(true)),
+            ("documentFormattingProvider", Value::Bool(true)),
+            ("experimental", object([("matchingBraceProvider", Value::Bool(true))])),
+        ]);
+        let server_info = object([("name", string("cljrs-lsp")), ("version", string(env!("CARGO_PKG_VERSION")))]);
+        object([("capabilities", capabilities), ("serverInfo", server_info)])
+    }
+
+    fn notify(&mut self, method: &str, params: &RcValue) -> Vec<RcValue> {
+        let uri = get_str(params, &["textDocument", "uri"]).map(str::to_owned);
+        match (self.state, method, uri) {
+            (State::ShutDown, "exit", _) => self.state = State::Exited(0),
+            (_, "exit", _) => self.state = State::Exited(1),
+            (State::Running, "textDocument/didOpen", Some(uri)) => {
+                let text = get_str(params, &["textDocument", "text"]).unwrap_or_default();
+                self.documents.insert(uri.clone(), text.to_owned());
+                return vec![self.publish_diagnostics(&uri)];
+            }
+            (State::Running, "textDocument/didChange", Some(uri)) => {
+                let changes = get(params, "contentChanges").and_then(Value::try_as_vect);
+                let text = changes.and_then(|changes| get_str(changes.last()?, &["text"]));
+                if let Some(text) = text {
+                    self.documents.insert(uri.clone(), text.to_owned());
+                    return vec![self.publish_diagnostics(&uri)];
+                }
+            }
+            (State::Running, "textDocument/didClose", Some(uri)) => {
+                self.documents.remove(&uri);
+                let params = object([("uri", string(uri)), ("diagnostics", array([]))]);
+                return vec![rpc::notification("textDocument/publishDiagnostics", params)];
+            }
+            // `initialized`, `$/cancelRequest` etc.
+            _ => {}
+        }
+        vec![]
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> RcValue {
+        let src = self.documents.get(uri).map(String::as_str).unwrap_or_default();
+        let index = LineIndex::new(src);
+        let diagnostics = analysis::diagnostics(src).into_iter().map(|diagnostic| {
+            object([
+                ("range", range(&index, diagnostic.span)),
+                // Error
+                ("severity", number(1)),
+                ("source", string("cljrs-reader")),
+                ("message", string(diagnostic.message)),
+            ])
+        });
+        let params = object([("uri", string(uri)), ("diagnostics", array(diagnostics))]);
+        rpc::notification("textDocument/publishDiagnostics", params)
+    }
+
+    fn request(&mut self, method: &str, params: &RcValue) -> Result<RcValue, (i64, String)> {
+        if !DOCUMENT_REQUESTS.contains(&method) {
+            return Err((rpc::METHOD_NOT_FOUND, format!("unknown method {method}")));
+        }
+        let src = match get_str(params, &["textDocument", "uri"]) {
+            Some(uri) => match self.documents.get(uri) {
+                Some(src) => src.as_str(),
+                None => return Err((rpc::INVALID_PARAMS, format!("{uri} is not open"))),
+            },
+            None => return Err((rpc::INVALID_PARAMS, "no textDocument.uri".to_owned())),
+        };
+        let index = LineIndex::new(src);
+        let positions = || {
+            let positions = get(params, "positions").and_then(Value::try_as_vect);
+            let position = |p: &RcValue| {
+                Some(Position {
+                    line: get_usize(p, &["line"])?,
+                    character: get_usize(p, &["character"])?,
+                })
+            };
+            let positions = positions.map(|ps| ps.iter().map(|p| position(p)).collect::<Option<Vec<_>>>());
+            positions.flatten().ok_or((rpc::INVALID_PARAMS, "invalid positions".to_owned()))
+        };
+        let root = syntax::parse(src).ok();
+        Ok(match method {
+            "textDocument/documentSymbol" => {
+                let definitions = root.map(|root| analysis::definitions(src, &root)).unwrap_or_default();
+                array(definitions.into_iter().map(|def| {
+                    object([
+                        ("name", string(def.name)),
+                        ("detail", string(def.head)),
+                        ("kind", number(def.kind.lsp_symbol_kind())),
+                        ("range", range(&index, def.span)),
+                        ("selectionRange", range(&index, def.name_span)),
+                    ])
+                }))
+            }
+            "textDocument/foldingRange" => {
+                let spans = root.map(|root| analysis::collection_spans(&root)).unwrap_or_default();
+                let lines = spans.into_iter().map(|(begin, end)| (index.position(begin).line, index.position(end).line));
+                array(lines.filter(|(start, end)| start < end).map(|(start, end)| {
+                    object([("startLine", number(start)), ("endLine", number(end))])
+                }))
+            }
+            "textDocument/selectionRange" => array(positions()?.into_iter().map(|position| {
+                let byte_idx = index.byte_idx(position);
+                let spans = root.as_ref().map(|root| analysis::enclosing_spans(root, byte_idx));
+                let ranges = spans.unwrap_or_default().into_iter().map(|span| range(&index, span));
+                // nested outermost first, so that each has its parent
+                let selection = ranges.rev().fold(None, |parent, range| {
+                    Some(match parent {
+                        Some(parent) => object([("range", range), ("parent", parent)]),
+                        None => object([("range", range)]),
+                    })
+                });
+                let empty = || object([("range", position_range(position, position))]);
+                selection.unwrap_or_else(empty)
+            })),
+            "textDocument/formatting" => match format_source(src, &self.format_config) {
+                Ok(formatted) if formatted == src => array([]),
+                Ok(formatted) => {
+                    let whole = position_range(Position { line: 0, character: 0 }, index.position(src.len()));
+                    array([object([("range", whole), ("newText", string(formatted))])])
+                }
+                // the diagnostic already says why
+                Err(_) => Value::Nil,
+            },
+            _ => array(positions()?.into_iter().map(|position| {
+                let matched = analysis::matching_delimiter(src, index.byte_idx(position));
+                position_json(matched.map_or(position, |byte_idx| index.position(byte_idx)))
+            })),
+        })
+    }
+}
+
+fn position_json(position: Position) -> RcValue {
+    object([("line", number(position.line)), ("character", number(position.character))])
+}
+
+fn position_range(start: Position, end: Position) -> RcValue {
+    object([("start", position_json(start)), ("end", position_json(end))])
+}
+
+fn range(index: &LineIndex, span: ByteIndexSpan) -> RcValue {
+    let Range { start, end } = index.range(span);
+    position_range(start, end)
+}
+
+/// the path of a `file://` URI, percent-decoded
+fn path_of_uri(uri: &str) -> Option<String> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut idx = 0;
+    while idx < encoded.len() {
+        let hex = encoded.get(idx + 1..idx + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (encoded[idx], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                idx += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                idx += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{path_of_uri, Server};
+
+    #[test]
+    fn lifecycle() {
+        let mut server = Server::new();
+        let replies = server.handle_json(r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/foldingRange"}"#);
+        assert_eq!(replies, [r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32002,"message":"not initialized"}}"#]);
+        assert_eq!(server.handle_json("{").len(), 1);
+
+        server.handle_json(r#"{"jsonrpc":"2.0","id":2,"method":"initialize","params":{}}"#);
+        let replies = server.handle_json(r#"{"jsonrpc":"2.0","id":"x","method":"nope"}"#);
+        assert_eq!(
+            replies,
+            [r#"{"jsonrpc":"2.0","id":"x","error":{"code":-32601,"message":"unknown method nope"}}"#]
+        );
+        assert_eq!(server.handle_json(r#"{"jsonrpc":"2.0","id":3,"method":"shutdown"}"#).len(), 1);
+        assert_eq!(server.exit_code(), None);
+        assert!(server.handle_json(r#"{"jsonrpc":"2.0","method":"exit"}"#).is_empty());
+        assert_eq!(server.exit_code(), Some(0));
+    }
+
+    #[test]
+    fn file_uris() {
+        assert_eq!(path_of_uri("file:///a%20b/c%"), Some("/a b/c%".to_owned()));
+        assert_eq!(path_of_uri("untitled:1"), None);
+    }
+}
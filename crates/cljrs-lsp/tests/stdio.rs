@@ -0,0 +1,174 @@
+//! the cljrs-lsp binary, driven over stdin and stdout as an editor would
+
+use std::process::{Command, Stdio};
+use cljrs_lsp::rpc::{self, get, get_str, read_message, write_message};
+use cljrs_reader::value::RcValue;
+
+const URI: &str = "file:///tmp/a.clj";
+
+/// the exit code, then the messages written, for messages read
+fn run(messages: &[String]) -> (Option<i32>, Vec<RcValue>) {
+    run_with_args(&[], messages)
+}
+
+fn run_with_args(args: &[&str], messages: &[String]) -> (Option<i32>, Vec<RcValue>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cljrs-lsp"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    for message in messages {
+        write_message(&mut stdin, message).unwrap();
+    }
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+
+    let mut stdout = &output.stdout[..];
+    let mut replies = vec![];
+    while let Some(body) = read_message(&mut stdout).unwrap() {
+        replies.push(rpc::parse(&body).unwrap());
+    }
+    (output.status.code(), replies)
+}
+
+fn request(id: usize, method: &str, params: &str) -> String {
+    format!(r#"{{"jsonrpc":"2.0","id":{id},"method":"{method}","params":{params}}}"#)
+}
+
+fn notification(method: &str, params: &str) -> String {
+    format!(r#"{{"jsonrpc":"2.0","method":"{method}","params":{params}}}"#)
+}
+
+fn session(src: &str, requests: &[(&str, &str)]) -> (Option<i32>, Vec<RcValue>) {
+    let mut messages = vec![
+        request(0, "initialize", r#"{"capabilities":{}}"#),
+        notification("initialized", "{}"),
+        notification(
+            "textDocument/didOpen",
+            &format!(
+                r#"{{"textDocument":{{"uri":"{URI}","languageId":"clojure","version":1,"text":{}}}}}"#,
+                json_str(src)
+            ),
+        ),
+    ];
+    for (idx, (method, params)) in requests.iter().enumerate() {
+        messages.push(request(idx + 1, method, params));
+    }
+    messages.push(request(requests.len() + 1, "shutdown", "null"));
+    messages.push(notification("exit", "null"));
+    run(&messages)
+}
+
+fn json_str(s: &str) -> String {
+    rpc::to_json(&rpc::string(s))
+}
+
+/// the result of the response with the id, as JSON
+fn result(replies: &[RcValue], id: usize) -> String {
+    let reply = replies
+        .iter()
+        .find(|reply| rpc::get_usize(reply, &["id"]) == Some(id))
+        .unwrap_or_else(|| panic!("no reply to {id}"));
+    rpc::to_json(get(reply, "result").unwrap())
+}
+
+fn doc(params: &str) -> String {
+    format!(r#"{{"textDocument":{{"uri":"{URI}"}}{params}}}"#)
+}
+
+#[test]
+fn lifecycle_and_diagnostics() {
+    let (code, replies) = session("(defn f [x]\n  [x)", &[]);
+    assert_eq!(code, Some(0));
+    let capabilities = result(&replies, 0);
+    assert!(capabilities.contains(r#""documentFormattingProvider":true"#), "{capabilities}");
+
+    let published = replies
+        .iter()
+        .find(|reply| get_str(reply, &["method"]) == Some("textDocument/publishDiagnostics"))
+        .unwrap();
+    assert_eq!(
+        rpc::to_json(get(published, "params").unwrap()),
+        concat!(
+            r#"{"uri":"file:///tmp/a.clj","diagnostics":[{"range":{"start":{"line":1,"character":2},"#,
+            r#""end":{"line":1,"character":5}},"severity":1,"source":"cljrs-reader","message":"invalid input"}]}"#,
+        )
+    );
+
+    let (code, replies) = run(&[request(0, "initialize", "{}"), notification("exit", "null")]);
+    assert_eq!((code, replies.len()), (Some(1), 1));
+}
+
+#[test]
+fn arguments() {
+    // as VS Code's language client and others launch servers
+    let messages = [request(0, "initialize", "{}"), request(1, "shutdown", "null"), notification("exit", "null")];
+    let (code, replies) = run_with_args(&["--stdio"], &messages);
+    assert_eq!((code, replies.len()), (Some(0), 2));
+    assert_eq!(result(&replies, 1), "null");
+
+    let (code, replies) = run_with_args(&["--tcp"], &messages);
+    assert_eq!((code, replies.len()), (Some(2), 0));
+    let help = Command::new(env!("CARGO_BIN_EXE_cljrs-lsp")).arg("--help").output().unwrap();
+    assert!(help.status.success() && help.stdout.starts_with(b"USAGE: cljrs-lsp"));
+}
+
+#[test]
+fn structure() {
+    let src = "(ns a)\n\n(defn ^:private f\n  [x]\n  {:a [x]})\n(def y 1)\n";
+    let (_, replies) = session(src, &[
+        ("textDocument/documentSymbol", &doc("")),
+        ("textDocument/foldingRange", &doc("")),
+        ("textDocument/selectionRange", &doc(r#","positions":[{"line":4,"character":7}]"#)),
+        ("experimental/matchingBrace", &doc(r#","positions":[{"line":4,"character":2},{"line":0,"character":1}]"#)),
+    ]);
+    assert_eq!(
+        result(&replies, 1),
+        concat!(
+            r#"[{"name":"f","detail":"defn","kind":12,"#,
+            r#""range":{"start":{"line":2,"character":0},"end":{"line":4,"character":11}},"#,
+            r#""selectionRange":{"start":{"line":2,"character":16},"end":{"line":2,"character":17}}},"#,
+            r#"{"name":"y","detail":"def","kind":13,"#,
+            r#""range":{"start":{"line":5,"character":0},"end":{"line":5,"character":9}},"#,
+            r#""selectionRange":{"start":{"line":5,"character":5},"end":{"line":5,"character":6}}}]"#,
+        )
+    );
+    assert_eq!(result(&replies, 2), r#"[{"startLine":2,"endLine":4}]"#);
+    let selection = [((4, 7), (4, 8)), ((4, 6), (4, 9)), ((4, 2), (4, 10)), ((2, 0), (4, 11)), ((0, 0), (6, 0))]
+        .iter()
+        .rev()
+        .fold(String::new(), |parent, (start, end)| {
+            let range = format!(
+                r#""range":{{"start":{{"line":{},"character":{}}},"end":{{"line":{},"character":{}}}}}"#,
+                start.0, start.1, end.0, end.1
+            );
+            match parent.is_empty() {
+                true => format!("{{{range}}}"),
+                false => format!(r#"{{{range},"parent":{parent}}}"#),
+            }
+        });
+    assert_eq!(result(&replies, 3), format!("[{selection}]"));
+    assert_eq!(result(&replies, 4), r#"[{"line":4,"character":9},{"line":0,"character":5}]"#);
+}
+
+#[test]
+fn formatting() {
+    let (_, replies) = session("(let [x 1]\n      x  )\n", &[("textDocument/formatting", &doc(""))]);
+    assert_eq!(
+        result(&replies, 1),
+        concat!(
+            r#"[{"range":{"start":{"line":0,"character":0},"end":{"line":2,"character":0}},"#,
+            r#""newText":"(let [x 1]\n  x)\n"}]"#,
+        )
+    );
+
+    let (_, replies) = session("(a)\n", &[
+        ("textDocument/formatting", &doc("")),
+        ("textDocument/formatting", r#"{"textDocument":{"uri":"file:///b.clj"}}"#),
+    ]);
+    assert_eq!(result(&replies, 1), "[]");
+    let unopened = replies.iter().find(|reply| rpc::get_usize(reply, &["id"]) == Some(2)).unwrap();
+    assert_eq!(rpc::to_json(get(unopened, "error").unwrap()), r#"{"code":-32602,"message":"file:///b.clj is not open"}"#);
+}
@@ -0,0 +1,29 @@
+//! a `#![no_std]` crate reading EDN with only `alloc`, which builds only while `cljrs-core` and
+//! `cljrs-reader` do without their default features
+//!
+//! `cljrs-reader`'s `no_std` test builds it on its own, since building it along with crates that
+//! enable those features would enable them for it too.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use archery::RcK;
+use cljrs_core::symbol::Symbol;
+use cljrs_reader::{printer::pr_str, reader, value::Value, ReadError};
+
+/// the top-level values of EDN
+pub fn read_all(src: &str) -> Result<Vec<Value<RcK>>, ReadError> {
+    let mut values = Vec::new();
+    if let Some(mut rdr) = reader::<RcK>(src) {
+        while let Some(span_value) = rdr.try_read_one()? {
+            values.push(span_value.data);
+        }
+    }
+    Ok(values)
+}
+
+pub fn symbol_str(namespace: &str, name: &str) -> String {
+    pr_str(&Value::<RcK>::Symbol(Symbol::qualified(namespace, name)))
+}
@@ -0,0 +1,142 @@
+use archery::RcK;
+use cljrs_core::pretty::PrettyOptions;
+use cljrs_data::{
+    json::{to_json_string, JsonOptions, Unrepresentable},
+    spans::line_col,
+};
+use cljrs_q::query::Query;
+use cljrs_reader::{pretty::pretty_str, printer::pr_str, reader, value::ValuePtr};
+use cljrs_tooling::source::describe_error;
+use std::{
+    fs,
+    io::{self, Read, Write},
+    process::ExitCode,
+};
+
+const USAGE: &str = "USAGE: cljrs-q [--json] [--compact] <query> [<path>...]
+
+  applies the query to each top-level form of each file, or of stdin if there are none
+  (or for -), and prints each result
+
+  a query is a sequence of steps, for example
+    :users 0 :name                       gets a key or index
+    :users (filter (>= :age 18)) count   filters, then counts
+    :users (map (select-keys [:name]))   projects
+  see the cljrs_q::query docs for all steps and predicates
+
+  --json     print results as JSON; sets and symbols are printed as strings
+  --compact  print each result on one line, rather than pretty-printed
+  -h, --help print this and exit
+
+  exits 0 on success, 2 on errors";
+
+struct Args {
+    json: bool,
+    compact: bool,
+    query: String,
+    paths: Vec<String>,
+}
+
+/// the arguments, or none if help was asked for
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut parsed = Args {
+        json: false,
+        compact: false,
+        query: String::new(),
+        paths: vec![],
+    };
+    let mut query = None;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => parsed.json = true,
+            "--compact" => parsed.compact = true,
+            "-h" | "--help" => return Ok(None),
+            flag if flag.starts_with("--") => return Err(format!("unknown flag {flag}")),
+            _ if query.is_none() => query = Some(arg),
+            path => parsed.paths.push(path.to_owned()),
+        }
+    }
+    match query {
+        Some(query) => parsed.query = query,
+        None => return Err("no query given".to_owned()),
+    }
+    if parsed.paths.is_empty() {
+        parsed.paths.push("-".to_owned());
+    }
+    Ok(Some(parsed))
+}
+
+fn print(out: &mut impl Write, value: &ValuePtr<RcK>, args: &Args) -> Result<(), String> {
+    let printed = match (args.json, args.compact) {
+        (true, _) => {
+            let options = JsonOptions::default().with_unrepresentable(Unrepresentable::Stringify);
+            to_json_string(value.as_ref(), &options).map_err(|err| err.to_string())?
+        }
+        (false, true) => pr_str(value.as_ref()),
+        (false, false) => pretty_str(value.as_ref(), &PrettyOptions::default()),
+    };
+    writeln!(out, "{printed}").map_err(|err| err.to_string())
+}
+
+/// queries each form of the file, stopping at the first error
+fn query_path(path: &str, query: &Query<RcK>, args: &Args, out: &mut impl Write) -> Result<(), String> {
+    let mut src = String::new();
+    let read = match path {
+        "-" => io::stdin().read_to_string(&mut src).map(|_| ()),
+        path => fs::read_to_string(path).map(|read| src = read),
+    };
+    let name = match path {
+        "-" => "<stdin>",
+        path => path,
+    };
+    read.map_err(|err| format!("{name}: {err}"))?;
+
+    let Some(mut rdr) = reader::<RcK>(&src) else {
+        return Ok(());
+    };
+    while let Some(form) = rdr.try_read_one().map_err(|err| describe_error(&err, &src, name))? {
+        let (line, col) = line_col(&src, form.span.0);
+        let result = query
+            .eval(form.data.to_ptr())
+            .map_err(|err| format!("{name}:{line}:{col}: {err}"))?;
+        print(out, &result, args)?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(msg) => {
+            eprintln!("{msg}");
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let query = match Query::parse(&args.query) {
+        Ok(query) => query,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut errored = false;
+    for path in &args.paths {
+        if let Err(msg) = query_path(path, &query, &args, &mut out) {
+            eprintln!("{msg}");
+            errored = true;
+        }
+    }
+
+    match errored {
+        true => ExitCode::from(2),
+        false => ExitCode::SUCCESS,
+    }
+}
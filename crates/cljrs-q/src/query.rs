@@ -0,0 +1,380 @@
+//! queries over EDN values, written in EDN
+//!
+//! A query is a sequence of steps, each applied to the result of the last:
+//!
+//! - a keyword, string or number gets that key of a map, or that index of a vector or list,
+//!   e.g. `:users 0 :name`
+//! - `count`, `keys`, `vals`, `first` and `last` do as in Clojure
+//! - `(map step...)` applies the steps to each element, `(filter pred)` and `(remove pred)`
+//!   keep the elements that match a predicate or don't, and `(select-keys [key...])` keeps
+//!   only those keys of a map
+//! - `[step...]` groups steps, e.g. within predicates
+//!
+//! Elements of maps are `[key value]` entries, as Clojure's `seq` gives. Predicates are:
+//!
+//! - `(= step value)`, `(not= step value)`, `(< step value)`, `(<= step value)`,
+//!   `(> step value)` and `(>= step value)`, which compare the step's result to a literal
+//!   value (ordering compares numbers or strings, and is false for anything else)
+//! - `(includes? step "s")` for substrings and `(contains? key)` for keys of maps
+//! - `(and pred...)`, `(or pred...)` and `(not pred)`
+//! - any other step, which matches if its result isn't nil or false
+//!
+//! Clojure example:
+//! ```clojure
+//! ;; cljrs-q ':users (filter (>= :age 18)) (map :name)'
+//! {:users [{:name "a", :age 20} {:name "b", :age 9}]} ; ["a"]
+//! ```
+
+use std::fmt;
+use archery::SharedPointerKind;
+use cljrs_reader::{
+    printer::pr_str,
+    reader,
+    value::{Value, ValuePtr},
+    ReadError,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryError {
+    /// the query isn't EDN
+    Read(ReadError),
+    /// the query is EDN, but not a query, e.g. `"(map)"`
+    Invalid(String),
+    /// a step doesn't apply to its input, e.g. `"count of 1.0"`
+    Eval(String),
+}
+
+impl From<ReadError> for QueryError {
+    fn from(err: ReadError) -> Self {
+        Self::Read(err)
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(err) => write!(f, "invalid query: {err}"),
+            Self::Invalid(form) => write!(f, "invalid query: {form}"),
+            Self::Eval(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cmp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+#[derive(Debug)]
+enum Step<P: SharedPointerKind> {
+    Get(ValuePtr<P>),
+    Count,
+    Keys,
+    Vals,
+    First,
+    Last,
+    Map(Query<P>),
+    Filter(Pred<P>),
+    Remove(Pred<P>),
+    SelectKeys(Vec<ValuePtr<P>>),
+    Path(Query<P>),
+}
+
+#[derive(Debug)]
+enum Pred<P: SharedPointerKind> {
+    Truthy(Query<P>),
+    Cmp(Cmp, Query<P>, ValuePtr<P>),
+    Includes(Query<P>, String),
+    Contains(ValuePtr<P>),
+    And(Vec<Pred<P>>),
+    Or(Vec<Pred<P>>),
+    Not(Box<Pred<P>>),
+}
+
+#[derive(Debug)]
+pub struct Query<P: SharedPointerKind> {
+    steps: Vec<Step<P>>,
+}
+
+impl<P: SharedPointerKind> Query<P> {
+    /// the steps written in `src`, where no steps is the identity query
+    pub fn parse(src: &str) -> Result<Self, QueryError> {
+        let mut forms = vec![];
+        if let Some(mut rdr) = reader::<P>(src) {
+            while let Some(form) = rdr.try_read_one()? {
+                forms.push(form.data.to_ptr());
+            }
+        }
+        Self::from_forms(&forms)
+    }
+
+    fn from_forms(forms: &[ValuePtr<P>]) -> Result<Self, QueryError> {
+        let steps = forms.iter().map(|form| step(form)).collect::<Result<_, _>>()?;
+        Ok(Self { steps })
+    }
+
+    pub fn eval(&self, value: ValuePtr<P>) -> Result<ValuePtr<P>, QueryError> {
+        self.steps.iter().try_fold(value, |value, step| step.eval(value))
+    }
+}
+
+fn invalid<T, P: SharedPointerKind>(form: &Value<P>) -> Result<T, QueryError> {
+    Err(QueryError::Invalid(pr_str(form)))
+}
+
+fn symbol_name<P: SharedPointerKind>(form: &Value<P>) -> Option<&str> {
+    match form {
+        Value::Symbol(symbol) if !symbol.is_qualified() => Some(symbol.name()),
+        _ => None,
+    }
+}
+
+fn step<P: SharedPointerKind>(form: &ValuePtr<P>) -> Result<Step<P>, QueryError> {
+    match form.as_ref() {
+        Value::Keyword(..) | Value::Str(..) | Value::Num(..) => Ok(Step::Get(form.clone())),
+        Value::Vect(forms) => Ok(Step::Path(Query::from_forms(forms)?)),
+        Value::Symbol(..) => match symbol_name(form) {
+            Some("count") => Ok(Step::Count),
+            Some("keys") => Ok(Step::Keys),
+            Some("vals") => Ok(Step::Vals),
+            Some("first") => Ok(Step::First),
+            Some("last") => Ok(Step::Last),
+            _ => invalid(form),
+        },
+        Value::List(forms) => {
+            let Some((op, args)) = forms.split_first() else {
+                return invalid(form);
+            };
+            match (symbol_name(op), args) {
+                (Some("count" | "keys" | "vals" | "first" | "last"), []) => step(op),
+                (Some("map"), [_, ..]) => Ok(Step::Map(Query::from_forms(args)?)),
+                (Some("filter"), [pred_form]) => Ok(Step::Filter(pred(pred_form)?)),
+                (Some("remove"), [pred_form]) => Ok(Step::Remove(pred(pred_form)?)),
+                (Some("select-keys"), [keys]) => match keys.as_ref() {
+                    Value::Vect(keys) => Ok(Step::SelectKeys(keys.clone())),
+                    _ => invalid(form),
+                },
+                _ => invalid(form),
+            }
+        }
+        _ => invalid(form),
+    }
+}
+
+fn pred<P: SharedPointerKind>(form: &ValuePtr<P>) -> Result<Pred<P>, QueryError> {
+    let Value::List(forms) = form.as_ref() else {
+        return Ok(Pred::Truthy(Query::from_forms(std::slice::from_ref(form))?));
+    };
+    let Some((op, args)) = forms.split_first() else {
+        return invalid(form);
+    };
+    let cmp = match symbol_name(op) {
+        Some("=") => Some(Cmp::Eq),
+        Some("not=") => Some(Cmp::NotEq),
+        Some("<") => Some(Cmp::Lt),
+        Some("<=") => Some(Cmp::LtEq),
+        Some(">") => Some(Cmp::Gt),
+        Some(">=") => Some(Cmp::GtEq),
+        _ => None,
+    };
+    let query = |form: &ValuePtr<P>| Query::from_forms(std::slice::from_ref(form));
+    match (cmp, symbol_name(op), args) {
+        (Some(cmp), _, [lhs, rhs]) => Ok(Pred::Cmp(cmp, query(lhs)?, rhs.clone())),
+        (None, Some("includes?"), [lhs, rhs]) => match rhs.as_ref() {
+            Value::Str(s) => Ok(Pred::Includes(query(lhs)?, s.clone())),
+            _ => invalid(form),
+        },
+        (None, Some("contains?"), [key]) => Ok(Pred::Contains(key.clone())),
+        (None, Some("and"), preds) => Ok(Pred::And(preds.iter().map(pred).collect::<Result<_, _>>()?)),
+        (None, Some("or"), preds) => Ok(Pred::Or(preds.iter().map(pred).collect::<Result<_, _>>()?)),
+        (None, Some("not"), [p]) => Ok(Pred::Not(Box::new(pred(p)?))),
+        (None, Some("=" | "not=" | "<" | "<=" | ">" | ">=" | "includes?" | "contains?" | "not"), _) => invalid(form),
+        _ => Ok(Pred::Truthy(Query::from_forms(std::slice::from_ref(form))?)),
+    }
+}
+
+fn eval_err<T, P: SharedPointerKind>(what: &str, value: &Value<P>) -> Result<T, QueryError> {
+    Err(QueryError::Eval(format!("{what} of {}", pr_str(value))))
+}
+
+/// the elements of a collection, entries of maps as `[key value]` vectors
+fn elements<P: SharedPointerKind>(what: &str, value: &ValuePtr<P>) -> Result<Vec<ValuePtr<P>>, QueryError> {
+    match value.as_ref() {
+        Value::Nil => Ok(vec![]),
+        Value::List(values) | Value::Vect(values) | Value::Set(values) => Ok(values.clone()),
+        Value::Map(entries) => Ok(entries
+            .iter()
+            .map(|(k, v)| Value::vect_ptr_from_value_ptrs([k.clone(), v.clone()]))
+            .collect()),
+        value => eval_err(what, value),
+    }
+}
+
+fn is_truthy<P: SharedPointerKind>(value: &Value<P>) -> bool {
+    !matches!(value, Value::Nil | Value::Bool(false))
+}
+
+impl<P: SharedPointerKind> Step<P> {
+    fn eval(&self, value: ValuePtr<P>) -> Result<ValuePtr<P>, QueryError> {
+        let nil = || Value::Nil.to_ptr();
+        Ok(match (self, value.as_ref()) {
+            (Self::Get(key), Value::Map(entries)) => {
+                entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone()).unwrap_or_else(nil)
+            }
+            (Self::Get(idx), Value::List(values) | Value::Vect(values)) => match idx.as_ref() {
+                Value::Num(n) if n.fract() == 0.0 && *n >= 0.0 => values.get(*n as usize).cloned().unwrap_or_else(nil),
+                _ => nil(),
+            },
+            (Self::Get(_), Value::Nil) => nil(),
+            (Self::Get(key), value) => return eval_err(&format!("get {}", pr_str(key)), value),
+            (Self::Count, Value::Str(s)) => Value::Num(s.chars().count() as f64).to_ptr(),
+            (Self::Count, _) => Value::Num(elements("count", &value)?.len() as f64).to_ptr(),
+            (Self::Keys | Self::Vals, Value::Nil) => nil(),
+            (Self::Keys, Value::Map(entries)) => Value::vect_ptr_from_value_ptrs(entries.iter().map(|(k, _)| k.clone())),
+            (Self::Vals, Value::Map(entries)) => Value::vect_ptr_from_value_ptrs(entries.iter().map(|(_, v)| v.clone())),
+            (Self::Keys, value) => return eval_err("keys", value),
+            (Self::Vals, value) => return eval_err("vals", value),
+            (Self::First, _) => elements("first", &value)?.first().cloned().unwrap_or_else(nil),
+            (Self::Last, _) => elements("last", &value)?.last().cloned().unwrap_or_else(nil),
+            (Self::Map(query), _) => {
+                let values = elements("map", &value)?.into_iter().map(|v| query.eval(v));
+                Value::vect_ptr_from_value_ptrs(values.collect::<Result<Vec<_>, _>>()?)
+            }
+            (Self::Filter(pred) | Self::Remove(pred), _) => {
+                let keep = matches!(self, Self::Filter(..));
+                let mut kept = vec![];
+                for v in elements("filter", &value)? {
+                    if pred.matches(&v)? == keep {
+                        kept.push(v);
+                    }
+                }
+                Value::vect_ptr_from_value_ptrs(kept)
+            }
+            (Self::SelectKeys(..), Value::Nil) => Value::empty_map().to_ptr(),
+            (Self::SelectKeys(keys), Value::Map(entries)) => Value::map_ptr_from_value_ptrs(
+                keys.iter().filter_map(|key| entries.iter().find(|(k, _)| k == key).cloned()),
+            ),
+            (Self::SelectKeys(..), value) => return eval_err("select-keys", value),
+            (Self::Path(query), _) => query.eval(value)?,
+        })
+    }
+}
+
+impl<P: SharedPointerKind> Pred<P> {
+    fn matches(&self, value: &ValuePtr<P>) -> Result<bool, QueryError> {
+        Ok(match self {
+            Self::Truthy(query) => is_truthy(query.eval(value.clone())?.as_ref()),
+            Self::Cmp(cmp, query, rhs) => {
+                let lhs = query.eval(value.clone())?;
+                let ordering = match (lhs.as_ref(), rhs.as_ref()) {
+                    (Value::Num(a), Value::Num(b)) => a.partial_cmp(b),
+                    (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+                    _ => None,
+                };
+                match (cmp, ordering) {
+                    (Cmp::Eq, _) => lhs == *rhs,
+                    (Cmp::NotEq, _) => lhs != *rhs,
+                    (_, None) => false,
+                    (Cmp::Lt, Some(ordering)) => ordering.is_lt(),
+                    (Cmp::LtEq, Some(ordering)) => ordering.is_le(),
+                    (Cmp::Gt, Some(ordering)) => ordering.is_gt(),
+                    (Cmp::GtEq, Some(ordering)) => ordering.is_ge(),
+                }
+            }
+            Self::Includes(query, s) => match query.eval(value.clone())?.as_ref() {
+                Value::Str(haystack) => haystack.contains(s.as_str()),
+                _ => false,
+            },
+            Self::Contains(key) => match value.as_ref() {
+                Value::Map(entries) => entries.iter().any(|(k, _)| k == key),
+                _ => false,
+            },
+            Self::And(preds) => {
+                for pred in preds {
+                    if !pred.matches(value)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            Self::Or(preds) => {
+                for pred in preds {
+                    if pred.matches(value)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+            Self::Not(pred) => !pred.matches(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Query, QueryError};
+    use archery::RcK;
+    use cljrs_reader::{printer::pr_str, reader};
+
+    const USERS: &str = r#"{:users [{:name "ann", :age 31, :roles #{:admin}}
+                                     {:name "bob", :age 17}
+                                     {:name "cy", :age 45, :email "cy@example.com"}]}"#;
+
+    fn q(query: &str, src: &str) -> Result<String, QueryError> {
+        let value = reader::<RcK>(src).unwrap().try_read_one().unwrap().unwrap().data;
+        let query = Query::<RcK>::parse(query)?;
+        Ok(pr_str(query.eval(value.to_ptr())?.as_ref()))
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(q(":users 0 :name", USERS).unwrap(), r#""ann""#);
+        assert_eq!(q(":users 5 :name", USERS).unwrap(), "nil");
+        assert_eq!(q(":users count", USERS).unwrap(), "3.0");
+        assert_eq!(q(":users (last) keys", USERS).unwrap(), "[:name :age :email]");
+        assert_eq!(q("", "[1]").unwrap(), "[1.0]");
+        assert_eq!(q(r#""a" 1"#, r#"{"a" [1 2]}"#).unwrap(), "2.0");
+    }
+
+    #[test]
+    fn projections() {
+        assert_eq!(q(":users (map :name)", USERS).unwrap(), r#"["ann" "bob" "cy"]"#);
+        assert_eq!(q(":users (map :roles count)", USERS).unwrap(), "[1.0 0.0 0.0]");
+        assert_eq!(
+            q(":users (map (select-keys [:age :name]))", USERS).unwrap(),
+            r#"[{:age 31.0, :name "ann"} {:age 17.0, :name "bob"} {:age 45.0, :name "cy"}]"#
+        );
+        assert_eq!(q("(map first)", "{:a 1 :b 2}").unwrap(), "[:a :b]");
+    }
+
+    #[test]
+    fn filters() {
+        let names = |pred: &str| q(&format!(":users (filter {pred}) (map :name)"), USERS).unwrap();
+        assert_eq!(names("(>= :age 18)"), r#"["ann" "cy"]"#);
+        assert_eq!(names(":email"), r#"["cy"]"#);
+        assert_eq!(names("(contains? :roles)"), r#"["ann"]"#);
+        assert_eq!(names(r#"(includes? :name "b")"#), r#"["bob"]"#);
+        assert_eq!(names(r#"(or (= :name "bob") (> [:roles count] 0))"#), r#"["ann" "bob"]"#);
+        assert_eq!(names("(and (not :email) (< :age 40))"), r#"["ann" "bob"]"#);
+        assert_eq!(names("(< :email 1)"), "[]");
+        assert_eq!(q(":users (remove :roles) count", USERS).unwrap(), "2.0");
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(q("(map)", USERS), Err(QueryError::Invalid(form)) if form == "(map)"));
+        assert!(matches!(q("frobnicate", USERS), Err(QueryError::Invalid(..))));
+        assert!(matches!(q("(filter (= :a))", USERS), Err(QueryError::Invalid(..))));
+        assert!(matches!(q("[", USERS), Err(QueryError::Read(..))));
+        assert_eq!(q(":users 0 :age count", USERS), Err(QueryError::Eval("count of 31.0".to_owned())));
+        assert_eq!(q(":name", "[{:name 1}]").unwrap(), "nil");
+        assert_eq!(q(":a :b", "{:a 1}"), Err(QueryError::Eval("get :b of 1.0".to_owned())));
+    }
+}
@@ -0,0 +1,88 @@
+//! runs the `cljrs-q` binary on files and stdin
+
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+fn cljrs_q(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cljrs-q"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).unwrap()
+}
+
+#[test]
+fn queries_stdin() {
+    let src = r#"{:users [{:name "a", :age 20} {:name "b", :age 9}]} {:users []}"#;
+    let output = cljrs_q(&["--compact", ":users (filter (>= :age 18)) (map :name)"], src);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "[\"a\"]\n[]\n");
+}
+
+#[test]
+fn queries_files_as_json() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cljrs-q-cli");
+    fs::create_dir_all(&dir).unwrap();
+    let (a, b) = (dir.join("a.edn"), dir.join("b.edn"));
+    fs::write(&a, "{:n 1, :s #{x}}").unwrap();
+    fs::write(&b, "{:n 2.5}").unwrap();
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    let output = cljrs_q(&["--json", ":n", a, b], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "1\n2.5\n");
+
+    let output = cljrs_q(&["--json", ":s", a], "");
+    assert_eq!(stdout(&output), "\"#{x}\"\n");
+}
+
+#[test]
+fn reports_errors() {
+    let output = cljrs_q(&[":a"], "{:a 1}\n[1");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stdout(&output), "1.0\n");
+    assert_eq!(stderr(&output), "<stdin>:2:1: unexpected end of input\n");
+
+    let output = cljrs_q(&["count"], "1");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("<stdin>:1:1: "), "{}", stderr(&output));
+
+    let output = cljrs_q(&["(map"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stderr(&output), "invalid query: unexpected end of input at byte 0\n");
+
+    let output = cljrs_q(&[":a", "does-not-exist.edn"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("does-not-exist.edn: "));
+}
+
+#[test]
+fn usage() {
+    let output = cljrs_q(&["--help"], "");
+    assert!(output.status.success());
+    assert!(stdout(&output).starts_with("USAGE: cljrs-q"));
+
+    let output = cljrs_q(&["--nope", ":a"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("unknown flag --nope\nUSAGE: cljrs-q"));
+
+    let output = cljrs_q(&[], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("no query given"));
+}
@@ -1,9 +1,16 @@
+#[cfg(not(feature = "tracing"))]
+use crate::tracing;
 use crate::{
         keyword::Keyword,
-        value::{Value, ValuePtr},
-        ByteIndexSpan, ReadClj, ReadError, ReadResult, SpanValue, WithSpan,
+        value::{KeyIndex, Value, ValuePtr},
+        ByteIndexSpan, DuplicateKeys, ReadClj, ReadError, ReadOptions, ReadResult, SpanValue, WithSpan,
 };
-use cljrs_core::symbol::Symbol;
+use cljrs_core::{
+    intern::Name,
+    printer::{char_from_name, f64_from_symbolic_name},
+    symbol::{Symbol, SymbolError},
+};
+use alloc::{collections::BTreeMap, format, string::String, vec, vec::Vec};
 use archery::SharedPointerKind;
 
 type ByteIdx = usize;
@@ -14,8 +21,14 @@ pub struct CharReader<'i> {
     _src: &'i str,
     src: Vec<(ByteIdx, char)>,
     src_idx: SrcIdx,
+    options: ReadOptions,
+    symbols: Symbols,
 }
 
+/// the symbols a reader has read by their tokens, so that each is interned once by the reader
+/// rather than every time it's read, which would take the interner's lock
+pub(crate) type Symbols = BTreeMap<String, Symbol>;
+
 impl<'i> CharReader<'i> {
     pub fn try_from_str(s: &'i str) -> Option<Self> {
         if s.is_empty() {
@@ -25,11 +38,41 @@ impl<'i> CharReader<'i> {
                 src: s.char_indices().collect(),
                 _src: s,
                 src_idx: 0,
+                options: ReadOptions::default(),
+                symbols: Symbols::new(),
             }
             .into()
         }
     }
 
+    pub fn with_options(mut self, options: ReadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// reads with the symbols another reader has read, e.g. one reading the same thread's
+    /// previous chunk of an input
+    #[cfg(feature = "std")]
+    pub(crate) fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn into_symbols(self) -> Symbols {
+        self.symbols
+    }
+
+    /// the symbol a token is, as [`token_symbol`] checks it
+    fn symbol(&mut self, token: &WithSpan<ByteIndexSpan, String>) -> Result<Symbol, ReadError> {
+        if let Some(symbol) = self.symbols.get(&token.data) {
+            return Ok(symbol.clone());
+        }
+        let symbol = token_symbol(token)?;
+        self.symbols.insert(token.data.clone(), symbol.clone());
+        Ok(symbol)
+    }
+
     pub fn is_at_end(&mut self) -> bool {
         self.src_idx == self.src.len() - 1
     }
@@ -92,222 +135,262 @@ impl<'i> CharReader<'i> {
             self.advance();
         }
     }
-}
 
-fn is_whitespace(ch: char) -> bool {
-    ch.is_whitespace() || ch == ','
+    fn last_byte_idx(&self) -> ByteIdx {
+        self.src.last().map(|(byte_idx, _)| *byte_idx).unwrap_or(0)
+    }
+
+    /// skips whitespace, `;` and `#!` comments and `#_` discarded forms,
+    /// so that afterwards the reader is on a form, a closing delimiter or beyond the end
+    ///
+    /// Clojure example:
+    /// ```clojure
+    /// [1 #_ #_ 2 3 ; four
+    ///  5] ; reads as [1 5]
+    /// ```
+    pub fn skip_trivia<P: SharedPointerKind>(&mut self) -> Result<(), ReadError> {
+        while let Some((byte_idx, ch)) = self.current() {
+            match ch {
+                ch if is_whitespace(ch) => self.advance(),
+                ';' => skip_line(self),
+                '#' if self.peek_char_eq('!') => skip_line(self),
+                '#' if self.peek_char_eq('_') => {
+                    self.advance(); // move beyond '#'
+                    self.advance(); // move beyond '_'
+                    self.skip_trivia::<P>()?;
+                    match self.current() {
+                        None => {
+                            return Err(ReadError::insufficient_input((byte_idx, self.last_byte_idx())))
+                        }
+                        Some((closer_byte_idx, ')' | ']' | '}')) => {
+                            return Err(ReadError::invalid_input((byte_idx, closer_byte_idx)))
+                        }
+                        Some(_) => {}
+                    }
+                    let _discarded: Option<SpanValue<P>> = ReadClj::<P>::try_read_one(self)?;
+                }
+                _ => break,
+            }
+        }
+        Ok(())
+    }
 }
 
-fn is_symbol_begin_char(ch: char) -> bool {
-    !is_whitespace(ch)
-        && match ch {
-            '(' | ')' | '{' | '}' | '[' | ']' => false,
-            _ => true,
+/// moves to the end of the line, leaving the newline (if any) to be skipped as whitespace
+fn skip_line(rdr: &mut CharReader<'_>) {
+    while let Some(ch) = rdr.current_char() {
+        if ch == '\n' || ch == '\r' {
+            break;
         }
+        rdr.advance();
+    }
 }
 
-fn is_symbol_continue_char(ch: char) -> bool {
+fn is_whitespace(ch: char) -> bool {
+    ch.is_whitespace() || ch == ','
+}
+
+/// whether a char can be part of a token (a symbol, keyword, number etc.), which is read up to the
+/// first that can't and then checked
+fn is_token_char(ch: char) -> bool {
     !is_whitespace(ch)
         && match ch {
-            '(' | ')' | '{' | '}' | '[' | ']' => false,
+            '(' | ')' | '{' | '}' | '[' | ']' | ';' => false,
             _ => true,
         }
 }
 
-fn try_read_symbol_part<'i>(
-    rdr: &mut CharReader<'i>,
-) -> Result<Option<WithSpan<ByteIndexSpan, String>>, ReadError> {
-    let (begin_byte_idx, first_ch) = rdr
-        .current()
-        .ok_or(ReadError::invalid_input((usize::MIN, usize::MAX)))?;
-    if !is_symbol_begin_char(first_ch) {
-        tracing::debug!("attempt to read symbol part beginning with non-'symbol begin' character, returning Ok(None)");
-        return Ok(None);
-        // return Err(ReadError::invalid_input(begin_byte_idx, begin_byte_idx));
+/// the symbol a token is, checked by `Symbol::from_str` so that what's read as a symbol is what
+/// can be made into one, but for `nil`, `true` and `false`, which only keywords may be named
+fn token_symbol(WithSpan { data: token, span }: &WithSpan<ByteIndexSpan, String>) -> Result<Symbol, ReadError> {
+    if let "nil" | "true" | "false" = token.as_str() {
+        return Ok(Symbol::unqualified(token));
     }
+    token.parse().map_err(|err| match err {
+        SymbolError::InvalidChar(idx, _ch) => ReadError::invalid_input((span.0 + idx, span.0 + idx)),
+        SymbolError::Empty | SymbolError::Literal => ReadError::invalid_input(*span),
+    })
+}
 
+/// reads the characters up to (not including) the next whitespace or collection delimiter
+fn read_token<'i>(rdr: &mut CharReader<'i>) -> Option<WithSpan<ByteIndexSpan, String>> {
+    let (begin_byte_idx, first_ch) = rdr.current()?;
     let mut end_byte_idx = begin_byte_idx;
-
-    tracing::trace!(
-        "symbol part begins on {:?} with char {:?}",
-        begin_byte_idx,
-        first_ch
-    );
-
     let mut buf = String::from(first_ch);
     rdr.advance();
-
     while let Some((ch_byte_idx, ch)) = rdr.current() {
-        if ch == '/' || !is_symbol_continue_char(ch) {
+        if !is_token_char(ch) {
             break;
         }
         buf.push(ch);
         end_byte_idx = ch_byte_idx;
         rdr.advance();
     }
-
-    Ok(Some(WithSpan {
+    Some(WithSpan {
         data: buf,
         span: (begin_byte_idx, end_byte_idx),
-    }))
+    })
+}
+
+/// expects to be on the `\` of an escape sequence inside a string literal,
+/// leaves the reader beyond the escape sequence
+fn read_string_escape<'i>(rdr: &mut CharReader<'i>) -> Result<char, ReadError> {
+    let (escape_begin_byte_idx, escape_begin_ch) = rdr.current().expect("on string escape");
+    debug_assert_eq!(escape_begin_ch, '\\', "on string escape");
+    rdr.advance(); // move beyond '\'
+
+    let (mut escape_end_byte_idx, ch) = rdr.current().ok_or(ReadError::insufficient_input((
+        escape_begin_byte_idx,
+        escape_begin_byte_idx,
+    )))?;
+    rdr.advance();
+
+    let escaped = match ch {
+        '"' => Some('"'),
+        '\\' => Some('\\'),
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        'b' => Some('\u{8}'),
+        'f' => Some('\u{c}'),
+        'u' | '0'..='7' => {
+            let (mut name, max_len, is_digit): (String, usize, fn(&char) -> bool) = match ch {
+                'u' => (String::from("u"), 5, char::is_ascii_hexdigit),
+                _ => (format!("o{ch}"), 4, |ch: &char| ('0'..='7').contains(ch)),
+            };
+            while name.len() < max_len {
+                match rdr.current() {
+                    Some((byte_idx, ch)) if is_digit(&ch) => {
+                        name.push(ch);
+                        escape_end_byte_idx = byte_idx;
+                        rdr.advance();
+                    }
+                    _ => break,
+                }
+            }
+            char_from_name(&name)
+        }
+        _ => None,
+    };
+
+    escaped.ok_or(ReadError::invalid_input((escape_begin_byte_idx, escape_end_byte_idx)))
 }
 
 impl<P: SharedPointerKind> ReadClj<P> for CharReader<'_> {
     fn try_read_one(&mut self) -> ReadResult<P> {
-        while let Some((byte_idx, ch)) = self.current() {
-            if is_whitespace(ch) {
+        self.skip_trivia::<P>()?;
+
+        // only the end of input reads as nothing, comments and discards are skipped above
+        let Some((byte_idx, ch)) = self.current() else {
+            return Ok(None);
+        };
+
+        match ch {
+            ')' => {
+                // tracing::error!("prior value is unclosed");
                 self.advance();
-                continue;
+                Err(ReadError::unclosed_collection((byte_idx, byte_idx)))
             }
-
-            match ch {
-                // TODO: these can also occur when read-ing, e.g. [#_]
-                ')' => {
-                    // tracing::error!("prior value is unclosed");
-                    self.advance();
-                    return Err(ReadError::unclosed_collection((byte_idx, byte_idx)));
-                }
-                ']' => {
-                    // tracing::error!("prior value is unclosed");
-                    self.advance();
-                    return Err(ReadError::unclosed_collection((byte_idx, byte_idx)));
-                }
-                '}' => {
-                    // tracing::error!("prior value is unclosed");
-                    self.advance();
-                    return Err(ReadError::unclosed_collection((byte_idx, byte_idx)));
-                }
-                '(' => return self.try_read_list(),
-                '[' => return self.try_read_vect(),
-                '{' => return self.try_read_map(),
-                '\'' => {
-                    self.advance();
-                    let SpanValue {
-                        data: quoted,
-                        span: (_, quoted_span_end),
-                    } = match self.try_read_one()? {
-                        Some(x) => x,
-                        None => return Err(ReadError::InvalidInput((byte_idx, byte_idx))),
-                    };
-                    return Ok(Some(SpanValue {
-                        data: Value::list_from_value_ptrs(vec![
-                            ValuePtr::from(Value::Symbol(Symbol::unqualified(String::from(
-                                "quote",
-                            )))),
-                            ValuePtr::from(quoted),
-                        ]),
-                        span: (byte_idx, quoted_span_end),
-                    }));
-                }
-                '@' => {
-                    self.advance();
-                    let SpanValue {
-                        data: derefed,
-                        span,
-                    } = match self.try_read_one()? {
-                        Some(v) => v,
-                        None => return Err(ReadError::InvalidInput((byte_idx, byte_idx))),
-                    };
-                    return Ok(Some(SpanValue {
-                        data: Value::list_from_value_ptrs(vec![
-                            ValuePtr::from(Value::Symbol(Symbol::unqualified(String::from(
-                                "deref",
-                            )))),
-                            ValuePtr::from(derefed),
-                        ]),
-                        span: (byte_idx, span.1),
-                    }));
-                }
-                '#' if self.peek_char_eq('{') => return self.try_read_set(),
-                '#' if self.peek_char_eq(':') => {
-                    self.advance(); // move beyond '#'
-                    let keyword_result: ReadResult<P> = self.try_read_keyword();
-                    let SpanValue {
-                        data: _keyword_value, // TODO
-                        span: keyword_span,
-                    } = match keyword_result {
-                        Ok(Some(span_value)) => span_value,
-                        Ok(None) => {
-                            return Err(ReadError::invalid_input((
-                                byte_idx,
-                                self.current_byte_idx().unwrap_or(0),
-                            )));
-                        }
-                        Err(e) => return Err(e),
-                    };
-                    debug_assert!(self.current_char_eq('{'));
-                    let map_begin_byte_idx = self.current_byte_idx().unwrap();
-                    let map_result: ReadResult<P> = self.try_read_map();
-                    let map_end_byte_idx = self.byte_idx_at(self.src_idx - 1).unwrap();
-                    let SpanValue {
-                        data: map_value,
-                        span: map_span,
-                    } = match map_result {
-                        Ok(Some(span_value)) => span_value,
-                        Ok(None) => {
-                            return Err(ReadError::invalid_input((
-                                map_begin_byte_idx,
-                                map_end_byte_idx,
-                            )))
-                        }
-                        Err(e) => return Err(e),
-                    };
-                    let span = (keyword_span.0, map_span.1);
-                    //
-                    // TODO: qualify unqualified top-level keyword keys in map, return updated map
-                    //let map = map_value.into_map();
-                    let data = map_value;
-                    //
-                    return Ok(Some(SpanValue { data, span }));
-                }
-                '#' if self.peek_char_eq('_') => {
-                    self.advance(); // move beyond '#'
-                    self.advance(); // move beyond '_'
-                    self.skip_whitespaces();
-                    let discarded: ReadResult<P> = self.try_read_one();
-                    match discarded {
-                        Ok(None) => {
+            ']' => {
+                // tracing::error!("prior value is unclosed");
+                self.advance();
+                Err(ReadError::unclosed_collection((byte_idx, byte_idx)))
+            }
+            '}' => {
+                // tracing::error!("prior value is unclosed");
+                self.advance();
+                Err(ReadError::unclosed_collection((byte_idx, byte_idx)))
+            }
+            '(' => self.try_read_list(),
+            '[' => self.try_read_vect(),
+            '{' => self.try_read_map(),
+            '\'' => {
+                self.advance();
+                let SpanValue {
+                    data: quoted,
+                    span: (_, quoted_span_end),
+                } = match self.try_read_one()? {
+                    Some(x) => x,
+                    None => return Err(ReadError::InvalidInput((byte_idx, byte_idx))),
+                };
+                Ok(Some(SpanValue {
+                    data: Value::list_from_value_ptrs(vec![
+                        ValuePtr::from(Value::Symbol(Symbol::unqualified("quote"))),
+                        ValuePtr::from(quoted),
+                    ]),
+                    span: (byte_idx, quoted_span_end),
+                }))
+            }
+            '@' => {
+                self.advance();
+                let SpanValue {
+                    data: derefed,
+                    span,
+                } = match self.try_read_one()? {
+                    Some(v) => v,
+                    None => return Err(ReadError::InvalidInput((byte_idx, byte_idx))),
+                };
+                Ok(Some(SpanValue {
+                    data: Value::list_from_value_ptrs(vec![
+                        ValuePtr::from(Value::Symbol(Symbol::unqualified("deref"))),
+                        ValuePtr::from(derefed),
+                    ]),
+                    span: (byte_idx, span.1),
+                }))
+            }
+            '#' if self.peek_char_eq('{') => self.try_read_set(),
+            '#' if self.peek_char_eq(':') => {
+                self.advance(); // move beyond '#'
+                // `#::{}`, as opposed to `#::alias{}`
+                let is_current_namespace = self.peek_char_eq(':')
+                    && self
+                        .at(self.src_idx + 2)
+                        .map(|(_, ch)| ch == '{' || is_whitespace(ch))
+                        .unwrap_or(false);
+                let namespace = if is_current_namespace {
+                    self.advance(); // move beyond first ':'
+                    self.advance(); // move beyond second ':'
+                    MapNamespace::Current
+                } else {
+                    let keyword: ReadResult<P> = self.try_read_keyword();
+                    match keyword?.map(|span_value| span_value.data) {
+                        Some(Value::Keyword(Keyword::Unqualified { name })) => MapNamespace::Namespace(name),
+                        Some(Value::Keyword(Keyword::SelfQualified { name })) => MapNamespace::Alias(name),
+                        _ => {
                             return Err(ReadError::invalid_input((
                                 byte_idx,
-                                self.current_byte_idx().unwrap(),
+                                self.byte_idx_at(self.src_idx - 1).unwrap_or(byte_idx),
                             )));
                         }
-                        Err(ReadError::UnclosedCollection(byte_range)) => {
-                            return Err(ReadError::unclosed_collection((byte_idx, byte_range.1)));
-                        }
-                        _ => {}
                     }
-                    self.skip_whitespaces();
+                };
+                self.skip_trivia::<P>()?;
+                match self.current() {
+                    Some((_, '{')) => {}
+                    Some((end_byte_idx, _)) => return Err(ReadError::invalid_input((byte_idx, end_byte_idx))),
+                    None => return Err(ReadError::insufficient_input((byte_idx, self.last_byte_idx()))),
                 }
-                // todo: dispatch read
-                // '#' if self.peek_char_eq('(') => { self.advance(); return self.try_read_list() },
-                ':' => return self.try_read_keyword(),
-                '"' => return self.try_read_string(),
-                ';' => return self.try_read_comment(),
-                ch if is_symbol_begin_char(ch) => {
-                    let opt_span_value_symbol: Option<SpanValue<P>> = self.try_read_symbol()?;
-                    let (symbol, symbol_span) = match opt_span_value_symbol {
-                        Some(SpanValue { data, span }) => match data {
-                            Value::Symbol(sym) => (sym, span),
-                            _ => todo!(),
-                        },
-                        _ => todo!(),
-                    };
-                    return Ok(Some(SpanValue {
-                        span: symbol_span,
-                        data: match symbol {
-                            Symbol::Unqualified { name } if name == "nil" => Value::Nil,
-                            Symbol::Unqualified { name } if name == "true" => Value::Bool(true),
-                            Symbol::Unqualified { name } if name == "false" => Value::Bool(false),
-                            other => Value::Symbol(other),
-                        },
-                    }));
-                }
-                _ => todo!("{}", ch),
+                let map: ReadResult<P> = read_map(self, Some(&namespace));
+                map.map(|map| {
+                    map.map(|SpanValue { data, span: (_, map_end_byte_idx) }| SpanValue {
+                        data,
+                        span: (byte_idx, map_end_byte_idx),
+                    })
+                })
+            }
+            // todo: dispatch read
+            // '#' if self.peek_char_eq('(') => { self.advance(); return self.try_read_list() },
+            '#' if self.peek_char_eq('#') => self.try_read_number(),
+            ':' => self.try_read_keyword(),
+            '"' => self.try_read_string(),
+            '\\' => self.try_read_char(),
+            ch if ch.is_ascii_digit() => self.try_read_number(),
+            '+' | '-' if self.peek_char().map(|ch| ch.is_ascii_digit()).unwrap_or(false) => {
+                self.try_read_number()
             }
+            ch if is_token_char(ch) => self.try_read_symbol(),
+            _ => todo!("{}", ch),
         }
-
-        Ok(None)
     }
 
     fn try_read_keyword(&mut self) -> ReadResult<P> {
@@ -322,31 +405,19 @@ impl<P: SharedPointerKind> ReadClj<P> for CharReader<'_> {
             self.advance();
         }
 
-        let SpanValue::<P> {
-            data: symbol,
-            span: symbol_span,
-        } = self.try_read_symbol()?.ok_or(ReadError::invalid_input((
-            first_colon_byte_idx,
-            first_colon_byte_idx,
-        )))?;
-        debug_assert!(symbol.is_symbol());
-        let symbol = match symbol {
-            Value::Symbol(sym) => sym,
-            _ => {
-                return Err(ReadError::invalid_input((
-                    first_colon_byte_idx,
-                    first_colon_byte_idx,
-                )))
-            }
+        let token = match self.current_char() {
+            Some(ch) if is_token_char(ch) => read_token(self),
+            _ => None,
         };
-
-        let keyword = match (double_colon, symbol) {
-            (false, Symbol::Unqualified { name }) => Keyword::unqualified(name),
-            (false, Symbol::Qualified { namespace, name }) => Keyword::qualified(namespace, name),
-            (true, Symbol::Unqualified { name }) => Keyword::self_qualified(name),
-            (true, Symbol::Qualified { namespace, name }) => {
-                Keyword::alias_qualified(namespace, name)
-            }
+        let token = token.ok_or(ReadError::invalid_input((first_colon_byte_idx, first_colon_byte_idx)))?;
+        let symbol = self.symbol(&token)?;
+        let symbol_span = token.span;
+
+        let keyword = match (double_colon, symbol.names()) {
+            (false, (None, name)) => Keyword::Unqualified { name },
+            (false, (Some(namespace), name)) => Keyword::Qualified { namespace, name },
+            (true, (None, name)) => Keyword::SelfQualified { name },
+            (true, (Some(alias), name)) => Keyword::AliasQualified { alias, name },
         };
 
         Ok(Some(SpanValue {
@@ -375,6 +446,10 @@ impl<P: SharedPointerKind> ReadClj<P> for CharReader<'_> {
                 str_end_byte_idx.replace(byte_idx);
                 break;
             }
+            if ch == '\\' {
+                buf.push(read_string_escape(self)?);
+                continue;
+            }
             buf.push(ch);
             self.advance();
         }
@@ -404,60 +479,21 @@ impl<P: SharedPointerKind> ReadClj<P> for CharReader<'_> {
     }
 
     fn try_read_symbol(&mut self) -> ReadResult<P> {
-        let WithSpan {
-            span: part1_span,
-            data: part1_buf,
-        } = match try_read_symbol_part(self)? {
-            Some(part1) => part1,
-            None => {
-                tracing::warn!("called try_read_symbol_v2 with invalid CharReader state");
-                return Ok(None);
-            }
+        let token = match self.current_char() {
+            Some(ch) if is_token_char(ch) => read_token(self),
+            _ => None,
         };
-
-        if part1_buf.len() > "/".len() && part1_buf.starts_with('/') {
-            tracing::debug!("invalid symbol form (leading slash)");
-            return Err(ReadError::invalid_input(part1_span));
-        }
-
-        let is_qualified = self.current_char_eq('/');
-
-        if is_qualified {
-            if self.is_at_end() {
-                return Err(ReadError::invalid_input((
-                    part1_span.0,
-                    self.current_byte_idx().unwrap(),
-                )));
-            }
-
-            self.advance(); // beyond '/'
-
-            let WithSpan {
-                span: part2_span,
-                data: part2_buf,
-            } = match try_read_symbol_part(self)? {
-                Some(part2) => part2,
-                None => {
-                    tracing::warn!("called try_read_symbol_v2 with invalid CharReader state");
-                    return Ok(None);
-                }
-            };
-
-            if part2_buf.len() > "/".len() && part2_buf.starts_with('/') {
-                tracing::debug!("invalid symbol form (leading slash)");
-                return Err(ReadError::invalid_input(part2_span));
-            }
-
-            Ok(Some(SpanValue {
-                data: Value::Symbol(Symbol::qualified(part1_buf, part2_buf)),
-                span: (part1_span.0, part2_span.1),
-            }))
-        } else {
-            Ok(Some(SpanValue {
-                data: Value::Symbol(Symbol::unqualified(part1_buf)),
-                span: part1_span,
-            }))
-        }
+        let Some(token) = token else {
+            tracing::warn!("called try_read_symbol with invalid CharReader state");
+            return Ok(None);
+        };
+        let data = match token.data.as_str() {
+            "nil" => Value::Nil,
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => Value::Symbol(self.symbol(&token)?),
+        };
+        Ok(Some(SpanValue { data, span: token.span }))
     }
 
     fn try_read_list(&mut self) -> ReadResult<P> {
@@ -475,39 +511,28 @@ impl<P: SharedPointerKind> ReadClj<P> for CharReader<'_> {
         self.advance(); // move beyond '('
 
         loop {
-            self.skip_whitespaces();
+            self.skip_trivia::<P>()?;
 
-            if self.is_beyond_end() {
-                tracing::error!("is beyond end while reading list");
-                return Err(ReadError::insufficient_input((
-                    list_begin_byte_idx,
-                    self.byte_idx_at(self.src.len() - 1).unwrap(),
-                )));
+            match self.current() {
+                Some((byte_idx, ')')) => {
+                    self.advance(); // move beyond )
+                    return Ok(Some(SpanValue {
+                        span: (list_begin_byte_idx, byte_idx),
+                        data: Value::list_from_value_ptrs(value_ptrs),
+                    }));
+                }
+                None => {
+                    tracing::error!("is beyond end while reading list");
+                    return Err(ReadError::insufficient_input((
+                        list_begin_byte_idx,
+                        self.last_byte_idx(),
+                    )));
+                }
+                _ => { /* carry-on to read-ing element */ }
             }
 
-            if let Some((byte_idx, ')')) = self.current() {
-                self.advance(); // move beyond )
-                return Ok(Some(SpanValue {
-                    span: (list_begin_byte_idx, byte_idx),
-                    data: Value::list_from_value_ptrs(value_ptrs),
-                }));
-            }
-
-            match self.try_read_one()? {
-                Some(SpanValue { data: value, .. }) => {
-                    value_ptrs.push(ValuePtr::from(value));
-
-                    self.skip_whitespaces();
-
-                    if let Some((byte_idx, ')')) = self.current() {
-                        self.advance(); // move beyond )
-                        return Ok(Some(SpanValue {
-                            span: (list_begin_byte_idx, byte_idx),
-                            data: Value::list_from_value_ptrs(value_ptrs),
-                        }));
-                    }
-                }
-                None => todo!("when does this happen"),
+            if let Some(SpanValue { data: value, .. }) = self.try_read_one()? {
+                value_ptrs.push(ValuePtr::from(value));
             }
         }
     }
@@ -516,62 +541,32 @@ impl<P: SharedPointerKind> ReadClj<P> for CharReader<'_> {
         let (vect_begin_byte_idx, vect_begin_ch) = self.current().expect("on vect start");
         debug_assert_eq!(vect_begin_ch, '[', "on vect start");
 
-        let mut vect_end_byte_idx = vect_begin_byte_idx;
-
         let mut value_ptrs = vec![];
 
         self.advance();
 
         loop {
-            self.skip_whitespaces();
+            self.skip_trivia::<P>()?;
 
-            if self.is_beyond_end() {
-                return Err(ReadError::insufficient_input((
-                    vect_begin_byte_idx,
-                    self.byte_idx_at(self.src_idx - 1).unwrap(),
-                )));
+            match self.current() {
+                Some((byte_idx, ']')) => {
+                    self.advance();
+                    return Ok(Some(SpanValue {
+                        span: (vect_begin_byte_idx, byte_idx),
+                        data: Value::vect_from_value_ptrs(value_ptrs),
+                    }));
+                }
+                None => {
+                    return Err(ReadError::insufficient_input((
+                        vect_begin_byte_idx,
+                        self.last_byte_idx(),
+                    )));
+                }
+                _ => { /* carry-on to read-ing element */ }
             }
 
-            if let Some((byte_idx, ']')) = self.current() {
-                vect_end_byte_idx = byte_idx;
-                self.advance();
-                return Ok(Some(SpanValue {
-                    span: (vect_begin_byte_idx, vect_end_byte_idx),
-                    data: Value::vect_from_value_ptrs(value_ptrs),
-                }));
-            }
-
-            match self.try_read_one()? {
-                Some(SpanValue { data: value, .. }) => {
-                    value_ptrs.push(ValuePtr::from(value));
-
-                    self.skip_whitespaces();
-
-                    if let Some((byte_idx, ']')) = self.current() {
-                        vect_end_byte_idx = byte_idx;
-                        self.advance();
-                        return Ok(Some(SpanValue {
-                            span: (vect_begin_byte_idx, vect_end_byte_idx),
-                            data: Value::vect_from_value_ptrs(value_ptrs),
-                        }));
-                    }
-                }
-                None => match self.current() {
-                    Some((byte_idx, ']')) => {
-                        vect_end_byte_idx = byte_idx;
-                        self.advance();
-                        return Ok(Some(SpanValue {
-                            span: (vect_begin_byte_idx, vect_end_byte_idx),
-                            data: Value::vect_from_value_ptrs(value_ptrs),
-                        }));
-                    }
-                    _ => {
-                        return Err(ReadError::insufficient_input((
-                            vect_begin_byte_idx,
-                            vect_end_byte_idx,
-                        )));
-                    }
-                },
+            if let Some(SpanValue { data: value, .. }) = self.try_read_one()? {
+                value_ptrs.push(ValuePtr::from(value));
             }
         }
     }
@@ -587,130 +582,220 @@ impl<P: SharedPointerKind> ReadClj<P> for CharReader<'_> {
             set_begin_ch,
         );
 
-        let mut set_end_byte_idx = set_begin_byte_idx;
-
         let mut value_ptrs = vec![];
+        // of each element, to point at both occurrences of a duplicate
+        let mut spans = vec![];
+        let mut seen = KeyIndex::default();
 
         self.advance(); // #
         self.advance(); // {
 
         loop {
-            self.skip_whitespaces();
+            self.skip_trivia::<P>()?;
 
-            if self.is_beyond_end() {
-                tracing::error!("insufficient set input");
-                return Err(ReadError::insufficient_input((
-                    set_begin_byte_idx,
-                    self.byte_idx_at(self.src_idx - 1).unwrap(),
-                )));
+            match self.current() {
+                Some((byte_idx, '}')) => {
+                    self.advance();
+                    return Ok(Some(SpanValue {
+                        span: (set_begin_byte_idx, byte_idx),
+                        data: Value::set_from_value_ptrs(value_ptrs),
+                    }));
+                }
+                None => {
+                    tracing::error!("insufficient set input");
+                    return Err(ReadError::insufficient_input((
+                        set_begin_byte_idx,
+                        self.last_byte_idx(),
+                    )));
+                }
+                _ => { /* carry-on to read-ing element */ }
             }
 
-            if let Some((byte_idx, '}')) = self.current() {
-                set_end_byte_idx = byte_idx;
-                self.advance();
-                return Ok(Some(SpanValue {
-                    span: (set_begin_byte_idx, set_end_byte_idx),
-                    data: Value::set_from_value_ptrs(value_ptrs),
-                }));
+            if let Some(SpanValue { data: value, span }) = self.try_read_one()? {
+                let is_eq = |idx: usize| value_ptrs[idx].as_ref() == &value;
+                match seen.find_or_insert(&value, value_ptrs.len(), is_eq) {
+                    Some(idx) => match self.options.duplicate_keys {
+                        DuplicateKeys::Reject => {
+                            return Err(ReadError::duplicate_key(spans[idx], span));
+                        }
+                        DuplicateKeys::LastWins => value_ptrs[idx] = ValuePtr::from(value),
+                    },
+                    None => {
+                        value_ptrs.push(ValuePtr::from(value));
+                        spans.push(span);
+                    }
+                }
             }
+        }
+    }
 
-            match self.try_read_one()? {
-                Some(SpanValue { data: value, .. }) => {
-                    value_ptrs.push(ValuePtr::from(value));
+    fn try_read_map(&mut self) -> ReadResult<P> {
+        read_map(self, None)
+    }
 
-                    self.skip_whitespaces();
+    fn try_read_number(&mut self) -> ReadResult<P> {
+        let (num_begin_byte_idx, num_begin_ch) = self.current().expect("on number start");
+
+        if num_begin_ch == '#' {
+            debug_assert!(self.peek_char_eq('#'), "on symbolic value start");
+            self.advance(); // move beyond first '#'
+            self.advance(); // move beyond second '#'
+            let WithSpan { data: name, span } = read_token(self).ok_or(
+                ReadError::insufficient_input((num_begin_byte_idx, num_begin_byte_idx + 1)),
+            )?;
+            return match f64_from_symbolic_name(&name) {
+                Some(n) => Ok(Some(SpanValue {
+                    data: Value::Num(n),
+                    span: (num_begin_byte_idx, span.1),
+                })),
+                None => Err(ReadError::invalid_input((num_begin_byte_idx, span.1))),
+            };
+        }
 
-                    if let Some((byte_idx, '}')) = self.current() {
-                        set_end_byte_idx = byte_idx;
-                        self.advance();
-                        return Ok(Some(SpanValue {
-                            span: (set_begin_byte_idx, set_end_byte_idx),
-                            data: Value::set_from_value_ptrs(value_ptrs),
-                        }));
-                    }
-                }
-                None => match self.current() {
-                    Some((byte_idx, '}')) => {
-                        set_end_byte_idx = byte_idx;
-                        self.advance();
-                        return Ok(Some(SpanValue {
-                            span: (set_begin_byte_idx, set_end_byte_idx),
-                            data: Value::set_from_value_ptrs(value_ptrs),
-                        }));
-                    }
-                    _ => {
-                        return Err(ReadError::insufficient_input((
-                            set_begin_byte_idx,
-                            set_end_byte_idx,
-                        )));
-                    }
-                },
+        let WithSpan { data: token, span } = read_token(self).expect("on number start");
+
+        tracing::trace!("number token {:?} spans {:?}", token, span);
+
+        match token.parse::<f64>() {
+            Ok(n) => Ok(Some(SpanValue {
+                data: Value::Num(n),
+                span,
+            })),
+            Err(_) => Err(ReadError::invalid_input(span)),
+        }
+    }
+
+    fn try_read_char(&mut self) -> ReadResult<P> {
+        let (char_begin_byte_idx, char_begin_ch) = self.current().expect("on char start");
+        debug_assert_eq!(char_begin_ch, '\\', "on char start");
+        self.advance(); // move beyond '\'
+
+        // the first character is taken as-is, so `\(` and `\ ` are chars too
+        let (first_byte_idx, first_ch) = self.current().ok_or(ReadError::insufficient_input((
+            char_begin_byte_idx,
+            char_begin_byte_idx,
+        )))?;
+        self.advance();
+
+        let mut name = String::from(first_ch);
+        let mut char_end_byte_idx = first_byte_idx;
+        while let Some((byte_idx, ch)) = self.current() {
+            if !is_token_char(ch) {
+                break;
             }
+            name.push(ch);
+            char_end_byte_idx = byte_idx;
+            self.advance();
+        }
+
+        let ch = if name.chars().count() == 1 {
+            Some(first_ch)
+        } else {
+            char_from_name(&name)
+        };
+
+        match ch {
+            Some(ch) => Ok(Some(SpanValue {
+                data: Value::Char(ch),
+                span: (char_begin_byte_idx, char_end_byte_idx),
+            })),
+            None => Err(ReadError::invalid_input((char_begin_byte_idx, char_end_byte_idx))),
         }
     }
 
-    fn try_read_map(&mut self) -> ReadResult<P> {
-        let (map_begin_byte_idx, map_begin_ch) = self.current().expect("on map start");
-        debug_assert_eq!(map_begin_ch, '{', "on map start");
+    /// comments are trivia (see [`CharReader::skip_trivia`]), so this only moves beyond one
+    fn try_read_comment(&mut self) -> ReadResult<P> {
+        let (_comment_begin_byte_idx, comment_begin_ch) = self.current().expect("on comment start");
+        debug_assert_eq!(comment_begin_ch, ';', "on comment start");
+        skip_line(self);
+        Ok(None)
+    }
+}
 
-        let mut value_ptr_pairs = vec![];
+/// the namespace of a `#:ns{}` or `#::alias{}` map
+#[derive(Debug, Clone, PartialEq)]
+enum MapNamespace {
+    Namespace(Name),
+    Alias(Name),
+    /// `#::{}`
+    Current,
+}
 
-        self.advance(); // move beyond '{'
+/// Clojure example:
+/// ```clojure
+/// #:foo{:a 1, b 2, :bar/c 3, :_/d 4} ; reads as {:foo/a 1, foo/b 2, :bar/c 3, :d 4}
+/// ```
+fn qualify_map_key<P: SharedPointerKind>(key: Value<P>, namespace: &MapNamespace) -> Value<P> {
+    match (key, namespace) {
+        (Value::Keyword(Keyword::Qualified { namespace, name }), _) if namespace == "_" => {
+            Value::Keyword(Keyword::Unqualified { name })
+        }
+        (Value::Keyword(Keyword::Unqualified { name }), MapNamespace::Namespace(namespace)) => {
+            Value::Keyword(Keyword::Qualified { namespace: namespace.clone(), name })
+        }
+        (Value::Keyword(Keyword::Unqualified { name }), MapNamespace::Alias(alias)) => {
+            Value::Keyword(Keyword::AliasQualified { alias: alias.clone(), name })
+        }
+        (Value::Keyword(Keyword::Unqualified { name }), MapNamespace::Current) => {
+            Value::Keyword(Keyword::SelfQualified { name })
+        }
+        (Value::Symbol(symbol), namespace) => match (symbol.names(), namespace) {
+            ((Some(namespace), name), _) if namespace == "_" => Value::Symbol(Symbol::from_names(None, name)),
+            ((None, name), MapNamespace::Namespace(namespace)) => {
+                Value::Symbol(Symbol::from_names(Some(namespace.clone()), name))
+            }
+            _ => Value::Symbol(symbol),
+        },
+        (key, _) => key,
+    }
+}
 
-        loop {
-            self.skip_whitespaces();
+/// reads a map, qualifying its keys if it's preceded by a `#:ns` (which has already been read)
+fn read_map<P: SharedPointerKind>(rdr: &mut CharReader<'_>, namespace: Option<&MapNamespace>) -> ReadResult<P> {
+    let (map_begin_byte_idx, map_begin_ch) = rdr.current().expect("on map start");
+    debug_assert_eq!(map_begin_ch, '{', "on map start");
+
+    let mut value_ptr_pairs: Vec<(ValuePtr<P>, ValuePtr<P>)> = vec![];
+    // of each key, to point at both occurrences of a duplicate
+    let mut key_spans = vec![];
+    let mut seen = KeyIndex::default();
+
+    rdr.advance(); // move beyond '{'
 
-            if self.is_beyond_end() {
+    loop {
+        rdr.skip_trivia::<P>()?;
+
+        match rdr.current() {
+            Some((byte_idx, '}')) => {
+                rdr.advance(); // move beyond '}'
+                return Ok(Some(SpanValue {
+                    span: (map_begin_byte_idx, byte_idx),
+                    data: Value::map_from_value_ptr_pairs(value_ptr_pairs),
+                }));
+            }
+            None => {
                 tracing::error!("unclosed map literal");
                 return Err(ReadError::insufficient_input((
                     map_begin_byte_idx,
-                    self.byte_idx_at(self.src_idx - 1).unwrap(),
+                    rdr.last_byte_idx(),
                 )));
             }
+            _ => { /* carry-on to read-ing key */ }
+        }
 
-            match self.current() {
-                Some((byte_idx, '}')) => {
-                    self.advance(); // move beyond '}'
-                    return Ok(Some(SpanValue {
-                        span: (map_begin_byte_idx, byte_idx),
-                        data: Value::map_from_value_ptr_pairs(value_ptr_pairs),
-                    }));
-                }
-                None => {
-                    tracing::error!("unclosed map literal");
-                    return Err(ReadError::insufficient_input((
-                        map_begin_byte_idx,
-                        self.byte_idx_at(self.src_idx - 1).unwrap(),
-                    )));
-                }
-                _ => { /* carry-on to read-ing key */ }
-            }
-
-            let key = match self.try_read_one()? {
-                Some(SpanValue { data: key, .. }) => key,
-                None => {
-                    self.skip_whitespaces();
-
-                    if let Some((byte_idx, '}')) = self.current() {
-                        self.advance(); // move beyond '}'
-                        return Ok(Some(SpanValue {
-                            span: (map_begin_byte_idx, byte_idx),
-                            data: Value::map_from_value_ptr_pairs(value_ptr_pairs),
-                        }));
-                    }
-
-                    tracing::error!("map: no key found");
-                    return Err(ReadError::insufficient_input((
-                        map_begin_byte_idx,
-                        self.current_byte_idx().unwrap(),
-                    )));
-                }
-            };
+        let (key, key_span) = match ReadClj::<P>::try_read_one(rdr)? {
+            Some(SpanValue { data: key, span }) => match namespace {
+                Some(namespace) => (qualify_map_key(key, namespace), span),
+                None => (key, span),
+            },
+            None => unreachable!("on a key after skipping trivia"),
+        };
 
-            self.skip_whitespaces();
+        rdr.skip_trivia::<P>()?;
 
-            if let Some((byte_idx, '}')) = self.current() {
-                self.advance(); // move beyond '}'
+        match rdr.current() {
+            Some((byte_idx, '}')) => {
+                rdr.advance(); // move beyond '}'
                 tracing::error!("map: no value for key found");
                 return Err(ReadError::insufficient_input((
                     // value-less key
@@ -718,49 +803,35 @@ impl<P: SharedPointerKind> ReadClj<P> for CharReader<'_> {
                     byte_idx,
                 )));
             }
+            None => {
+                tracing::error!("unclosed & unabalanced map literal");
+                return Err(ReadError::insufficient_input((
+                    map_begin_byte_idx,
+                    rdr.last_byte_idx(),
+                )));
+            }
+            _ => { /* carry-on to read-ing value */ }
+        }
 
-            let value = match self.try_read_one()? {
-                Some(SpanValue { data: value, .. }) => value,
-                None => {
-                    self.skip_whitespaces();
+        let value = match ReadClj::<P>::try_read_one(rdr)? {
+            Some(SpanValue { data: value, .. }) => value,
+            None => unreachable!("on a value after skipping trivia"),
+        };
 
-                    match self.current() {
-                        Some((byte_idx, '}')) => {
-                            self.advance(); // move beyond '}'
-                            tracing::error!("map: no value for key found");
-                            return Err(ReadError::insufficient_input((
-                                map_begin_byte_idx,
-                                byte_idx,
-                            )));
-                        }
-                        Some(_) => unreachable!(),
-                        None => {
-                            tracing::error!("unclosed & unabalanced map literal");
-                            return Err(ReadError::insufficient_input((
-                                map_begin_byte_idx,
-                                self.byte_idx_at(self.src_idx - 1).unwrap(),
-                            )));
-                        }
-                    }
+        let is_eq = |idx: usize| value_ptr_pairs[idx].0.as_ref() == &key;
+        match seen.find_or_insert(&key, value_ptr_pairs.len(), is_eq) {
+            Some(idx) => match rdr.options.duplicate_keys {
+                DuplicateKeys::Reject => {
+                    tracing::error!("map: duplicate key");
+                    return Err(ReadError::duplicate_key(key_spans[idx], key_span));
                 }
-            };
-
-            value_ptr_pairs.push((ValuePtr::from(key), ValuePtr::from(value)));
-        }
-    }
-
-    fn try_read_comment(&mut self) -> ReadResult<P> {
-        let (_comment_begin_byte_idx, comment_begin_ch) = self.current().expect("on comment start");
-        debug_assert_eq!(comment_begin_ch, ';', "on comment start");
-        self.advance(); // move beyond beginning ';'
-        while let Some(ch) = self.current_char() {
-            match ch {
-                '\n' | '\r' => break,
-                _ => {}
+                DuplicateKeys::LastWins => value_ptr_pairs[idx].1 = ValuePtr::from(value),
+            },
+            None => {
+                value_ptr_pairs.push((ValuePtr::from(key), ValuePtr::from(value)));
+                key_spans.push(key_span);
             }
-            self.advance();
         }
-        Ok(None)
     }
 }
 
@@ -770,9 +841,9 @@ mod t {
     use crate::{
             char_reader::{self, is_whitespace},
             keyword::Keyword,
-            reader,
+            reader, reader_with_options,
             value::{RcValue, Value, ValuePtr},
-            ReadClj, ReadError, ReadResult, SpanValue,
+            DuplicateKeys, ReadClj, ReadError, ReadOptions, ReadResult, SpanValue,
     };
     use cljrs_core::symbol::Symbol;
 
@@ -1030,7 +1101,6 @@ mod t {
         )
     }
 
-    #[ignore = "todo: rewrite part of reader to do this"]
     #[test]
     fn too_many_trailing_forward_slashes_invalid_qualified_symbol() {
         let src = "clojure.core///";
@@ -1041,29 +1111,45 @@ mod t {
     }
 
     #[test]
-    fn symbol_part() {
-        let src = "foo";
+    fn symbol_token() {
+        let src = "foo/bar baz";
         let mut rdr = CharReader::try_from_str(src).unwrap();
-        let res = char_reader::try_read_symbol_part(&mut rdr);
-        let value = res.unwrap().unwrap().data;
-        assert_eq!(value, String::from("foo"));
+        let token = char_reader::read_token(&mut rdr).unwrap();
+        assert_eq!((token.data.as_str(), token.span), ("foo/bar", (0, 6)));
+        assert_eq!(char_reader::token_symbol(&token), Ok(Symbol::qualified("foo", "bar")));
     }
 
     #[test]
-    fn composite_symbol_parts() {
-        let src = "foo/bar";
-        let mut rdr = CharReader::try_from_str(src).unwrap();
-        let sym_part_1 = char_reader::try_read_symbol_part(&mut rdr)
-            .expect("read-able symbol part")
-            .expect("non-empty")
-            .data;
-        assert_eq!(sym_part_1, String::from("foo"));
-        rdr.advance();
-        let sym_part_2 = char_reader::try_read_symbol_part(&mut rdr)
-            .expect("read-able symbol part")
-            .expect("non-empty")
-            .data;
-        assert_eq!(sym_part_2, String::from("bar"));
+    fn symbols_are_read_as_they_are_parsed() {
+        let srcs = [
+            "a", "a/b", "/", "clojure.core//", "clojure.core///", "a/b/c", "//", "/a", "a/", "->>", "é", "a#", "a'",
+            "a.b", "a:b", "a:", "a::b", "1a", "+1", "-a", ".5", ".a", "'a", "#a", "a@b", "a^b", "a~b", "a`b", "a\\b",
+            "a\"b", ":a", "nil", "true", "a/nil", "nil/a",
+        ];
+        for src in srcs {
+            let mut rdr = reader::<archery::RcK>(src).unwrap();
+            // only a symbol read from the whole of `src`
+            let read = match rdr.try_read_one() {
+                Ok(Some(SpanValue { data: Value::Symbol(symbol), .. })) => Some(symbol),
+                _ => None,
+            };
+            let read = read.filter(|_| matches!(rdr.try_read_one(), Ok(None)));
+            assert_eq!(read, src.parse::<Symbol>().ok(), "{src}");
+        }
+    }
+
+    #[test]
+    fn names_are_freed_with_what_was_read() {
+        use cljrs_core::intern::Name;
+        let names = ["freed-a", "freed-b", "freed-c", "freed-d"];
+        let src = "(freed-a :freed-b/freed-c) #{::freed-d} {'freed-a 1}";
+        let mut rdr = reader::<archery::RcK>(src).unwrap();
+        let values = [rdr.try_read_one(), rdr.try_read_one(), rdr.try_read_one()];
+        assert!(names.iter().all(|name| Name::existing(name).is_some()));
+        drop(rdr);
+        assert!(names.iter().all(|name| Name::existing(name).is_some()));
+        drop(values);
+        assert!(names.iter().all(|name| Name::existing(name).is_none()));
     }
 
     #[test]
@@ -1159,6 +1245,75 @@ mod t {
         assert_eq!(value, RcValue::Str(String::from("hello")),);
     }
 
+    #[test]
+    fn string_with_escapes() {
+        let src = r#""a\"b\\c\n\u00e9\101""#;
+        let mut rdr = reader(src).unwrap();
+        let value: RcValue = rdr.try_read_one().unwrap().unwrap().data;
+        assert_eq!(value, RcValue::Str(String::from("a\"b\\c\n\u{e9}A")));
+    }
+
+    #[test]
+    fn invalid_string_escape() {
+        let src = r#""a\qb""#;
+        let mut rdr = reader(src).unwrap();
+        let res: ReadResult<archery::RcK> = rdr.try_read_one();
+        assert_eq!(res.unwrap_err(), ReadError::invalid_input((2, 3)));
+    }
+
+    #[test]
+    fn numbers() {
+        let src = "1 -2.5 +3e2 ##Inf ##-Inf";
+        let mut rdr = reader(src).unwrap();
+        let mut values = vec![];
+        while let Some(SpanValue { data, .. }) = rdr.try_read_one().unwrap() {
+            values.push(data);
+        }
+        assert_eq!(
+            values,
+            vec![
+                RcValue::Num(1.0),
+                RcValue::Num(-2.5),
+                RcValue::Num(300.0),
+                RcValue::Num(f64::INFINITY),
+                RcValue::Num(f64::NEG_INFINITY),
+            ]
+        );
+    }
+
+    #[test]
+    fn minus_alone_is_a_symbol() {
+        let mut rdr = reader("(- 1)").unwrap();
+        let value: RcValue = rdr.try_read_one().unwrap().unwrap().data;
+        assert_eq!(
+            value,
+            RcValue::list_from_values(vec![
+                Value::Symbol(Symbol::unqualified(String::from("-"))),
+                Value::Num(1.0),
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_number() {
+        let mut rdr = reader("1x2").unwrap();
+        let res: ReadResult<archery::RcK> = rdr.try_read_one();
+        assert_eq!(res.unwrap_err(), ReadError::invalid_input((0, 2)));
+    }
+
+    #[test]
+    fn chars() {
+        let src = r"[\a \newline \( \u0041 \o101 \\]";
+        let mut rdr = reader(src).unwrap();
+        let value: RcValue = rdr.try_read_one().unwrap().unwrap().data;
+        assert_eq!(
+            value,
+            RcValue::vect_from_values(
+                ['a', '\n', '(', 'A', 'A', '\\'].into_iter().map(Value::Char)
+            )
+        );
+    }
+
     #[test]
     fn symbol_ends_at_newline() {
         let src = "x\ny";
@@ -1187,11 +1342,8 @@ mod t {
         let src = "hello\n; world\nbob";
         let mut rdr = reader(src).unwrap();
         let mut values = vec![];
-        while let Ok(opt_span_val) = rdr.try_read_one() {
-            match opt_span_val {
-                Some(SpanValue { data, .. }) => values.push(data),
-                None => {}
-            }
+        while let Ok(Some(SpanValue { data, .. })) = rdr.try_read_one() {
+            values.push(data);
         }
         assert_eq!(
             values,
@@ -1202,6 +1354,126 @@ mod t {
         );
     }
 
+    fn read_all(src: &str) -> Result<Vec<RcValue>, ReadError> {
+        let mut rdr = reader(src).unwrap();
+        let mut values = vec![];
+        while let Some(SpanValue { data, .. }) = rdr.try_read_one()? {
+            values.push(data);
+        }
+        Ok(values)
+    }
+
+    #[test]
+    fn leading_comment_is_not_the_end() {
+        let values = read_all("; just a comment\n;; and another\nx ; trailing").unwrap();
+        assert_eq!(values, vec![RcValue::Symbol(Symbol::unqualified(String::from("x")))]);
+        assert_eq!(read_all("; nothing else").unwrap(), vec![]);
+        assert_eq!(read_all("a;b").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn comments_and_discards_within_collections() {
+        let values = read_all("[1 ; one\n #_ 2 3 #_(4)] {:a #_ :b 1 ; c\n} #{#_ #_ 5 6}").unwrap();
+        assert_eq!(
+            values,
+            vec![
+                RcValue::vect_from_values(vec![Value::Num(1.0), Value::Num(3.0)]),
+                RcValue::map_from_value_pairs(vec![(
+                    Value::unqualified_keyword(String::from("a")),
+                    Value::Num(1.0),
+                )]),
+                RcValue::set_from_values(vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn stacked_discards() {
+        let values = read_all("#_ #_ a b c (#_ #_ d e)").unwrap();
+        assert_eq!(
+            values,
+            vec![
+                RcValue::Symbol(Symbol::unqualified(String::from("c"))),
+                RcValue::list_from_values(vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn discard_without_form() {
+        assert_eq!(read_all("[#_]"), Err(ReadError::invalid_input((1, 3))));
+        assert_eq!(read_all("(a #_ #_ b)"), Err(ReadError::invalid_input((3, 10))));
+        assert_eq!(read_all("x #_"), Err(ReadError::insufficient_input((2, 3))));
+    }
+
+    #[test]
+    fn shebang_line() {
+        let values = read_all("#!/usr/bin/env cljrs\n(println 1)").unwrap();
+        assert_eq!(values.len(), 1);
+        assert!(values[0].is_list());
+    }
+
+    #[test]
+    fn duplicate_map_keys() {
+        assert_eq!(read_all("{:a 1 :b 2 :a 3}"), Err(ReadError::duplicate_key((1, 2), (11, 12))));
+        assert_eq!(read_all("[{[1 2] 1, [1 2] 2}]"), Err(ReadError::duplicate_key((2, 6), (11, 15))));
+        assert_eq!(read_all("#:a{:b 1 :a/b 2}"), Err(ReadError::duplicate_key((4, 5), (9, 12))));
+        assert_eq!(read_all("{:a 1 :b 2}").unwrap().len(), 1);
+        assert_eq!(read_all("{:a 1 :a 2}").unwrap_err().to_string(), "duplicate key at byte 6");
+    }
+
+    #[test]
+    fn duplicate_set_elements() {
+        assert_eq!(read_all("#{1 \"x\" 1}"), Err(ReadError::duplicate_key((2, 2), (8, 8))));
+        assert_eq!(read_all("#{1 #_ 1}").unwrap().len(), 1);
+        assert_eq!(read_all("#{0.0 -0.0}"), Err(ReadError::duplicate_key((2, 4), (6, 9))));
+        assert_eq!(read_all("#{[1 #{2}] (1 #{2}) [1 #{2}]}"), Err(ReadError::duplicate_key((2, 9), (20, 27))));
+        let many = (0..10_000).map(|n| format!("{n} ")).collect::<String>();
+        assert_eq!(read_all(&format!("#{{{many}}}")).unwrap().len(), 1);
+        assert!(matches!(read_all(&format!("#{{{many}9999}}")), Err(ReadError::DuplicateKey(..))));
+    }
+
+    #[test]
+    fn duplicates_last_wins() {
+        let options = ReadOptions::default().with_duplicate_keys(DuplicateKeys::LastWins);
+        let mut rdr = reader_with_options::<archery::RcK>("{:a 1 :b 2 :a 3} #{1 1}", options).unwrap();
+        let SpanValue { data: map, .. } = rdr.try_read_one().unwrap().unwrap();
+        assert_eq!(
+            map,
+            RcValue::map_from_value_pairs(vec![
+                (Value::unqualified_keyword(String::from("a")), Value::Num(3.0)),
+                (Value::unqualified_keyword(String::from("b")), Value::Num(2.0)),
+            ])
+        );
+        let SpanValue { data: set, .. } = rdr.try_read_one().unwrap().unwrap();
+        assert_eq!(set, RcValue::set_from_values(vec![Value::Num(1.0)]));
+    }
+
+    #[test]
+    fn namespaced_maps() {
+        let values = read_all("#:foo{:a 1, b 2, :bar/c 3, :_/d 4} #::{:e 5} #::s {:f 6}").unwrap();
+        assert_eq!(
+            values,
+            vec![
+                RcValue::map_from_value_pairs(vec![
+                    (Value::qualified_keyword(String::from("foo"), String::from("a")), Value::Num(1.0)),
+                    (Value::qualified_symbol(String::from("foo"), String::from("b")), Value::Num(2.0)),
+                    (Value::qualified_keyword(String::from("bar"), String::from("c")), Value::Num(3.0)),
+                    (Value::unqualified_keyword(String::from("d")), Value::Num(4.0)),
+                ]),
+                RcValue::map_from_value_pairs(vec![(
+                    Value::self_qualified_keyword(String::from("e")),
+                    Value::Num(5.0),
+                )]),
+                RcValue::map_from_value_pairs(vec![(
+                    Value::alias_qualified_keyword(String::from("s"), String::from("f")),
+                    Value::Num(6.0),
+                )]),
+            ]
+        );
+        assert_eq!(read_all("#:a/b{}"), Err(ReadError::invalid_input((0, 4))));
+    }
+
     #[test]
     fn qualified_map() {
         let src = "#:foo{:bar :zap}";
@@ -0,0 +1,96 @@
+//! syntax-highlighted source, from [`crate::token::tokens`]
+
+use alloc::string::String;
+use core::fmt::Write;
+use crate::token::{tokens, TokenKind};
+
+/// the SGR parameters a kind is coloured with, or `None` for the terminal's default colour
+pub fn ansi_style(kind: TokenKind) -> Option<&'static str> {
+    match kind {
+        TokenKind::Whitespace | TokenKind::Newline | TokenKind::Symbol => None,
+        // grey
+        TokenKind::Comment => Some("90"),
+        // bold
+        TokenKind::Delimiter => Some("1"),
+        // green
+        TokenKind::String | TokenKind::Regex => Some("32"),
+        // cyan
+        TokenKind::Char | TokenKind::Number => Some("36"),
+        // magenta
+        TokenKind::Keyword => Some("35"),
+        // yellow
+        TokenKind::ReaderMacro => Some("33"),
+        // white on red
+        TokenKind::Error => Some("37;41"),
+    }
+}
+
+/// the source with ANSI escape sequences colouring each token as [`ansi_style`] does
+pub fn to_ansi(src: &str) -> String {
+    let mut out = String::with_capacity(src.len() * 2);
+    for token in tokens(src) {
+        match ansi_style(token.kind) {
+            Some(style) => {
+                let _ = write!(out, "\x1b[{style}m{}\x1b[0m", token.text(src));
+            }
+            None => out.push_str(token.text(src)),
+        }
+    }
+    out
+}
+
+/// the source as HTML, where each token but whitespace and newlines is a `<span>` with a class of
+/// `clj-` then the [`TokenKind::name`], e.g. `<span class="clj-keyword">:a</span>`
+///
+/// The output has no enclosing element, so is usually put in a `<pre>`.
+pub fn to_html(src: &str) -> String {
+    let mut out = String::with_capacity(src.len() * 4);
+    for token in tokens(src) {
+        match token.kind {
+            TokenKind::Whitespace | TokenKind::Newline => escape_html(&mut out, token.text(src)),
+            kind => {
+                let _ = write!(out, r#"<span class="clj-{}">"#, kind.name());
+                escape_html(&mut out, token.text(src));
+                out.push_str("</span>");
+            }
+        }
+    }
+    out
+}
+
+fn escape_html(out: &mut String, text: &str) {
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            ch => out.push(ch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::{to_ansi, to_html};
+
+    #[test]
+    fn ansi() {
+        assert_eq!(to_ansi("(f :a)"), "\x1b[1m(\x1b[0mf \x1b[35m:a\x1b[0m\x1b[1m)\x1b[0m");
+        assert_eq!(to_ansi("\"a"), "\x1b[37;41m\"a\x1b[0m");
+    }
+
+    #[test]
+    fn html() {
+        assert_eq!(
+            to_html("(< 1 \"&\")\n"),
+            concat!(
+                r#"<span class="clj-delimiter">(</span><span class="clj-symbol">&lt;</span> "#,
+                r#"<span class="clj-number">1</span> <span class="clj-string">&quot;&amp;&quot;</span>"#,
+                r#"<span class="clj-delimiter">)</span>"#,
+                "\n",
+            )
+        );
+    }
+}
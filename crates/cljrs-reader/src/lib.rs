@@ -1,7 +1,33 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+/// stands in for `tracing`'s event macros without the `tracing` feature, doing nothing with
+/// their arguments but using them, so they aren't warned of as unused
+#[cfg(not(feature = "tracing"))]
+mod tracing {
+    macro_rules! event {
+        ($($arg:tt)+) => {
+            if false {
+                let _ = format_args!($($arg)+);
+            }
+        };
+    }
+    pub(crate) use {event as debug, event as error, event as trace, event as warn};
+}
+
 pub mod value;
 pub mod keyword;
 pub mod char_reader;
+pub mod printer;
+pub mod pretty;
+pub mod syntax;
+pub mod token;
+pub mod highlight;
+#[cfg(feature = "std")]
+pub mod parallel;
 
+use alloc::boxed::Box;
 use archery::SharedPointerKind;
 use value::Value;
 use char_reader::CharReader;
@@ -12,6 +38,8 @@ pub trait ReadClj<P: SharedPointerKind> {
     fn try_read_symbol(&mut self) -> ReadResult<P>;
     fn try_read_keyword(&mut self) -> ReadResult<P>;
     fn try_read_string(&mut self) -> ReadResult<P>;
+    fn try_read_number(&mut self) -> ReadResult<P>;
+    fn try_read_char(&mut self) -> ReadResult<P>;
     fn try_read_list(&mut self) -> ReadResult<P>;
     fn try_read_vect(&mut self) -> ReadResult<P>;
     fn try_read_set(&mut self) -> ReadResult<P>;
@@ -19,12 +47,43 @@ pub trait ReadClj<P: SharedPointerKind> {
 }
 
 pub fn reader<P: SharedPointerKind>(s: &str) -> Option<Box<dyn ReadClj<P> + '_>> {
+    reader_with_options(s, ReadOptions::default())
+}
+
+pub fn reader_with_options<P: SharedPointerKind>(s: &str, options: ReadOptions) -> Option<Box<dyn ReadClj<P> + '_>> {
     match CharReader::try_from_str(s) {
-        Some(rdr) => Some(Box::new(rdr)),
+        Some(rdr) => Some(Box::new(rdr.with_options(options))),
         _ => None,
     }
 }
 
+/// what to do when a map literal repeats a key or a set literal repeats an element
+///
+/// Clojure example:
+/// ```clojure
+/// {:a 1 :a 2} ; Duplicate key: :a
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// fail with [`ReadError::DuplicateKey`], as Clojure's reader does
+    #[default]
+    Reject,
+    /// keep the last occurrence (in the position of the first), e.g. for lenient data ingestion
+    LastWins,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReadOptions {
+    pub duplicate_keys: DuplicateKeys,
+}
+
+impl ReadOptions {
+    pub fn with_duplicate_keys(mut self, duplicate_keys: DuplicateKeys) -> Self {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+}
+
 pub type ReadInput<'input, T, Span> = WithSpan<Span, &'input T>;
 pub type ReadResult<P> = Result<ReadOutput<P>, ReadError>;
 pub type ReadOutput<P> = Option<SpanValue<P>>;
@@ -55,7 +114,9 @@ pub type ByteIndexSpan = (BeginByteIdx, EndByteIdx);
 pub enum ReadError {
     InsufficientInput(ByteIndexSpan),
     InvalidInput(ByteIndexSpan),
-    UnclosedCollection(ByteIndexSpan)
+    UnclosedCollection(ByteIndexSpan),
+    /// the first occurrence, then the duplicate
+    DuplicateKey(ByteIndexSpan, ByteIndexSpan),
 }
 
 impl ReadError {
@@ -86,4 +147,37 @@ impl ReadError {
             _ => false,
         }
     }
-}
\ No newline at end of file
+    pub fn duplicate_key(first: ByteIndexSpan, duplicate: ByteIndexSpan) -> Self {
+        Self::DuplicateKey(first, duplicate)
+    }
+    pub fn is_duplicate_key(&self) -> bool {
+        matches!(self, Self::DuplicateKey(..))
+    }
+    /// what went wrong, e.g. `"unexpected end of input"`
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::InsufficientInput(..) => "unexpected end of input",
+            Self::InvalidInput(..) => "invalid input",
+            Self::UnclosedCollection(..) => "unmatched closing delimiter",
+            Self::DuplicateKey(..) => "duplicate key",
+        }
+    }
+    /// where it went wrong, which for a duplicate key is the duplicate
+    pub fn span(&self) -> ByteIndexSpan {
+        match self {
+            Self::InsufficientInput(span)
+            | Self::InvalidInput(span)
+            | Self::UnclosedCollection(span)
+            | Self::DuplicateKey(_, span) => *span,
+        }
+    }
+}
+
+impl core::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} at byte {}", self.message(), self.span().0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReadError {}
\ No newline at end of file
@@ -0,0 +1,467 @@
+//! reading inputs of many top-level forms, such as EDN logs, on several threads
+//!
+//! A pre-scan over the input's bytes finds where top-level forms end, skipping over strings,
+//! regexes, chars and comments, and splits the input there into chunks of at least
+//! [`ParallelOptions::chunk_len`] bytes. The chunks are then read concurrently, each by a
+//! [`CharReader`] of its own, and their forms are put back in order with spans into the whole
+//! input, so the result is what reading the forms one after another gives.
+//!
+//! Each thread interns the symbols and keywords it reads once, and then shares them between
+//! the chunks it reads, so the threads don't all wait on the interner's lock at every name.
+
+use std::{
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+use archery::ArcK;
+use crate::{
+    char_reader::{CharReader, Symbols},
+    ReadClj, ReadError, ReadOptions, SpanValue,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParallelOptions {
+    pub read: ReadOptions,
+    /// how many threads read chunks, where 0 is as many as the machine has cores
+    pub threads: usize,
+    /// the least number of bytes in a chunk, so that each thread has more to do than taking
+    /// its next chunk
+    pub chunk_len: usize,
+}
+
+impl Default for ParallelOptions {
+    fn default() -> Self {
+        Self {
+            read: ReadOptions::default(),
+            threads: 0,
+            chunk_len: 1 << 16,
+        }
+    }
+}
+
+impl ParallelOptions {
+    pub fn with_read_options(mut self, read: ReadOptions) -> Self {
+        self.read = read;
+        self
+    }
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+    pub fn with_chunk_len(mut self, chunk_len: usize) -> Self {
+        self.chunk_len = chunk_len;
+        self
+    }
+}
+
+/// every top-level form of the input in order, or the error reading them one after another
+/// would stop at
+pub fn read_all(src: &str, options: ParallelOptions) -> Result<Vec<SpanValue<ArcK>>, ReadError> {
+    let chunks = chunks(src, options.chunk_len);
+    let threads = match options.threads {
+        0 => thread::available_parallelism().map_or(1, |threads| threads.get()),
+        threads => threads,
+    };
+    let next_chunk = AtomicUsize::new(0);
+    // chunks after one that failed to read need not be read
+    let first_failed = AtomicUsize::new(usize::MAX);
+    let read_chunks = || {
+        let (mut read, mut symbols) = (vec![], Symbols::new());
+        loop {
+            let idx = next_chunk.fetch_add(1, Ordering::Relaxed);
+            if idx >= chunks.len() || idx > first_failed.load(Ordering::Relaxed) {
+                return read;
+            }
+            let forms = read_chunk(src, chunks[idx].clone(), options.read, &mut symbols);
+            if forms.is_err() {
+                first_failed.fetch_min(idx, Ordering::Relaxed);
+            }
+            read.push((idx, forms));
+        }
+    };
+
+    let mut read: Vec<_> = match threads.min(chunks.len()) {
+        0 | 1 => read_chunks(),
+        threads => thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads).map(|_| scope.spawn(read_chunks)).collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("reading a chunk does not panic"))
+                .collect()
+        }),
+    };
+    read.sort_by_key(|(idx, _)| *idx);
+    let mut forms = vec![];
+    for (_idx, chunk_forms) in read {
+        forms.extend(chunk_forms?);
+    }
+    Ok(forms)
+}
+
+/// reads with, and then puts back, the `symbols` the thread has read
+fn read_chunk(
+    src: &str,
+    chunk: Range<usize>,
+    options: ReadOptions,
+    symbols: &mut Symbols,
+) -> Result<Vec<SpanValue<ArcK>>, ReadError> {
+    let offset = chunk.start;
+    let shift = |(begin, end): (usize, usize)| (begin.saturating_add(offset), end.saturating_add(offset));
+    let Some(rdr) = CharReader::try_from_str(&src[chunk]) else {
+        return Ok(vec![]);
+    };
+    let mut rdr = rdr.with_options(options).with_symbols(core::mem::take(symbols));
+    let mut forms = vec![];
+    let read = loop {
+        match ReadClj::<ArcK>::try_read_one(&mut rdr) {
+            Ok(Some(SpanValue { span, data })) => forms.push(SpanValue { span: shift(span), data }),
+            Ok(None) => break Ok(forms),
+            Err(err) => {
+                break Err(match err {
+                    ReadError::InsufficientInput(span) => ReadError::InsufficientInput(shift(span)),
+                    ReadError::InvalidInput(span) => ReadError::InvalidInput(shift(span)),
+                    ReadError::UnclosedCollection(span) => ReadError::UnclosedCollection(shift(span)),
+                    ReadError::DuplicateKey(first, span) => ReadError::DuplicateKey(shift(first), shift(span)),
+                })
+            }
+        }
+    };
+    *symbols = rdr.into_symbols();
+    read
+}
+
+/// the ranges the input is read in, which cover it and each end where a top-level form does
+/// (or at the end of the input), so that no form is split between them, e.g. with a `chunk_len`
+/// of 1, `(a ")") 'b ; (` is split into `(a ")")` and ` 'b ; (`
+pub fn chunks(src: &str, chunk_len: usize) -> Vec<Range<usize>> {
+    let mut chunks = vec![];
+    let mut begin = 0;
+    for end in FormEnds::new(src) {
+        if end - begin >= chunk_len.max(1) {
+            chunks.push(begin..end);
+            begin = end;
+        }
+    }
+    if begin < src.len() || chunks.is_empty() {
+        chunks.push(begin..src.len());
+    }
+    chunks
+}
+
+/// the byte indices just after each top-level form, found with no more than a few states,
+/// following [`CharReader`]'s rules for where tokens end
+struct FormEnds<'s> {
+    src: &'s str,
+    byte_idx: usize,
+    depth: usize,
+    /// how many forms the top-level form being scanned needs before it ends, which is more than
+    /// one after prefixes such as `^meta` and `#_`
+    needed: usize,
+}
+
+impl<'s> FormEnds<'s> {
+    fn new(src: &'s str) -> Self {
+        Self {
+            src,
+            byte_idx: 0,
+            depth: 0,
+            needed: 0,
+        }
+    }
+
+    fn byte(&self, byte_idx: usize) -> Option<u8> {
+        self.src.as_bytes().get(byte_idx).copied()
+    }
+
+    /// the char at the byte index, which may not be ASCII
+    fn char_at(&self, byte_idx: usize) -> Option<char> {
+        self.src.get(byte_idx..).and_then(|rest| rest.chars().next())
+    }
+
+    fn is_whitespace(&self, byte_idx: usize) -> bool {
+        self.char_at(byte_idx).is_some_and(|ch| ch.is_whitespace() || ch == ',')
+    }
+
+    /// past the end of a symbol, keyword, number or char name
+    fn skip_token(&mut self) {
+        while let Some(byte) = self.byte(self.byte_idx) {
+            if matches!(byte, b'(' | b')' | b'[' | b']' | b'{' | b'}' | b';') || self.is_whitespace(self.byte_idx) {
+                break;
+            }
+            self.byte_idx += 1;
+        }
+    }
+
+    /// past the closing quote of a string or regex, from just after its opening one
+    fn skip_string(&mut self) {
+        while let Some(byte) = self.byte(self.byte_idx) {
+            self.byte_idx += match byte {
+                b'\\' => 2,
+                b'"' => return self.byte_idx += 1,
+                _ => 1,
+            };
+        }
+        self.byte_idx = self.byte_idx.min(self.src.len());
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(byte) = self.byte(self.byte_idx) {
+            if byte == b'\n' || byte == b'\r' {
+                break;
+            }
+            self.byte_idx += 1;
+        }
+    }
+
+    /// of a prefix that makes what follows it a form of `arity` forms, e.g. 2 for `^meta form`
+    fn prefix(&mut self, arity: usize) {
+        if self.depth == 0 {
+            self.needed = self.needed.max(1) - 1 + arity;
+        }
+    }
+
+    /// of `#_`, which needs a form but, unlike other prefixes, isn't one
+    fn discard(&mut self) {
+        if self.depth == 0 {
+            self.needed += 1;
+        }
+    }
+
+    fn open(&mut self) {
+        if self.depth == 0 {
+            self.needed = self.needed.max(1);
+        }
+        self.depth += 1;
+    }
+
+    /// whether a top-level form has ended, with the end of an atom or collection
+    fn close(&mut self) -> bool {
+        match self.depth {
+            0 | 1 => {
+                self.depth = 0;
+                self.needed = self.needed.max(1) - 1;
+                self.needed == 0
+            }
+            _ => {
+                self.depth -= 1;
+                false
+            }
+        }
+    }
+}
+
+impl Iterator for FormEnds<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while let Some(byte) = self.byte(self.byte_idx) {
+            let next = self.byte(self.byte_idx + 1);
+            let ended = match byte {
+                _ if self.is_whitespace(self.byte_idx) => {
+                    self.byte_idx += self.char_at(self.byte_idx).map_or(1, char::len_utf8);
+                    false
+                }
+                b';' => {
+                    self.skip_line();
+                    false
+                }
+                b'(' | b'[' | b'{' => {
+                    self.byte_idx += 1;
+                    self.open();
+                    false
+                }
+                b')' | b']' | b'}' => {
+                    self.byte_idx += 1;
+                    self.close()
+                }
+                b'"' => {
+                    self.byte_idx += 1;
+                    self.skip_string();
+                    self.close_atom()
+                }
+                // the char after `\` is taken as-is, so `\(` and `\;` are chars
+                b'\\' => {
+                    self.byte_idx += 1 + self.char_at(self.byte_idx + 1).map_or(0, char::len_utf8);
+                    self.skip_token();
+                    self.close_atom()
+                }
+                b'\'' | b'`' | b'@' => {
+                    self.byte_idx += 1;
+                    self.prefix(1);
+                    false
+                }
+                b'~' => {
+                    self.byte_idx += if next == Some(b'@') { 2 } else { 1 };
+                    self.prefix(1);
+                    false
+                }
+                b'^' => {
+                    self.byte_idx += 1;
+                    self.prefix(2);
+                    false
+                }
+                b'#' => match next {
+                    Some(b'{' | b'(') => {
+                        self.byte_idx += 2;
+                        self.open();
+                        false
+                    }
+                    Some(b'"') => {
+                        self.byte_idx += 2;
+                        self.skip_string();
+                        self.close_atom()
+                    }
+                    Some(b'!') => {
+                        self.skip_line();
+                        false
+                    }
+                    Some(b'^') => {
+                        self.byte_idx += 2;
+                        self.prefix(2);
+                        false
+                    }
+                    Some(b'_') => {
+                        self.byte_idx += 2;
+                        self.discard();
+                        false
+                    }
+                    Some(b'\'' | b'=') => {
+                        self.byte_idx += 2;
+                        self.prefix(1);
+                        false
+                    }
+                    Some(b'?') => {
+                        self.byte_idx += if self.byte(self.byte_idx + 2) == Some(b'@') { 3 } else { 2 };
+                        self.prefix(1);
+                        false
+                    }
+                    // `##Inf`
+                    Some(b'#') => {
+                        self.skip_token();
+                        self.close_atom()
+                    }
+                    // `#:ns{...}` and tagged literals such as `#inst "..."`
+                    _ => {
+                        self.byte_idx += 1;
+                        self.skip_token();
+                        self.prefix(1);
+                        false
+                    }
+                },
+                _ => {
+                    self.skip_token();
+                    self.close_atom()
+                }
+            };
+            if ended {
+                return Some(self.byte_idx);
+            }
+        }
+        None
+    }
+}
+
+impl FormEnds<'_> {
+    fn close_atom(&mut self) -> bool {
+        self.depth == 0 && self.close()
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::{chunks, read_all, ParallelOptions};
+    use crate::{char_reader::CharReader, ReadClj, ReadError, SpanValue};
+    use archery::ArcK;
+    use proptest::prelude::*;
+
+    fn read_sequentially(src: &str) -> Result<Vec<SpanValue<ArcK>>, ReadError> {
+        let mut forms = vec![];
+        if let Some(mut rdr) = CharReader::try_from_str(src) {
+            while let Some(form) = ReadClj::<ArcK>::try_read_one(&mut rdr)? {
+                forms.push(form);
+            }
+        }
+        Ok(forms)
+    }
+
+    fn assert_reads_as_sequentially(src: &str) {
+        let options = ParallelOptions::default().with_threads(3).with_chunk_len(1);
+        let spans_and_values = |read: Result<Vec<SpanValue<ArcK>>, ReadError>| {
+            read.map(|forms| forms.into_iter().map(|form| (form.span, form.data)).collect::<Vec<_>>())
+        };
+        assert_eq!(spans_and_values(read_all(src, options)), spans_and_values(read_sequentially(src)), "{src:?}");
+    }
+
+    #[test]
+    fn splits_between_top_level_forms() {
+        let texts = |src: &str| -> Vec<String> {
+            chunks(src, 1).into_iter().map(|chunk| src[chunk].to_owned()).collect()
+        };
+        assert_eq!(texts("(a \")\") b ; (\n'c"), vec!["(a \")\")", " b", " ; (\n'c"]);
+        assert_eq!(texts("\\( x #\"[(]\" #_ (a) b"), vec!["\\(", " x", " #\"[(]\"", " #_ (a)", " b"]);
+        assert_eq!(texts("^:m ^{:a [1]} x #inst \"2020\" #(a) #?(:clj 1)"), vec![
+            "^:m ^{:a [1]} x",
+            " #inst \"2020\"",
+            " #(a)",
+            " #?(:clj 1)",
+        ]);
+        assert_eq!(texts("#:a{:b 1}\u{2003}@c d"), vec!["#:a{:b 1}", "\u{2003}@c", " d"]);
+        assert_eq!(texts("(a"), vec!["(a"]);
+        assert_eq!(texts(""), vec![""]);
+        assert_eq!(chunks("a b c d", 3), vec![0..3, 3..7]);
+    }
+
+    #[test]
+    fn reads_in_order_with_spans() {
+        assert_reads_as_sequentially("1 (a \"]\" \\)) ; b)\n {:c #{d}} 'e #_ f @g #:h{:i 2} ##Inf 😀");
+        let src: String = (0..1000).map(|n| format!("{{:n {n} :s \"{n} )\"}}\n")).collect();
+        let forms = read_all(&src, ParallelOptions::default().with_threads(4).with_chunk_len(100)).unwrap();
+        assert_eq!(forms.len(), 1000);
+        assert_eq!(forms[999].span, (src.len() - 20, src.len() - 2));
+        assert_reads_as_sequentially(&src);
+    }
+
+    #[test]
+    fn fails_as_sequentially() {
+        assert_reads_as_sequentially("(a) (b c] (d)");
+        assert_reads_as_sequentially("(a) b) (c");
+        assert_reads_as_sequentially("(a) {:b 1 :b 2} (c");
+        assert_reads_as_sequentially("(a) (b");
+        assert_reads_as_sequentially("a #_");
+        assert_reads_as_sequentially("@ #_ a] b");
+    }
+
+    /// a benchmark more than a test, as it's only meaningful built with optimizations, on a
+    /// machine with cores to spare: `cargo test --release -p cljrs-reader -- --ignored faster`
+    #[test]
+    #[ignore]
+    fn faster_than_sequentially_on_symbols() {
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get()).min(4);
+        let src: String = (0..200_000).map(|n| format!("(sym{} ns{}/sym :kw{} {n})\n", n % 500, n % 7, n % 300)).collect();
+        let time = |read: &dyn Fn() -> usize| {
+            let start = std::time::Instant::now();
+            assert_eq!(read(), 200_000);
+            start.elapsed()
+        };
+        let sequential = time(&|| read_sequentially(&src).unwrap().len());
+        let parallel = time(&|| read_all(&src, ParallelOptions::default().with_threads(threads)).unwrap().len());
+        eprintln!("{threads} threads: {parallel:?}, sequentially: {sequential:?}");
+        if threads > 1 {
+            assert!(parallel < sequential);
+        }
+    }
+
+    const FRAGMENTS: &[&str] = &[
+        "a", " ", "\n", "(", ")", "[", "]", "{", "}", "#{", "\"s\"", "\")\"", "\\(", "\\a", "; c\n", "#_", "'", "@",
+        ":k", "1", "-2.5", ",", "😀",
+    ];
+
+    proptest! {
+        #[test]
+        fn reads_as_sequentially(fragments in prop::collection::vec(prop::sample::select(FRAGMENTS), 0..24)) {
+            let src = fragments.concat();
+            assert_reads_as_sequentially(&src);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use alloc::string::String;
+use archery::SharedPointerKind;
+use cljrs_core::pretty::{self, Doc, Highlight, PrettyOptions};
+use crate::{printer, value::Value};
+
+/// Clojure example:
+/// ```clojure
+/// (binding [*print-length* 2] (pprint [1 2 3])) ; prints [1 2 ...]
+/// ```
+pub fn pretty_str<P: SharedPointerKind>(value: &Value<P>, options: &PrettyOptions) -> String {
+    pretty::render(&to_doc(value, options), options)
+}
+
+pub fn to_doc<P: SharedPointerKind>(value: &Value<P>, options: &PrettyOptions) -> Doc {
+    doc_at_depth(value, options, 0)
+}
+
+fn doc_at_depth<P: SharedPointerKind>(value: &Value<P>, options: &PrettyOptions, depth: usize) -> Doc {
+    let leaf = |highlight| Doc::highlighted(printer::to_string(value, options.mode), highlight);
+    let item_doc = |v: &Value<P>| doc_at_depth(v, options, depth + 1);
+    match value {
+        Value::Nil | Value::Bool(..) => leaf(Highlight::Literal),
+        Value::Num(..) => leaf(Highlight::Num),
+        Value::Str(..) => leaf(Highlight::Str),
+        Value::Char(..) => leaf(Highlight::Char),
+        Value::Keyword(..) => leaf(Highlight::Keyword),
+        Value::Symbol(..) => leaf(Highlight::Symbol),
+        Value::List(vs) => options.collection_doc(depth, ("(", "", ")"), vs.iter().map(AsRef::as_ref), item_doc),
+        Value::Vect(vs) => options.collection_doc(depth, ("[", "", "]"), vs.iter().map(AsRef::as_ref), item_doc),
+        Value::Set(vs) => options.collection_doc(depth, ("#{", "", "}"), vs.iter().map(AsRef::as_ref), item_doc),
+        Value::Map(kvs) => options.collection_doc(depth, ("{", ",", "}"), kvs, |(k, v)| {
+            Doc::map_entry(item_doc(k), item_doc(v))
+        }),
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::pretty_str;
+    use crate::reader;
+    use cljrs_core::pretty::{PrettyOptions, Theme};
+
+    fn pretty(src: &str, options: &PrettyOptions) -> String {
+        let value = reader::<archery::RcK>(src).unwrap().try_read_one().unwrap().unwrap().data;
+        pretty_str(&value, options)
+    }
+
+    #[test]
+    fn short_values_stay_on_one_line() {
+        let src = r#"{:a [1 2], :b #{"c"}}"#;
+        assert_eq!(pretty(src, &PrettyOptions::default()), r#"{:a [1.0 2.0], :b #{"c"}}"#);
+    }
+
+    #[test]
+    fn map_entries_break_onto_their_own_lines() {
+        let src = "{:user {:name \"ada\" :langs [:clojure :rust]} :id 1}";
+        let opts = PrettyOptions::default().with_width(30);
+        assert_eq!(
+            pretty(src, &opts),
+            "{:user\n {:name \"ada\",\n  :langs [:clojure :rust]},\n :id 1.0}",
+        );
+    }
+
+    #[test]
+    fn print_length_and_level() {
+        let src = "[1 [2 [3]] 4 5]";
+        let opts = PrettyOptions::default().with_print_length(3).with_print_level(2);
+        assert_eq!(pretty(src, &opts), "[1.0 [2.0 #] 4.0 ...]");
+    }
+
+    #[test]
+    fn theme_does_not_count_towards_width() {
+        let src = "[:a :b]";
+        let opts = PrettyOptions::default().with_width(7).with_theme(Theme::default());
+        assert!(!pretty(src, &opts).contains('\n'));
+    }
+}
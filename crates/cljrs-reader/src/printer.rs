@@ -0,0 +1,216 @@
+use alloc::string::String;
+use core::fmt;
+use archery::SharedPointerKind;
+use cljrs_core::printer::{self as core_printer, PrintMode};
+use crate::value::Value;
+
+/// Clojure example:
+/// ```clojure
+/// (pr-str {:a "b"}) ;=> "{:a \"b\"}"
+/// ```
+pub fn pr_str<P: SharedPointerKind>(value: &Value<P>) -> String {
+    to_string(value, PrintMode::Pr)
+}
+
+/// Clojure example:
+/// ```clojure
+/// (print-str {:a "b"}) ;=> "{:a b}"
+/// ```
+pub fn print_str<P: SharedPointerKind>(value: &Value<P>) -> String {
+    to_string(value, PrintMode::Print)
+}
+
+pub fn to_string<P: SharedPointerKind>(value: &Value<P>, mode: PrintMode) -> String {
+    let mut buf = String::new();
+    write_value(&mut buf, value, mode).expect("writing to a String does not fail");
+    buf
+}
+
+/// in [`PrintMode::Pr`] the output `read`s back into a [`Value`] equal to `value`
+/// (`##NaN` excepted, it is never equal to itself)
+pub fn write_value<W: fmt::Write, P: SharedPointerKind>(
+    w: &mut W,
+    value: &Value<P>,
+    mode: PrintMode,
+) -> fmt::Result {
+    match value {
+        Value::Nil => w.write_str("nil"),
+        Value::Bool(b) => write!(w, "{b}"),
+        Value::Num(n) => core_printer::write_f64(w, *n),
+        Value::Str(s) => core_printer::write_str(w, s, mode),
+        Value::Char(ch) => core_printer::write_char(w, *ch, mode),
+        Value::Keyword(k) => write!(w, "{k}"),
+        Value::Symbol(s) => write!(w, "{s}"),
+        Value::List(vs) => {
+            w.write_char('(')?;
+            core_printer::write_joined(w, vs, " ", |w, v| write_value(w, v, mode))?;
+            w.write_char(')')
+        }
+        Value::Vect(vs) => {
+            w.write_char('[')?;
+            core_printer::write_joined(w, vs, " ", |w, v| write_value(w, v, mode))?;
+            w.write_char(']')
+        }
+        Value::Set(vs) => {
+            w.write_str("#{")?;
+            core_printer::write_joined(w, vs, " ", |w, v| write_value(w, v, mode))?;
+            w.write_char('}')
+        }
+        Value::Map(kvs) => {
+            w.write_char('{')?;
+            core_printer::write_joined(w, kvs, ", ", |w, (k, v)| {
+                write_value(w, k, mode)?;
+                w.write_char(' ')?;
+                write_value(w, v, mode)
+            })?;
+            w.write_char('}')
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::{pr_str, print_str};
+    use crate::{
+        keyword::Keyword,
+        reader,
+        value::{RcValue, Value},
+    };
+    use cljrs_core::symbol::Symbol;
+    use proptest::prelude::*;
+
+    fn read(src: &str) -> RcValue {
+        reader(src).unwrap().try_read_one().unwrap().unwrap().data
+    }
+
+    #[test]
+    fn pr_vs_print() {
+        let v = RcValue::vect_from_values(vec![
+            Value::Str(String::from("a \"b\"\n")),
+            Value::Char('\n'),
+            Value::Num(1.0),
+        ]);
+        assert_eq!(pr_str(&v), r#"["a \"b\"\n" \newline 1.0]"#);
+        assert_eq!(print_str(&v), "[a \"b\"\n \n 1.0]");
+    }
+
+    #[test]
+    fn map_entries_are_comma_separated() {
+        let v = RcValue::map_from_value_pairs(vec![
+            (Value::unqualified_keyword(String::from("a")), Value::Num(1.0)),
+            (Value::unqualified_keyword(String::from("b")), Value::Nil),
+        ]);
+        assert_eq!(pr_str(&v), "{:a 1.0, :b nil}");
+    }
+
+    #[test]
+    fn non_finite_numbers_round_trip() {
+        assert_eq!(read(&pr_str(&RcValue::Num(f64::INFINITY))), Value::Num(f64::INFINITY));
+        assert_eq!(read(&pr_str(&RcValue::Num(f64::NEG_INFINITY))), Value::Num(f64::NEG_INFINITY));
+        match read(&pr_str(&RcValue::Num(f64::NAN))) {
+            Value::Num(n) => assert!(n.is_nan()),
+            other => panic!("expected ##NaN, got {other}"),
+        }
+    }
+
+    fn arb_name() -> impl Strategy<Value = String> {
+        "[a-zA-Z*!_?<>=][a-zA-Z0-9*+!_?<>=.-]{0,8}"
+            .prop_filter("reserved", |s| !matches!(s.as_str(), "nil" | "true" | "false"))
+    }
+
+    fn arb_symbol() -> impl Strategy<Value = Symbol> {
+        prop_oneof![
+            arb_name().prop_map(Symbol::unqualified),
+            (arb_name(), arb_name()).prop_map(|(ns, n)| Symbol::qualified(ns, n)),
+        ]
+    }
+
+    fn arb_keyword() -> impl Strategy<Value = Keyword> {
+        prop_oneof![
+            arb_name().prop_map(Keyword::unqualified),
+            (arb_name(), arb_name()).prop_map(|(ns, n)| Keyword::qualified(ns, n)),
+            arb_name().prop_map(Keyword::self_qualified),
+            (arb_name(), arb_name()).prop_map(|(a, n)| Keyword::alias_qualified(a, n)),
+        ]
+    }
+
+    /// [`Value`] is not [`Clone`] (which proptest needs), so values are generated as this first
+    #[derive(Debug, Clone)]
+    enum ArbValue {
+        Nil,
+        Bool(bool),
+        Num(f64),
+        Str(String),
+        Char(char),
+        Keyword(Keyword),
+        Symbol(Symbol),
+        List(Vec<ArbValue>),
+        Vect(Vec<ArbValue>),
+        Set(Vec<ArbValue>),
+        Map(Vec<(ArbValue, ArbValue)>),
+    }
+
+    impl ArbValue {
+        fn to_value(&self) -> RcValue {
+            let to_values = |vs: &[ArbValue]| vs.iter().map(ArbValue::to_value).collect::<Vec<_>>();
+            match self {
+                Self::Nil => Value::Nil,
+                Self::Bool(b) => Value::Bool(*b),
+                Self::Num(n) => Value::Num(*n),
+                Self::Str(s) => Value::Str(s.clone()),
+                Self::Char(ch) => Value::Char(*ch),
+                Self::Keyword(k) => Value::Keyword(k.clone()),
+                Self::Symbol(s) => Value::Symbol(s.clone()),
+                Self::List(vs) => Value::list_from_values(to_values(vs)),
+                Self::Vect(vs) => Value::vect_from_values(to_values(vs)),
+                Self::Set(vs) => Value::set_from_values(distinct(to_values(vs))),
+                Self::Map(kvs) => {
+                    let (ks, vs): (Vec<_>, Vec<_>) = kvs.iter().cloned().unzip();
+                    let ks = distinct(to_values(&ks));
+                    Value::map_from_value_pairs(ks.into_iter().zip(to_values(&vs)))
+                }
+            }
+        }
+    }
+
+    fn distinct(values: Vec<RcValue>) -> Vec<RcValue> {
+        let mut out: Vec<RcValue> = vec![];
+        for v in values {
+            if !out.contains(&v) {
+                out.push(v);
+            }
+        }
+        out
+    }
+
+    fn arb_value() -> impl Strategy<Value = ArbValue> {
+        let leaf = prop_oneof![
+            Just(ArbValue::Nil),
+            any::<bool>().prop_map(ArbValue::Bool),
+            any::<f64>()
+                .prop_filter("NaN != NaN", |n| !n.is_nan())
+                .prop_map(ArbValue::Num),
+            any::<String>().prop_map(ArbValue::Str),
+            any::<char>().prop_map(ArbValue::Char),
+            arb_keyword().prop_map(ArbValue::Keyword),
+            arb_symbol().prop_map(ArbValue::Symbol),
+        ];
+        leaf.prop_recursive(4, 64, 8, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..8).prop_map(ArbValue::List),
+                prop::collection::vec(inner.clone(), 0..8).prop_map(ArbValue::Vect),
+                prop::collection::vec(inner.clone(), 0..8).prop_map(ArbValue::Set),
+                prop::collection::vec((inner.clone(), inner), 0..8).prop_map(ArbValue::Map),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn read_pr_str_round_trips(v in arb_value()) {
+            let v = v.to_value();
+            let printed = pr_str(&v);
+            prop_assert_eq!(read(&printed), v, "printed: {}", printed);
+        }
+    }
+}
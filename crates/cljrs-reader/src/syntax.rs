@@ -0,0 +1,633 @@
+//! a lossless parse of Clojure source: unlike [`crate::char_reader::CharReader`], which produces
+//! [`crate::value::Value`]s, the [`Node`] tree produced here keeps whitespace, newlines and comments,
+//! so the source can be rewritten (e.g. by a formatter) without losing anything
+
+use alloc::{string::String, vec, vec::Vec};
+use crate::{ByteIndexSpan, ReadError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Delim {
+    /// `(`
+    List,
+    /// `[`
+    Vect,
+    /// `{`
+    Map,
+    /// `#{`
+    Set,
+    /// `#(`
+    AnonFn,
+    /// `#?(`
+    ReaderCond,
+    /// `#?@(`
+    ReaderCondSplicing,
+}
+
+impl Delim {
+    pub fn open(&self) -> &'static str {
+        match self {
+            Self::List => "(",
+            Self::Vect => "[",
+            Self::Map => "{",
+            Self::Set => "#{",
+            Self::AnonFn => "#(",
+            Self::ReaderCond => "#?(",
+            Self::ReaderCondSplicing => "#?@(",
+        }
+    }
+    pub fn close(&self) -> char {
+        match self {
+            Self::List | Self::AnonFn | Self::ReaderCond | Self::ReaderCondSplicing => ')',
+            Self::Vect => ']',
+            Self::Map | Self::Set => '}',
+        }
+    }
+    /// whether the first element is (usually) invoked with the rest
+    pub fn is_list_like(&self) -> bool {
+        matches!(self, Self::List | Self::AnonFn)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    /// the whole input, children are top-level forms and trivia
+    Root,
+    /// spaces, tabs and commas
+    Whitespace,
+    /// `\n`, `\r\n` or `\r`
+    Newline,
+    /// `; ...` up to (not including) the end of the line, also a `#!` shebang line
+    Comment,
+    /// symbols, keywords, numbers, strings, chars, regexes and `##` symbolic values
+    Atom,
+    /// children exclude the delimiters
+    Coll(Delim),
+    /// the `'`, `@`, `#_`, `^`, `#inst`, `#:ns` etc. of a [`NodeKind::Prefixed`]
+    Prefix,
+    /// first child is a [`NodeKind::Prefix`], followed by the form(s) it applies to
+    /// (two for metadata `^meta form`, one otherwise) and any trivia between them
+    Prefixed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub span: ByteIndexSpan,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn text<'s>(&self, src: &'s str) -> &'s str {
+        let (begin, end) = self.span;
+        // spans are inclusive of the last char, which may be wider than a byte
+        let end = end + src[end..].chars().next().map(char::len_utf8).unwrap_or(0);
+        &src[begin..end]
+    }
+    /// whitespace, newlines and comments
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind, NodeKind::Whitespace | NodeKind::Newline | NodeKind::Comment)
+    }
+    /// whether this is a `#_` discarded form
+    pub fn is_discard(&self, src: &str) -> bool {
+        self.kind == NodeKind::Prefixed
+            && self.children.first().map(|prefix| prefix.text(src) == "#_").unwrap_or(false)
+    }
+    /// non-trivia children
+    pub fn forms(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter(|child| !child.is_trivia())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Lexeme {
+    Whitespace,
+    Newline,
+    Comment,
+    Atom,
+    Open(Delim),
+    Close(char),
+    /// the number of forms the prefix applies to
+    Prefix(usize),
+}
+
+fn is_delimiter(ch: char) -> bool {
+    ch.is_whitespace() || matches!(ch, ',' | '(' | ')' | '[' | ']' | '{' | '}' | '"' | ';')
+}
+
+pub(crate) struct Lexer<'s> {
+    src: &'s str,
+    byte_idx: usize,
+}
+
+impl<'s> Lexer<'s> {
+    pub(crate) fn new(src: &'s str) -> Self {
+        Self { src, byte_idx: 0 }
+    }
+
+    /// the byte index of the next char
+    pub(crate) fn byte_idx(&self) -> usize {
+        self.byte_idx
+    }
+
+    fn peek_char(&self, n: usize) -> Option<char> {
+        self.src[self.byte_idx..].chars().nth(n)
+    }
+
+    /// moves beyond the current char, returning its byte index
+    fn bump(&mut self) -> usize {
+        let byte_idx = self.byte_idx;
+        self.byte_idx += self.peek_char(0).map(char::len_utf8).unwrap_or(0);
+        byte_idx
+    }
+
+    /// moves beyond chars while `pred` holds, returning the byte index of the last one moved beyond
+    fn bump_while(&mut self, last: usize, pred: impl Fn(char) -> bool) -> usize {
+        let mut last = last;
+        while let Some(ch) = self.peek_char(0) {
+            if !pred(ch) {
+                break;
+            }
+            last = self.bump();
+        }
+        last
+    }
+
+    /// expects to be beyond the opening `"`
+    fn bump_string(&mut self, begin: usize) -> Result<usize, ReadError> {
+        loop {
+            match self.peek_char(0) {
+                Some('"') => return Ok(self.bump()),
+                Some('\\') => {
+                    self.bump();
+                    if self.peek_char(0).is_some() {
+                        self.bump();
+                    }
+                }
+                Some(_) => {
+                    self.bump();
+                }
+                None => {
+                    return Err(ReadError::insufficient_input((
+                        begin,
+                        self.src.len().saturating_sub(1),
+                    )))
+                }
+            }
+        }
+    }
+
+    pub(crate) fn next(&mut self) -> Option<Result<(Lexeme, ByteIndexSpan), ReadError>> {
+        let ch = self.peek_char(0)?;
+        let begin = self.bump();
+        let lexeme = match ch {
+            '\r' if self.peek_char(0) == Some('\n') => {
+                return Some(Ok((Lexeme::Newline, (begin, self.bump()))));
+            }
+            '\n' | '\r' => Lexeme::Newline,
+            ';' => {
+                let end = self.bump_while(begin, |ch| ch != '\n' && ch != '\r');
+                return Some(Ok((Lexeme::Comment, (begin, end))));
+            }
+            ch if ch.is_whitespace() || ch == ',' => {
+                let end = self.bump_while(begin, |ch| (ch.is_whitespace() || ch == ',') && ch != '\n' && ch != '\r');
+                return Some(Ok((Lexeme::Whitespace, (begin, end))));
+            }
+            '(' => Lexeme::Open(Delim::List),
+            '[' => Lexeme::Open(Delim::Vect),
+            '{' => Lexeme::Open(Delim::Map),
+            ')' | ']' | '}' => Lexeme::Close(ch),
+            '"' => {
+                return Some(self.bump_string(begin).map(|end| (Lexeme::Atom, (begin, end))));
+            }
+            '\\' => {
+                if self.peek_char(0).is_none() {
+                    return Some(Err(ReadError::insufficient_input((begin, begin))));
+                }
+                let first = self.bump();
+                let end = self.bump_while(first, |ch| !is_delimiter(ch));
+                return Some(Ok((Lexeme::Atom, (begin, end))));
+            }
+            '\'' | '`' | '@' => Lexeme::Prefix(1),
+            '~' if self.peek_char(0) == Some('@') => {
+                return Some(Ok((Lexeme::Prefix(1), (begin, self.bump()))));
+            }
+            '~' => Lexeme::Prefix(1),
+            '^' => Lexeme::Prefix(2),
+            '#' => return Some(self.next_dispatch(begin)),
+            _ => {
+                let end = self.bump_while(begin, |ch| !is_delimiter(ch));
+                return Some(Ok((Lexeme::Atom, (begin, end))));
+            }
+        };
+        Some(Ok((lexeme, (begin, begin))))
+    }
+
+    /// expects to be beyond the `#`
+    fn next_dispatch(&mut self, begin: usize) -> Result<(Lexeme, ByteIndexSpan), ReadError> {
+        let ch = self
+            .peek_char(0)
+            .ok_or(ReadError::insufficient_input((begin, begin)))?;
+        match ch {
+            '{' => Ok((Lexeme::Open(Delim::Set), (begin, self.bump()))),
+            '(' => Ok((Lexeme::Open(Delim::AnonFn), (begin, self.bump()))),
+            '?' => match (self.peek_char(1), self.peek_char(2)) {
+                (Some('('), _) => {
+                    self.bump();
+                    Ok((Lexeme::Open(Delim::ReaderCond), (begin, self.bump())))
+                }
+                (Some('@'), Some('(')) => {
+                    self.bump();
+                    self.bump();
+                    Ok((Lexeme::Open(Delim::ReaderCondSplicing), (begin, self.bump())))
+                }
+                _ => Err(ReadError::invalid_input((begin, self.bump()))),
+            },
+            '_' | '\'' | '=' => Ok((Lexeme::Prefix(1), (begin, self.bump()))),
+            '^' => Ok((Lexeme::Prefix(2), (begin, self.bump()))),
+            '!' => {
+                let end = self.bump_while(begin, |ch| ch != '\n' && ch != '\r');
+                Ok((Lexeme::Comment, (begin, end)))
+            }
+            '"' => {
+                self.bump();
+                let end = self.bump_string(begin)?;
+                Ok((Lexeme::Atom, (begin, end)))
+            }
+            '#' => {
+                let end = self.bump_while(begin, |ch| !is_delimiter(ch));
+                Ok((Lexeme::Atom, (begin, end)))
+            }
+            // `#:ns{...}` and `#::{...}` namespaced maps, `#inst "..."` and other tagged literals
+            ch if !is_delimiter(ch) => {
+                let end = self.bump_while(begin, |ch| !is_delimiter(ch));
+                Ok((Lexeme::Prefix(1), (begin, end)))
+            }
+            _ => Err(ReadError::invalid_input((begin, begin))),
+        }
+    }
+}
+
+enum Frame {
+    Coll {
+        delim: Delim,
+        open_span: ByteIndexSpan,
+        children: Vec<Node>,
+    },
+    Prefixed {
+        remaining_forms: usize,
+        is_discard: bool,
+        children: Vec<Node>,
+    },
+}
+
+impl Frame {
+    fn children(&mut self) -> &mut Vec<Node> {
+        match self {
+            Self::Coll { children, .. } | Self::Prefixed { children, .. } => children,
+        }
+    }
+}
+
+/// pushes a complete form onto the innermost frame, completing any [`NodeKind::Prefixed`] it finishes
+fn push_form(stack: &mut Vec<Frame>, root: &mut Vec<Node>, mut form: Node) {
+    // `#_` discarded forms do not count towards the forms a prefix applies to, e.g. `^:m #_ x y`
+    let mut is_discard = false;
+    loop {
+        match stack.last_mut() {
+            Some(Frame::Prefixed {
+                remaining_forms,
+                children,
+                ..
+            }) => {
+                children.push(form);
+                if !is_discard {
+                    *remaining_forms -= 1;
+                }
+                if *remaining_forms > 0 {
+                    return;
+                }
+                let children = match stack.pop() {
+                    Some(Frame::Prefixed {
+                        children,
+                        is_discard: was_discard,
+                        ..
+                    }) => {
+                        is_discard = was_discard;
+                        children
+                    }
+                    _ => unreachable!(),
+                };
+                let span = (children[0].span.0, children[children.len() - 1].span.1);
+                form = Node {
+                    kind: NodeKind::Prefixed,
+                    span,
+                    children,
+                };
+            }
+            Some(frame) => {
+                frame.children().push(form);
+                return;
+            }
+            None => {
+                root.push(form);
+                return;
+            }
+        }
+    }
+}
+
+/// the state of a parse between lexemes
+struct Parser<'s> {
+    src: &'s str,
+    lexer: Lexer<'s>,
+    stack: Vec<Frame>,
+    root: Vec<Node>,
+}
+
+impl<'s> Parser<'s> {
+    /// parses from a byte index where no form is open, after the given top-level nodes
+    fn new(src: &'s str, byte_idx: usize, root: Vec<Node>) -> Self {
+        Self {
+            src,
+            lexer: Lexer { src, byte_idx },
+            stack: vec![],
+            root,
+        }
+    }
+
+    /// parses the next lexeme, returning false at the end of input
+    fn step(&mut self) -> Result<bool, ReadError> {
+        let Some(lexed) = self.lexer.next() else {
+            return Ok(false);
+        };
+        let (lexeme, span) = lexed?;
+        let (stack, root) = (&mut self.stack, &mut self.root);
+        let leaf = |kind| Node {
+            kind,
+            span,
+            children: vec![],
+        };
+        match lexeme {
+            Lexeme::Whitespace | Lexeme::Newline | Lexeme::Comment => {
+                let kind = match lexeme {
+                    Lexeme::Whitespace => NodeKind::Whitespace,
+                    Lexeme::Newline => NodeKind::Newline,
+                    _ => NodeKind::Comment,
+                };
+                match stack.last_mut() {
+                    Some(frame) => frame.children().push(leaf(kind)),
+                    None => root.push(leaf(kind)),
+                }
+            }
+            Lexeme::Atom => push_form(stack, root, leaf(NodeKind::Atom)),
+            Lexeme::Prefix(forms) => stack.push(Frame::Prefixed {
+                remaining_forms: forms,
+                is_discard: self.src[span.0..].starts_with("#_"),
+                children: vec![leaf(NodeKind::Prefix)],
+            }),
+            Lexeme::Open(delim) => stack.push(Frame::Coll {
+                delim,
+                open_span: span,
+                children: vec![],
+            }),
+            Lexeme::Close(ch) => match stack.pop() {
+                Some(Frame::Coll {
+                    delim,
+                    open_span,
+                    children,
+                }) if delim.close() == ch => {
+                    let node = Node {
+                        kind: NodeKind::Coll(delim),
+                        span: (open_span.0, span.1),
+                        children,
+                    };
+                    push_form(stack, root, node);
+                }
+                Some(Frame::Coll { open_span, .. }) => {
+                    return Err(ReadError::invalid_input((open_span.0, span.1)));
+                }
+                Some(Frame::Prefixed { children, .. }) => {
+                    return Err(ReadError::invalid_input((children[0].span.0, span.1)));
+                }
+                None => return Err(ReadError::unclosed_collection(span)),
+            },
+        }
+        Ok(true)
+    }
+
+    fn finish(self) -> Result<Node, ReadError> {
+        let last_byte_idx = self.src.len().saturating_sub(1);
+        match self.stack.last() {
+            Some(Frame::Coll { open_span, .. }) => {
+                Err(ReadError::insufficient_input((open_span.0, last_byte_idx)))
+            }
+            Some(Frame::Prefixed { children, .. }) => {
+                Err(ReadError::insufficient_input((children[0].span.0, last_byte_idx)))
+            }
+            None => Ok(Node {
+                kind: NodeKind::Root,
+                span: (0, last_byte_idx),
+                children: self.root,
+            }),
+        }
+    }
+}
+
+/// Clojure example:
+/// ```clojure
+/// ; parses to a Root with children: Comment, Newline, Coll(List) and Newline
+/// (defn f [x]
+///   x)
+/// ```
+pub fn parse(src: &str) -> Result<Node, ReadError> {
+    let mut parser = Parser::new(src, 0, vec![]);
+    while parser.step()? {}
+    parser.finish()
+}
+
+/// the replacement of a byte range of source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// exclusive of the end, unlike spans
+    pub range: core::ops::Range<usize>,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    pub fn apply(&self, src: &str) -> String {
+        let mut edited = String::with_capacity(src.len() + self.replacement.len());
+        edited.push_str(&src[..self.range.start]);
+        edited.push_str(&self.replacement);
+        edited.push_str(&src[self.range.end..]);
+        edited
+    }
+}
+
+/// the parse of `src`, the source that `old` is the parse of with the edit applied, which is what
+/// [`parse`] gives but reads only the top-level forms the edit could have changed
+///
+/// Forms before the edit are kept as they are. Reading resumes from the first that ends at or
+/// beyond the start of the edit, until it is beyond the edit at the start of one of the old forms,
+/// whereupon that form and those following are kept with their spans shifted.
+pub fn reparse(old: Node, edit: &TextEdit, src: &str) -> Result<Node, ReadError> {
+    let mut old_children = old.children;
+    // the source before the edit is unchanged, so the forms there end where they did
+    let first_changed = old_children
+        .partition_point(|child| child.span.1 < edit.range.start && end(src, child.span) < edit.range.start);
+    let after = old_children.split_off(first_changed);
+    let byte_idx = old_children.last().map_or(0, |child| end(src, child.span));
+    let mut after = after.into_iter().peekable();
+
+    let edit_end = edit.range.start + edit.replacement.len();
+    let shift = |byte_idx: usize| byte_idx - edit_end + edit.range.end;
+    let mut parser = Parser::new(src, byte_idx, old_children);
+    while parser.step()? {
+        let byte_idx = parser.lexer.byte_idx;
+        if !parser.stack.is_empty() || byte_idx < edit_end {
+            continue;
+        }
+        while after.next_if(|child| child.span.0 < shift(byte_idx)).is_some() {}
+        if after.peek().is_some_and(|child| child.span.0 == shift(byte_idx)) {
+            let delta = edit_end as isize - edit.range.end as isize;
+            parser.root.extend(after.map(|mut child| {
+                shift_spans(&mut child, delta);
+                child
+            }));
+            break;
+        }
+    }
+    parser.finish()
+}
+
+/// the byte index after a span, which is inclusive of its last char, however long that is
+fn end(src: &str, span: ByteIndexSpan) -> usize {
+    span.1 + src[span.1..].chars().next().map_or(1, char::len_utf8)
+}
+
+fn shift_spans(node: &mut Node, delta: isize) {
+    node.span = (
+        node.span.0.wrapping_add_signed(delta),
+        node.span.1.wrapping_add_signed(delta),
+    );
+    for child in &mut node.children {
+        shift_spans(child, delta);
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::{parse, reparse, Delim, Node, NodeKind, TextEdit};
+    use crate::ReadError;
+    use proptest::prelude::*;
+
+    fn kinds(node: &Node) -> Vec<NodeKind> {
+        node.children.iter().map(|child| child.kind).collect()
+    }
+
+    #[test]
+    fn trivia_is_kept() {
+        let src = "; hi\n(a, b) ; there\n";
+        let root = parse(src).unwrap();
+        assert_eq!(
+            kinds(&root),
+            vec![
+                NodeKind::Comment,
+                NodeKind::Newline,
+                NodeKind::Coll(Delim::List),
+                NodeKind::Whitespace,
+                NodeKind::Comment,
+                NodeKind::Newline,
+            ]
+        );
+        let list = &root.children[2];
+        assert_eq!(list.text(src), "(a, b)");
+        assert_eq!(
+            kinds(list),
+            vec![NodeKind::Atom, NodeKind::Whitespace, NodeKind::Atom]
+        );
+        assert_eq!(list.children[1].text(src), ", ");
+    }
+
+    #[test]
+    fn prefixed_forms() {
+        let src = "^:private #_ x 'y #inst \"2000\" #:a{:b 1}";
+        let root = parse(src).unwrap();
+        let texts = root.forms().map(|form| form.text(src)).collect::<Vec<_>>();
+        assert_eq!(texts, vec!["^:private #_ x 'y", "#inst \"2000\"", "#:a{:b 1}"]);
+        assert!(root.children[0].children[3].is_discard(src));
+    }
+
+    #[test]
+    fn strings_chars_and_regexes_are_atoms() {
+        let src = r#"["a ) \" b" \) #"[(]" ##Inf]"#;
+        let root = parse(src).unwrap();
+        let vect = &root.children[0];
+        let texts = vect.forms().map(|form| form.text(src)).collect::<Vec<_>>();
+        assert_eq!(texts, vec![r#""a ) \" b""#, r"\)", r#"#"[(]""#, "##Inf"]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("(a"), Err(ReadError::insufficient_input((0, 1))));
+        assert_eq!(parse("a)"), Err(ReadError::unclosed_collection((1, 1))));
+        assert_eq!(parse("(a]"), Err(ReadError::invalid_input((0, 2))));
+        assert_eq!(parse("[#_]"), Err(ReadError::invalid_input((1, 3))));
+        assert_eq!(parse("\"abc"), Err(ReadError::insufficient_input((0, 3))));
+    }
+
+    fn edit(src: &str, start: usize, end: usize, replacement: &str) -> Result<Node, ReadError> {
+        let edit = TextEdit {
+            range: start..end,
+            replacement: replacement.to_owned(),
+        };
+        let edited = edit.apply(src);
+        let reparsed = reparse(parse(src).unwrap(), &edit, &edited);
+        assert_eq!(reparsed, parse(&edited), "{src:?} edited to {edited:?}");
+        reparsed
+    }
+
+    #[test]
+    fn reparses() {
+        let src = "(ns a)\n\n(defn f [x]\n  x)\n\n(def y 1) ; y\n";
+        let reparsed = edit(src, 22, 23, "(inc x)").unwrap();
+        let edited = "(ns a)\n\n(defn f [x]\n  (inc x))\n\n(def y 1) ; y\n";
+        assert_eq!(reparsed.children[3].text(edited), "(defn f [x]\n  (inc x))");
+        assert_eq!(reparsed.children[6].span, (32, 40));
+        assert!(edit(src, 0, 0, "; hi\n").is_ok());
+        assert!(edit(src, src.len(), src.len(), "(def z 2)").is_ok());
+        // merging into and splitting from neighbouring forms
+        assert!(edit(src, 6, 8, "").is_ok());
+        assert!(edit(src, 24, 25, "").is_ok());
+        assert!(edit(src, 36, 37, ";").is_ok());
+        assert!(edit(src, 10, 10, "\"").is_err());
+        assert!(edit(src, 8, 9, "").is_err());
+        assert!(edit("(a) (b)", 3, 4, ")").is_err());
+        // forms ending in chars of more than one byte
+        assert!(edit("é ", 2, 2, "nil").is_ok());
+        assert!(edit("\\é é", 3, 4, "").is_ok());
+        assert!(edit("#é 1", 4, 4, "2").is_ok());
+    }
+
+    proptest! {
+        #[test]
+        fn reparse_is_parse(
+            forms in prop::collection::vec(prop::sample::select(FRAGMENTS), 0..12),
+            start in 0..1000usize,
+            len in 0..20usize,
+            replacement in prop::sample::select(FRAGMENTS),
+        ) {
+            let src: String = forms.concat();
+            prop_assume!(parse(&src).is_ok());
+            let start = start % (src.len() + 1);
+            let end = (start + len).min(src.len());
+            prop_assume!(src.is_char_boundary(start) && src.is_char_boundary(end));
+            // only whether it's what parse gives matters
+            let _ = edit(&src, start, end, replacement);
+        }
+    }
+
+    const FRAGMENTS: &[&str] = &[
+        " ", "\n", ",", "\r\n", "; c\n", "a", "b1", ":k", "\\a", "\"s\"", "\"é\"", "é", "\\é", "1", "'", "^:m ", "#_", "#inst ",
+        "(", ")", "[", "]", "{", "}", "#{", "#(", "(x y)", "[1 2]", "{:a 1}", "#\"r\"", "##Inf",
+    ];
+}
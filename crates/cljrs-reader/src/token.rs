@@ -0,0 +1,243 @@
+//! a flat stream of tokens for editors and highlighters: unlike [`crate::syntax::parse`], tokenizing
+//! never fails, so unbalanced or half-typed source still has tokens for everything in it
+
+use alloc::{vec, vec::Vec};
+use crate::{
+    syntax::{Lexeme, Lexer},
+    ByteIndexSpan,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// spaces, tabs and commas
+    Whitespace,
+    /// `\n`, `\r\n` or `\r`
+    Newline,
+    /// `; ...` up to (not including) the end of the line, also a `#!` shebang line
+    Comment,
+    /// `(`, `[`, `{`, `#{`, `#(`, `#?(`, `#?@(` and their closing `)`, `]` and `}`
+    Delimiter,
+    /// `"..."`
+    String,
+    /// `\a`, `\newline`, `\u00e9` etc.
+    Char,
+    /// `1`, `-2.5`, `1/2`, `0xff`, `##Inf` etc.
+    Number,
+    /// `:a`, `:a/b`, `::a`
+    Keyword,
+    /// everything else that is neither a delimiter nor whitespace, including `nil`, `true` and `false`
+    Symbol,
+    /// `#"..."`
+    Regex,
+    /// `'`, `` ` ``, `~`, `~@`, `@`, `^`, `#^`, `#_`, `#'`, `#=`, and tags such as `#inst` and `#:ns`
+    ReaderMacro,
+    /// an unterminated string or regex, a `\` or `#` with nothing valid after it, or bytes that
+    /// aren't UTF-8
+    Error,
+}
+
+impl TokenKind {
+    /// a lowercase name, e.g. for CSS classes
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Whitespace => "whitespace",
+            Self::Newline => "newline",
+            Self::Comment => "comment",
+            Self::Delimiter => "delimiter",
+            Self::String => "string",
+            Self::Char => "char",
+            Self::Number => "number",
+            Self::Keyword => "keyword",
+            Self::Symbol => "symbol",
+            Self::Regex => "regex",
+            Self::ReaderMacro => "reader-macro",
+            Self::Error => "error",
+        }
+    }
+    /// whitespace, newlines and comments
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Self::Whitespace | Self::Newline | Self::Comment)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: ByteIndexSpan,
+}
+
+impl Token {
+    pub fn text<'s>(&self, src: &'s str) -> &'s str {
+        let (begin, end) = self.span;
+        // spans are inclusive of the last char, which may be wider than a byte
+        let end = end + src[end..].chars().next().map(char::len_utf8).unwrap_or(0);
+        &src[begin..end]
+    }
+}
+
+/// an iterator over every token of the source, which together cover it without gaps
+pub struct Tokens<'s> {
+    src: &'s str,
+    lexer: Lexer<'s>,
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        let begin = self.lexer.byte_idx();
+        let (kind, span) = match self.lexer.next()? {
+            Ok((lexeme, span)) => (self.kind(lexeme, span), span),
+            // the lexer always moves beyond what it fails to lex, so that is the error token
+            Err(_) => {
+                let end = self.src[..self.lexer.byte_idx()].char_indices().last().map(|(idx, _)| idx);
+                (TokenKind::Error, (begin, end.unwrap_or(begin)))
+            }
+        };
+        Some(Token { kind, span })
+    }
+}
+
+impl Tokens<'_> {
+    fn kind(&self, lexeme: Lexeme, span: ByteIndexSpan) -> TokenKind {
+        match lexeme {
+            Lexeme::Whitespace => TokenKind::Whitespace,
+            Lexeme::Newline => TokenKind::Newline,
+            Lexeme::Comment => TokenKind::Comment,
+            Lexeme::Open(..) | Lexeme::Close(..) => TokenKind::Delimiter,
+            Lexeme::Prefix(..) => TokenKind::ReaderMacro,
+            Lexeme::Atom => {
+                let text = &self.src[span.0..];
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some('"'), _) => TokenKind::String,
+                    (Some('#'), Some('"')) => TokenKind::Regex,
+                    (Some('#'), Some('#')) => TokenKind::Number,
+                    (Some('\\'), _) => TokenKind::Char,
+                    (Some(':'), _) => TokenKind::Keyword,
+                    (Some(ch), _) if ch.is_ascii_digit() => TokenKind::Number,
+                    (Some('+' | '-'), Some(ch)) if ch.is_ascii_digit() => TokenKind::Number,
+                    _ => TokenKind::Symbol,
+                }
+            }
+        }
+    }
+}
+
+/// Clojure example:
+/// ```clojure
+/// ; tokenizes to Delimiter, Symbol, Whitespace, Keyword, Whitespace, Error
+/// (f :a "b
+/// ```
+pub fn tokens(src: &str) -> Tokens<'_> {
+    Tokens {
+        src,
+        lexer: Lexer::new(src),
+    }
+}
+
+/// as [`tokens`], where each run of bytes that isn't UTF-8 is an error token, and a token never
+/// spans such a run
+pub fn tokens_from_bytes(bytes: &[u8]) -> Vec<Token> {
+    let mut all = vec![];
+    let mut offset = 0;
+    for chunk in bytes.utf8_chunks() {
+        let valid = chunk.valid();
+        all.extend(tokens(valid).map(|token| Token {
+            kind: token.kind,
+            span: (token.span.0 + offset, token.span.1 + offset),
+        }));
+        offset += valid.len();
+        let invalid = chunk.invalid().len();
+        if invalid > 0 {
+            all.push(Token {
+                kind: TokenKind::Error,
+                span: (offset, offset + invalid - 1),
+            });
+            offset += invalid;
+        }
+    }
+    all
+}
+
+#[cfg(test)]
+mod t {
+    use super::{tokens, tokens_from_bytes, TokenKind};
+
+    fn kinds_and_texts(src: &str) -> Vec<(TokenKind, &str)> {
+        tokens(src).map(|token| (token.kind, token.text(src))).collect()
+    }
+
+    #[test]
+    fn kinds() {
+        use TokenKind::*;
+        let src = "#?(:clj ^:m 'x) ; é\n#{1 -2 ##NaN} \\a #\"[a]\" \"s\" nil #inst";
+        assert_eq!(
+            kinds_and_texts(src),
+            vec![
+                (Delimiter, "#?("),
+                (Keyword, ":clj"),
+                (Whitespace, " "),
+                (ReaderMacro, "^"),
+                (Keyword, ":m"),
+                (Whitespace, " "),
+                (ReaderMacro, "'"),
+                (Symbol, "x"),
+                (Delimiter, ")"),
+                (Whitespace, " "),
+                (Comment, "; é"),
+                (Newline, "\n"),
+                (Delimiter, "#{"),
+                (Number, "1"),
+                (Whitespace, " "),
+                (Number, "-2"),
+                (Whitespace, " "),
+                (Number, "##NaN"),
+                (Delimiter, "}"),
+                (Whitespace, " "),
+                (Char, "\\a"),
+                (Whitespace, " "),
+                (Regex, "#\"[a]\""),
+                (Whitespace, " "),
+                (String, "\"s\""),
+                (Whitespace, " "),
+                (Symbol, "nil"),
+                (Whitespace, " "),
+                (ReaderMacro, "#inst"),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_are_tokens() {
+        use TokenKind::*;
+        assert_eq!(kinds_and_texts(")) #?x #"), vec![
+            (Delimiter, ")"),
+            (Delimiter, ")"),
+            (Whitespace, " "),
+            (Error, "#?"),
+            (Symbol, "x"),
+            (Whitespace, " "),
+            (Error, "#"),
+        ]);
+        assert_eq!(kinds_and_texts("(a \"é"), vec![
+            (Delimiter, "("),
+            (Symbol, "a"),
+            (Whitespace, " "),
+            (Error, "\"é"),
+        ]);
+
+        let bytes = b"(a \xff\xfe b)";
+        let kinds: Vec<_> = tokens_from_bytes(bytes).into_iter().map(|token| (token.kind, token.span)).collect();
+        assert_eq!(kinds, vec![
+            (Delimiter, (0, 0)),
+            (Symbol, (1, 1)),
+            (Whitespace, (2, 2)),
+            (Error, (3, 3)),
+            (Error, (4, 4)),
+            (Whitespace, (5, 5)),
+            (Symbol, (6, 6)),
+            (Delimiter, (7, 7)),
+        ]);
+    }
+}
@@ -1,7 +1,10 @@
-use std::fmt;
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+use core::{fmt, hash::{Hash, Hasher}};
 use cljrs_core::symbol::Symbol;
 use archery::{ArcK, RcK, SharedPointer, SharedPointerKind};
+use cljrs_core::printer::PrintMode;
 use super::keyword::Keyword;
+use super::printer;
 
 pub type List<P> = Vec<ValuePtr<P>>;
 pub type Vect<P> = Vec<ValuePtr<P>>;
@@ -15,6 +18,7 @@ pub enum Value<P: SharedPointerKind> {
     Bool(bool),
     Num(f64),
     Str(String),
+    Char(char),
     Keyword(Keyword),
     Symbol(Symbol),
     List(List<P>),
@@ -48,6 +52,12 @@ impl<P: SharedPointerKind> Value<P> {
             _ => false,
         }
     }
+    pub fn is_char(&self) -> bool {
+        match self {
+            Self::Char(..) => true,
+            _ => false,
+        }
+    }
     pub fn is_keyword(&self) -> bool {
         match self {
             Self::Keyword(..) => true,
@@ -171,6 +181,7 @@ impl<P: SharedPointerKind> Value<P> {
             Self::Bool(arg0) => Self::Bool(arg0.clone()),
             Self::Num(arg0) => Self::Num(arg0.clone()),
             Self::Str(arg0) => Self::Str(arg0.clone()),
+            Self::Char(arg0) => Self::Char(*arg0),
             Self::Keyword(arg0) => Self::Keyword(arg0.clone()),
             Self::Symbol(arg0) => Self::Symbol(arg0.clone()),
             Self::List(arg0) => Self::List(arg0.clone()),
@@ -288,6 +299,7 @@ impl<P: SharedPointerKind> PartialEq for Value<P> {
             (Self::Bool(l0), Self::Bool(r0)) => l0 == r0,
             (Self::Num(l0), Self::Num(r0)) => l0 == r0,
             (Self::Str(l0), Self::Str(r0)) => l0 == r0,
+            (Self::Char(l0), Self::Char(r0)) => l0 == r0,
             (Self::Keyword(l0), Self::Keyword(r0)) => l0 == r0,
             (Self::Symbol(l0), Self::Symbol(r0)) => l0 == r0,
             (Self::List(l0), Self::List(r0)) => l0 == r0,
@@ -299,52 +311,85 @@ impl<P: SharedPointerKind> PartialEq for Value<P> {
     }
 }
 
-impl<P: SharedPointerKind> fmt::Display for Value<P> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Value::Nil => "nil".to_owned(),
-                Value::Bool(b) => b.to_string(),
-                Value::Num(n) => n.to_string(),
-                Value::Str(s) => format!("\"{s}\""),
-                Value::Keyword(k) => format!("{}", k),
-                Value::Symbol(s) => s.to_string(),
-                Value::List(vs) => {
-                    let contents = vs
-                        .iter()
-                        .map(|v| format!("{v}"))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    format!("({contents})")
-                }
-                Value::Vect(vs) => {
-                    let contents = vs
-                        .iter()
-                        .map(|v| format!("{v}"))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    format!("[{contents}]")
-                }
-                Value::Set(vs) => {
-                    let contents = vs
-                        .iter()
-                        .map(|v| format!("{v}"))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    format!("#{{{contents}}}")
-                }
-                Value::Map(kvs) => {
-                    let contents = kvs
-                        .iter()
-                        .map(|(k, v)| format!("{k} {v}"))
-                        .collect::<Vec<_>>()
-                        .join(", ");
-                    format!("{{{contents}}}")
+/// consistent with [`PartialEq`], so `0.0` and `-0.0` hash alike, and a set or map hashes by its
+/// elements in the order they were read
+impl<P: SharedPointerKind> Hash for Value<P> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Self::Nil => {}
+            Self::Bool(b) => b.hash(state),
+            Self::Num(n) => (if *n == 0.0 { 0.0 } else { *n }).to_bits().hash(state),
+            Self::Str(s) => s.hash(state),
+            Self::Char(ch) => ch.hash(state),
+            Self::Keyword(keyword) => keyword.hash(state),
+            Self::Symbol(symbol) => symbol.hash(state),
+            Self::List(values) | Self::Vect(values) | Self::Set(values) => {
+                values.len().hash(state);
+                values.iter().for_each(|value| value.as_ref().hash(state));
+            }
+            Self::Map(pairs) => {
+                pairs.len().hash(state);
+                for (key, value) in pairs {
+                    key.as_ref().hash(state);
+                    value.as_ref().hash(state);
                 }
             }
-        )
+        }
+    }
+}
+
+/// the elements of a set or keys of a map being built, indexed by hash so that finding a duplicate
+/// compares against only those with an equal hash
+#[derive(Default)]
+pub struct KeyIndex {
+    idxs_by_hash: BTreeMap<u64, Vec<usize>>,
+}
+
+impl KeyIndex {
+    /// the index of an element equal to `value`, by `is_eq`, or none after recording `value` as
+    /// the element at `next_idx`
+    pub fn find_or_insert<P: SharedPointerKind>(
+        &mut self,
+        value: &Value<P>,
+        next_idx: usize,
+        is_eq: impl Fn(usize) -> bool,
+    ) -> Option<usize> {
+        let mut hasher = FnvHasher::default();
+        value.hash(&mut hasher);
+        let idxs = self.idxs_by_hash.entry(hasher.finish()).or_default();
+        let found = idxs.iter().copied().find(|&idx| is_eq(idx));
+        if found.is_none() {
+            idxs.push(next_idx);
+        }
+        found
+    }
+}
+
+/// FNV-1a, as without `std` there's no randomly keyed hasher to hand, and a value is hashed only
+/// to look it up among its own collection's
+struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl<P: SharedPointerKind> fmt::Display for Value<P> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        printer::write_value(f, self, PrintMode::Pr)
     }
 }
 
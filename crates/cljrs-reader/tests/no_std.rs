@@ -0,0 +1,18 @@
+//! `cljrs-core` and `cljrs-reader` without their default features, as a `#![no_std]` crate
+//! depends on them
+
+use std::{path::Path, process::Command};
+
+#[test]
+fn builds_without_std() {
+    let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    // built by itself, and apart from the other crates (which enable `std`), so nothing else
+    // unifies the features it depends on
+    let output = Command::new(env!("CARGO"))
+        .args(["build", "--package", "cljrs-nostd", "--target-dir"])
+        .arg(workspace.join("target/no-std"))
+        .current_dir(&workspace)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
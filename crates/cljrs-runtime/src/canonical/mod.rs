@@ -0,0 +1,254 @@
+//! a canonical EDN form for values, and content hashes of it
+//!
+//! Maps and sets iterate in an order that depends on hashing and insertion, so [`crate::printer`]
+//! may print equal values differently. The canonical form sorts map entries by key and set
+//! elements by [`compare`], and separates everything by a single space, so that equal values
+//! always have the same canonical form and [`content_hash`].
+
+mod sha256;
+
+use std::{cmp::Ordering, fmt, sync::Arc};
+use archery::SharedPointerKind;
+use cljrs_core::printer::PrintMode;
+use crate::{printer, value::Value};
+use self::sha256::Sha256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanonicalError {
+    /// fns have no readable form, let alone a canonical one
+    Unreadable,
+    /// the writer failed
+    Fmt,
+}
+
+impl From<fmt::Error> for CanonicalError {
+    fn from(_: fmt::Error) -> Self {
+        Self::Fmt
+    }
+}
+
+impl fmt::Display for CanonicalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreadable => write!(f, "fns have no canonical form"),
+            Self::Fmt => write!(f, "an error occurred writing a canonical form"),
+        }
+    }
+}
+
+impl std::error::Error for CanonicalError {}
+
+/// a total order over values
+///
+/// Values of different types order by type: nil, bools, numbers, chars, strings, keywords,
+/// symbols, lists, vectors, sets, maps then fns. Keywords and symbols order unqualified before
+/// qualified, then by namespace and name. Lists and vectors order lexicographically, as do sets
+/// by their sorted elements and maps by their entries sorted by key. Fns order by address, so
+/// only consistently within a process.
+pub fn compare<P: SharedPointerKind>(a: &Value<P>, b: &Value<P>) -> Ordering {
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Num(a), Value::Num(b)) => a.cmp(b),
+        (Value::Char(a), Value::Char(b)) => a.cmp(b),
+        (Value::Str(a), Value::Str(b)) => a.cmp(b),
+        (Value::Keyword(a), Value::Keyword(b)) => a.cmp(b),
+        (Value::Symbol(a), Value::Symbol(b)) => a.cmp(b),
+        (Value::List(a), Value::List(b)) => compare_seqs(a.into_iter(), b.into_iter()),
+        (Value::Vect(a), Value::Vect(b)) => compare_seqs(a.into_iter(), b.into_iter()),
+        (Value::Set(a), Value::Set(b)) => compare_seqs(sorted(a).into_iter(), sorted(b).into_iter()),
+        (Value::Map(a), Value::Map(b)) => {
+            let (a, b) = (sorted_entries(a), sorted_entries(b));
+            a.iter()
+                .zip(&b)
+                .map(|((ak, av), (bk, bv))| compare(ak, bk).then_with(|| compare(av, bv)))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len()))
+        }
+        (Value::IFn(a), Value::IFn(b)) => (Arc::as_ptr(a) as *const ()).cmp(&(Arc::as_ptr(b) as *const ())),
+        (a, b) => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn rank<P: SharedPointerKind>(value: &Value<P>) -> u8 {
+    match value {
+        Value::Nil => 0,
+        Value::Bool(..) => 1,
+        Value::Num(..) => 2,
+        Value::Char(..) => 3,
+        Value::Str(..) => 4,
+        Value::Keyword(..) => 5,
+        Value::Symbol(..) => 6,
+        Value::List(..) => 7,
+        Value::Vect(..) => 8,
+        Value::Set(..) => 9,
+        Value::Map(..) => 10,
+        Value::IFn(..) => 11,
+    }
+}
+
+fn compare_seqs<'v, P, I>(mut a: I, mut b: I) -> Ordering
+where
+    P: SharedPointerKind + 'v,
+    I: Iterator<Item = &'v Value<P>>,
+{
+    loop {
+        match (a.next(), b.next()) {
+            (Some(a), Some(b)) => match compare(a, b) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            },
+            (a, b) => return a.is_some().cmp(&b.is_some()),
+        }
+    }
+}
+
+pub(crate) fn sorted<'v, P: SharedPointerKind>(values: impl IntoIterator<Item = &'v Value<P>>) -> Vec<&'v Value<P>> {
+    let mut values: Vec<_> = values.into_iter().collect();
+    values.sort_by(|a, b| compare(a, b));
+    values
+}
+
+pub(crate) type Entry<'v, P> = (&'v Value<P>, &'v Value<P>);
+
+pub(crate) fn sorted_entries<'v, P: SharedPointerKind>(entries: impl IntoIterator<Item = Entry<'v, P>>) -> Vec<Entry<'v, P>> {
+    let mut entries: Vec<_> = entries.into_iter().collect();
+    entries.sort_by(|(a, _), (b, _)| compare(a, b));
+    entries
+}
+
+/// Clojure example:
+/// ```clojure
+/// (canonical-str {:b #{2 1}, :a "x"}) ;=> "{:a \"x\" :b #{1 2}}"
+/// ```
+pub fn canonical_str<P: SharedPointerKind>(value: &Value<P>) -> Result<String, CanonicalError> {
+    let mut buf = String::new();
+    write_canonical(&mut buf, value)?;
+    Ok(buf)
+}
+
+pub fn write_canonical<W: fmt::Write, P: SharedPointerKind>(w: &mut W, value: &Value<P>) -> Result<(), CanonicalError> {
+    let write_all = |w: &mut W, open: &str, values: &[&Value<P>], close: &str| {
+        w.write_str(open)?;
+        for (idx, v) in values.iter().enumerate() {
+            if idx > 0 {
+                w.write_char(' ')?;
+            }
+            write_canonical(w, v)?;
+        }
+        w.write_str(close)?;
+        Ok(())
+    };
+    match value {
+        Value::IFn(..) => Err(CanonicalError::Unreadable),
+        Value::List(list) => write_all(w, "(", &list.into_iter().collect::<Vec<_>>(), ")"),
+        Value::Vect(vect) => write_all(w, "[", &vect.into_iter().collect::<Vec<_>>(), "]"),
+        Value::Set(set) => write_all(w, "#{", &sorted(set), "}"),
+        Value::Map(map) => {
+            let entries = sorted_entries(map);
+            let flattened: Vec<_> = entries.into_iter().flat_map(|(k, v)| [k, v]).collect();
+            write_all(w, "{", &flattened, "}")
+        }
+        scalar => Ok(printer::write_value(w, scalar, PrintMode::Pr)?),
+    }
+}
+
+/// the SHA-256 digest of the value's canonical form, as UTF-8
+pub fn content_hash<P: SharedPointerKind>(value: &Value<P>) -> Result<[u8; 32], CanonicalError> {
+    struct Hasher(Sha256);
+    impl fmt::Write for Hasher {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0.update(s.as_bytes());
+            Ok(())
+        }
+    }
+    let mut hasher = Hasher(Sha256::new());
+    write_canonical(&mut hasher, value)?;
+    Ok(hasher.0.finish())
+}
+
+/// [`content_hash`] as lowercase hex
+pub fn content_hash_hex<P: SharedPointerKind>(value: &Value<P>) -> Result<String, CanonicalError> {
+    let digest = content_hash(value)?;
+    Ok(digest.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{canonical_str, compare, content_hash_hex, sha256::Sha256, CanonicalError};
+    use crate::{
+        fns::get_fn::GetFn,
+        value::{RcValue, Value},
+    };
+    use std::{cmp::Ordering, sync::Arc};
+
+    fn kw(name: &str) -> RcValue {
+        Value::unqualified_keyword(String::from(name))
+    }
+
+    fn hex(digest: [u8; 32]) -> String {
+        digest.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn sha256_vectors() {
+        let digest = |bytes: &[u8]| {
+            let mut sha = Sha256::new();
+            sha.update(bytes);
+            hex(sha.finish())
+        };
+        assert_eq!(digest(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(digest(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(
+            digest(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        );
+        let mut sha = Sha256::new();
+        for _ in 0..1000 {
+            sha.update(&[b'a'; 1000]);
+        }
+        assert_eq!(hex(sha.finish()), "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+    }
+
+    #[test]
+    fn equal_values_are_printed_and_hashed_alike() {
+        let keys = (0..40).map(|n| kw(&format!("k{n}")));
+        let forwards = RcValue::map_from_value_pairs(keys.clone().zip((0..40).map(Value::Num)));
+        let backwards = RcValue::map_from_value_pairs(keys.zip((0..40).map(Value::Num)).rev());
+        assert_eq!(canonical_str(&forwards), canonical_str(&backwards));
+        assert_eq!(content_hash_hex(&forwards), content_hash_hex(&backwards));
+
+        let v = RcValue::map_from_value_pairs(vec![
+            (kw("b"), Value::set_from_values(vec![Value::Num(2), Value::Num(-1), Value::Nil])),
+            (kw("a"), Value::Str(String::from("x y"))),
+            (Value::Num(1), Value::list_from_values(vec![Value::Char(' ')])),
+            (Value::qualified_keyword(String::from("a"), String::from("a")), Value::empty_vect()),
+        ]);
+        assert_eq!(canonical_str(&v).unwrap(), r#"{1 (\space) :a "x y" :b #{nil -1 2} :a/a []}"#);
+        assert_ne!(content_hash_hex(&v), content_hash_hex(&forwards));
+    }
+
+    #[test]
+    fn total_order() {
+        let mut values = vec![
+            Value::map_from_value_pairs(vec![(kw("a"), Value::Num(1))]),
+            Value::vect_from_values(vec![Value::Num(1), Value::Num(2)]),
+            Value::vect_from_values(vec![Value::Num(1)]),
+            Value::unqualified_symbol(String::from("s")),
+            kw("k"),
+            Value::Str(String::from("s")),
+            Value::Num(3),
+            Value::Bool(true),
+            Value::Nil,
+        ];
+        values.sort_by(compare);
+        let printed: Vec<String> = values.iter().map(|v| canonical_str(v).unwrap()).collect();
+        assert_eq!(printed, ["nil", "true", "3", r#""s""#, ":k", "s", "[1]", "[1 2]", "{:a 1}"]);
+        assert_eq!(compare(&values[6], &values[6]), Ordering::Equal);
+    }
+
+    #[test]
+    fn fns_have_no_canonical_form() {
+        let v = RcValue::vect_from_values(vec![RcValue::IFn(Arc::new(GetFn))]);
+        assert_eq!(canonical_str(&v), Err(CanonicalError::Unreadable));
+    }
+}
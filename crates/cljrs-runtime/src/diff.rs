@@ -0,0 +1,554 @@
+//! structural diffs of values, and edit scripts that patch one value into another
+//!
+//! [`diff`] is `clojure.data/diff`. [`EditScript`]s are like those of the editscript library:
+//! `[path op value?]` edits, where `op` is `:+` (add), `:-` (remove) or `:r` (replace), and a
+//! path is a vector of map keys, vector and list indices, and set elements.
+
+use std::fmt;
+use archery::SharedPointerKind;
+use crate::{
+    canonical::{sorted, sorted_entries},
+    keyword::Keyword,
+    printer::pr_str,
+    read::{from_reader_value, FromReaderError},
+    value::{map::Map, Value},
+};
+
+/// what [`diff`] gives
+#[derive(Debug)]
+pub struct Diff<P: SharedPointerKind> {
+    pub only_a: Value<P>,
+    pub only_b: Value<P>,
+    pub both: Value<P>,
+}
+
+impl<P: SharedPointerKind> Diff<P> {
+    /// `[only-a only-b both]`, as `clojure.data/diff` returns
+    pub fn to_value(&self) -> Value<P> {
+        Value::vect_from_values([self.only_a.clone(), self.only_b.clone(), self.both.clone()])
+    }
+}
+
+enum Partition {
+    Atom,
+    Sequential,
+    Set,
+    Map,
+}
+
+fn partition<P: SharedPointerKind>(value: &Value<P>) -> Partition {
+    match value {
+        Value::List(..) | Value::Vect(..) => Partition::Sequential,
+        Value::Set(..) => Partition::Set,
+        Value::Map(..) => Partition::Map,
+        _ => Partition::Atom,
+    }
+}
+
+/// Clojure example:
+/// ```clojure
+/// (clojure.data/diff {:a 1 :b [1 2]} {:a 1 :b [1 3] :c 4})
+/// ;=> [{:b [nil 2]} {:b [nil 3] :c 4} {:a 1 :b [1]}]
+/// ```
+pub fn diff<P: SharedPointerKind>(a: &Value<P>, b: &Value<P>) -> Diff<P> {
+    if a == b {
+        return Diff {
+            only_a: Value::Nil,
+            only_b: Value::Nil,
+            both: a.clone(),
+        };
+    }
+    match (partition(a), partition(b), a, b) {
+        (Partition::Map, Partition::Map, Value::Map(a), Value::Map(b)) => {
+            let keys = a.into_iter().map(|(k, _)| k).chain(b.into_iter().map(|(k, _)| k).filter(|k| !a.raw().contains_key(k)));
+            let [only_a, only_b, both] = diff_associative(keys, |k| a.raw().get(k), |k| b.raw().get(k));
+            let map = |entries: Vec<(Value<P>, Value<P>)>| match entries.is_empty() {
+                true => Value::Nil,
+                false => Value::map_from_value_pairs(entries),
+            };
+            Diff {
+                only_a: map(only_a),
+                only_b: map(only_b),
+                both: map(both),
+            }
+        }
+        (Partition::Sequential, Partition::Sequential, ..) => {
+            let (a, b) = (elements(a), elements(b));
+            let indices: Vec<Value<P>> = (0..a.len().max(b.len())).map(|idx| Value::Num(idx as isize)).collect();
+            let [only_a, only_b, both] = diff_associative(indices.iter(), |idx| nth(&a, idx), |idx| nth(&b, idx));
+            Diff {
+                only_a: vectorize(only_a),
+                only_b: vectorize(only_b),
+                both: vectorize(both),
+            }
+        }
+        (Partition::Set, Partition::Set, Value::Set(a), Value::Set(b)) => {
+            let set = |values: Vec<Value<P>>| match values.is_empty() {
+                true => Value::Nil,
+                false => Value::set_from_values(values),
+            };
+            Diff {
+                only_a: set(a.into_iter().filter(|v| !b.raw().contains(v)).map(Value::clone).collect()),
+                only_b: set(b.into_iter().filter(|v| !a.raw().contains(v)).map(Value::clone).collect()),
+                both: set(a.into_iter().filter(|v| b.raw().contains(v)).map(Value::clone).collect()),
+            }
+        }
+        _ => Diff {
+            only_a: a.clone(),
+            only_b: b.clone(),
+            both: Value::Nil,
+        },
+    }
+}
+
+fn elements<P: SharedPointerKind>(value: &Value<P>) -> Vec<&Value<P>> {
+    match value {
+        Value::List(list) => list.into_iter().collect(),
+        Value::Vect(vect) => vect.into_iter().collect(),
+        _ => vec![],
+    }
+}
+
+fn nth<'v, P: SharedPointerKind>(values: &[&'v Value<P>], idx: &Value<P>) -> Option<&'v Value<P>> {
+    match idx {
+        Value::Num(idx) => values.get(*idx as usize).copied(),
+        _ => None,
+    }
+}
+
+type Entries<P> = Vec<(Value<P>, Value<P>)>;
+
+/// as `clojure.data/diff-associative`, the only-a, only-b and both entries
+fn diff_associative<'v, P, K, A, B>(keys: K, get_a: A, get_b: B) -> [Entries<P>; 3]
+where
+    P: SharedPointerKind + 'v,
+    K: Iterator<Item = &'v Value<P>>,
+    A: Fn(&Value<P>) -> Option<&'v Value<P>>,
+    B: Fn(&Value<P>) -> Option<&'v Value<P>>,
+{
+    let [mut only_a, mut only_b, mut both] = [vec![], vec![], vec![]];
+    for key in keys {
+        let (a, b) = (get_a(key), get_b(key));
+        let Diff {
+            only_a: a_only,
+            only_b: b_only,
+            both: in_both,
+        } = diff(a.unwrap_or(&Value::Nil), b.unwrap_or(&Value::Nil));
+        let same = a.is_some() && b.is_some() && (!in_both.is_nil() || (a.is_some_and(Value::is_nil) && b.is_some_and(Value::is_nil)));
+        if a.is_some() && (!a_only.is_nil() || !same) {
+            only_a.push((key.clone(), a_only));
+        }
+        if b.is_some() && (!b_only.is_nil() || !same) {
+            only_b.push((key.clone(), b_only));
+        }
+        if same {
+            both.push((key.clone(), in_both));
+        }
+    }
+    [only_a, only_b, both]
+}
+
+/// index entries as a vector, with nil at the missing indices
+fn vectorize<P: SharedPointerKind>(entries: Entries<P>) -> Value<P> {
+    let len = entries.iter().filter_map(|(idx, _)| match idx {
+        Value::Num(idx) => Some(*idx as usize + 1),
+        _ => None,
+    });
+    let Some(len) = len.max() else {
+        return Value::Nil;
+    };
+    let mut values: Vec<Value<P>> = (0..len).map(|_| Value::Nil).collect();
+    for (idx, value) in entries {
+        if let Value::Num(idx) = idx {
+            values[idx as usize] = value;
+        }
+    }
+    Value::vect_from_values(values)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// `:+`, which inserts into vectors and lists rather than replacing
+    Add,
+    /// `:-`
+    Remove,
+    /// `:r`
+    Replace,
+}
+
+#[derive(Debug)]
+pub struct Edit<P: SharedPointerKind> {
+    pub path: Vec<Value<P>>,
+    pub op: Op,
+    /// nil for [`Op::Remove`]
+    pub value: Value<P>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchError {
+    /// the edit script isn't EDN, or isn't made of runtime values
+    Read(FromReaderError),
+    /// the value isn't an edit script, e.g. `"[[:a] :+]"`
+    Invalid(String),
+    /// an edit whose path doesn't exist in the value being patched, e.g. `"[[:a 3] :-]"`
+    Path(String),
+}
+
+impl From<FromReaderError> for PatchError {
+    fn from(err: FromReaderError) -> Self {
+        Self::Read(err)
+    }
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(err) => write!(f, "invalid edit script: {err}"),
+            Self::Invalid(edit) => write!(f, "invalid edit: {edit}"),
+            Self::Path(edit) => write!(f, "no such path for edit {edit}"),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// sequences longer than this align by index rather than by their longest common subsequence,
+/// which takes time and space proportional to the product of their lengths
+const MAX_ALIGNED_LEN: usize = 1000;
+
+/// edits that patch one value into another, which edit map entries and set elements in the
+/// order of [`crate::canonical::compare`] so that equal values give equal scripts
+#[derive(Debug)]
+pub struct EditScript<P: SharedPointerKind> {
+    pub edits: Vec<Edit<P>>,
+}
+
+impl<P: SharedPointerKind> EditScript<P> {
+    /// the fewest edits to `a` that give `b`, where a changed element of a collection is
+    /// edited within when it's of the same type, and replaced otherwise
+    ///
+    /// Clojure example:
+    /// ```clojure
+    /// (editscript/diff {:a 1 :b [1 2]} {:b [0 1 2]}) ;=> [[[:a] :-] [[:b 0] :+ 0]]
+    /// ```
+    pub fn diff(a: &Value<P>, b: &Value<P>) -> Self {
+        let mut edits = vec![];
+        edit(&mut vec![], a, b, &mut edits);
+        Self { edits }
+    }
+
+    /// `[[path op value?]...]`
+    pub fn to_value(&self) -> Value<P> {
+        Value::vect_from_values(self.edits.iter().map(Edit::to_value))
+    }
+
+    pub fn from_value(value: &Value<P>) -> Result<Self, PatchError> {
+        let invalid = |v: &Value<P>| PatchError::Invalid(pr_str(v));
+        let Value::Vect(edits) = value else {
+            return Err(invalid(value));
+        };
+        let edits = edits.into_iter().map(|edit| {
+            let parts = match edit {
+                Value::Vect(parts) => parts.into_iter().collect::<Vec<_>>(),
+                _ => return Err(invalid(edit)),
+            };
+            let (path, op, value) = match parts[..] {
+                [Value::Vect(path), op, value] => (path, op, Some(value)),
+                [Value::Vect(path), op] => (path, op, None),
+                _ => return Err(invalid(edit)),
+            };
+            let op_name = match op {
+                Value::Keyword(k) => match k.keyword() {
+                    Keyword::Unqualified { name } => Some(name.as_str()),
+                    _ => None,
+                },
+                _ => None,
+            };
+            let op = match (op_name, value) {
+                (Some("+"), Some(_)) => Op::Add,
+                (Some("-"), None) => Op::Remove,
+                (Some("r"), Some(_)) => Op::Replace,
+                _ => return Err(invalid(edit)),
+            };
+            Ok(Edit {
+                path: path.into_iter().map(Value::clone).collect(),
+                op,
+                value: value.map_or(Value::Nil, Value::clone),
+            })
+        });
+        Ok(Self { edits: edits.collect::<Result<_, _>>()? })
+    }
+
+    /// the EDN of [`Self::to_value`]
+    pub fn to_edn(&self) -> String {
+        pr_str(&self.to_value())
+    }
+
+    pub fn from_edn(src: &str) -> Result<Self, PatchError>
+    where
+        P: 'static,
+    {
+        let Some(mut rdr) = cljrs_reader::reader::<P>(src) else {
+            return Err(PatchError::Invalid(src.to_owned()));
+        };
+        match rdr.try_read_one().map_err(FromReaderError::Read)? {
+            Some(form) => Self::from_value(&from_reader_value(&form.data)?),
+            None => Err(PatchError::Invalid(src.to_owned())),
+        }
+    }
+
+    /// applies each edit in turn
+    pub fn patch(&self, value: &Value<P>) -> Result<Value<P>, PatchError> {
+        let mut value = value.clone();
+        for edit in &self.edits {
+            value = apply(&value, &edit.path, edit).ok_or_else(|| PatchError::Path(pr_str(&edit.to_value())))?;
+        }
+        Ok(value)
+    }
+}
+
+impl<P: SharedPointerKind> Edit<P> {
+    /// `[path op value?]`
+    pub fn to_value(&self) -> Value<P> {
+        let path = Value::vect_from_values(self.path.iter().map(Value::clone));
+        let op = Value::unqualified_keyword(String::from(match self.op {
+            Op::Add => "+",
+            Op::Remove => "-",
+            Op::Replace => "r",
+        }));
+        match self.op {
+            Op::Remove => Value::vect_from_values([path, op]),
+            Op::Add | Op::Replace => Value::vect_from_values([path, op, self.value.clone()]),
+        }
+    }
+}
+
+fn push<P: SharedPointerKind>(edits: &mut Vec<Edit<P>>, path: &[Value<P>], op: Op, value: Value<P>) {
+    let path = path.iter().map(Value::clone).collect();
+    edits.push(Edit { path, op, value });
+}
+
+fn edit<P: SharedPointerKind>(path: &mut Vec<Value<P>>, a: &Value<P>, b: &Value<P>, edits: &mut Vec<Edit<P>>) {
+    if a == b {
+        return;
+    }
+    match (a, b) {
+        (Value::Map(a), Value::Map(b)) => {
+            for (k, va) in sorted_entries(a) {
+                path.push(k.clone());
+                match b.raw().get(k) {
+                    Some(vb) => edit(path, va, vb, edits),
+                    None => push(edits, path, Op::Remove, Value::Nil),
+                }
+                path.pop();
+            }
+            for (k, vb) in sorted_entries(b.into_iter().filter(|(k, _)| !a.raw().contains_key(k))) {
+                path.push(k.clone());
+                push(edits, path, Op::Add, vb.clone());
+                path.pop();
+            }
+        }
+        (Value::Set(a), Value::Set(b)) => {
+            for v in sorted(a.into_iter().filter(|v| !b.raw().contains(v))) {
+                path.push(v.clone());
+                push(edits, path, Op::Remove, Value::Nil);
+                path.pop();
+            }
+            for v in sorted(b.into_iter().filter(|v| !a.raw().contains(v))) {
+                path.push(v.clone());
+                push(edits, path, Op::Add, v.clone());
+                path.pop();
+            }
+        }
+        (Value::List(..), Value::List(..)) | (Value::Vect(..), Value::Vect(..)) => {
+            edit_sequence(path, &elements(a), &elements(b), edits)
+        }
+        _ => push(edits, path, Op::Replace, b.clone()),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Align {
+    Keep,
+    Remove,
+    Add,
+    Change,
+}
+
+/// aligns the sequences by Levenshtein distance, where a change is an edit within the element
+fn edit_sequence<P: SharedPointerKind>(path: &mut Vec<Value<P>>, a: &[&Value<P>], b: &[&Value<P>], edits: &mut Vec<Edit<P>>) {
+    let alignment = match a.len() <= MAX_ALIGNED_LEN && b.len() <= MAX_ALIGNED_LEN {
+        true => align(a, b),
+        false => {
+            let common = a.len().min(b.len());
+            let mut alignment: Vec<Align> = a.iter().zip(b).map(|(a, b)| if a == b { Align::Keep } else { Align::Change }).collect();
+            alignment.extend(std::iter::repeat_n(Align::Remove, a.len() - common));
+            alignment.extend(std::iter::repeat_n(Align::Add, b.len() - common));
+            alignment
+        }
+    };
+    // the index in the sequence as patched so far
+    let (mut idx, mut a_idx, mut b_idx) = (0, 0, 0);
+    for step in alignment {
+        path.push(Value::Num(idx as isize));
+        match step {
+            Align::Keep => {}
+            Align::Remove => push(edits, path, Op::Remove, Value::Nil),
+            Align::Add => push(edits, path, Op::Add, b[b_idx].clone()),
+            Align::Change => edit(path, a[a_idx], b[b_idx], edits),
+        }
+        path.pop();
+        let (next_idx, next_a, next_b) = match step {
+            Align::Keep | Align::Change => (idx + 1, a_idx + 1, b_idx + 1),
+            Align::Remove => (idx, a_idx + 1, b_idx),
+            Align::Add => (idx + 1, a_idx, b_idx + 1),
+        };
+        (idx, a_idx, b_idx) = (next_idx, next_a, next_b);
+    }
+}
+
+fn align<P: SharedPointerKind>(a: &[&Value<P>], b: &[&Value<P>]) -> Vec<Align> {
+    // costs[i][j] is the distance between a[i..] and b[j..]
+    let mut costs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..=a.len()).rev() {
+        for j in (0..=b.len()).rev() {
+            costs[i][j] = match (i < a.len(), j < b.len()) {
+                (false, false) => 0,
+                (true, false) => costs[i + 1][j] + 1,
+                (false, true) => costs[i][j + 1] + 1,
+                (true, true) if a[i] == b[j] => costs[i + 1][j + 1],
+                (true, true) => 1 + costs[i + 1][j + 1].min(costs[i + 1][j]).min(costs[i][j + 1]),
+            };
+        }
+    }
+    let (mut i, mut j, mut alignment) = (0, 0, vec![]);
+    while i < a.len() || j < b.len() {
+        let step = match (i < a.len(), j < b.len()) {
+            (true, true) if a[i] == b[j] => Align::Keep,
+            (true, true) if costs[i][j] == 1 + costs[i + 1][j + 1] => Align::Change,
+            (true, _) if costs[i][j] == 1 + costs[i + 1][j] => Align::Remove,
+            _ => Align::Add,
+        };
+        match step {
+            Align::Keep | Align::Change => (i, j) = (i + 1, j + 1),
+            Align::Remove => i += 1,
+            Align::Add => j += 1,
+        }
+        alignment.push(step);
+    }
+    alignment
+}
+
+/// the value with the edit applied at `path` within it, or `None` if there's no such path
+fn apply<P: SharedPointerKind>(value: &Value<P>, path: &[Value<P>], edit: &Edit<P>) -> Option<Value<P>> {
+    let Some((key, rest)) = path.split_first() else {
+        return match edit.op {
+            Op::Add | Op::Replace => Some(edit.value.clone()),
+            Op::Remove => None,
+        };
+    };
+    let index = |len: usize, allow_end: bool| match key {
+        Value::Num(idx) if *idx >= 0 && ((*idx as usize) < len || (allow_end && *idx as usize == len)) => {
+            Some(*idx as usize)
+        }
+        _ => None,
+    };
+    match value {
+        Value::Map(map) => {
+            let raw = map.raw();
+            let patched = match (rest.is_empty(), edit.op) {
+                (true, Op::Remove) => raw.contains_key(key).then(|| raw.remove(key))?,
+                (true, Op::Add | Op::Replace) => raw.insert(key.clone(), edit.value.clone()),
+                (false, _) => raw.insert(key.clone(), apply(raw.get(key)?, rest, edit)?),
+            };
+            Some(Value::from_map(Map::from_raw(patched)))
+        }
+        Value::Set(set) if rest.is_empty() => {
+            let raw = set.raw();
+            match edit.op {
+                Op::Remove => raw.contains(key).then(|| Value::from_raw_set(raw.remove(key))),
+                Op::Add | Op::Replace => Some(Value::from_raw_set(raw.insert(edit.value.clone()))),
+            }
+        }
+        Value::List(..) | Value::Vect(..) => {
+            let mut values: Vec<Value<P>> = elements(value).into_iter().map(Value::clone).collect();
+            match (rest.is_empty(), edit.op) {
+                (true, Op::Add) => values.insert(index(values.len(), true)?, edit.value.clone()),
+                (true, Op::Remove) => {
+                    values.remove(index(values.len(), false)?);
+                }
+                (true, Op::Replace) => {
+                    let idx = index(values.len(), false)?;
+                    values[idx] = edit.value.clone();
+                }
+                (false, _) => {
+                    let idx = index(values.len(), false)?;
+                    values[idx] = apply(&values[idx], rest, edit)?;
+                }
+            }
+            Some(match value {
+                Value::List(..) => Value::list_from_values(values),
+                _ => Value::vect_from_values(values),
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, EditScript, PatchError};
+    use crate::{printer::pr_str, read::read_str, value::RcValue};
+
+    fn edn(src: &str) -> RcValue {
+        read_str(src).unwrap()
+    }
+
+    fn diff_str(a: &str, b: &str) -> String {
+        pr_str(&diff(&edn(a), &edn(b)).to_value())
+    }
+
+    #[test]
+    fn clojure_data_diff() {
+        assert_eq!(diff_str("1", "1"), "[nil nil 1]");
+        assert_eq!(diff_str("1", "[1]"), "[1 [1] nil]");
+        assert_eq!(diff_str("[1 2 3]", "[1 4 3 5]"), "[[nil 2] [nil 4 nil 5] [1 nil 3]]");
+        assert_eq!(diff_str("(1 2)", "[1 2 3]"), "[nil [nil nil 3] [1 2]]");
+        assert_eq!(diff_str("{:a nil}", "{:a nil :b 1}"), "[nil {:b 1} {:a nil}]");
+        assert_eq!(diff_str("{:a {:b 1 :c 2}}", "{:a {:b 1 :c 3}}"), "[{:a {:c 2}} {:a {:c 3}} {:a {:b 1}}]");
+        assert_eq!(diff_str("#{1 2}", "#{2 3}"), "[#{1} #{3} #{2}]");
+    }
+
+    fn round_trip(a: &str, b: &str) -> String {
+        let (a, b) = (edn(a), edn(b));
+        let script = EditScript::diff(&a, &b);
+        let edn = script.to_edn();
+        let read = EditScript::<archery::RcK>::from_edn(&edn).unwrap();
+        assert_eq!(read.patch(&a).unwrap(), b, "{edn}");
+        edn
+    }
+
+    #[test]
+    fn edit_scripts() {
+        assert_eq!(round_trip("{:a 1}", "{:a 1}"), "[]");
+        assert_eq!(round_trip("1", "[1]"), "[[[] :r [1]]]");
+        assert_eq!(round_trip(r#"{:a 1 :b [1 2]}"#, r#"{:b [0 1 2]}"#), "[[[:a] :-] [[:b 0] :+ 0]]");
+        assert_eq!(round_trip("[1 2 3 4]", "[1 3 4 5]"), "[[[1] :-] [[3] :+ 5]]");
+        assert_eq!(round_trip("[{:a 1} 2]", "[{:a 2} 2]"), "[[[0 :a] :r 2]]");
+        assert_eq!(round_trip("#{1 2}", "#{2 3}"), "[[[1] :-] [[3] :+ 3]]");
+        round_trip("{:a {:b (1 2 3)} :c #{[1]}}", "{:a {:b (3 2)} :c #{[2]} :d nil}");
+        round_trip("[1 2 3]", "[]");
+        round_trip("[]", "[[1] 2]");
+    }
+
+    #[test]
+    fn patch_errors() {
+        let a = edn("{:a [1]}");
+        let missing = EditScript::from_edn("[[[:a 3] :-]]").unwrap();
+        assert_eq!(missing.patch(&a), Err(PatchError::Path("[[:a 3] :-]".to_owned())));
+        assert!(matches!(EditScript::<archery::RcK>::from_edn("[[[:a] :+]]"), Err(PatchError::Invalid(..))));
+        assert!(matches!(EditScript::<archery::RcK>::from_edn("[[[:a] :x/+ 1]]"), Err(PatchError::Invalid(..))));
+        assert!(matches!(EditScript::<archery::RcK>::from_edn("[[[:a] :+ 1] [[:b] :-]]"), Ok(..)));
+        assert!(matches!(EditScript::<archery::RcK>::from_edn("[[[:a] :+ 1.5]]"), Err(PatchError::Read(..))));
+    }
+}
@@ -3,3 +3,8 @@ pub mod keyword;
 pub mod protocol;
 pub mod protocols;
 pub mod fns;
+pub mod printer;
+pub mod pretty;
+pub mod canonical;
+pub mod read;
+pub mod diff;
@@ -0,0 +1,61 @@
+use archery::SharedPointerKind;
+use cljrs_core::pretty::{self, Doc, Highlight, PrettyOptions};
+use crate::{printer, value::Value};
+
+/// Clojure example:
+/// ```clojure
+/// (binding [*print-length* 2] (pprint [1 2 3])) ; prints [1 2 ...]
+/// ```
+pub fn pretty_str<P: SharedPointerKind>(value: &Value<P>, options: &PrettyOptions) -> String {
+    pretty::render(&to_doc(value, options), options)
+}
+
+pub fn to_doc<P: SharedPointerKind>(value: &Value<P>, options: &PrettyOptions) -> Doc {
+    doc_at_depth(value, options, 0)
+}
+
+fn doc_at_depth<P: SharedPointerKind>(value: &Value<P>, options: &PrettyOptions, depth: usize) -> Doc {
+    let leaf = |highlight| Doc::highlighted(printer::to_string(value, options.mode), highlight);
+    let item_doc = |v: &Value<P>| doc_at_depth(v, options, depth + 1);
+    match value {
+        Value::Nil | Value::Bool(..) => leaf(Highlight::Literal),
+        Value::Num(..) => leaf(Highlight::Num),
+        Value::Str(..) => leaf(Highlight::Str),
+        Value::Char(..) => leaf(Highlight::Char),
+        Value::Keyword(..) => leaf(Highlight::Keyword),
+        Value::Symbol(..) => leaf(Highlight::Symbol),
+        Value::IFn(..) => leaf(Highlight::Elision),
+        Value::List(list) => options.collection_doc(depth, ("(", "", ")"), list, item_doc),
+        Value::Vect(vect) => options.collection_doc(depth, ("[", "", "]"), vect, item_doc),
+        Value::Set(set) => options.collection_doc(depth, ("#{", "", "}"), set, item_doc),
+        Value::Map(map) => options.collection_doc(depth, ("{", ",", "}"), map, |(k, v)| {
+            Doc::map_entry(item_doc(k), item_doc(v))
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pretty_str;
+    use crate::value::{RcValue, Value};
+    use cljrs_core::pretty::PrettyOptions;
+
+    #[test]
+    fn nested_vects_break_aligned() {
+        let v = RcValue::vect_from_values(vec![
+            Value::vect_from_values((0..6).map(Value::Num)),
+            Value::unqualified_keyword(String::from("end")),
+        ]);
+        assert_eq!(pretty_str(&v, &PrettyOptions::default()), "[[0 1 2 3 4 5] :end]");
+        assert_eq!(
+            pretty_str(&v, &PrettyOptions::default().with_width(12)),
+            "[[0\n  1\n  2\n  3\n  4\n  5]\n :end]",
+        );
+    }
+
+    #[test]
+    fn print_length() {
+        let v = RcValue::list_from_values((0..100).map(Value::Num));
+        assert_eq!(pretty_str(&v, &PrettyOptions::default().with_print_length(3)), "(0 1 2 ...)");
+    }
+}
@@ -0,0 +1,219 @@
+use std::{fmt, sync::Arc};
+use archery::SharedPointerKind;
+use cljrs_core::printer::{self as core_printer, PrintMode};
+use crate::value::Value;
+
+/// Clojure example:
+/// ```clojure
+/// (pr-str {:a "b"}) ;=> "{:a \"b\"}"
+/// ```
+pub fn pr_str<P: SharedPointerKind>(value: &Value<P>) -> String {
+    to_string(value, PrintMode::Pr)
+}
+
+/// Clojure example:
+/// ```clojure
+/// (print-str {:a "b"}) ;=> "{:a b}"
+/// ```
+pub fn print_str<P: SharedPointerKind>(value: &Value<P>) -> String {
+    to_string(value, PrintMode::Print)
+}
+
+pub fn to_string<P: SharedPointerKind>(value: &Value<P>, mode: PrintMode) -> String {
+    let mut buf = String::new();
+    write_value(&mut buf, value, mode).expect("writing to a String does not fail");
+    buf
+}
+
+/// in [`PrintMode::Pr`] the output of every readable [`Value`] can be `read` back,
+/// unreadable values (fns) are written as `#object[...]`
+pub fn write_value<W: fmt::Write, P: SharedPointerKind>(
+    w: &mut W,
+    value: &Value<P>,
+    mode: PrintMode,
+) -> fmt::Result {
+    match value {
+        Value::Nil => w.write_str("nil"),
+        Value::Bool(b) => write!(w, "{b}"),
+        Value::Num(n) => write!(w, "{n}"),
+        Value::Str(s) => core_printer::write_str(w, s, mode),
+        Value::Char(ch) => core_printer::write_char(w, *ch, mode),
+        Value::Keyword(keyword) => write!(w, "{keyword}"),
+        Value::Symbol(sym) => write!(w, "{sym}"),
+        Value::List(list) => {
+            w.write_char('(')?;
+            core_printer::write_joined(w, list, " ", |w, v| write_value(w, v, mode))?;
+            w.write_char(')')
+        }
+        Value::Vect(vect) => {
+            w.write_char('[')?;
+            core_printer::write_joined(w, vect, " ", |w, v| write_value(w, v, mode))?;
+            w.write_char(']')
+        }
+        Value::Set(set) => {
+            w.write_str("#{")?;
+            core_printer::write_joined(w, set, " ", |w, v| write_value(w, v, mode))?;
+            w.write_char('}')
+        }
+        Value::Map(map) => {
+            w.write_char('{')?;
+            core_printer::write_joined(w, map, ", ", |w, (k, v)| {
+                write_value(w, k, mode)?;
+                w.write_char(' ')?;
+                write_value(w, v, mode)
+            })?;
+            w.write_char('}')
+        }
+        Value::IFn(ifn) => write!(w, "#object[cljrs.runtime.IFn {:p}]", Arc::as_ptr(ifn) as *const ()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pr_str, print_str};
+    use crate::{
+        fns::get_fn::GetFn,
+        read::read_str,
+        value::{RcValue, Value},
+    };
+    use cljrs_core::{keyword::ResolvedKeyword, symbol::Symbol};
+    use proptest::prelude::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn readable_values() {
+        let v = RcValue::vect_from_values(vec![
+            Value::Num(-1),
+            Value::Str(String::from("say \"hi\"\n")),
+            Value::Char(' '),
+            Value::qualified_keyword(String::from("a"), String::from("b")),
+            Value::list_from_values(vec![Value::unqualified_symbol(String::from("inc")), Value::Nil]),
+            Value::set_from_values(vec![Value::Bool(true)]),
+            Value::map_from_value_pairs(vec![(Value::Char('x'), Value::empty_vect())]),
+        ]);
+        assert_eq!(
+            pr_str(&v),
+            r#"[-1 "say \"hi\"\n" \space :a/b (inc nil) #{true} {\x []}]"#
+        );
+        assert_eq!(
+            print_str(&v),
+            "[-1 say \"hi\"\n   :a/b (inc nil) #{true} {x []}]"
+        );
+    }
+
+    #[test]
+    fn fns_are_unreadable_objects() {
+        let v = RcValue::IFn(Arc::new(GetFn));
+        let printed = pr_str(&v);
+        assert!(printed.starts_with("#object[cljrs.runtime.IFn 0x"), "{printed}");
+        assert!(printed.ends_with(']'), "{printed}");
+    }
+
+    fn arb_name() -> impl Strategy<Value = String> {
+        "[a-zA-Z*!_?<>=][a-zA-Z0-9*+!_?<>=.-]{0,8}"
+            .prop_filter("reserved", |s| !matches!(s.as_str(), "nil" | "true" | "false"))
+    }
+
+    fn arb_symbol() -> impl Strategy<Value = Symbol> {
+        prop_oneof![
+            arb_name().prop_map(Symbol::unqualified),
+            (arb_name(), arb_name()).prop_map(|(ns, n)| Symbol::qualified(ns, n)),
+        ]
+    }
+
+    fn arb_keyword() -> impl Strategy<Value = ResolvedKeyword> {
+        prop_oneof![
+            arb_name().prop_map(ResolvedKeyword::unqualified),
+            (arb_name(), arb_name()).prop_map(|(ns, n)| ResolvedKeyword::qualified(ns, n)),
+        ]
+    }
+
+    /// the reader reads numbers as `f64`s, so integers are read exactly only up to 2^53 in
+    /// magnitude, and at the extremes, which are read as the nearest `f64` and saturate
+    fn arb_num() -> impl Strategy<Value = isize> {
+        const EXACT: isize = 1 << f64::MANTISSA_DIGITS;
+        prop_oneof![Just(isize::MIN), Just(isize::MAX), Just(0), -EXACT..=EXACT]
+    }
+
+    /// [`Value`] is not [`Clone`] (which proptest needs), and fns aren't readable, so values are
+    /// generated as this first
+    #[derive(Debug, Clone)]
+    enum ArbValue {
+        Nil,
+        Bool(bool),
+        Num(isize),
+        Str(String),
+        Char(char),
+        Keyword(ResolvedKeyword),
+        Symbol(Symbol),
+        List(Vec<ArbValue>),
+        Vect(Vec<ArbValue>),
+        Set(Vec<ArbValue>),
+        Map(Vec<(ArbValue, ArbValue)>),
+    }
+
+    impl ArbValue {
+        fn to_value(&self) -> RcValue {
+            let to_values = |vs: &[ArbValue]| vs.iter().map(ArbValue::to_value).collect::<Vec<_>>();
+            match self {
+                Self::Nil => Value::Nil,
+                Self::Bool(b) => Value::Bool(*b),
+                Self::Num(n) => Value::Num(*n),
+                Self::Str(s) => Value::Str(s.clone()),
+                Self::Char(ch) => Value::Char(*ch),
+                Self::Keyword(k) => Value::keyword(k.clone()),
+                Self::Symbol(s) => Value::symbol(s.clone()),
+                Self::List(vs) => Value::list_from_values(to_values(vs)),
+                Self::Vect(vs) => Value::vect_from_values(to_values(vs)),
+                Self::Set(vs) => Value::set_from_values(distinct(to_values(vs))),
+                Self::Map(kvs) => {
+                    let (ks, vs): (Vec<_>, Vec<_>) = kvs.iter().cloned().unzip();
+                    let ks = distinct(to_values(&ks));
+                    Value::map_from_value_pairs(ks.into_iter().zip(to_values(&vs)))
+                }
+            }
+        }
+    }
+
+    fn distinct(values: Vec<RcValue>) -> Vec<RcValue> {
+        let mut out: Vec<RcValue> = vec![];
+        for v in values {
+            if !out.contains(&v) {
+                out.push(v);
+            }
+        }
+        out
+    }
+
+    fn arb_value() -> impl Strategy<Value = ArbValue> {
+        let leaf = prop_oneof![
+            Just(ArbValue::Nil),
+            any::<bool>().prop_map(ArbValue::Bool),
+            arb_num().prop_map(ArbValue::Num),
+            any::<String>().prop_map(ArbValue::Str),
+            "[\"\\\\\n\r\t ]*".prop_map(ArbValue::Str),
+            any::<char>().prop_map(ArbValue::Char),
+            prop::sample::select(&['\n', '\r', '\t', ' ', '\\', '"', '\u{0}', '\u{8}', '\u{c}'][..])
+                .prop_map(ArbValue::Char),
+            arb_keyword().prop_map(ArbValue::Keyword),
+            arb_symbol().prop_map(ArbValue::Symbol),
+        ];
+        leaf.prop_recursive(4, 64, 8, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..8).prop_map(ArbValue::List),
+                prop::collection::vec(inner.clone(), 0..8).prop_map(ArbValue::Vect),
+                prop::collection::vec(inner.clone(), 0..8).prop_map(ArbValue::Set),
+                prop::collection::vec((inner.clone(), inner), 0..8).prop_map(ArbValue::Map),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn read_str_pr_str_round_trips(v in arb_value()) {
+            let v = v.to_value();
+            let printed = pr_str(&v);
+            prop_assert_eq!(read_str(&printed), Ok(v), "printed: {}", printed);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! runtime values from those the reader reads
+
+use std::fmt;
+use archery::SharedPointerKind;
+use cljrs_core::{keyword::ResolveError, ns::NsContext};
+use cljrs_reader::{reader, value::Value as ReaderValue, ReadError};
+use crate::value::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FromReaderError {
+    Read(ReadError),
+    /// runtime numbers are integers, so e.g. `1.5` and `##NaN` have no runtime value
+    NotAnInteger(f64),
+    /// `::foo` and `::alias/foo`, which need a namespace to resolve them
+    AutoResolvedKeyword(String),
+    /// `::alias/foo`, where the namespace has no such alias
+    Resolve(ResolveError),
+}
+
+impl From<ReadError> for FromReaderError {
+    fn from(err: ReadError) -> Self {
+        Self::Read(err)
+    }
+}
+
+impl fmt::Display for FromReaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(err) => write!(f, "{err}"),
+            Self::NotAnInteger(n) => write!(f, "{n} is not an integer"),
+            Self::AutoResolvedKeyword(k) => write!(f, "{k} can't be resolved outside a namespace"),
+            Self::Resolve(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for FromReaderError {}
+
+/// a runtime value of a value read outside any namespace, in which `::` keywords can't be resolved
+pub fn from_reader_value<P: SharedPointerKind>(value: &ReaderValue<P>) -> Result<Value<P>, FromReaderError> {
+    from_reader_value_resolving(value, None)
+}
+
+/// a runtime value of a value read in the namespace `ns`, in which `::` keywords are resolved
+///
+/// Clojure example:
+/// ```clojure
+/// (ns something.xyz (:require [something.abc :as abc]))
+/// [::foo ::abc/foo] ;=> [:something.xyz/foo :something.abc/foo]
+/// ```
+pub fn from_reader_value_in<P: SharedPointerKind>(
+    value: &ReaderValue<P>,
+    ns: &NsContext,
+) -> Result<Value<P>, FromReaderError> {
+    from_reader_value_resolving(value, Some(ns))
+}
+
+fn from_reader_value_resolving<P: SharedPointerKind>(
+    value: &ReaderValue<P>,
+    ns: Option<&NsContext>,
+) -> Result<Value<P>, FromReaderError> {
+    let from_reader_value = |value: &ReaderValue<P>| from_reader_value_resolving(value, ns);
+    let values = |values: &[cljrs_reader::value::ValuePtr<P>]| {
+        values.iter().map(|v| from_reader_value(v)).collect::<Result<Vec<_>, _>>()
+    };
+    Ok(match value {
+        ReaderValue::Nil => Value::Nil,
+        ReaderValue::Bool(b) => Value::Bool(*b),
+        ReaderValue::Num(n) if n.fract() == 0.0 && n.abs() <= isize::MAX as f64 => Value::Num(*n as isize),
+        ReaderValue::Num(n) => return Err(FromReaderError::NotAnInteger(*n)),
+        ReaderValue::Str(s) => Value::Str(s.clone()),
+        ReaderValue::Char(ch) => Value::Char(*ch),
+        ReaderValue::Keyword(k) => match ns {
+            Some(ns) => Value::keyword(k.clone().resolve(ns).map_err(FromReaderError::Resolve)?),
+            None => match k.clone().try_resolved() {
+                Some(k) => Value::keyword(k),
+                None => return Err(FromReaderError::AutoResolvedKeyword(k.to_string())),
+            },
+        },
+        ReaderValue::Symbol(sym) => Value::symbol(sym.clone()),
+        ReaderValue::List(vs) => Value::list_from_values(values(vs)?),
+        ReaderValue::Vect(vs) => Value::vect_from_values(values(vs)?),
+        ReaderValue::Set(vs) => Value::set_from_values(values(vs)?),
+        ReaderValue::Map(entries) => {
+            let pairs = entries
+                .iter()
+                .map(|(k, v)| Ok((from_reader_value(k)?, from_reader_value(v)?)))
+                .collect::<Result<Vec<_>, FromReaderError>>()?;
+            Value::map_from_value_pairs(pairs)
+        }
+    })
+}
+
+/// the first form of `src`, or nil if there's none
+///
+/// Clojure example:
+/// ```clojure
+/// (read-string "{:a [1 2]}") ;=> {:a [1 2]}
+/// ```
+pub fn read_str<P: SharedPointerKind>(src: &str) -> Result<Value<P>, FromReaderError> {
+    match reader::<P>(src) {
+        Some(mut rdr) => match rdr.try_read_one()? {
+            Some(form) => from_reader_value(&form.data),
+            None => Ok(Value::Nil),
+        },
+        None => Ok(Value::Nil),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_reader_value_in, read_str, FromReaderError};
+    use crate::{printer::pr_str, value::RcValue};
+    use cljrs_core::{intern::Name, keyword::ResolveError, ns::NsContext};
+    use cljrs_reader::reader;
+
+    #[test]
+    fn reads() {
+        let v: RcValue = read_str(r#"{:a [1 "x" \y] :b/c #{(sym nil true)}}"#).unwrap();
+        let printed = pr_str(&v);
+        assert!(printed.contains(r#":a [1 "x" \y]"#), "{printed}");
+        assert!(printed.contains(":b/c #{(sym nil true)}"), "{printed}");
+        assert_eq!(pr_str(&read_str::<archery::RcK>("").unwrap()), "nil");
+        assert_eq!(read_str::<archery::RcK>("[1.5]"), Err(FromReaderError::NotAnInteger(1.5)));
+        assert!(matches!(read_str::<archery::RcK>("::a"), Err(FromReaderError::AutoResolvedKeyword(..))));
+    }
+
+    #[test]
+    fn resolves_keywords() {
+        let ns = NsContext::new("a.b".parse().unwrap()).with_alias(Name::new("c"), "c.d".parse().unwrap());
+        let read = |src| {
+            let form = reader::<archery::RcK>(src).unwrap().try_read_one().unwrap().unwrap();
+            from_reader_value_in(&form.data, &ns).map(|v: RcValue| pr_str(&v))
+        };
+        assert_eq!(read("[::x ::c/y :z :e/f]"), Ok("[:a.b/x :c.d/y :z :e/f]".to_owned()));
+        assert_eq!(read("::e/f"), Err(FromReaderError::Resolve(ResolveError::UnknownAlias(Name::new("e")))));
+    }
+}
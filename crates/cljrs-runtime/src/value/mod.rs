@@ -4,11 +4,12 @@ pub mod set;
 pub mod vect;
 
 use archery::{ArcK, RcK, SharedPointer, SharedPointerKind};
-use cljrs_core::symbol::Symbol;
+use cljrs_core::{keyword::ResolvedKeyword, printer::PrintMode, symbol::Symbol};
 use std::hash::Hash;
 use std::sync::Arc;
 
 use crate::{
+    printer,
     protocols::ifn,
     value::{
         list::{List, RawList},
@@ -35,7 +36,9 @@ pub enum Value<P: SharedPointerKind> {
     Bool(bool),
     Num(isize),
     Str(String),
-    Keyword(Symbol),
+    Char(char),
+    /// resolved, as `::` keywords are by [`crate::read::from_reader_value_in`]
+    Keyword(ResolvedKeyword),
     Symbol(Symbol),
     List(List<P>),
     Vect(Vect<P>),
@@ -51,6 +54,7 @@ impl<P: SharedPointerKind + std::fmt::Debug> std::fmt::Debug for Value<P> {
             Self::Bool(arg0) => f.debug_tuple("Bool").field(arg0).finish(),
             Self::Num(arg0) => f.debug_tuple("Num").field(arg0).finish(),
             Self::Str(arg0) => f.debug_tuple("Str").field(arg0).finish(),
+            Self::Char(arg0) => f.debug_tuple("Char").field(arg0).finish(),
             Self::Keyword(arg0) => f.debug_tuple("Keyword").field(arg0).finish(),
             Self::Symbol(arg0) => f.debug_tuple("Symbol").field(arg0).finish(),
             Self::List(arg0) => f.debug_tuple("List").field(arg0).finish(),
@@ -94,6 +98,12 @@ impl<P: SharedPointerKind> Value<P> {
             _ => false,
         }
     }
+    pub fn is_char(&self) -> bool {
+        match self {
+            Self::Char(..) => true,
+            _ => false,
+        }
+    }
     pub fn is_keyword(&self) -> bool {
         match self {
             Self::Keyword(..) => true,
@@ -144,6 +154,7 @@ impl<P: SharedPointerKind> PartialEq for Value<P> {
             (Self::Bool(l0), Self::Bool(r0)) => l0 == r0,
             (Self::Num(l0), Self::Num(r0)) => l0 == r0,
             (Self::Str(l0), Self::Str(r0)) => l0 == r0,
+            (Self::Char(l0), Self::Char(r0)) => l0 == r0,
             (Self::Keyword(l0), Self::Keyword(r0)) => l0 == r0,
             (Self::Symbol(l0), Self::Symbol(r0)) => l0 == r0,
             (Self::List(List::<P>(l0)), Self::List(List::<P>(r0))) => l0 == r0,
@@ -163,6 +174,7 @@ impl<P: SharedPointerKind> Value<P> {
             Self::Bool(arg0) => Self::Bool(arg0.clone()),
             Self::Num(arg0) => Self::Num(arg0.clone()),
             Self::Str(arg0) => Self::Str(arg0.clone()),
+            Self::Char(arg0) => Self::Char(*arg0),
             Self::Keyword(arg0) => Self::Keyword(arg0.clone()),
             Self::Symbol(arg0) => Self::Symbol(arg0.clone()),
             Self::List(list) => Self::List(List::clone(list)),
@@ -299,14 +311,14 @@ impl<P: SharedPointerKind> Value<P> {
         Self::Symbol(Symbol::qualified(namespace, name))
     }
 
-    pub fn keyword(symbol: Symbol) -> Self {
-        Self::Keyword(symbol)
+    pub fn keyword(keyword: ResolvedKeyword) -> Self {
+        Self::Keyword(keyword)
     }
     pub fn unqualified_keyword(name: String) -> Self {
-        Self::Keyword(Symbol::unqualified(name))
+        Self::Keyword(ResolvedKeyword::unqualified(name))
     }
     pub fn qualified_keyword(namespace: String, name: String) -> Self {
-        Self::Keyword(Symbol::qualified(namespace, name))
+        Self::Keyword(ResolvedKeyword::qualified(namespace, name))
     }
 
     pub fn to_ptr(self) -> ValuePtr<P> {
@@ -336,53 +348,6 @@ impl<P: SharedPointerKind> Hash for Value<P> {
 
 impl<P: SharedPointerKind> std::fmt::Display for Value<P> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Value::Nil => "nil".to_string(),
-                Value::Bool(b) => b.to_string(),
-                Value::Num(n) => n.to_string(),
-                Value::Str(s) => format!("\"{s}\""),
-                Value::Keyword(sym) => format!(":{sym}"),
-                Value::Symbol(sym) => sym.to_string(),
-                Value::List(list) => format!(
-                    "({})",
-                    list.into_iter()
-                        .map(ToString::to_string)
-                        .collect::<Vec<_>>()
-                        .join(" ")
-                ),
-                Value::Vect(vect) => format!(
-                    "[{}]",
-                    vect.into_iter()
-                        .map(ToString::to_string)
-                        .collect::<Vec<_>>()
-                        .join(" ")
-                ),
-                Value::Set(set) => format!(
-                    "#{{{}}}",
-                    set.into_iter()
-                        .map(ToString::to_string)
-                        .collect::<Vec<_>>()
-                        .join(" ")
-                ),
-                Value::Map(map) => format!(
-                    "{{{}}}",
-                    map.into_iter()
-                        .map(|(k, v)| vec![k.to_string(), v.to_string()].join(" "))
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                ),
-                Value::IFn(ifn) => {
-                    let raw = Arc::into_raw(Arc::clone(ifn));
-                    let fmt = format!("#object[fn,{:?}]", raw);
-                    unsafe {
-                        drop(Arc::from_raw(raw));
-                    }
-                    fmt
-                }
-            }
-        )
+        printer::write_value(f, self, PrintMode::Pr)
     }
 }
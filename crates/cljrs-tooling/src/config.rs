@@ -0,0 +1,75 @@
+//! reading the EDN maps tools are configured by
+
+use std::fmt;
+use archery::SharedPointerKind;
+use cljrs_reader::{
+    keyword::Keyword,
+    reader,
+    value::{Map, Value},
+    ReadError,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    Read(ReadError),
+    /// what was wrong, e.g. `":indents must be a map"`
+    Invalid(String),
+}
+
+impl From<ReadError> for ConfigError {
+    fn from(err: ReadError) -> Self {
+        Self::Read(err)
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(err) => write!(f, "unreadable config: {err}"),
+            Self::Invalid(msg) => write!(f, "invalid config: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// the entries of the map a config is, or none if it's empty
+pub fn read_config<P: SharedPointerKind>(src: &str) -> Result<Option<Map<P>>, ConfigError> {
+    let value = match reader::<P>(src) {
+        Some(mut rdr) => rdr.try_read_one()?.map(|span_value| span_value.data),
+        None => None,
+    };
+    match value {
+        Some(Value::Map(entries)) => Ok(Some(entries)),
+        None => Ok(None),
+        Some(_) => invalid("config must be a map"),
+    }
+}
+
+/// `foo` of `:foo`, the keys and many values of configs
+pub fn keyword_name<P: SharedPointerKind>(value: &Value<P>) -> Option<&str> {
+    match value {
+        Value::Keyword(Keyword::Unqualified { name }) => Some(name),
+        _ => None,
+    }
+}
+
+pub fn invalid<T>(msg: &str) -> Result<T, ConfigError> {
+    Err(ConfigError::Invalid(msg.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{keyword_name, read_config, ConfigError};
+    use archery::RcK;
+    use cljrs_reader::ReadError;
+
+    #[test]
+    fn reads_maps() {
+        let entries = read_config::<RcK>("{:a 1} ; ignored").unwrap().unwrap();
+        assert_eq!(keyword_name(&entries[0].0), Some("a"));
+        assert_eq!(read_config::<RcK>(" ; empty"), Ok(None));
+        assert_eq!(read_config::<RcK>("[]"), Err(ConfigError::Invalid("config must be a map".into())));
+        assert_eq!(read_config::<RcK>("{"), Err(ConfigError::Read(ReadError::insufficient_input((0, 0)))));
+    }
+}
@@ -0,0 +1,6 @@
+//! what the tools (cljrs-fmt, cljrs-lint, cljrs-doc etc.) share, which the reader, as it's also
+//! built without `std` and behind the C API, doesn't have
+
+pub mod config;
+pub mod source;
+pub mod syntax;
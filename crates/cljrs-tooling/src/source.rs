@@ -0,0 +1,69 @@
+//! finding Clojure sources and pointing into them
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use cljrs_data::spans::line_col;
+use cljrs_reader::ReadError;
+
+/// Clojure sources, as linted and documented
+pub const CLOJURE_EXTENSIONS: &[&str] = &["clj", "cljs", "cljc"];
+/// Clojure sources and EDN data, as formatted
+pub const CLOJURE_AND_EDN_EXTENSIONS: &[&str] = &["clj", "cljs", "cljc", "edn"];
+
+/// `name:line:col: message` for an error reading `src`, which is named `name`
+pub fn describe_error(err: &ReadError, src: &str, name: &str) -> String {
+    let (line, col) = line_col(src, err.span().0);
+    format!("{name}:{line}:{col}: {}", err.message())
+}
+
+/// `path` itself if it's a file, else the files beneath it with one of the `extensions`, in a
+/// stable order
+pub fn source_files(path: &Path, extensions: &[&str], files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_owned());
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        let has_extension = entry
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| extensions.contains(&ext));
+        if entry.is_dir() || has_extension {
+            source_files(&entry, extensions, files)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{describe_error, source_files, CLOJURE_EXTENSIONS};
+    use cljrs_reader::ReadError;
+    use std::{fs, path::Path};
+
+    #[test]
+    fn describes_errors() {
+        let err = ReadError::insufficient_input((3, 10));
+        assert_eq!(describe_error(&err, "(a\n  λb c)", "a.clj"), "a.clj:2:1: unexpected end of input");
+    }
+
+    #[test]
+    fn finds_sources() {
+        let dir = std::env::temp_dir().join(format!("cljrs-tooling-sources-{}", std::process::id()));
+        fs::create_dir_all(dir.join("b")).unwrap();
+        for file in ["b/c.cljs", "a.clj", "d.edn", "e.txt"] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        let mut files = vec![];
+        source_files(&dir, CLOJURE_EXTENSIONS, &mut files).unwrap();
+        let names: Vec<_> = files.iter().map(|file| file.strip_prefix(&dir).unwrap()).collect();
+        assert_eq!(names, [Path::new("a.clj"), Path::new("b/c.cljs")]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+//! what the tools look for in syntax trees, e.g. the name, metadata and params of a `defn`
+
+use cljrs_reader::syntax::{Delim, Node, NodeKind};
+
+/// the forms of a node, but not those discarded with `#_`
+pub fn forms<'n>(src: &str, node: &'n Node) -> Vec<&'n Node> {
+    node.forms().filter(|form| !form.is_discard(src)).collect()
+}
+
+/// the text of a symbol, but not of `nil`, `true` or `false`
+pub fn symbol<'s>(src: &'s str, node: &Node) -> Option<&'s str> {
+    if node.kind != NodeKind::Atom {
+        return None;
+    }
+    let text = node.text(src);
+    let mut chars = text.chars();
+    match (chars.next()?, chars.next()) {
+        (':' | '"' | '\\' | '#', _) => None,
+        (ch, _) if ch.is_ascii_digit() => None,
+        ('+' | '-', Some(ch)) if ch.is_ascii_digit() => None,
+        _ if matches!(text, "nil" | "true" | "false") => None,
+        _ => Some(text),
+    }
+}
+
+pub fn is_string(src: &str, node: &Node) -> bool {
+    node.kind == NodeKind::Atom && node.text(src).starts_with('"')
+}
+
+/// the prefix text of a prefixed node, e.g. `^` or `#_`
+pub fn prefix<'s>(src: &'s str, node: &Node) -> Option<&'s str> {
+    match node.kind {
+        NodeKind::Prefixed => node.children.first().map(|prefix| prefix.text(src)),
+        _ => None,
+    }
+}
+
+/// the form metadata is attached to, and the metadata, e.g. of `^:private ^String x`
+pub fn without_meta<'n>(src: &str, node: &'n Node) -> (&'n Node, Vec<&'n Node>) {
+    let (mut node, mut meta) = (node, vec![]);
+    while let Some("^" | "#^") = prefix(src, node) {
+        match forms(src, node)[..] {
+            [_prefix, data, form] => {
+                meta.push(data);
+                node = form;
+            }
+            _ => break,
+        }
+    }
+    (node, meta)
+}
+
+/// the symbol a list's forms start with
+pub fn head<'s>(src: &'s str, forms: &[&Node]) -> Option<&'s str> {
+    forms.first().and_then(|head| symbol(src, head))
+}
+
+/// the params vectors of each arity, without their metadata, of the forms after a fn's name,
+/// docstring and attr-map
+///
+/// Clojure example:
+/// ```clojure
+/// (defn f "doc" ([x] x) ([x & more] x)) ; [x] and [x & more]
+/// ```
+pub fn fn_params<'n>(src: &str, forms: &[&'n Node]) -> Vec<&'n Node> {
+    match forms.first().map(|params| without_meta(src, params).0) {
+        Some(params) if params.kind == NodeKind::Coll(Delim::Vect) => vec![params],
+        _ => forms
+            .iter()
+            .filter(|form| form.kind == NodeKind::Coll(Delim::List))
+            .filter_map(|arity| self::forms(src, arity).first().map(|params| without_meta(src, params).0))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fn_params, forms, head, symbol, without_meta};
+    use cljrs_reader::syntax::parse;
+
+    #[test]
+    fn forms_of_defs() {
+        let src = "(defn ^:private f #_x \"doc\" ([x] x) (^long [x & more] x)) (nil 1 -a)";
+        let root = parse(src).unwrap();
+        let tops = forms(src, &root);
+        let def = forms(src, tops[0]);
+        assert_eq!(head(src, &def), Some("defn"));
+        let (name, meta) = without_meta(src, def[1]);
+        assert_eq!((name.text(src), meta[0].text(src)), ("f", ":private"));
+        assert_eq!(def[2].text(src), "\"doc\"");
+        let params: Vec<_> = fn_params(src, &def[3..]).iter().map(|params| params.text(src)).collect();
+        assert_eq!(params, ["[x]", "[x & more]"]);
+
+        let call = forms(src, tops[1]);
+        assert_eq!(head(src, &call), None);
+        assert_eq!(call.iter().map(|form| symbol(src, form)).collect::<Vec<_>>(), [None, None, Some("-a")]);
+    }
+}
@@ -1,5 +1,7 @@
 use archery::ArcK;
+use cljrs_core::pretty::PrettyOptions;
 use cljrs_reader::{
+    pretty::pretty_str,
     value::{ArcValuePtrs, ValuePtr},
     ReadError,
 };
@@ -29,6 +31,7 @@ fn app() {
     setup_read_str_channels(Arc::new(|| Box::new(io::stdin().lock())), send_str);
     setup_values_reader_channels(recv_str, send_vals);
     //
+    let pretty_options = PrettyOptions::default().with_print_length(100);
     loop {
         for vals in recv_vals.iter() {
             let vals = vals
@@ -36,7 +39,7 @@ fn app() {
                 .map(ValuePtr::try_unwrap)
                 .map(Result::unwrap);
             for val in vals {
-                tracing::error!("val: {:?}", val);
+                tracing::error!("val: {}", pretty_str(&val, &pretty_options));
             }
         }
     }
@@ -107,6 +110,16 @@ fn setup_values_reader_channels(
                                         "unclosed collection",
                                     )
                                 }
+                                ReadError::DuplicateKey((first_begin_idx, first_end_idx), (begin_idx, end_idx)) => {
+                                    tracing::error!(
+                                        src = &src[..=end_idx],
+                                        err_src = &src[begin_idx..=end_idx],
+                                        first_src = &src[first_begin_idx..=first_end_idx],
+                                        err_begin_idx = begin_idx,
+                                        err_end_idx = end_idx,
+                                        "duplicate key",
+                                    )
+                                }
                             }
                         }
                     }
@@ -174,6 +187,16 @@ fn app2() {
                                     "unclosed collection",
                                 )
                             }
+                            ReadError::DuplicateKey((first_begin_idx, first_end_idx), (begin_idx, end_idx)) => {
+                                tracing::error!(
+                                    src = &buf[..=end_idx],
+                                    err_src = &buf[begin_idx..=end_idx],
+                                    first_src = &buf[first_begin_idx..=first_end_idx],
+                                    err_begin_idx = begin_idx,
+                                    err_end_idx = end_idx,
+                                    "duplicate key",
+                                )
+                            }
                         }
                     }
                 }